@@ -0,0 +1,188 @@
+//! Offline tracking of many videos (`batch <manifest.csv> [model] [jobs]`)
+//!
+//! The manifest has one entry per line, `video,x,y,w,h,output`, where the box
+//! is the target in the first frame; blank lines and lines starting with `#`
+//! are skipped. Each output is the telemetry CSV of `--report`, so `postfix`
+//! can clean it up afterwards.
+//!
+//! With `jobs` above 1 the entries run on that many threads sharing one
+//! model; NPU turns go through a scheduler. A summary table is printed at the
+//! end and written to `batch_summary.csv`. A failing entry is reported in the
+//! summary and does not stop the others.
+
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use opencv::{core, imgproc, prelude::*, videoio};
+use vit_tracker::error::{PipelineContext, PipelineError, Stage};
+use vit_tracker::report::{ReportConfig, RunReport, SessionSummary};
+use vit_tracker::rknn::NpuScheduler;
+use vit_tracker::tracker::VitTrackConfig;
+use vit_tracker::{BBox, VitTrack, VitTrackModel};
+
+use crate::mat_to_array3;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// One video to track
+struct Entry {
+    video: PathBuf,
+    init: BBox,
+    output: PathBuf,
+}
+
+/// Result of one entry
+struct Outcome {
+    summary: SessionSummary,
+    elapsed: Duration,
+}
+
+/// Track every manifest entry and print the summary
+pub fn run(
+    manifest: &Path,
+    model_path: &str,
+    jobs: usize,
+    mut config: VitTrackConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = parse(&fs::read_to_string(manifest)?)?;
+    let jobs = jobs.clamp(1, entries.len().max(1));
+
+    // Concurrent trackers take NPU turns on the shared model
+    let scheduler = NpuScheduler::new();
+    if jobs > 1 {
+        config.npu_client = Some(scheduler.client(100));
+    }
+    let model = Arc::new(VitTrackModel::load(model_path, config)?);
+    eprintln!("{} videos, {} jobs, model on {:?}", entries.len(), jobs, model.device());
+
+    let next = AtomicUsize::new(0);
+    let outcomes: Vec<Mutex<Option<Result<Outcome, String>>>> =
+        entries.iter().map(|_| Mutex::new(None)).collect();
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(entry) = entries.get(index) else { break };
+                    let outcome = track(entry, model.clone()).map_err(|e| e.to_string());
+                    match &outcome {
+                        Ok(_) => eprintln!("done: {}", entry.video.display()),
+                        Err(e) => eprintln!("failed: {}: {}", entry.video.display(), e),
+                    }
+                    *outcomes[index].lock().unwrap() = Some(outcome);
+                }
+            });
+        }
+    });
+
+    let mut summary = BufWriter::new(fs::File::create("batch_summary.csv")?);
+    writeln!(summary, "video,frames,tracked,losses,longest_loss,mean_score,fps,error")?;
+    println!(
+        "{:<40} {:>7} {:>8} {:>6} {:>8} {:>6} {:>7}",
+        "video", "frames", "tracked", "losses", "longest", "score", "fps"
+    );
+    for (entry, outcome) in entries.iter().zip(outcomes) {
+        let video = entry.video.display().to_string();
+        match outcome.into_inner().unwrap() {
+            Some(Ok(Outcome { summary: s, elapsed })) => {
+                let tracked = s.tracked as f32 / s.frames.max(1) as f32 * 100.0;
+                let fps = s.frames as f32 / elapsed.as_secs_f32().max(1e-6);
+                println!(
+                    "{:<40} {:>7} {:>7.1}% {:>6} {:>8} {:>6.3} {:>7.1}",
+                    video, s.frames, tracked, s.losses, s.longest_loss, s.mean_score, fps
+                );
+                writeln!(
+                    summary,
+                    "{},{},{},{},{},{:.4},{:.1},",
+                    video, s.frames, s.tracked, s.losses, s.longest_loss, s.mean_score, fps
+                )?;
+            }
+            Some(Err(e)) => {
+                println!("{:<40} error: {}", video, e);
+                writeln!(summary, "{},,,,,,,\"{}\"", video, e.replace('"', "'"))?;
+            }
+            None => {}
+        }
+    }
+    summary.flush()?;
+    Ok(())
+}
+
+/// Manifest lines `video,x,y,w,h,output`
+fn parse(text: &str) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+    let mut entries = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [video, x, y, w, h, output] = fields[..] else {
+            let message = format!("manifest line {}: expected video,x,y,w,h,output", number + 1);
+            return Err(message.into());
+        };
+        let value = |v: &str| {
+            v.parse::<i32>()
+                .map_err(|e| format!("manifest line {}: {}: {}", number + 1, v, e))
+        };
+        entries.push(Entry {
+            video: video.into(),
+            init: BBox::new(value(x)?, value(y)?, value(w)?, value(h)?),
+            output: output.into(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Track one video from its first frame and write the telemetry CSV
+fn track(entry: &Entry, model: Arc<VitTrackModel>) -> Result<Outcome, BoxError> {
+    let mut capture = videoio::VideoCapture::from_file(
+        &entry.video.to_string_lossy(),
+        videoio::CAP_ANY,
+    )?;
+    if !capture.is_opened()? {
+        return Err("cannot open video".into());
+    }
+    let mut tracker = VitTrack::with_model(model);
+    let mut report = RunReport::new(ReportConfig {
+        thumbnails: false,
+        ..ReportConfig::default()
+    });
+
+    let start = Instant::now();
+    let (mut frame, mut rgb_frame) = (core::Mat::default(), core::Mat::default());
+    let mut index = 0u64;
+    while capture.read(&mut frame).at(Stage::Capture, index)? && !frame.empty() {
+        let position = capture.get(videoio::CAP_PROP_POS_MSEC)?;
+        let timestamp = Duration::from_secs_f64(position.max(0.0) / 1e3);
+        imgproc::cvt_color(&frame, &mut rgb_frame, imgproc::COLOR_BGR2RGB, 0)
+            .at(Stage::Preprocess, index)?;
+        let image = mat_to_array3(&rgb_frame).at(Stage::Preprocess, index)?;
+        index += 1;
+        if index == 1 {
+            tracker.init(&image, entry.init);
+            continue;
+        }
+        tracker.set_frame_timestamp(Some(timestamp));
+        let result = tracker
+            .update(&image)
+            .map_err(|error| PipelineError::from(error).with_frame(index - 1))?;
+        report.observe(&result, None);
+    }
+    if index == 0 {
+        return Err("no frames".into());
+    }
+
+    let mut output = BufWriter::new(fs::File::create(&entry.output)?);
+    report.write_csv(&mut output)?;
+    output.flush()?;
+    Ok(Outcome {
+        summary: report.summary(),
+        elapsed: start.elapsed(),
+    })
+}
@@ -0,0 +1,210 @@
+//! Camera capture settings
+//!
+//! Requested values are only hints: drivers pick the closest mode they
+//! support, so the negotiated values are read back and printed. Many UVC
+//! cameras deliver 1080p only as MJPG and fall back to a few FPS in YUYV.
+
+use std::io;
+use std::time::Duration;
+
+use opencv::{core, prelude::*, videoio};
+use vit_tracker::stream::FrameSource;
+
+#[derive(Debug, Clone)]
+pub struct CaptureSettings {
+    /// Pixel format, e.g. "MJPG" or "YUYV"
+    pub fourcc: Option<String>,
+    pub width: f64,
+    pub height: f64,
+    pub fps: Option<f64>,
+    /// Driver-specific exposure value (V4L2: 100 us units with auto exposure off)
+    pub exposure: Option<f64>,
+    /// Driver buffer count; 1 keeps latency low at the cost of dropped frames
+    pub buffers: Option<f64>,
+}
+
+impl Default for CaptureSettings {
+    fn default() -> Self {
+        Self {
+            fourcc: None,
+            width: 1920.0,
+            height: 1080.0,
+            fps: None,
+            exposure: None,
+            buffers: None,
+        }
+    }
+}
+
+impl CaptureSettings {
+    /// Flags accepted by `parse_option`, each followed by a value
+    pub const FLAGS: [&'static str; 5] = ["--fourcc", "--size", "--fps", "--exposure", "--buffers"];
+
+    /// Apply a `--flag value` option
+    pub fn parse_option(&mut self, flag: &str, value: &str) -> Result<(), String> {
+        let number = || value.parse::<f64>().map_err(|_| format!("{} expects a number", flag));
+        match flag {
+            "--fourcc" => {
+                if value.len() != 4 {
+                    return Err("--fourcc expects 4 characters, e.g. MJPG".into());
+                }
+                self.fourcc = Some(value.to_uppercase());
+            }
+            "--size" => {
+                let (w, h) = value.split_once('x').ok_or("--size expects WIDTHxHEIGHT")?;
+                self.width = w.parse().map_err(|_| "--size expects WIDTHxHEIGHT")?;
+                self.height = h.parse().map_err(|_| "--size expects WIDTHxHEIGHT")?;
+            }
+            "--fps" => self.fps = Some(number()?),
+            "--exposure" => self.exposure = Some(number()?),
+            "--buffers" => self.buffers = Some(number()?),
+            _ => return Err(format!("unknown capture option {}", flag)),
+        }
+        Ok(())
+    }
+
+    /// Open a camera and request these settings
+    pub fn open(
+        &self,
+        camera_id: i32,
+    ) -> Result<videoio::VideoCapture, Box<dyn std::error::Error>> {
+        let mut cap = videoio::VideoCapture::new(camera_id, videoio::CAP_ANY)?;
+
+        if !cap.is_opened()? {
+            return Err(format!("Cannot open camera {}", camera_id).into());
+        }
+
+        // FOURCC first: the available sizes and rates depend on it
+        if let Some(fourcc) = &self.fourcc {
+            let c: Vec<char> = fourcc.chars().collect();
+            let code = videoio::VideoWriter::fourcc(c[0], c[1], c[2], c[3])?;
+            cap.set(videoio::CAP_PROP_FOURCC, code as f64)?;
+        }
+        cap.set(videoio::CAP_PROP_FRAME_WIDTH, self.width)?;
+        cap.set(videoio::CAP_PROP_FRAME_HEIGHT, self.height)?;
+        if let Some(fps) = self.fps {
+            cap.set(videoio::CAP_PROP_FPS, fps)?;
+        }
+        if let Some(exposure) = self.exposure {
+            // V4L2 manual exposure mode
+            cap.set(videoio::CAP_PROP_AUTO_EXPOSURE, 1.0)?;
+            cap.set(videoio::CAP_PROP_EXPOSURE, exposure)?;
+        }
+        if let Some(buffers) = self.buffers {
+            cap.set(videoio::CAP_PROP_BUFFERSIZE, buffers)?;
+        }
+
+        Ok(cap)
+    }
+}
+
+/// Settings actually in effect, as reported by the driver
+pub fn negotiated(cap: &videoio::VideoCapture) -> opencv::Result<String> {
+    let code = cap.get(videoio::CAP_PROP_FOURCC)? as u32;
+    let fourcc: String = code.to_le_bytes().iter().map(|&b| b as char).collect();
+    Ok(format!(
+        "{}x{} {} @ {:.1} FPS, exposure {}, buffers {}",
+        cap.get(videoio::CAP_PROP_FRAME_WIDTH)?,
+        cap.get(videoio::CAP_PROP_FRAME_HEIGHT)?,
+        fourcc.trim_end_matches('\0'),
+        cap.get(videoio::CAP_PROP_FPS)?,
+        cap.get(videoio::CAP_PROP_EXPOSURE)?,
+        cap.get(videoio::CAP_PROP_BUFFERSIZE)?
+    ))
+}
+
+/// Camera reopened with the same settings by `ReconnectingSource`
+pub struct CameraSource {
+    settings: CaptureSettings,
+    camera_id: i32,
+    cap: videoio::VideoCapture,
+}
+
+impl CameraSource {
+    pub fn open(
+        settings: CaptureSettings,
+        camera_id: i32,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let cap = settings.open(camera_id)?;
+        Ok(Self {
+            settings,
+            camera_id,
+            cap,
+        })
+    }
+
+    pub fn capture(&self) -> &videoio::VideoCapture {
+        &self.cap
+    }
+}
+
+impl FrameSource for CameraSource {
+    /// Frame with its capture timestamp
+    type Frame = (core::Mat, Option<Duration>);
+
+    fn open(&mut self) -> io::Result<()> {
+        self.cap.release().map_err(io::Error::other)?;
+        self.cap = self
+            .settings
+            .open(self.camera_id)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(())
+    }
+
+    fn read(&mut self) -> io::Result<Option<Self::Frame>> {
+        read_frame(&mut self.cap)
+    }
+}
+
+/// Frame with its capture timestamp, `None` when the capture returned nothing
+fn read_frame(
+    cap: &mut videoio::VideoCapture,
+) -> io::Result<Option<(core::Mat, Option<Duration>)>> {
+    let mut frame = core::Mat::default();
+    cap.read(&mut frame).map_err(io::Error::other)?;
+    if frame.empty() {
+        return Ok(None);
+    }
+    // V4L2 buffer timestamp or stream PTS; 0 when the backend has none
+    let ms = cap.get(videoio::CAP_PROP_POS_MSEC).map_err(io::Error::other)?;
+    let timestamp = (ms > 0.0).then(|| Duration::from_secs_f64(ms / 1e3));
+    Ok(Some((frame, timestamp)))
+}
+
+/// Network stream (RTSP, HTTP) or video file opened through FFmpeg
+pub struct UrlSource {
+    url: String,
+    cap: videoio::VideoCapture,
+}
+
+impl UrlSource {
+    pub fn open(url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let cap = open_url(url)?;
+        Ok(Self {
+            url: url.to_string(),
+            cap,
+        })
+    }
+}
+
+fn open_url(url: &str) -> io::Result<videoio::VideoCapture> {
+    let cap = videoio::VideoCapture::from_file(url, videoio::CAP_FFMPEG).map_err(io::Error::other)?;
+    if !cap.is_opened().map_err(io::Error::other)? {
+        return Err(io::Error::other(format!("Cannot open stream {}", url)));
+    }
+    Ok(cap)
+}
+
+impl FrameSource for UrlSource {
+    type Frame = (core::Mat, Option<Duration>);
+
+    fn open(&mut self) -> io::Result<()> {
+        self.cap.release().map_err(io::Error::other)?;
+        self.cap = open_url(&self.url)?;
+        Ok(())
+    }
+
+    fn read(&mut self) -> io::Result<Option<Self::Frame>> {
+        read_frame(&mut self.cap)
+    }
+}
@@ -0,0 +1,430 @@
+use ndarray::ArrayView3;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+use vit_tracker::error::{PipelineContext, PipelineError, Stage};
+use vit_tracker::klv::{KlvConfig, KlvEncoder};
+use vit_tracker::pose::{PoseLog, PoseSync};
+use vit_tracker::reload::{FileWatcher, RuntimeSettings};
+use vit_tracker::report::{ReportConfig, RunReport};
+use vit_tracker::selftest::{self, SelftestConfig};
+use vit_tracker::snapshot::{LossSnapshots, SnapshotConfig};
+use vit_tracker::soak::{self, SoakConfig, SoakInput};
+use vit_tracker::stream::{ReconnectConfig, ReconnectingSource, StreamEvent};
+use vit_tracker::subtitle::SubtitleWriter;
+use vit_tracker::tensor_dump::TensorDumpConfig;
+use vit_tracker::tracker::VitTrackConfig;
+use vit_tracker::{BBox, VitTrack};
+
+use opencv::{
+    core, highgui, imgproc, prelude::*, videoio, Result as CvResult,
+};
+
+mod batch;
+mod capture;
+mod mqtt;
+mod overlay;
+mod postfix;
+mod rpc;
+mod server;
+
+use capture::{CameraSource, CaptureSettings};
+use overlay::OverlayStyle;
+
+fn mat_to_array3(mat: &'_ core::Mat) -> CvResult<ArrayView3<'_, u8>> {
+    let bytes = mat.data_bytes().unwrap();
+    let rows = mat.rows() as usize;
+    let cols = mat.cols() as usize;
+    let channels = mat.channels() as usize;
+    
+    let array = ArrayView3::from_shape((rows, cols, channels), bytes).unwrap();
+    Ok(array)
+}
+
+/// Up to 300 frames of a video as RGB, and the box to track them from
+fn read_recording(video: &str, bbox: &str) -> Result<SoakInput, Box<dyn std::error::Error>> {
+    let values: Vec<i32> = bbox.split(',').map(|v| v.trim().parse()).collect::<Result<_, _>>()?;
+    let [x, y, w, h] = values[..] else {
+        return Err("box must be x,y,w,h".into());
+    };
+    let mut capture = videoio::VideoCapture::from_file(video, videoio::CAP_ANY)?;
+    let (mut frame, mut rgb_frame) = (core::Mat::default(), core::Mat::default());
+    let mut frames = Vec::new();
+    while frames.len() < 300 && capture.read(&mut frame)? && !frame.empty() {
+        imgproc::cvt_color(&frame, &mut rgb_frame, imgproc::COLOR_BGR2RGB, 0)?;
+        frames.push(mat_to_array3(&rgb_frame)?.to_owned());
+    }
+    Ok(SoakInput::Recorded {
+        frames,
+        init: BBox::new(x, y, w, h),
+    })
+}
+
+/// Apply the settings file when it changed; a bad edit keeps the previous settings
+fn reload_settings(watcher: &mut FileWatcher, tracker: &mut VitTrack) {
+    let settings = match watcher.changed() {
+        Ok(false) => return,
+        Ok(true) => RuntimeSettings::load(watcher.path()),
+        Err(e) => Err(e),
+    };
+    match settings {
+        Ok(settings) => {
+            tracker.apply_settings(&settings);
+            eprintln!("Settings from {}: {:?}", watcher.path().display(), settings);
+        }
+        Err(e) => eprintln!("Settings in {} not applied: {}", watcher.path().display(), e),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // --rpc: no GUI, controlled by JSON-RPC lines on stdin (see rpc.rs)
+    // --overlay <file>: overlay style as JSON (see overlay.rs), reloaded when edited
+    // --settings <file>: thresholds and search settings, reloaded when edited (see reload.rs)
+    // --pose-log <file>, --pose-offset <s>: attach the platform pose of an IMU/GPS log
+    //   (see pose.rs); the offset is added to the frame timestamps
+    // --snapshots <dir>: save the frames before each loss of track
+    // --report <file.html>: write a standalone HTML report of the session on exit
+    // --subtitles <file.srt|.vtt>: write the box and score of every frame as subtitles
+    // --klv <file>: write a MISB ST 0601 KLV packet per frame, for muxing as a data stream
+    // --dump-tensors <dir>: save the raw NPU inputs before each loss of track as .npy
+    // --fourcc, --size, --fps, --exposure, --buffers: capture settings (see capture.rs);
+    //   --fps also scales the motion-related tracker parameters
+    let mut rpc_mode = false;
+    let mut capture = CaptureSettings::default();
+    let mut overlay = OverlayStyle::default();
+    let mut overlay_file: Option<FileWatcher> = None;
+    let mut settings: Option<FileWatcher> = None;
+    let mut snapshots = None;
+    let mut report: Option<(String, RunReport)> = None;
+    let mut subtitles = None;
+    let mut klv = None;
+    let mut pose_log = None;
+    let mut pose_offset = 0.0;
+    let mut config = VitTrackConfig::default();
+    let mut args: Vec<String> = Vec::new();
+    let mut raw_args = std::env::args();
+    while let Some(arg) = raw_args.next() {
+        match arg.as_str() {
+            "--rpc" => rpc_mode = true,
+            "--overlay" => {
+                let path = raw_args.next().ok_or("--overlay needs a file")?;
+                overlay = OverlayStyle::load(Path::new(&path))?;
+                let mut watcher = FileWatcher::new(&path);
+                watcher.changed()?;
+                overlay_file = Some(watcher);
+            }
+            "--settings" => {
+                let path = raw_args.next().ok_or("--settings needs a file")?;
+                settings = Some(FileWatcher::new(path));
+            }
+            "--pose-log" => {
+                let path = raw_args.next().ok_or("--pose-log needs a file")?;
+                pose_log = Some(PoseLog::load(path)?);
+            }
+            "--pose-offset" => {
+                let value = raw_args.next().ok_or("--pose-offset needs seconds")?;
+                pose_offset = value.parse()?;
+            }
+            "--snapshots" => {
+                let dir = raw_args.next().ok_or("--snapshots needs a directory")?;
+                snapshots = Some(LossSnapshots::new(SnapshotConfig {
+                    dir: dir.into(),
+                    ..SnapshotConfig::default()
+                }));
+            }
+            "--report" => {
+                let path = raw_args.next().ok_or("--report needs a file")?;
+                report = Some((path, RunReport::new(ReportConfig::default())));
+            }
+            "--subtitles" => {
+                let path = raw_args.next().ok_or("--subtitles needs a file")?;
+                subtitles = Some(SubtitleWriter::create(path, 30.0)?);
+            }
+            "--klv" => {
+                let path = raw_args.next().ok_or("--klv needs a file")?;
+                let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+                klv = Some((file, KlvEncoder::new(KlvConfig::default())));
+            }
+            "--dump-tensors" => {
+                let dir = raw_args.next().ok_or("--dump-tensors needs a directory")?;
+                config.tensor_dump = Some(TensorDumpConfig {
+                    dir: dir.into(),
+                    ..TensorDumpConfig::default()
+                });
+            }
+            flag if CaptureSettings::FLAGS.contains(&flag) => {
+                let value = raw_args.next().ok_or(format!("{} needs a value", flag))?;
+                capture.parse_option(flag, &value)?;
+            }
+            _ => args.push(arg),
+        }
+    }
+    config.pose_sync = pose_log.map(|log| PoseSync::new(log, pose_offset));
+    config.frame_rate = capture.fps.map(|fps| fps as f32);
+
+    // selftest [model]: pass/fail report for deployment checks
+    if args.get(1).is_some_and(|arg| arg == "selftest") {
+        let model_path = args
+            .get(2)
+            .map(|s| s.as_str())
+            .unwrap_or("models/object_tracking_vittrack_2023sep.rknn");
+        let report = selftest::run(model_path, &SelftestConfig::default());
+        println!("{}", report);
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+
+    // soak [model] [hours] [video x,y,w,h]: burn-in test on synthetic frames, or on a
+    // recording tracked from the given box; samples go to soak.csv
+    if args.get(1).is_some_and(|arg| arg == "soak") {
+        let model_path = args
+            .get(2)
+            .map(|s| s.as_str())
+            .unwrap_or("models/object_tracking_vittrack_2023sep.rknn");
+        let hours: f32 = args.get(3).map(|s| s.parse()).transpose()?.unwrap_or(4.0);
+        let input = match (args.get(4), args.get(5)) {
+            (Some(video), Some(bbox)) => read_recording(video, bbox)?,
+            _ => SoakInput::Synthetic,
+        };
+        let soak_config = SoakConfig {
+            duration: Duration::from_secs_f32(hours * 3600.0),
+            ..SoakConfig::default()
+        };
+        let mut tracker = VitTrack::with_config(model_path, config)?;
+        let report = soak::run(&mut tracker, &input, &soak_config, |sample| {
+            println!(
+                "{:.0} min: {} frames, {} errors, {:.1} ms",
+                sample.elapsed.as_secs_f32() / 60.0,
+                sample.frames,
+                sample.errors,
+                sample.mean_latency.as_secs_f32() * 1e3
+            );
+        });
+        report.write_csv(&mut std::fs::File::create("soak.csv")?)?;
+        for error in &report.errors {
+            println!("error: {}", error);
+        }
+        println!("{}", report.checks);
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+
+    // postfix <results.csv> [cleaned.csv]: offline smoothing of a finished run (see postfix.rs)
+    if args.get(1).is_some_and(|arg| arg == "postfix") {
+        let input = args.get(2).ok_or("postfix needs a results file")?;
+        return postfix::run(Path::new(input), args.get(3).map(Path::new));
+    }
+
+    // batch <manifest.csv> [model] [jobs]: track many videos offline (see batch.rs)
+    if args.get(1).is_some_and(|arg| arg == "batch") {
+        let manifest = args.get(2).ok_or("batch needs a manifest file")?;
+        let model_path = args
+            .get(3)
+            .map(|s| s.as_str())
+            .unwrap_or("models/object_tracking_vittrack_2023sep.rknn");
+        let jobs: usize = args.get(4).map(|s| s.parse()).transpose()?.unwrap_or(1);
+        return batch::run(Path::new(manifest), model_path, jobs, config);
+    }
+
+    // server <config.json>: track several RTSP streams, one NPU context each (see server.rs)
+    if args.get(1).is_some_and(|arg| arg == "server") {
+        let config = args.get(2).ok_or("server needs a config file")?;
+        return server::run(Path::new(config));
+    }
+
+    let model_path = args
+        .get(1)
+        .map(|s| s.as_str())
+        .unwrap_or("models/object_tracking_vittrack_2023sep.rknn");
+
+    let camera_id: i32 = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(11);
+
+    if rpc_mode {
+        let mut tracker = VitTrack::with_config(model_path, config)?;
+        let camera = CameraSource::open(capture, camera_id)?;
+        eprintln!("Camera: {}", capture::negotiated(camera.capture())?);
+        let mut source = ReconnectingSource::new(camera, ReconnectConfig::default());
+        source.add_sink(|event: &StreamEvent| eprintln!("Stream: {}", event));
+        eprintln!("Ready: model {}, camera {}", model_path, camera_id);
+        return rpc::run(&mut tracker, &mut source, settings.as_mut());
+    }
+
+    println!("VitTrack Rust + RKNN");
+    println!("====================");
+    println!("Model: {}", model_path);
+    println!("Camera: {}", camera_id);
+
+    // Create tracker
+    println!("\nLoading tracker...");
+    let mut tracker = VitTrack::with_config(model_path, config)?;
+    println!("Tracker loaded!");
+
+    // Open camera
+    println!("Opening camera {}...", camera_id);
+    let camera = CameraSource::open(capture, camera_id)?;
+    println!("Negotiated: {}", capture::negotiated(camera.capture())?);
+    let mut source = ReconnectingSource::new(camera, ReconnectConfig::default());
+    source.add_sink(|event: &StreamEvent| println!("Stream: {}", event));
+
+    let (mut frame, _) = source.next_frame()?;
+
+    // Select ROI
+    println!("\nSelect object to track...");
+    let roi = highgui::select_roi(&frame, false, false)?;
+    // highgui::destroy_window("Select Object")?;
+
+    if roi.width == 0 || roi.height == 0 {
+        return Err("No object selected".into());
+    }
+
+    let mut rgb_frame = core::Mat::default();
+    imgproc::cvt_color(&frame, &mut rgb_frame, imgproc::COLOR_BGR2RGB, 0)?;
+
+    // Initialize tracker
+    let image = mat_to_array3(&rgb_frame)?;
+    let bbox = BBox::new(roi.x, roi.y, roi.width, roi.height);
+    let quality = tracker.init(&image, bbox);
+    if quality.is_poor() {
+        println!(
+            "Warning: poor template (score {:.2}): {:?}",
+            quality.score, quality.warnings
+        );
+    }
+    let test_result = tracker.update(&image)?;
+    println!("TEST: update on same frame: {:?}", test_result);
+
+    println!(
+        "Tracker initialized: x={}, y={}, w={}, h={}",
+        roi.x, roi.y, roi.width, roi.height
+    );
+    println!("\nControls: Q - quit, R - reinitialize");
+
+    let mut fps_history: Vec<f64> = Vec::with_capacity(32);
+    let mut avg_fps = 0.0;
+    let source_id = format!("camera {}", camera_id);
+    for index in 1u64.. {
+        // Failures name the frame and camera, for logs of long runs
+        let context = |error: PipelineError| error.with_source(source_id.as_str());
+        let start = Instant::now();
+        let (next, timestamp) = source.next_frame().at(Stage::Capture, index).map_err(context)?;
+        frame = next;
+        let elapsed = start.elapsed().as_micros();
+        println!("Read frame: {} usec", elapsed);
+
+        let timer = Instant::now();
+        let mut rgb_frame = core::Mat::default();
+        imgproc::cvt_color(&frame, &mut rgb_frame, imgproc::COLOR_BGR2RGB, 0)
+            .at(Stage::Preprocess, index)
+            .map_err(context)?;
+        let image = mat_to_array3(&rgb_frame).at(Stage::Preprocess, index).map_err(context)?;
+        let elapsed = timer.elapsed().as_micros();
+        println!("mat_to_array3: {} usec", elapsed);
+
+        if let Some(watcher) = &mut settings {
+            reload_settings(watcher, &mut tracker);
+        }
+        if let Some(watcher) = &mut overlay_file
+            && watcher.changed().unwrap_or(false)
+        {
+            match OverlayStyle::load(watcher.path()) {
+                Ok(style) => overlay = style,
+                Err(e) => eprintln!("Overlay in {} not applied: {}", watcher.path().display(), e),
+            }
+        }
+
+        // Track
+        let timer = Instant::now();
+        tracker.set_frame_timestamp(timestamp);
+        let result = tracker
+            .update(&image)
+            .map_err(|error| context(PipelineError::from(error).with_frame(index)))?;
+        let elapsed = timer.elapsed().as_micros();
+        println!("tracker.update: {} usec", elapsed);
+        if let Some(snapshots) = &mut snapshots {
+            match snapshots.observe(&image, &result, tracker.score_map()) {
+                Ok(Some(dir)) => println!("Target lost, snapshot saved to {}", dir.display()),
+                Ok(None) => {}
+                Err(e) => println!("Snapshot failed: {}", e),
+            }
+        }
+        if let Some((_, report)) = &mut report {
+            report.observe(&result, Some(&image));
+        }
+        if let Some(subtitles) = &mut subtitles {
+            subtitles.push(&result).at(Stage::Output, index).map_err(context)?;
+        }
+        if let Some((file, encoder)) = &mut klv {
+            let (h, w, _) = image.dim();
+            file.write_all(&encoder.encode(&result, (w, h), SystemTime::now()))
+                .at(Stage::Output, index)
+                .map_err(context)?;
+        }
+        if let Some(recorder) = tracker.tensor_recorder_mut()
+            && let Some(dump) = recorder.take_last_dump()
+        {
+            match dump {
+                Ok(dir) => println!("NPU inputs saved to {}", dir.display()),
+                Err(e) => println!("Tensor dump failed: {}", e),
+            }
+        }
+        if let Some(event) = &result.reinit {
+            println!(
+                "Watchdog: target lost for {:.1} s, {:?} -> recovered: {}",
+                event.lost_for.as_secs_f32(),
+                event.action,
+                event.recovered
+            );
+        }
+
+        // Draw result
+        let timer = Instant::now();
+        overlay.draw(&mut frame, &result, avg_fps)?;
+        let elapsed = timer.elapsed().as_micros();
+        println!("overlay.draw: {} usec", elapsed);
+
+        let timer = Instant::now();
+        highgui::imshow("VitTrack Rust", &frame)?;
+        let elapsed = timer.elapsed().as_micros();
+        println!("highgui::imshow: {} usec", elapsed);
+
+        let key = highgui::wait_key(1)?;
+        if key == 'q' as i32 || key == 27 {
+            break;
+        } else if key == 'r' as i32 {
+            println!("\nReinitializing...");
+            let roi = highgui::select_roi(&frame, false, false)?;
+            // highgui::destroy_window("Select Object")?;
+            if roi.width > 0 && roi.height > 0 {
+                let image = mat_to_array3(&frame)?;
+                let bbox = BBox::new(roi.x, roi.y, roi.width, roi.height);
+                tracker.init(&image, bbox);
+                fps_history.clear();
+                println!("Reinitialized!");
+            }
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+
+        // Calculate FPS
+        let fps = 1.0 / elapsed;
+        fps_history.push(fps);
+        if fps_history.len() > 30 {
+            fps_history.remove(0);
+        }
+        avg_fps = fps_history.iter().sum::<f64>() / fps_history.len() as f64;
+    }
+
+    if !fps_history.is_empty() {
+        let avg_fps: f64 = fps_history.iter().sum::<f64>() / fps_history.len() as f64;
+        println!("\nAverage FPS: {:.1}", avg_fps);
+    }
+    if let Some(subtitles) = subtitles {
+        subtitles.finish()?;
+    }
+    if let Some((mut file, _)) = klv {
+        file.flush()?;
+    }
+    if let Some((path, report)) = report {
+        report.save(&path)?;
+        println!("Report written to {}", path);
+    }
+
+    Ok(())
+}
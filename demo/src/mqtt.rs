@@ -0,0 +1,73 @@
+//! Minimal MQTT 3.1.1 publisher for the stream server
+//!
+//! Connects with a clean session and no keep-alive, and publishes at QoS 0:
+//! tracking results are superseded by the next frame, so nothing is retried.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+
+/// Broker connection publishing under a topic prefix
+pub struct MqttSink {
+    stream: TcpStream,
+}
+
+impl MqttSink {
+    /// # Arguments
+    /// * `address` - Broker as host:port
+    /// * `client_id` - Client identifier, unique per broker
+    pub fn connect(address: &str, client_id: &str) -> io::Result<Self> {
+        let mut body = Vec::new();
+        put_str(&mut body, "MQTT");
+        // Protocol level 4, clean session, keep-alive off
+        body.extend_from_slice(&[4, 0x02, 0, 0]);
+        put_str(&mut body, client_id);
+
+        let mut stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        stream.write_all(&packet(CONNECT, &body))?;
+
+        let mut ack = [0u8; 4];
+        stream.read_exact(&mut ack)?;
+        if ack[0] != CONNACK || ack[1] != 2 {
+            return Err(io::Error::other("MQTT broker sent no CONNACK"));
+        }
+        if ack[3] != 0 {
+            return Err(io::Error::other(format!("MQTT connect refused (code {})", ack[3])));
+        }
+        Ok(Self { stream })
+    }
+
+    /// Publish one message at QoS 0
+    pub fn publish(&mut self, topic: &str, payload: &[u8]) -> io::Result<()> {
+        let mut body = Vec::with_capacity(topic.len() + payload.len() + 2);
+        put_str(&mut body, topic);
+        body.extend_from_slice(payload);
+        self.stream.write_all(&packet(PUBLISH, &body))
+    }
+}
+
+/// Fixed header (type, remaining length) followed by the body
+fn packet(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![kind];
+    let mut len = body.len();
+    loop {
+        let byte = (len % 128) as u8;
+        len /= 128;
+        packet.push(if len > 0 { byte | 0x80 } else { byte });
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+/// Length-prefixed UTF-8 string
+fn put_str(buf: &mut Vec<u8>, text: &str) {
+    buf.extend_from_slice(&(text.len() as u16).to_be_bytes());
+    buf.extend_from_slice(text.as_bytes());
+}
@@ -0,0 +1,155 @@
+//! Overlay drawn on the preview window
+//!
+//! Loaded from a JSON file with `--overlay <file>`; missing fields keep their
+//! defaults, e.g. `{"watermark": null, "labels": {"lost": "Perdu"}}`.
+
+use std::path::Path;
+
+use opencv::{core, imgproc, Result as CvResult};
+use serde::Deserialize;
+use vit_tracker::TrackingResult;
+
+/// Label texts, for translation
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Labels {
+    pub fps: String,
+    pub score: String,
+    pub tracking: String,
+    pub lost: String,
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self {
+            fps: "FPS".into(),
+            score: "Score".into(),
+            tracking: "Tracking".into(),
+            lost: "Lost".into(),
+        }
+    }
+}
+
+/// Colors are BGR, 0-255
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OverlayStyle {
+    pub tracking_color: [f64; 3],
+    pub lost_color: [f64; 3],
+    pub fps_color: [f64; 3],
+    pub text_color: [f64; 3],
+    pub watermark_color: [f64; 3],
+    pub line_thickness: i32,
+    pub font_scale: f64,
+    pub show_center: bool,
+    pub show_fps: bool,
+    pub show_score: bool,
+    pub show_status: bool,
+    pub labels: Labels,
+    /// Text in the corner; null hides it
+    pub watermark: Option<String>,
+}
+
+impl Default for OverlayStyle {
+    fn default() -> Self {
+        Self {
+            tracking_color: [0.0, 255.0, 0.0],
+            lost_color: [0.0, 0.0, 255.0],
+            fps_color: [0.0, 0.0, 255.0],
+            text_color: [255.0, 255.0, 255.0],
+            watermark_color: [0.0, 255.0, 255.0],
+            line_thickness: 2,
+            font_scale: 0.7,
+            show_center: true,
+            show_fps: true,
+            show_score: true,
+            show_status: true,
+            labels: Labels::default(),
+            watermark: Some("RKNN NPU (Rust)".into()),
+        }
+    }
+}
+
+fn scalar([b, g, r]: [f64; 3]) -> core::Scalar {
+    core::Scalar::new(b, g, r, 0.0)
+}
+
+impl OverlayStyle {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn draw(&self, frame: &mut core::Mat, result: &TrackingResult, fps: f64) -> CvResult<()> {
+        let [x, y, w, h] = result.bbox;
+        // Tagged tracks keep their color while lost, so they stay recognizable
+        let color = scalar(match result.tag {
+            Some(tag) => {
+                let [r, g, b] = tag.color().map(f64::from);
+                [b, g, r]
+            }
+            None if result.success => self.tracking_color,
+            None => self.lost_color,
+        });
+
+        // Draw bounding box
+        imgproc::rectangle(
+            frame,
+            core::Rect::new(x, y, w, h),
+            color,
+            self.line_thickness,
+            imgproc::LINE_8,
+            0,
+        )?;
+
+        // Draw center point
+        if self.show_center && result.success {
+            imgproc::circle(
+                frame,
+                core::Point::new(x + w / 2, y + h / 2),
+                2 * self.line_thickness,
+                color,
+                -1,
+                imgproc::LINE_8,
+                0,
+            )?;
+        }
+
+        // Text lines, stacked from the top left
+        let mut lines = Vec::new();
+        if self.show_fps {
+            lines.push((format!("{}: {:.1}", self.labels.fps, fps), scalar(self.fps_color)));
+        }
+        if self.show_score {
+            let text = format!("{}: {:.3}", self.labels.score, result.score);
+            lines.push((text, scalar(self.text_color)));
+        }
+        if self.show_status {
+            let status = if result.success {
+                &self.labels.tracking
+            } else {
+                &self.labels.lost
+            };
+            lines.push((status.clone(), color));
+        }
+        if let Some(watermark) = &self.watermark {
+            lines.push((watermark.clone(), scalar(self.watermark_color)));
+        }
+
+        let line_height = (self.font_scale * 43.0).round() as i32;
+        for (row, (text, color)) in lines.iter().enumerate() {
+            imgproc::put_text(
+                frame,
+                text,
+                core::Point::new(10, line_height * (row as i32 + 1)),
+                imgproc::FONT_HERSHEY_SIMPLEX,
+                self.font_scale,
+                *color,
+                self.line_thickness,
+                imgproc::LINE_8,
+                false,
+            )?;
+        }
+
+        Ok(())
+    }
+}
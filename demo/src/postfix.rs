@@ -0,0 +1,68 @@
+//! Offline track clean-up (`postfix <results.csv> [cleaned.csv]`)
+//!
+//! Reads either the telemetry CSV of `--report` (frame, timestamp_ms,
+//! success, coasting, score, peak_ratio, x, y, w, h, ...) or a plain `x,y,w,h`
+//! box per line, where an empty box marks a lost frame. Writes one line per
+//! frame: `frame,x,y,w,h,kind` with kind measured, interpolated, outlier or
+//! missing (and an empty box).
+
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use vit_tracker::postfix::{postfix, PointKind, PostfixConfig};
+
+/// Smooth `input` and write the cleaned track to `output` (stdout when `None`)
+pub fn run(input: &Path, output: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(input)?;
+    let measurements = parse(&text);
+    let track = postfix(&measurements, &PostfixConfig::default());
+
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(BufWriter::new(fs::File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    writeln!(out, "frame,x,y,w,h,kind")?;
+    for (frame, (bbox, kind)) in track.boxes.iter().zip(&track.kinds).enumerate() {
+        let kind = match kind {
+            PointKind::Measured => "measured",
+            PointKind::Interpolated => "interpolated",
+            PointKind::Outlier => "outlier",
+            PointKind::Missing => "missing",
+        };
+        match bbox {
+            Some([x, y, w, h]) => writeln!(out, "{},{},{},{},{},{}", frame, x, y, w, h, kind)?,
+            None => writeln!(out, "{},,,,,{}", frame, kind)?,
+        }
+    }
+    out.flush()?;
+
+    let count = |kind| track.kinds.iter().filter(|&&k| k == kind).count();
+    eprintln!(
+        "{} frames: {} measured, {} interpolated, {} outliers replaced",
+        track.kinds.len(),
+        count(PointKind::Measured),
+        count(PointKind::Interpolated),
+        count(PointKind::Outlier)
+    );
+    Ok(())
+}
+
+/// One entry per data line; lost frames and zero-sized boxes are `None`
+fn parse(text: &str) -> Vec<Option<[i32; 4]>> {
+    text.lines()
+        .filter(|line| !line.starts_with("frame"))
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let (success, values) = match fields.len() {
+                n if n >= 10 => (fields[2] == "true", &fields[6..10]),
+                _ => (true, &fields[..]),
+            };
+            let values: Vec<i32> = values.iter().filter_map(|v| v.parse().ok()).collect();
+            match values[..] {
+                [x, y, w, h] if success && w > 0 && h > 0 => Some([x, y, w, h]),
+                _ => None,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,271 @@
+//! Machine control over stdin/stdout (JSON-RPC 2.0, one message per line)
+//!
+//! Requests on stdin:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","id":1,"method":"init","params":{"bbox":[x,y,w,h]}}
+//! {"jsonrpc":"2.0","id":2,"method":"set_threshold","params":{"value":0.3}}
+//! {"jsonrpc":"2.0","id":3,"method":"refresh_template","params":{"bbox":[x,y,w,h]}}
+//! {"jsonrpc":"2.0","id":4,"method":"reload"}
+//! {"jsonrpc":"2.0","id":5,"method":"reset"}
+//! {"jsonrpc":"2.0","id":6,"method":"resource_usage"}
+//! {"jsonrpc":"2.0","id":7,"method":"quit"}
+//! ```
+//!
+//! `reload` re-reads the `--settings` file now; it is also applied whenever
+//! the file changes. `resource_usage` returns approximate memory in bytes:
+//! `model`, `npu` (null on the CPU backend), `buffers` and `total`.
+//!
+//! Each request gets a response with the same id. While initialized, every
+//! frame is reported as a `result` notification:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","method":"result","params":{"frame":12,"timestamp":4.032,...}}
+//! ```
+//!
+//! `timestamp` is the capture time in seconds on the camera clock (null when
+//! the backend reports none).
+//!
+//! Nothing else is written to stdout in this mode; diagnostics go to stderr.
+
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use opencv::{core, imgproc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use vit_tracker::reload::{FileWatcher, RuntimeSettings};
+use vit_tracker::stream::ReconnectingSource;
+use vit_tracker::{BBox, TrackingResult, VitTrack};
+
+use crate::capture::CameraSource;
+use crate::{mat_to_array3, reload_settings};
+
+pub(crate) const PARSE_ERROR: i32 = -32700;
+pub(crate) const INVALID_REQUEST: i32 = -32600;
+pub(crate) const METHOD_NOT_FOUND: i32 = -32601;
+pub(crate) const INVALID_PARAMS: i32 = -32602;
+
+#[derive(Deserialize)]
+pub(crate) struct Request {
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Deserialize)]
+struct InitParams {
+    bbox: [i32; 4],
+}
+
+#[derive(Deserialize)]
+struct ThresholdParams {
+    /// None restores the configured threshold
+    value: Option<f32>,
+}
+
+/// Parsed request method
+enum Command {
+    Init(BBox),
+    /// Replace the template, keeping the track
+    RefreshTemplate(BBox),
+    SetThreshold(Option<f32>),
+    /// Re-read the settings file
+    Reload,
+    Reset,
+    ResourceUsage,
+    Quit,
+}
+
+fn parse(line: &str) -> Result<(Value, Command), Value> {
+    let request: Request = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) if e.is_syntax() || e.is_eof() => {
+            return Err(error(Value::Null, PARSE_ERROR, &e.to_string()));
+        }
+        Err(e) => return Err(error(Value::Null, INVALID_REQUEST, &e.to_string())),
+    };
+    let id = request.id;
+    let invalid = |e: serde_json::Error| error(id.clone(), INVALID_PARAMS, &e.to_string());
+
+    let command = match request.method.as_str() {
+        "init" => {
+            let params: InitParams = serde_json::from_value(request.params).map_err(invalid)?;
+            Command::Init(BBox::from_array(&params.bbox))
+        }
+        "refresh_template" => {
+            let params: InitParams = serde_json::from_value(request.params).map_err(invalid)?;
+            Command::RefreshTemplate(BBox::from_array(&params.bbox))
+        }
+        "set_threshold" => {
+            let params: ThresholdParams =
+                serde_json::from_value(request.params).map_err(invalid)?;
+            Command::SetThreshold(params.value)
+        }
+        "reload" => Command::Reload,
+        "reset" => Command::Reset,
+        "resource_usage" => Command::ResourceUsage,
+        "quit" => Command::Quit,
+        method => {
+            let message = format!("unknown method {}", method);
+            return Err(error(id, METHOD_NOT_FOUND, &message));
+        }
+    };
+    Ok((id, command))
+}
+
+pub(crate) fn error(id: Value, code: i32, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+pub(crate) fn response(id: Value, result: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "result": result})
+}
+
+pub(crate) fn notification(frame: u64, result: &TrackingResult) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "result",
+        "params": {
+            "frame": frame,
+            "timestamp": result.timestamp.map(|t| t.as_secs_f64()),
+            "success": result.success,
+            "bbox": result.bbox,
+            "bbox_raw": result.bbox_raw,
+            "bbox_converted": result.bbox_converted,
+            "score": result.score,
+            "score_smoothed": result.score_smoothed,
+            "peak_ratio": result.peak_ratio,
+            "apce": result.apce,
+            "occlusion": result.occlusion,
+            "occluded": result.occluded,
+            "coasting": result.coasting,
+            "cached": result.cached,
+            "geo": result.geo.map(|g| [g.latitude, g.longitude, g.altitude as f64]),
+            "track": result
+                .tag
+                .map(|t| json!({"id": t.id, "class_id": t.class_id, "color": t.color()})),
+        }
+    })
+}
+
+pub(crate) fn send(out: &mut impl Write, message: &Value) -> io::Result<()> {
+    writeln!(out, "{}", message)?;
+    out.flush()
+}
+
+/// Lines from stdin, read on a separate thread so frames keep flowing
+fn stdin_lines() -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Run the capture loop under stdin/stdout control until `quit` or end of input
+///
+/// Camera drops are handled by `source`; its events go to stderr.
+pub fn run(
+    tracker: &mut VitTrack,
+    source: &mut ReconnectingSource<CameraSource>,
+    mut settings: Option<&mut FileWatcher>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let commands = stdin_lines();
+    let mut out = io::stdout().lock();
+    let mut rgb_frame = core::Mat::default();
+    let mut index: u64 = 0;
+
+    loop {
+        let (frame, timestamp) = source.next_frame()?;
+        imgproc::cvt_color(&frame, &mut rgb_frame, imgproc::COLOR_BGR2RGB, 0)?;
+        let image = mat_to_array3(&rgb_frame)?;
+
+        // Apply all pending commands before tracking this frame
+        loop {
+            let line = match commands.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => break,
+                // Supervisor closed stdin
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (id, command) = match parse(&line) {
+                Ok(parsed) => parsed,
+                Err(message) => {
+                    send(&mut out, &message)?;
+                    continue;
+                }
+            };
+            let result = match command {
+                Command::Init(bbox) => {
+                    let quality = tracker.init(&image, bbox);
+                    json!({"quality": quality.score, "frame": index})
+                }
+                Command::RefreshTemplate(bbox) => {
+                    let quality = tracker.refresh_template(&image, bbox);
+                    json!({"quality": quality.score, "frame": index})
+                }
+                Command::SetThreshold(value) => {
+                    tracker.set_score_threshold(value);
+                    json!({"threshold": tracker.score_threshold()})
+                }
+                Command::Reload => {
+                    let Some(watcher) = settings.as_deref() else {
+                        send(&mut out, &error(id, INVALID_REQUEST, "no --settings file"))?;
+                        continue;
+                    };
+                    match RuntimeSettings::load(watcher.path()) {
+                        Ok(loaded) => {
+                            tracker.apply_settings(&loaded);
+                            json!({"threshold": tracker.score_threshold()})
+                        }
+                        Err(e) => {
+                            send(&mut out, &error(id, INVALID_PARAMS, &e.to_string()))?;
+                            continue;
+                        }
+                    }
+                }
+                Command::Reset => {
+                    tracker.reset();
+                    Value::Bool(true)
+                }
+                Command::ResourceUsage => {
+                    let usage = tracker.resource_usage();
+                    json!({
+                        "model": usage.model_bytes,
+                        "npu": usage.npu_bytes,
+                        "buffers": usage.buffer_bytes,
+                        "total": usage.total(),
+                    })
+                }
+                Command::Quit => {
+                    send(&mut out, &response(id, Value::Bool(true)))?;
+                    return Ok(());
+                }
+            };
+            send(&mut out, &response(id, result))?;
+        }
+
+        if let Some(watcher) = settings.as_deref_mut() {
+            reload_settings(watcher, tracker);
+        }
+
+        if tracker.is_initialized() {
+            tracker.set_frame_timestamp(timestamp);
+            let result = tracker.update(&image)?;
+            send(&mut out, &notification(index, &result))?;
+        }
+        index += 1;
+    }
+}
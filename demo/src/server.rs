@@ -0,0 +1,327 @@
+//! Multi-stream tracking server (`server <config.json>`)
+//!
+//! Tracks one target per stream, each stream on its own thread with its own
+//! RKNN context, so the runtime spreads the streams' inferences over the NPU
+//! cores instead of queueing them on one.
+//!
+//! ```text
+//! {
+//!   "model": "models/object_tracking_vittrack_2023sep.rknn",
+//!   "mqtt": {"address": "10.0.0.2:1883", "topic": "tracker"},
+//!   "streams": [
+//!     {"name": "gate", "url": "rtsp://10.0.0.5/main", "init": "manual"},
+//!     {"name": "yard", "url": "rtsp://10.0.0.6/main", "init": "motion"}
+//!   ]
+//! }
+//! ```
+//!
+//! `manual` streams wait for an `init` request. `motion` streams start on the
+//! largest moving object (`MotionProposals`, the stand-in for a detector) and
+//! go back to it after `lost_frames` frames without the target.
+//!
+//! Control is JSON-RPC 2.0 on stdin as in `--rpc`, with a `stream` param:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","id":1,"method":"init","params":{"stream":"gate","bbox":[x,y,w,h]}}
+//! {"jsonrpc":"2.0","id":2,"method":"reset","params":{"stream":"gate"}}
+//! {"jsonrpc":"2.0","id":3,"method":"quit"}
+//! ```
+//!
+//! Every tracked frame is a `result` notification on stdout carrying the
+//! stream name; diagnostics go to stderr. With `mqtt` the notifications are
+//! also published to `<topic>/<stream>`.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use opencv::{core, imgproc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use vit_tracker::proposal::{MotionProposalConfig, MotionProposals};
+use vit_tracker::stream::{ReconnectConfig, ReconnectingSource, StreamEvent};
+use vit_tracker::tracker::VitTrackConfig;
+use vit_tracker::{BBox, VitTrack, VitTrackModel};
+
+use crate::capture::UrlSource;
+use crate::mat_to_array3;
+use crate::mqtt::MqttSink;
+use crate::rpc::{self, Request, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR};
+
+#[derive(Deserialize)]
+struct ServerConfig {
+    model: PathBuf,
+    #[serde(default)]
+    mqtt: Option<MqttSpec>,
+    streams: Vec<StreamSpec>,
+}
+
+#[derive(Deserialize)]
+struct MqttSpec {
+    /// Broker as host:port
+    address: String,
+    /// Results go to `<topic>/<stream>`
+    topic: String,
+    #[serde(default = "default_client_id")]
+    client_id: String,
+}
+
+fn default_client_id() -> String {
+    "vit_tracker".into()
+}
+
+#[derive(Deserialize)]
+struct StreamSpec {
+    name: String,
+    url: String,
+    #[serde(default)]
+    init: InitPolicy,
+    /// Frames without the target before a `motion` stream re-initializes
+    #[serde(default = "default_lost_frames")]
+    lost_frames: u32,
+}
+
+fn default_lost_frames() -> u32 {
+    30
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum InitPolicy {
+    /// Wait for an `init` request
+    #[default]
+    Manual,
+    /// Start on the largest moving object
+    Motion,
+}
+
+#[derive(Deserialize)]
+struct StreamParams {
+    stream: String,
+    #[serde(default)]
+    bbox: Option<[i32; 4]>,
+}
+
+/// Request forwarded to a stream thread
+enum StreamCommand {
+    Init(Value, BBox),
+    Reset(Value),
+    Quit,
+}
+
+/// Parsed request
+enum Command {
+    Stream(Value, String, StreamCommand),
+    Quit(Value),
+}
+
+/// Run the server until `quit` or end of input
+pub fn run(config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let config: ServerConfig = serde_json::from_str(&std::fs::read_to_string(config_path)?)?;
+
+    let (out_tx, out_rx) = mpsc::channel::<Value>();
+    let mqtt = config.mqtt;
+    let writer = thread::spawn(move || -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        let mut publisher = mqtt.map(ResultPublisher::new);
+        for message in out_rx {
+            rpc::send(&mut out, &message)?;
+            if let Some(publisher) = &mut publisher {
+                publisher.publish(&message);
+            }
+        }
+        Ok(())
+    });
+
+    let mut streams = HashMap::new();
+    let mut handles = Vec::new();
+    for spec in config.streams {
+        // A context per stream: the runtime places each on an idle NPU core
+        let model = Arc::new(VitTrackModel::load(&config.model, VitTrackConfig::default())?);
+        eprintln!("{}: model {} on {:?}", spec.name, config.model.display(), model.device());
+        let (tx, rx) = mpsc::channel();
+        streams.insert(spec.name.clone(), tx);
+        let out = out_tx.clone();
+        handles.push(thread::spawn(move || {
+            let name = spec.name.clone();
+            if let Err(e) = run_stream(spec, model, rx, out) {
+                eprintln!("{}: stopped: {}", name, e);
+            }
+        }));
+    }
+
+    let mut quit_id = Value::Null;
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse(&line) {
+            Ok(Command::Quit(id)) => {
+                quit_id = id;
+                break;
+            }
+            Ok(Command::Stream(id, name, command)) => match streams.get(&name) {
+                Some(stream) if stream.send(command).is_ok() => {}
+                _ => {
+                    let message = format!("stream {} is not running", name);
+                    out_tx.send(rpc::error(id, INVALID_PARAMS, &message))?;
+                }
+            },
+            Err(message) => out_tx.send(message)?,
+        }
+    }
+
+    for stream in streams.values() {
+        let _ = stream.send(StreamCommand::Quit);
+    }
+    for handle in handles {
+        let _ = handle.join();
+    }
+    out_tx.send(rpc::response(quit_id, Value::Bool(true)))?;
+    drop(out_tx);
+    writer.join().map_err(|_| "writer thread panicked")??;
+    Ok(())
+}
+
+/// Result notifications on MQTT; a lost broker is retried every few seconds
+struct ResultPublisher {
+    spec: MqttSpec,
+    sink: Option<MqttSink>,
+    retry_at: Instant,
+}
+
+impl ResultPublisher {
+    const RETRY: Duration = Duration::from_secs(5);
+
+    fn new(spec: MqttSpec) -> Self {
+        Self {
+            spec,
+            sink: None,
+            retry_at: Instant::now(),
+        }
+    }
+
+    /// Publish `message` if it is a stream result; responses are skipped
+    fn publish(&mut self, message: &Value) {
+        let Some(stream) = message["params"]["stream"].as_str() else {
+            return;
+        };
+        if self.sink.is_none() && Instant::now() >= self.retry_at {
+            match MqttSink::connect(&self.spec.address, &self.spec.client_id) {
+                Ok(sink) => self.sink = Some(sink),
+                Err(e) => self.fail(&e),
+            }
+        }
+        let Some(sink) = &mut self.sink else {
+            return;
+        };
+        let topic = format!("{}/{}", self.spec.topic, stream);
+        if let Err(e) = sink.publish(&topic, message.to_string().as_bytes()) {
+            self.sink = None;
+            self.fail(&e);
+        }
+    }
+
+    fn fail(&mut self, error: &io::Error) {
+        eprintln!("MQTT {}: {}", self.spec.address, error);
+        self.retry_at = Instant::now() + Self::RETRY;
+    }
+}
+
+fn parse(line: &str) -> Result<Command, Value> {
+    let request: Request = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) if e.is_syntax() || e.is_eof() => {
+            return Err(rpc::error(Value::Null, PARSE_ERROR, &e.to_string()));
+        }
+        Err(e) => return Err(rpc::error(Value::Null, INVALID_REQUEST, &e.to_string())),
+    };
+    let id = request.id;
+    if request.method == "quit" {
+        return Ok(Command::Quit(id));
+    }
+
+    let params: StreamParams = serde_json::from_value(request.params)
+        .map_err(|e| rpc::error(id.clone(), INVALID_PARAMS, &e.to_string()))?;
+    let command = match (request.method.as_str(), params.bbox) {
+        ("init", Some(bbox)) => StreamCommand::Init(id.clone(), BBox::from_array(&bbox)),
+        ("init", None) => return Err(rpc::error(id, INVALID_PARAMS, "init needs a bbox")),
+        ("reset", _) => StreamCommand::Reset(id.clone()),
+        (method, _) => {
+            let message = format!("unknown method {}", method);
+            return Err(rpc::error(id, METHOD_NOT_FOUND, &message));
+        }
+    };
+    Ok(Command::Stream(id, params.stream, command))
+}
+
+/// Capture and track one stream until `Quit` or a fatal capture error
+fn run_stream(
+    spec: StreamSpec,
+    model: Arc<VitTrackModel>,
+    commands: Receiver<StreamCommand>,
+    out: Sender<Value>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tracker = VitTrack::with_model(model);
+    let stream = UrlSource::open(&spec.url)?;
+    let mut source = ReconnectingSource::new(stream, ReconnectConfig::default());
+    let name = spec.name.clone();
+    source.add_sink(move |event: &StreamEvent| eprintln!("{}: {}", name, event));
+    let mut detector = (spec.init == InitPolicy::Motion)
+        .then(|| MotionProposals::new(MotionProposalConfig::default()));
+    eprintln!("{}: streaming {}", spec.name, spec.url);
+
+    let mut rgb_frame = core::Mat::default();
+    let mut index: u64 = 0;
+    let mut lost_frames = 0;
+    loop {
+        let (frame, timestamp) = source.next_frame()?;
+        imgproc::cvt_color(&frame, &mut rgb_frame, imgproc::COLOR_BGR2RGB, 0)?;
+        let image = mat_to_array3(&rgb_frame)?;
+
+        loop {
+            let (id, result) = match commands.try_recv() {
+                Ok(StreamCommand::Init(id, bbox)) => {
+                    let quality = tracker.init(&image, bbox);
+                    (id, json!({"quality": quality.score, "frame": index}))
+                }
+                Ok(StreamCommand::Reset(id)) => {
+                    tracker.reset();
+                    (id, Value::Bool(true))
+                }
+                Ok(StreamCommand::Quit) | Err(TryRecvError::Disconnected) => return Ok(()),
+                Err(TryRecvError::Empty) => break,
+            };
+            out.send(rpc::response(id, result))?;
+        }
+
+        // The background model learns on every frame, tracked or not
+        let proposals = detector.as_mut().map(|d| d.update(&image)).unwrap_or_default();
+        if !tracker.is_initialized()
+            && let Some(proposal) = proposals.first()
+        {
+            tracker.init(&image, *proposal);
+            lost_frames = 0;
+            eprintln!("{}: initialized on motion {:?}", spec.name, proposal.to_array());
+        }
+
+        if tracker.is_initialized() {
+            tracker.set_frame_timestamp(timestamp);
+            let result = tracker.update(&image)?;
+            lost_frames = if result.success { 0 } else { lost_frames + 1 };
+            let mut message = rpc::notification(index, &result);
+            message["params"]["stream"] = json!(spec.name);
+            out.send(message)?;
+
+            if detector.is_some() && lost_frames >= spec.lost_frames {
+                tracker.reset();
+            }
+        }
+        index += 1;
+    }
+}
@@ -0,0 +1,93 @@
+//! Combine a detector with the tracker
+//!
+//! Usage: detector_fusion <model.rknn> <frames_dir>
+//!
+//! The background-model motion proposals stand in for a detector: the first
+//! proposal initializes the tracker, and while the tracker is lost the
+//! proposal overlapping the last known box best re-initializes it. Replace
+//! `MotionProposals` with any detector producing `BBox`es.
+
+use ndarray::Array3;
+use std::path::{Path, PathBuf};
+use vit_tracker::proposal::{MotionProposalConfig, MotionProposals};
+use vit_tracker::VitTrack;
+
+/// Frames without success before a detection may replace the track
+const LOST_FRAMES: usize = 5;
+
+fn load_rgb(path: &Path) -> Result<Array3<u8>, Box<dyn std::error::Error>> {
+    let image = image::open(path)?.to_rgb8();
+    let (width, height) = image.dimensions();
+    Ok(Array3::from_shape_vec(
+        (height as usize, width as usize, 3),
+        image.into_raw(),
+    )?)
+}
+
+fn iou(a: &[i32; 4], b: &[i32; 4]) -> f32 {
+    let x1 = a[0].max(b[0]);
+    let y1 = a[1].max(b[1]);
+    let x2 = (a[0] + a[2]).min(b[0] + b[2]);
+    let y2 = (a[1] + a[3]).min(b[1] + b[3]);
+    let inter = ((x2 - x1).max(0) * (y2 - y1).max(0)) as f32;
+    let union = (a[2] * a[3] + b[2] * b[3]) as f32 - inter;
+    if union > 0.0 { inter / union } else { 0.0 }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        return Err("Usage: detector_fusion <model.rknn> <frames_dir>".into());
+    }
+
+    let mut frames: Vec<PathBuf> = std::fs::read_dir(&args[2])?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == "png" || e == "jpg"))
+        .collect();
+    frames.sort();
+
+    let mut detector = MotionProposals::new(MotionProposalConfig::default());
+    let mut tracker = VitTrack::new(&args[1])?;
+    let mut last_box: Option<[i32; 4]> = None;
+    let mut lost_frames = 0;
+
+    for path in &frames {
+        let image = load_rgb(path)?;
+        let view = image.view();
+        let detections = detector.update(&view);
+
+        if !tracker.is_initialized() {
+            if let Some(detection) = detections.first() {
+                tracker.init(&view, *detection);
+                last_box = Some(detection.to_array());
+                println!("{}: initialized from detection {:?}", path.display(), detection);
+            }
+            continue;
+        }
+
+        let result = tracker.update(&view)?;
+        if result.success {
+            lost_frames = 0;
+            last_box = Some(result.bbox);
+            println!("{}: tracking {:?} ({:.3})", path.display(), result.bbox, result.score);
+            continue;
+        }
+
+        lost_frames += 1;
+        let reference = last_box.unwrap_or(result.bbox);
+        let best = detections
+            .iter()
+            .map(|d| (d, iou(&d.to_array(), &reference)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((detection, overlap)) if lost_frames >= LOST_FRAMES || overlap > 0.3 => {
+                tracker.init(&view, *detection);
+                lost_frames = 0;
+                println!("{}: re-initialized from detection {:?}", path.display(), detection);
+            }
+            _ => println!("{}: lost", path.display()),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,227 @@
+//! Evaluate on a LaSOT/GOT-10k style dataset with parallel workers
+//!
+//! Usage: eval <model.rknn> <dataset_dir> <results_dir> [workers]
+//!
+//! Every directory under `dataset_dir` holding a `groundtruth.txt` (x,y,w,h per
+//! line) and its frames, directly or in `img/`, is one sequence. Each worker
+//! loads its own model context, so with one worker per NPU core (3 on RK3588)
+//! sequences run concurrently while the rest wait in the queue.
+//!
+//! Results are written per sequence as `<results_dir>/<name>.txt` in the same
+//! format as the ground truth. Sequences with an existing result are skipped,
+//! so an interrupted run resumes where it stopped. The tracker runs in
+//! deterministic mode, so repeated runs write identical result files.
+
+use ndarray::Array3;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use vit_tracker::tracker::VitTrackConfig;
+use vit_tracker::{BBox, VitTrack};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+struct Sequence {
+    name: String,
+    frames: Vec<PathBuf>,
+    groundtruth: Vec<[i32; 4]>,
+}
+
+fn load_rgb(path: &Path) -> Result<Array3<u8>, Error> {
+    let image = image::open(path)?.to_rgb8();
+    let (width, height) = image.dimensions();
+    Ok(Array3::from_shape_vec(
+        (height as usize, width as usize, 3),
+        image.into_raw(),
+    )?)
+}
+
+fn parse_boxes(text: &str) -> Vec<[i32; 4]> {
+    text.lines()
+        .filter_map(|line| {
+            let values: Vec<f32> = line
+                .split([',', ' ', '\t'])
+                .filter_map(|v| v.trim().parse().ok())
+                .collect();
+            match values[..] {
+                [x, y, w, h] => Some([x, y, w, h].map(|v| v.round() as i32)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn frame_paths(dir: &Path) -> Vec<PathBuf> {
+    let mut frames: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("png" | "jpg" | "jpeg")
+            )
+        })
+        .collect();
+    frames.sort();
+    frames
+}
+
+/// Sequences under `dir`, named by their path relative to the dataset root
+fn find_sequences(root: &Path, dir: &Path, sequences: &mut Vec<Sequence>) -> Result<(), Error> {
+    let groundtruth = dir.join("groundtruth.txt");
+    if groundtruth.is_file() {
+        let img = dir.join("img");
+        let frames = frame_paths(if img.is_dir() { &img } else { dir });
+        let name = dir.strip_prefix(root)?.to_string_lossy().replace(['/', '\\'], "_");
+        sequences.push(Sequence {
+            name,
+            frames,
+            groundtruth: parse_boxes(&fs::read_to_string(groundtruth)?),
+        });
+        return Ok(());
+    }
+
+    let mut children: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    children.sort();
+    for child in children {
+        find_sequences(root, &child, sequences)?;
+    }
+    Ok(())
+}
+
+fn run_sequence(tracker: &mut VitTrack, sequence: &Sequence) -> Result<Vec<[i32; 4]>, Error> {
+    let (Some(first), Some(init)) = (sequence.frames.first(), sequence.groundtruth.first()) else {
+        return Err(format!("{}: no frames or ground truth", sequence.name).into());
+    };
+    tracker.init(&load_rgb(first)?.view(), BBox::from_array(init));
+
+    let mut boxes = vec![*init];
+    for path in &sequence.frames[1..] {
+        let result = tracker.update(&load_rgb(path)?.view())?;
+        boxes.push(result.bbox);
+    }
+    Ok(boxes)
+}
+
+fn write_boxes(path: &Path, boxes: &[[i32; 4]]) -> Result<(), Error> {
+    // Write then rename, so an interrupted run never leaves a partial result
+    let partial = path.with_extension("partial");
+    let mut file = fs::File::create(&partial)?;
+    for [x, y, w, h] in boxes {
+        writeln!(file, "{},{},{},{}", x, y, w, h)?;
+    }
+    fs::rename(partial, path)?;
+    Ok(())
+}
+
+fn iou(a: &[i32; 4], b: &[i32; 4]) -> f32 {
+    let x1 = a[0].max(b[0]);
+    let y1 = a[1].max(b[1]);
+    let x2 = (a[0] + a[2]).min(b[0] + b[2]);
+    let y2 = (a[1] + a[3]).min(b[1] + b[3]);
+    let inter = ((x2 - x1).max(0) * (y2 - y1).max(0)) as f32;
+    let union = (a[2] * a[3] + b[2] * b[3]) as f32 - inter;
+    if union > 0.0 { inter / union } else { 0.0 }
+}
+
+fn print_progress(done: usize, total: usize, current: &str) {
+    const WIDTH: usize = 30;
+    let filled = WIDTH * done / total.max(1);
+    eprint!(
+        "\r[{}{}] {}/{} {:<40.40}",
+        "#".repeat(filled),
+        " ".repeat(WIDTH - filled),
+        done,
+        total,
+        current
+    );
+}
+
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 4 {
+        return Err("Usage: eval <model.rknn> <dataset_dir> <results_dir> [workers]".into());
+    }
+    let workers: usize = args.get(4).and_then(|v| v.parse().ok()).unwrap_or(3).max(1);
+    let dataset = Path::new(&args[2]);
+    let results = Path::new(&args[3]);
+    fs::create_dir_all(results)?;
+
+    let mut sequences = Vec::new();
+    find_sequences(dataset, dataset, &mut sequences)?;
+    let result_path = |sequence: &Sequence| results.join(format!("{}.txt", sequence.name));
+    let pending: Vec<&Sequence> = sequences.iter().filter(|s| !result_path(s).exists()).collect();
+    eprintln!(
+        "{} sequences, {} already done, {} workers",
+        sequences.len(),
+        sequences.len() - pending.len(),
+        workers
+    );
+
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(sequences.len() - pending.len());
+    let failures = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..workers.min(pending.len()) {
+            scope.spawn(|| {
+                let config = VitTrackConfig {
+                    deterministic: true,
+                    ..VitTrackConfig::default()
+                };
+                let mut tracker = match VitTrack::with_config(&args[1], config) {
+                    Ok(tracker) => tracker,
+                    Err(e) => {
+                        failures.lock().unwrap().push(format!("model: {}", e));
+                        return;
+                    }
+                };
+                while let Some(sequence) = pending.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let outcome = run_sequence(&mut tracker, sequence)
+                        .and_then(|boxes| write_boxes(&result_path(sequence), &boxes));
+                    if let Err(e) = outcome {
+                        failures.lock().unwrap().push(format!("{}: {}", sequence.name, e));
+                    }
+                    let finished = done.fetch_add(1, Ordering::Relaxed) + 1;
+                    print_progress(finished, sequences.len(), &sequence.name);
+                }
+            });
+        }
+    });
+    eprintln!();
+    for failure in failures.into_inner().unwrap() {
+        eprintln!("failed: {}", failure);
+    }
+
+    // Summary over every finished sequence, including earlier runs
+    let (mut overlaps, mut evaluated) = (Vec::new(), 0);
+    for sequence in &sequences {
+        let Ok(text) = fs::read_to_string(result_path(sequence)) else {
+            continue;
+        };
+        evaluated += 1;
+        let boxes = parse_boxes(&text);
+        overlaps.extend(boxes.iter().zip(&sequence.groundtruth).map(|(a, b)| iou(a, b)));
+    }
+    let n = overlaps.len().max(1) as f32;
+    let auc = (0..=20)
+        .map(|i| overlaps.iter().filter(|&&o| o > i as f32 * 0.05).count() as f32 / n)
+        .sum::<f32>()
+        / 21.0;
+    println!(
+        "{} sequences, {} frames: mean IoU {:.3}, success@0.5 {:.3}, AUC {:.3}",
+        evaluated,
+        overlaps.len(),
+        overlaps.iter().sum::<f32>() / n,
+        overlaps.iter().filter(|&&o| o > 0.5).count() as f32 / n,
+        auc
+    );
+
+    Ok(())
+}
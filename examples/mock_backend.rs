@@ -0,0 +1,80 @@
+//! Run the pre/post-processing pipeline without an NPU
+//!
+//! Usage: mock_backend
+//!
+//! A synthetic target moves across generated frames and a mock inference
+//! function produces the three VitTrack outputs from the ground truth. This
+//! exercises cropping, tensor conversion and decoding on any machine, which is
+//! useful when developing off-target or testing a new backend against known
+//! outputs.
+
+use ndarray::Array3;
+use vit_tracker::postprocess::{hann2d, process_outputs};
+use vit_tracker::preprocess::{crop_resized, crop_size, BBox, InputTensor, InputType};
+
+const SEARCH_SIZE: usize = 256;
+const SCORE_SIZE: usize = 16;
+const SEARCH_FACTOR: u32 = 4;
+
+/// Frame with a bright square at `target` [x, y, w, h]
+fn render(target: &[i32; 4]) -> Array3<u8> {
+    Array3::from_shape_fn((480, 640, 3), |(y, x, _)| {
+        let [tx, ty, tw, th] = *target;
+        let inside = (tx..tx + tw).contains(&(x as i32)) && (ty..ty + th).contains(&(y as i32));
+        if inside { 230 } else { 40 }
+    })
+}
+
+/// Mock inference: outputs that point at the true target inside the search crop
+fn mock_inference(target: &[i32; 4], rect: &[i32; 4], crop: i32) -> [Vec<f32>; 3] {
+    let area = SCORE_SIZE * SCORE_SIZE;
+    let origin_x = rect[0] + (rect[2] - crop) / 2;
+    let origin_y = rect[1] + (rect[3] - crop) / 2;
+    let cx = (target[0] as f32 + target[2] as f32 / 2.0 - origin_x as f32) / crop as f32;
+    let cy = (target[1] as f32 + target[3] as f32 / 2.0 - origin_y as f32) / crop as f32;
+
+    let cell_x = ((cx * SCORE_SIZE as f32) as usize).min(SCORE_SIZE - 1);
+    let cell_y = ((cy * SCORE_SIZE as f32) as usize).min(SCORE_SIZE - 1);
+    let idx = cell_y * SCORE_SIZE + cell_x;
+
+    let mut conf = vec![0.0f32; area];
+    let mut size = vec![0.0f32; 2 * area];
+    let mut offset = vec![0.0f32; 2 * area];
+    conf[idx] = 0.9;
+    size[idx] = target[2] as f32 / crop as f32;
+    size[area + idx] = target[3] as f32 / crop as f32;
+    offset[idx] = cx * SCORE_SIZE as f32 - cell_x as f32;
+    offset[area + idx] = cy * SCORE_SIZE as f32 - cell_y as f32;
+
+    [conf, size, offset]
+}
+
+fn main() {
+    let hanning = hann2d(SCORE_SIZE, SCORE_SIZE);
+    let mut target = [200, 200, 40, 30];
+    let mut rect = target;
+
+    for frame_index in 0..20 {
+        target[0] += 6;
+        target[1] += 2;
+        let frame = render(&target);
+
+        // Same preprocessing as VitTrack::update
+        let bbox = BBox::from_array(&rect);
+        let (search, crop) = crop_resized(&frame.view(), &bbox, SEARCH_FACTOR, SEARCH_SIZE);
+        let tensor = InputTensor::from_image(&search, InputType::Float32);
+        assert_eq!(tensor.len(), SEARCH_SIZE * SEARCH_SIZE * 3);
+        assert_eq!(crop, crop_size(&bbox, SEARCH_FACTOR));
+
+        let [conf, size, offset] = mock_inference(&target, &rect, crop);
+        let result = process_outputs(&conf, &size, &offset, &hanning, &rect, crop, 0.25);
+        if result.success {
+            rect = result.bbox;
+        }
+
+        println!(
+            "frame {:2}: truth {:?} tracked {:?} score {:.2}",
+            frame_index, target, result.bbox, result.score
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! Track several objects with `MultiVitTrack`
+//!
+//! Usage: multi_object <model.rknn> <frames_dir> <x,y,w,h> [<x,y,w,h> ...]
+//!
+//! All targets share one loaded model; each only keeps its own state. The NPU
+//! runs them one after another, so the frame time grows linearly with the
+//! number of objects.
+
+use ndarray::Array3;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use vit_tracker::label::TrackLabel;
+use vit_tracker::tracker::{MultiVitTrack, VitTrackConfig};
+use vit_tracker::{BBox, VitTrackModel};
+
+fn load_rgb(path: &Path) -> Result<Array3<u8>, Box<dyn std::error::Error>> {
+    let image = image::open(path)?.to_rgb8();
+    let (width, height) = image.dimensions();
+    Ok(Array3::from_shape_vec(
+        (height as usize, width as usize, 3),
+        image.into_raw(),
+    )?)
+}
+
+fn parse_bbox(arg: &str) -> Option<BBox> {
+    let values: Vec<i32> = arg.split(',').filter_map(|v| v.trim().parse().ok()).collect();
+    match values[..] {
+        [x, y, w, h] => Some(BBox::new(x, y, w, h)),
+        _ => None,
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 4 {
+        return Err("Usage: multi_object <model.rknn> <frames_dir> <x,y,w,h> [...]".into());
+    }
+    let boxes = args[3..]
+        .iter()
+        .map(|arg| parse_bbox(arg).ok_or("Boxes must be x,y,w,h"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut frames: Vec<PathBuf> = std::fs::read_dir(&args[2])?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == "png" || e == "jpg"))
+        .collect();
+    frames.sort();
+    let Some((first, rest)) = frames.split_first() else {
+        return Err("No frames found".into());
+    };
+
+    let model = Arc::new(VitTrackModel::load(&args[1], VitTrackConfig::default())?);
+    let image = load_rgb(first)?;
+    let mut targets = MultiVitTrack::new(model);
+    for (index, bbox) in boxes.into_iter().enumerate() {
+        let id = index as u32 + 1;
+        targets.init_target(id, &image.view(), bbox);
+        if let Some(tracker) = targets.target_mut(id) {
+            tracker.set_label(Some(TrackLabel::new(id, format!("object {}", id))));
+        }
+    }
+
+    for path in rest {
+        let image = load_rgb(path)?;
+        print!("{}:", path.display());
+        for (id, result) in targets.update_all(&image.view()) {
+            let name = targets.target(id).and_then(|t| t.label()).map_or("", |l| l.name.as_str());
+            match result {
+                Ok(result) => {
+                    let state = if result.success { "ok" } else { "lost" };
+                    print!(" [{}] {} {:?}", name, state, result.bbox);
+                }
+                Err(e) => print!(" [{}] error: {}", name, e),
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
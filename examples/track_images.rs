@@ -0,0 +1,74 @@
+//! Track an object through a directory of image files
+//!
+//! Usage: track_images <model.rknn> <frames_dir> <x,y,w,h>
+//!
+//! Frames are processed in file-name order; the box is given for the first frame.
+
+use ndarray::Array3;
+use std::path::{Path, PathBuf};
+use vit_tracker::{BBox, VitTrack};
+
+/// Load an image file as an RGB HWC array
+fn load_rgb(path: &Path) -> Result<Array3<u8>, Box<dyn std::error::Error>> {
+    let image = image::open(path)?.to_rgb8();
+    let (width, height) = image.dimensions();
+    Ok(Array3::from_shape_vec(
+        (height as usize, width as usize, 3),
+        image.into_raw(),
+    )?)
+}
+
+fn parse_bbox(arg: &str) -> Option<BBox> {
+    let values: Vec<i32> = arg.split(',').filter_map(|v| v.trim().parse().ok()).collect();
+    match values[..] {
+        [x, y, w, h] => Some(BBox::new(x, y, w, h)),
+        _ => None,
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 4 {
+        return Err("Usage: track_images <model.rknn> <frames_dir> <x,y,w,h>".into());
+    }
+    let bbox = parse_bbox(&args[3]).ok_or("Box must be x,y,w,h")?;
+
+    let mut frames: Vec<PathBuf> = std::fs::read_dir(&args[2])?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("png" | "jpg" | "jpeg")
+            )
+        })
+        .collect();
+    frames.sort();
+    let Some((first, rest)) = frames.split_first() else {
+        return Err("No frames found".into());
+    };
+
+    let mut tracker = VitTrack::new(&args[1])?;
+    let image = load_rgb(first)?;
+    let quality = tracker.init(&image.view(), bbox);
+    if quality.is_poor() {
+        println!("Warning: poor template: {:?}", quality.warnings);
+    }
+
+    for path in rest {
+        let image = load_rgb(path)?;
+        let result = tracker.update(&image.view())?;
+        let [x, y, w, h] = result.bbox;
+        println!(
+            "{}: success={} score={:.3} bbox=[{}, {}, {}, {}]",
+            path.display(),
+            result.success,
+            result.score,
+            x,
+            y,
+            w,
+            h
+        );
+    }
+
+    Ok(())
+}
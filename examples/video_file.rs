@@ -0,0 +1,109 @@
+//! Track an object through a raw YUV4MPEG2 (.y4m) video
+//!
+//! Usage: video_file <model.rknn> <video.y4m> <x,y,w,h>
+//!
+//! Y4M is decoded in pure Rust (4:2:0 only). Convert other formats with
+//! `ffmpeg -i input.mp4 -pix_fmt yuv420p output.y4m`.
+
+use ndarray::Array3;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use vit_tracker::{BBox, VitTrack};
+
+/// Minimal 4:2:0 YUV4MPEG2 reader producing RGB frames
+struct Y4mReader<R: BufRead> {
+    reader: R,
+    width: usize,
+    height: usize,
+}
+
+impl<R: BufRead> Y4mReader<R> {
+    fn new(mut reader: R) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let mut params = header.split_whitespace();
+        if params.next() != Some("YUV4MPEG2") {
+            return Err("Not a YUV4MPEG2 stream".into());
+        }
+
+        let (mut width, mut height) = (0, 0);
+        for param in params {
+            match param.split_at(1) {
+                ("W", value) => width = value.parse()?,
+                ("H", value) => height = value.parse()?,
+                ("C", value) if !value.starts_with("420") => {
+                    return Err(format!("Unsupported chroma {}", value).into());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            reader,
+            width,
+            height,
+        })
+    }
+
+    /// Next frame as RGB HWC, None at end of stream
+    fn next_frame(&mut self) -> Result<Option<Array3<u8>>, Box<dyn std::error::Error>> {
+        let mut marker = String::new();
+        if self.reader.read_line(&mut marker)? == 0 {
+            return Ok(None);
+        }
+        if !marker.starts_with("FRAME") {
+            return Err("Missing FRAME marker".into());
+        }
+
+        let (w, h) = (self.width, self.height);
+        let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+        let mut yuv = vec![0u8; w * h + 2 * cw * ch];
+        self.reader.read_exact(&mut yuv)?;
+        let (y_plane, chroma) = yuv.split_at(w * h);
+        let (u_plane, v_plane) = chroma.split_at(cw * ch);
+
+        // BT.601 limited range
+        let frame = Array3::from_shape_fn((h, w, 3), |(y, x, c)| {
+            let luma = 1.164 * (y_plane[y * w + x] as f32 - 16.0);
+            let u = u_plane[(y / 2) * cw + x / 2] as f32 - 128.0;
+            let v = v_plane[(y / 2) * cw + x / 2] as f32 - 128.0;
+            let value = match c {
+                0 => luma + 1.596 * v,
+                1 => luma - 0.392 * u - 0.813 * v,
+                _ => luma + 2.017 * u,
+            };
+            value.clamp(0.0, 255.0) as u8
+        });
+
+        Ok(Some(frame))
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 4 {
+        return Err("Usage: video_file <model.rknn> <video.y4m> <x,y,w,h>".into());
+    }
+    let values: Vec<i32> = args[3].split(',').filter_map(|v| v.trim().parse().ok()).collect();
+    let [x, y, w, h] = values[..] else {
+        return Err("Box must be x,y,w,h".into());
+    };
+
+    let mut video = Y4mReader::new(BufReader::new(File::open(&args[2])?))?;
+    let mut tracker = VitTrack::new(&args[1])?;
+
+    let first = video.next_frame()?.ok_or("Empty video")?;
+    tracker.init(&first.view(), BBox::new(x, y, w, h));
+
+    let mut index = 1;
+    while let Some(frame) = video.next_frame()? {
+        let result = tracker.update(&frame.view())?;
+        println!(
+            "frame {}: success={} score={:.3} bbox={:?}",
+            index, result.success, result.score, result.bbox
+        );
+        index += 1;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+/// Degradation applied to meet the latency budget, in escalation order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Degradation {
+    #[default]
+    None,
+    /// Multi-scale search reduced to the nominal scale
+    SingleScale,
+    /// Frame downscaled before cropping the search region
+    DownscaledCrop,
+    /// Inference on the fast (lower-accuracy) model
+    FastModel,
+    /// Inference skipped on this frame, previous box reported
+    SkippedInference,
+    /// Inference failed or skipped; box from the CPU fallback tracker
+    Fallback,
+}
+
+impl Degradation {
+    fn next(self) -> Self {
+        match self {
+            Self::None => Self::SingleScale,
+            Self::SingleScale => Self::DownscaledCrop,
+            Self::DownscaledCrop => Self::FastModel,
+            Self::FastModel | Self::SkippedInference | Self::Fallback => Self::SkippedInference,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            Self::None | Self::SingleScale => Self::None,
+            Self::DownscaledCrop => Self::SingleScale,
+            Self::FastModel => Self::DownscaledCrop,
+            Self::SkippedInference | Self::Fallback => Self::FastModel,
+        }
+    }
+}
+
+/// Tracks per-frame latency against a budget and picks a degradation level
+#[derive(Debug, Clone)]
+pub struct LatencyBudget {
+    budget: Duration,
+    /// Exponential moving average of update latency in seconds
+    average: Option<f32>,
+    level: Degradation,
+    fast_model: bool,
+    skip_next: bool,
+}
+
+impl LatencyBudget {
+    /// Smoothing factor of the latency average
+    const ALPHA: f32 = 0.2;
+    /// Recover a level once the average falls below this fraction of the budget
+    const RECOVER_RATIO: f32 = 0.7;
+
+    pub fn new(budget: Duration, fast_model: bool) -> Self {
+        Self {
+            budget,
+            average: None,
+            level: Degradation::None,
+            fast_model,
+            skip_next: false,
+        }
+    }
+
+    /// Current degradation level
+    pub fn level(&self) -> Degradation {
+        self.level
+    }
+
+    /// Whether inference should be skipped on this frame
+    ///
+    /// At the highest level every other frame is skipped.
+    pub fn should_skip(&mut self) -> bool {
+        if self.level < Degradation::SkippedInference {
+            return false;
+        }
+        self.skip_next = !self.skip_next;
+        !self.skip_next
+    }
+
+    /// Record latency of a processed frame and adjust the level
+    pub fn record(&mut self, elapsed: Duration) {
+        let elapsed = elapsed.as_secs_f32();
+        let average = match self.average {
+            Some(avg) => avg + Self::ALPHA * (elapsed - avg),
+            None => elapsed,
+        };
+        self.average = Some(average);
+
+        let budget = self.budget.as_secs_f32();
+        if average > budget {
+            self.level = self.skip_unavailable(self.level.next(), Degradation::next);
+            // Restart averaging so the new level is judged on its own latency
+            self.average = None;
+        } else if average < budget * Self::RECOVER_RATIO && self.level != Degradation::None {
+            self.level = self.skip_unavailable(self.level.prev(), Degradation::prev);
+            self.average = None;
+        }
+    }
+
+    fn skip_unavailable(
+        &self,
+        level: Degradation,
+        step: fn(Degradation) -> Degradation,
+    ) -> Degradation {
+        if level == Degradation::FastModel && !self.fast_model {
+            step(level)
+        } else {
+            level
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escalation_and_recovery() {
+        let mut budget = LatencyBudget::new(Duration::from_millis(25), false);
+        budget.record(Duration::from_millis(40));
+        assert_eq!(budget.level(), Degradation::SingleScale);
+        budget.record(Duration::from_millis(40));
+        budget.record(Duration::from_millis(40));
+        // No fast model: jumps straight to skipping
+        assert_eq!(budget.level(), Degradation::SkippedInference);
+        assert!(!budget.should_skip());
+        assert!(budget.should_skip());
+
+        budget.record(Duration::from_millis(5));
+        assert_eq!(budget.level(), Degradation::DownscaledCrop);
+    }
+}
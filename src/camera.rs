@@ -0,0 +1,251 @@
+use ndarray::{Array3, ArrayView3};
+
+use crate::preprocess::{crop_size, BBox};
+
+/// Pinhole camera intrinsics with Brown-Conrady distortion
+#[derive(Debug, Clone, Copy)]
+pub struct CameraIntrinsics {
+    pub fx: f32,
+    pub fy: f32,
+    pub cx: f32,
+    pub cy: f32,
+    /// Distortion coefficients [k1, k2, p1, p2, k3] (OpenCV order)
+    pub distortion: [f32; 5],
+}
+
+/// Angular position and size of the target relative to the optical axis
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AngularTarget {
+    /// Horizontal angle in radians, positive to the right
+    pub azimuth: f32,
+    /// Vertical angle in radians, positive upwards
+    pub elevation: f32,
+    /// Angular width in radians
+    pub width: f32,
+    /// Angular height in radians
+    pub height: f32,
+}
+
+impl CameraIntrinsics {
+    pub fn new(fx: f32, fy: f32, cx: f32, cy: f32) -> Self {
+        Self {
+            fx,
+            fy,
+            cx,
+            cy,
+            distortion: [0.0; 5],
+        }
+    }
+
+    pub fn with_distortion(mut self, distortion: [f32; 5]) -> Self {
+        self.distortion = distortion;
+        self
+    }
+
+    /// Convert pixel coordinates to undistorted normalized image coordinates
+    pub fn undistort_point(&self, u: f32, v: f32) -> (f32, f32) {
+        let xd = (u - self.cx) / self.fx;
+        let yd = (v - self.cy) / self.fy;
+
+        let [k1, k2, p1, p2, k3] = self.distortion;
+        if self.distortion.iter().all(|&k| k == 0.0) {
+            return (xd, yd);
+        }
+
+        // Fixed-point iteration (same scheme as cv::undistortPoints)
+        let mut x = xd;
+        let mut y = yd;
+        for _ in 0..5 {
+            let r2 = x * x + y * y;
+            let radial = 1.0 + r2 * (k1 + r2 * (k2 + r2 * k3));
+            let dx = 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x);
+            let dy = p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y;
+            x = (xd - dx) / radial;
+            y = (yd - dy) / radial;
+        }
+
+        (x, y)
+    }
+
+    /// Whether any distortion coefficient is set
+    pub fn is_distorted(&self) -> bool {
+        self.distortion.iter().any(|&k| k != 0.0)
+    }
+
+    /// Convert normalized image coordinates to distorted pixel coordinates
+    pub fn distort_point(&self, x: f32, y: f32) -> (f32, f32) {
+        let [k1, k2, p1, p2, k3] = self.distortion;
+        let r2 = x * x + y * y;
+        let radial = 1.0 + r2 * (k1 + r2 * (k2 + r2 * k3));
+        let xd = x * radial + 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x);
+        let yd = y * radial + p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y;
+        (xd * self.fx + self.cx, yd * self.fy + self.cy)
+    }
+
+    /// Map a box [x, y, w, h] from frame pixels to ideal (undistorted) pixels
+    ///
+    /// Ideal pixels are the pixels of a distortion-free camera with the same
+    /// intrinsics. Size is measured between the edge midpoints.
+    pub fn undistort_rect(&self, rect: &[i32; 4]) -> [i32; 4] {
+        self.map_rect(rect, |u, v| {
+            let (x, y) = self.undistort_point(u, v);
+            (x * self.fx + self.cx, y * self.fy + self.cy)
+        })
+    }
+
+    /// Map a box [x, y, w, h] from ideal pixels back to frame pixels
+    pub fn distort_rect(&self, rect: &[i32; 4]) -> [i32; 4] {
+        self.map_rect(rect, |u, v| {
+            self.distort_point((u - self.cx) / self.fx, (v - self.cy) / self.fy)
+        })
+    }
+
+    fn map_rect(&self, rect: &[i32; 4], map: impl Fn(f32, f32) -> (f32, f32)) -> [i32; 4] {
+        let [x, y, w, h] = rect.map(|v| v as f32);
+        let (cx, cy) = map(x + w / 2.0, y + h / 2.0);
+        let (left, _) = map(x, y + h / 2.0);
+        let (right, _) = map(x + w, y + h / 2.0);
+        let (_, top) = map(x + w / 2.0, y);
+        let (_, bottom) = map(x + w / 2.0, y + h);
+
+        let (w, h) = ((right - left).max(1.0), (bottom - top).max(1.0));
+        [
+            (cx - w / 2.0).round() as i32,
+            (cy - h / 2.0).round() as i32,
+            w.round() as i32,
+            h.round() as i32,
+        ]
+    }
+
+    /// Crop around a box given in ideal pixels, undistorting only the crop
+    ///
+    /// Same layout as `crop_resized`, but every output pixel is sampled
+    /// through the lens model, so templates at the frame edges are not skewed.
+    ///
+    /// # Returns
+    /// * Cropped image (output_size x output_size x 3)
+    /// * Crop size in ideal pixels
+    pub fn crop_undistorted(
+        &self,
+        image: &ArrayView3<u8>,
+        bbox: &BBox,
+        factor: u32,
+        output_size: usize,
+    ) -> (Array3<u8>, i32) {
+        let (img_h, img_w, _) = image.dim();
+        let crop_sz = crop_size(bbox, factor);
+        let x1 = (bbox.x + (bbox.width - crop_sz) / 2) as f32;
+        let y1 = (bbox.y + (bbox.height - crop_sz) / 2) as f32;
+        let scale = crop_sz as f32 / output_size as f32;
+
+        // Source position of each output pixel, shared by the channels
+        let mut source = Vec::with_capacity(output_size * output_size);
+        for y in 0..output_size {
+            for x in 0..output_size {
+                let u = x1 + x as f32 * scale;
+                let v = y1 + y as f32 * scale;
+                source.push(self.distort_point((u - self.cx) / self.fx, (v - self.cy) / self.fy));
+            }
+        }
+
+        // Bilinear sampling; outside the frame is zero padding
+        let sample = |y: i32, x: i32, c: usize| -> f32 {
+            if y < 0 || x < 0 || y >= img_h as i32 || x >= img_w as i32 {
+                return 0.0;
+            }
+            image[[y as usize, x as usize, c]] as f32
+        };
+        let crop = Array3::from_shape_fn((output_size, output_size, 3), |(y, x, c)| {
+            let (src_x, src_y) = source[y * output_size + x];
+            let (x0, y0) = (src_x.floor(), src_y.floor());
+            let (dx, dy) = (src_x - x0, src_y - y0);
+            let (x0, y0) = (x0 as i32, y0 as i32);
+
+            let value = sample(y0, x0, c) * (1.0 - dx) * (1.0 - dy)
+                + sample(y0, x0 + 1, c) * dx * (1.0 - dy)
+                + sample(y0 + 1, x0, c) * (1.0 - dx) * dy
+                + sample(y0 + 1, x0 + 1, c) * dx * dy;
+            value.round().clamp(0.0, 255.0) as u8
+        });
+
+        (crop, crop_sz)
+    }
+
+    /// Compute angular offset and size of a bounding box [x, y, w, h]
+    pub fn angular_target(&self, bbox: &[i32; 4]) -> AngularTarget {
+        let [x, y, w, h] = bbox.map(|v| v as f32);
+
+        let (cx, cy) = self.undistort_point(x + w / 2.0, y + h / 2.0);
+        let (left, top) = self.undistort_point(x, y);
+        let (right, bottom) = self.undistort_point(x + w, y + h);
+
+        AngularTarget {
+            azimuth: cx.atan(),
+            elevation: -cy.atan(),
+            width: right.atan() - left.atan(),
+            height: bottom.atan() - top.atan(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_center_is_on_axis() {
+        let camera = CameraIntrinsics::new(1000.0, 1000.0, 960.0, 540.0);
+        let angles = camera.angular_target(&[910, 490, 100, 100]);
+        assert!(angles.azimuth.abs() < 1e-6);
+        assert!(angles.elevation.abs() < 1e-6);
+        assert!((angles.width - 2.0 * (0.05f32).atan()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_angular_sign() {
+        let camera = CameraIntrinsics::new(1000.0, 1000.0, 960.0, 540.0);
+        // Target up and to the right of the principal point
+        let angles = camera.angular_target(&[1900, 0, 20, 20]);
+        assert!(angles.azimuth > 0.0);
+        assert!(angles.elevation > 0.0);
+    }
+
+    #[test]
+    fn test_undistort_roundtrip() {
+        let camera = CameraIntrinsics::new(800.0, 800.0, 640.0, 360.0)
+            .with_distortion([-0.2, 0.05, 0.0, 0.0, 0.0]);
+        // Distort a known normalized point and check we recover it
+        let (x, y) = (0.3f32, -0.2f32);
+        let r2 = x * x + y * y;
+        let radial = 1.0 - 0.2 * r2 + 0.05 * r2 * r2;
+        let u = x * radial * camera.fx + camera.cx;
+        let v = y * radial * camera.fy + camera.cy;
+        let (ux, uy) = camera.undistort_point(u, v);
+        assert!((ux - x).abs() < 1e-3);
+        assert!((uy - y).abs() < 1e-3);
+        let (du, dv) = camera.distort_point(x, y);
+        assert!((du - u).abs() < 1e-3);
+        assert!((dv - v).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_rect_roundtrip_and_undistorted_crop() {
+        let camera = CameraIntrinsics::new(400.0, 400.0, 320.0, 240.0)
+            .with_distortion([-0.3, 0.08, 0.0, 0.0, 0.0]);
+        let rect = [520, 60, 60, 40];
+        let ideal = camera.undistort_rect(&rect);
+        // Barrel distortion compresses the edges, the ideal box is larger
+        assert!(ideal[2] > rect[2]);
+        let back = camera.distort_rect(&ideal);
+        assert!(back.iter().zip(&rect).all(|(a, b)| (a - b).abs() <= 2));
+
+        // Bright target inside the box ends up in the center of the crop
+        let image = Array3::from_shape_fn((480, 640, 3), |(y, x, _)| {
+            let inside = (530..570).contains(&x) && (70..90).contains(&y);
+            if inside { 255 } else { 0 }
+        });
+        let (crop, _) = camera.crop_undistorted(&image.view(), &BBox::from_array(&ideal), 2, 64);
+        assert_eq!(crop[[32, 32, 0]], 255);
+        assert_eq!(crop[[0, 0, 0]], 0);
+    }
+}
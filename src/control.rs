@@ -0,0 +1,346 @@
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+
+use crate::postprocess::TrackingResult;
+
+/// PID controller for a single axis
+#[derive(Debug, Clone)]
+pub struct Pid {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    /// Absolute limit applied to the output and the integral term
+    pub limit: f32,
+    integral: f32,
+    last_error: Option<f32>,
+}
+
+impl Pid {
+    pub fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            limit: 1.0,
+            integral: 0.0,
+            last_error: None,
+        }
+    }
+
+    /// Compute controller output for the given error and time step (seconds)
+    pub fn update(&mut self, error: f32, dt: f32) -> f32 {
+        self.integral = (self.integral + error * dt).clamp(-self.limit, self.limit);
+
+        let derivative = match self.last_error {
+            Some(last) if dt > 0.0 => (error - last) / dt,
+            _ => 0.0,
+        };
+        self.last_error = Some(error);
+
+        (self.kp * error + self.ki * self.integral + self.kd * derivative)
+            .clamp(-self.limit, self.limit)
+    }
+
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.last_error = None;
+    }
+}
+
+/// Pan/tilt/zoom velocity command, each axis in [-1, 1]
+///
+/// Positive pan is right, positive tilt is up, positive zoom is tele.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PtzCommand {
+    pub pan: f32,
+    pub tilt: f32,
+    pub zoom: f32,
+}
+
+impl PtzCommand {
+    pub fn stop() -> Self {
+        Self::default()
+    }
+}
+
+/// Keeps the tracked target centered (and optionally sized) in the frame
+#[derive(Debug, Clone)]
+pub struct PtzController {
+    pub pan: Pid,
+    pub tilt: Pid,
+    pub zoom: Pid,
+    /// Desired target height as a fraction of frame height (None disables zoom)
+    pub target_size: Option<f32>,
+    /// Normalized errors below this value are treated as zero
+    pub deadband: f32,
+}
+
+impl Default for PtzController {
+    fn default() -> Self {
+        Self {
+            pan: Pid::new(0.8, 0.05, 0.1),
+            tilt: Pid::new(0.8, 0.05, 0.1),
+            zoom: Pid::new(0.5, 0.0, 0.0),
+            target_size: None,
+            deadband: 0.02,
+        }
+    }
+}
+
+impl PtzController {
+    /// Compute PTZ command from a tracking result
+    ///
+    /// # Arguments
+    /// * `result` - Latest tracking result
+    /// * `frame_size` - Frame (width, height) in pixels
+    /// * `dt` - Time since the previous update in seconds
+    pub fn update(
+        &mut self,
+        result: &TrackingResult,
+        frame_size: (usize, usize),
+        dt: f32,
+    ) -> PtzCommand {
+        if !result.success {
+            self.reset();
+            return PtzCommand::stop();
+        }
+
+        let (frame_w, frame_h) = (frame_size.0 as f32, frame_size.1 as f32);
+        let [x, y, w, h] = result.bbox.map(|v| v as f32);
+
+        // Normalized offset from frame center in [-1, 1]
+        let err_x = self.apply_deadband((x + w / 2.0 - frame_w / 2.0) / (frame_w / 2.0));
+        let err_y = self.apply_deadband((frame_h / 2.0 - (y + h / 2.0)) / (frame_h / 2.0));
+
+        let zoom = match self.target_size {
+            Some(size) => {
+                let err_z = self.apply_deadband(size - h / frame_h);
+                self.zoom.update(err_z, dt)
+            }
+            None => 0.0,
+        };
+
+        PtzCommand {
+            pan: self.pan.update(err_x, dt),
+            tilt: self.tilt.update(err_y, dt),
+            zoom,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.pan.reset();
+        self.tilt.reset();
+        self.zoom.reset();
+    }
+
+    fn apply_deadband(&self, error: f32) -> f32 {
+        if error.abs() < self.deadband {
+            0.0
+        } else {
+            error
+        }
+    }
+}
+
+/// Output sink for PTZ commands
+pub trait PtzSink {
+    fn send(&mut self, command: &PtzCommand) -> io::Result<()>;
+}
+
+/// VISCA sink writing to a serial port (or any byte stream)
+pub struct ViscaSink<W: Write> {
+    writer: W,
+    address: u8,
+}
+
+impl<W: Write> ViscaSink<W> {
+    /// Maximum VISCA pan speed
+    const PAN_SPEED_MAX: f32 = 0x18 as f32;
+    /// Maximum VISCA tilt speed
+    const TILT_SPEED_MAX: f32 = 0x14 as f32;
+    /// Maximum VISCA variable zoom speed
+    const ZOOM_SPEED_MAX: f32 = 7.0;
+
+    pub fn new(writer: W, address: u8) -> Self {
+        Self { writer, address }
+    }
+
+    /// Encode Pan-tiltDrive packet
+    pub fn pan_tilt_packet(&self, command: &PtzCommand) -> [u8; 9] {
+        let pan_speed = (command.pan.abs() * Self::PAN_SPEED_MAX).round().max(1.0) as u8;
+        let tilt_speed = (command.tilt.abs() * Self::TILT_SPEED_MAX).round().max(1.0) as u8;
+
+        let pan_dir = if command.pan > 0.0 {
+            0x02
+        } else if command.pan < 0.0 {
+            0x01
+        } else {
+            0x03
+        };
+        let tilt_dir = if command.tilt > 0.0 {
+            0x01
+        } else if command.tilt < 0.0 {
+            0x02
+        } else {
+            0x03
+        };
+
+        [
+            0x80 | self.address,
+            0x01,
+            0x06,
+            0x01,
+            pan_speed,
+            tilt_speed,
+            pan_dir,
+            tilt_dir,
+            0xFF,
+        ]
+    }
+
+    /// Encode CAM_Zoom variable speed packet
+    pub fn zoom_packet(&self, command: &PtzCommand) -> [u8; 6] {
+        let speed = (command.zoom.abs() * Self::ZOOM_SPEED_MAX).round() as u8;
+        let value = if command.zoom > 0.0 {
+            0x20 | speed
+        } else if command.zoom < 0.0 {
+            0x30 | speed
+        } else {
+            0x00
+        };
+
+        [0x80 | self.address, 0x01, 0x04, 0x07, value, 0xFF]
+    }
+}
+
+impl<W: Write> PtzSink for ViscaSink<W> {
+    fn send(&mut self, command: &PtzCommand) -> io::Result<()> {
+        let pan_tilt = self.pan_tilt_packet(command);
+        let zoom = self.zoom_packet(command);
+        self.writer.write_all(&pan_tilt)?;
+        self.writer.write_all(&zoom)?;
+        self.writer.flush()
+    }
+}
+
+/// ONVIF sink issuing PTZ ContinuousMove requests over HTTP
+pub struct OnvifSink {
+    /// Device address as "host:port"
+    pub address: String,
+    /// PTZ service path, e.g. "/onvif/ptz_service"
+    pub path: String,
+    pub profile_token: String,
+}
+
+impl OnvifSink {
+    pub fn new(address: &str, path: &str, profile_token: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            path: path.to_string(),
+            profile_token: profile_token.to_string(),
+        }
+    }
+
+    /// Build the SOAP ContinuousMove envelope
+    pub fn continuous_move_body(&self, command: &PtzCommand) -> String {
+        format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope">"#,
+                r#"<s:Body>"#,
+                r#"<ContinuousMove xmlns="http://www.onvif.org/ver20/ptz/wsdl">"#,
+                r#"<ProfileToken>{}</ProfileToken>"#,
+                r#"<Velocity>"#,
+                r#"<PanTilt xmlns="http://www.onvif.org/ver10/schema" x="{:.3}" y="{:.3}"/>"#,
+                r#"<Zoom xmlns="http://www.onvif.org/ver10/schema" x="{:.3}"/>"#,
+                r#"</Velocity>"#,
+                r#"</ContinuousMove>"#,
+                r#"</s:Body>"#,
+                r#"</s:Envelope>"#,
+            ),
+            self.profile_token, command.pan, command.tilt, command.zoom
+        )
+    }
+}
+
+impl PtzSink for OnvifSink {
+    fn send(&mut self, command: &PtzCommand) -> io::Result<()> {
+        let body = self.continuous_move_body(command);
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/soap+xml; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.address,
+            body.len(),
+            body
+        );
+
+        let mut stream = TcpStream::connect(&self.address)?;
+        stream.write_all(request.as_bytes())?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+
+        let status = response.lines().next().unwrap_or_default();
+        if status.split_whitespace().nth(1) != Some("200") {
+            return Err(io::Error::other(format!(
+                "ONVIF request failed: {}",
+                status
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(bbox: [i32; 4]) -> TrackingResult {
+        TrackingResult {
+            success: true,
+            bbox,
+            score: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pid_proportional() {
+        let mut pid = Pid::new(0.5, 0.0, 0.0);
+        assert!((pid.update(0.4, 0.1) - 0.2).abs() < 1e-6);
+        assert_eq!(pid.update(10.0, 0.1), 1.0);
+    }
+
+    #[test]
+    fn test_controller_direction() {
+        let mut controller = PtzController::default();
+        // Target in the top-right quadrant: pan right, tilt up
+        let cmd = controller.update(&result([1500, 100, 100, 100]), (1920, 1080), 0.04);
+        assert!(cmd.pan > 0.0);
+        assert!(cmd.tilt > 0.0);
+        assert_eq!(cmd.zoom, 0.0);
+
+        // Lost target stops the camera
+        let lost = TrackingResult::default();
+        assert_eq!(
+            controller.update(&lost, (1920, 1080), 0.04),
+            PtzCommand::stop()
+        );
+    }
+
+    #[test]
+    fn test_visca_packets() {
+        let sink = ViscaSink::new(Vec::new(), 1);
+        let cmd = PtzCommand {
+            pan: 1.0,
+            tilt: -0.5,
+            zoom: 0.0,
+        };
+        assert_eq!(
+            sink.pan_tilt_packet(&cmd),
+            [0x81, 0x01, 0x06, 0x01, 0x18, 0x0A, 0x02, 0x02, 0xFF]
+        );
+        assert_eq!(sink.zoom_packet(&cmd), [0x81, 0x01, 0x04, 0x07, 0x00, 0xFF]);
+    }
+}
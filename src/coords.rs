@@ -0,0 +1,132 @@
+//! Box coordinates in the convention a consumer expects
+//!
+//! `TrackingResult::bbox` is in image pixels with the origin at the top-left
+//! corner and y pointing down. Gimbal controllers and OSD systems often want
+//! the origin at the image center, y pointing up, or normalized device
+//! coordinates; set `VitTrackConfig::coordinates` and read
+//! `TrackingResult::bbox_converted` instead of converting downstream.
+
+/// Where (0, 0) is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Origin {
+    #[default]
+    TopLeft,
+    BottomLeft,
+    Center,
+}
+
+/// Direction of increasing y
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YAxis {
+    #[default]
+    Down,
+    Up,
+}
+
+/// Unit of the coordinates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Units {
+    #[default]
+    Pixels,
+    /// The frame is 1 wide and 1 high
+    Normalized,
+    /// Half the frame is 1: [-1, 1] across the frame with a centered origin
+    Ndc,
+}
+
+/// Coordinate convention of `TrackingResult::bbox_converted`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Convention {
+    pub origin: Origin,
+    pub y_axis: YAxis,
+    pub units: Units,
+}
+
+impl Convention {
+    /// Image pixels, top-left origin, y down (same as `bbox`)
+    pub const IMAGE: Self = Self {
+        origin: Origin::TopLeft,
+        y_axis: YAxis::Down,
+        units: Units::Pixels,
+    };
+
+    /// Pixels from the image center, y up
+    pub const CENTERED: Self = Self {
+        origin: Origin::Center,
+        y_axis: YAxis::Up,
+        units: Units::Pixels,
+    };
+
+    /// Normalized device coordinates: [-1, 1] from the center, y up
+    pub const NDC: Self = Self {
+        origin: Origin::Center,
+        y_axis: YAxis::Up,
+        units: Units::Ndc,
+    };
+
+    /// Convert an image pixel position
+    ///
+    /// # Arguments
+    /// * `u`, `v` - Pixel position, top-left origin, y down
+    /// * `frame_size` - Frame (width, height) in pixels
+    pub fn point(&self, u: f32, v: f32, frame_size: (usize, usize)) -> (f32, f32) {
+        let (fw, fh) = (frame_size.0 as f32, frame_size.1 as f32);
+        let (ox, oy) = match self.origin {
+            Origin::TopLeft => (0.0, 0.0),
+            Origin::BottomLeft => (0.0, fh),
+            Origin::Center => (fw / 2.0, fh / 2.0),
+        };
+        let (x, mut y) = (u - ox, v - oy);
+        if self.y_axis == YAxis::Up {
+            y = -y;
+        }
+        let (sx, sy) = match self.units {
+            Units::Pixels => (1.0, 1.0),
+            Units::Normalized => (fw, fh),
+            Units::Ndc => (fw / 2.0, fh / 2.0),
+        };
+        (x / sx.max(f32::EPSILON), y / sy.max(f32::EPSILON))
+    }
+
+    /// Convert a box [x, y, w, h]
+    ///
+    /// # Returns
+    /// * [x, y, w, h] where (x, y) is the corner with the smallest coordinates
+    ///   in this convention (the bottom-left corner when y points up)
+    pub fn convert(&self, bbox: &[i32; 4], frame_size: (usize, usize)) -> [f32; 4] {
+        let [x, y, w, h] = bbox.map(|v| v as f32);
+        let (x1, y1) = self.point(x, y, frame_size);
+        let (x2, y2) = self.point(x + w, y + h, frame_size);
+        [x1.min(x2), y1.min(y2), (x2 - x1).abs(), (y2 - y1).abs()]
+    }
+
+    /// Center of a converted box
+    pub fn center(converted: &[f32; 4]) -> (f32, f32) {
+        let [x, y, w, h] = *converted;
+        (x + w / 2.0, y + h / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conventions() {
+        let frame = (640, 480);
+        let bbox = [320, 140, 64, 40];
+        assert_eq!(Convention::IMAGE.convert(&bbox, frame), [320.0, 140.0, 64.0, 40.0]);
+        // Box above and right of the center: y up puts it at positive y
+        assert_eq!(Convention::CENTERED.convert(&bbox, frame), [0.0, 60.0, 64.0, 40.0]);
+        let [x, y, w, h] = Convention::NDC.convert(&bbox, frame);
+        assert!((x - 0.0).abs() < 1e-6 && (y - 0.25).abs() < 1e-6);
+        assert!((w - 0.2).abs() < 1e-6 && (h - 1.0 / 6.0).abs() < 1e-6);
+        let bottom_left = Convention {
+            origin: Origin::BottomLeft,
+            y_axis: YAxis::Up,
+            units: Units::Normalized,
+        };
+        let (cx, cy) = Convention::center(&bottom_left.convert(&bbox, frame));
+        assert!((cx - 0.55).abs() < 1e-6 && (cy - 0.6666667).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,329 @@
+//! Allocation-free decoding core
+//!
+//! Windowing, peak finding and box decoding on caller-provided slices. This
+//! module only depends on `core`, so it is available without the `std`
+//! feature (e.g. on a co-processor that receives raw NPU outputs).
+
+/// Decoded peak of one score map
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Peak {
+    /// Flat index of the peak cell (y * score_size + x)
+    pub idx: usize,
+    /// Windowed score at the peak
+    pub score: f32,
+    /// Second-best (non-adjacent) peak divided by the best one
+    pub peak_ratio: f32,
+    pub crop_box: CropBox,
+}
+
+/// Window the confidence map, find the peak and decode its box
+///
+/// # Arguments
+/// * `conf_map` - Confidence map (score_size x score_size)
+/// * `size_map` - Size map (2 x score_size x score_size)
+/// * `offset_map` - Offset map (2 x score_size x score_size)
+/// * `window` - Window applied to the confidence map (score_size x score_size)
+/// * `scratch` - Receives the windowed map (score_size x score_size)
+/// * `score_size` - Side of the score map
+pub fn decode_peak(
+    conf_map: &[f32],
+    size_map: &[f32],
+    offset_map: &[f32],
+    window: &[f32],
+    scratch: &mut [f32],
+    score_size: usize,
+) -> Peak {
+    apply_window(conf_map, window, scratch);
+    let (idx, score) = find_max(scratch);
+    let peak_ratio = if score > 0.0 {
+        second_peak(scratch, score_size, idx) / score
+    } else {
+        0.0
+    };
+
+    Peak {
+        idx,
+        score,
+        peak_ratio,
+        crop_box: decode_box(size_map, offset_map, idx, score_size),
+    }
+}
+
+/// Highest value outside the 3x3 neighbourhood of the main peak
+pub fn second_peak(arr: &[f32], size: usize, max_idx: usize) -> f32 {
+    let (peak_y, peak_x) = (max_idx / size, max_idx % size);
+
+    arr.iter()
+        .enumerate()
+        .filter(|&(idx, _)| (idx / size).abs_diff(peak_y) > 1 || (idx % size).abs_diff(peak_x) > 1)
+        .map(|(_, &val)| val)
+        .fold(0.0f32, f32::max)
+}
+
+/// Highest local peaks in descending order
+///
+/// Each peak is the highest cell outside the 3x3 neighbourhoods of the
+/// peaks before it, as in `second_peak`.
+///
+/// # Arguments
+/// * `arr` - Score map (size x size)
+/// * `out` - Receives (flat index, value) of up to `out.len()` peaks
+///
+/// # Returns
+/// * Number of peaks written
+pub fn top_peaks(arr: &[f32], size: usize, out: &mut [(usize, f32)]) -> usize {
+    let mut found = 0;
+    while found < out.len() {
+        let taken = &out[..found];
+        let next = arr
+            .iter()
+            .enumerate()
+            .filter(|&(idx, _)| {
+                taken.iter().all(|&(peak, _)| {
+                    (idx / size).abs_diff(peak / size) > 1 || (idx % size).abs_diff(peak % size) > 1
+                })
+            })
+            .fold(None, |best: Option<(usize, f32)>, (idx, &val)| match best {
+                Some((_, best_val)) if best_val >= val => best,
+                _ => Some((idx, val)),
+            });
+        let Some(peak) = next else { break };
+        out[found] = peak;
+        found += 1;
+    }
+    found
+}
+
+/// Multiply a confidence map by a window into `out`
+pub fn apply_window(conf_map: &[f32], window: &[f32], out: &mut [f32]) {
+    for ((out, &conf), &weight) in out.iter_mut().zip(conf_map).zip(window) {
+        *out = conf * weight;
+    }
+}
+
+/// Find maximum value and its index
+pub fn find_max(arr: &[f32]) -> (usize, f32) {
+    let mut max_idx = 0;
+    let mut max_val = f32::NEG_INFINITY;
+
+    for (idx, &val) in arr.iter().enumerate() {
+        if val > max_val {
+            max_val = val;
+            max_idx = idx;
+        }
+    }
+
+    (max_idx, max_val)
+}
+
+/// Box decoded from the heads, normalized to the search crop ([0, 1])
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropBox {
+    pub cx: f32,
+    pub cy: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl CropBox {
+    /// Convert to image coordinates [x, y, w, h] (matching OpenCV logic)
+    ///
+    /// # Arguments
+    /// * `origin` - Top-left corner of the search crop in the image
+    /// * `crop_size` - Crop size in original image pixels
+    pub fn to_image(&self, origin: (i32, i32), crop_size: i32) -> [i32; 4] {
+        let x1 = self.cx - self.w / 2.0;
+        let y1 = self.cy - self.h / 2.0;
+        let crop = crop_size as f32;
+
+        [
+            floor(x1 * crop + origin.0 as f32),
+            floor(y1 * crop + origin.1 as f32),
+            floor(self.w * crop),
+            floor(self.h * crop),
+        ]
+    }
+}
+
+/// Decode the size and offset heads at a score map cell
+///
+/// # Arguments
+/// * `size_map` - Size map (2 x score_size x score_size)
+/// * `offset_map` - Offset map (2 x score_size x score_size)
+/// * `idx` - Flat index of the cell (y * score_size + x)
+/// * `score_size` - Side of the score map
+pub fn decode_box(size_map: &[f32], offset_map: &[f32], idx: usize, score_size: usize) -> CropBox {
+    // Layout: [2, score_size, score_size] -> index = channel * area + y * score_size + x
+    let area = score_size * score_size;
+    let (y, x) = (idx / score_size, idx % score_size);
+
+    CropBox {
+        cx: (x as f32 + offset_map[idx]) / score_size as f32,
+        cy: (y as f32 + offset_map[area + idx]) / score_size as f32,
+        w: size_map[idx],
+        h: size_map[area + idx],
+    }
+}
+
+/// Memory layout of the multi-channel output maps
+///
+/// The decoders here index the size and offset maps channel-major (NCHW).
+/// Some exports emit them pixel-major instead (NHWC flattened, the two
+/// channels of a cell next to each other); those are reordered once with
+/// `to_nchw` before decoding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputLayout {
+    /// [channels, score_size, score_size]
+    #[default]
+    Nchw,
+    /// [score_size, score_size, channels]
+    Nhwc,
+}
+
+impl OutputLayout {
+    /// Flat index of `channel` at a score map cell
+    ///
+    /// # Arguments
+    /// * `channel` - Channel of the map (0 = x / width, 1 = y / height)
+    /// * `idx` - Flat index of the cell (y * score_size + x)
+    /// * `channels` - Number of channels of the map
+    /// * `area` - score_size * score_size
+    #[inline]
+    pub fn index(self, channel: usize, idx: usize, channels: usize, area: usize) -> usize {
+        match self {
+            Self::Nchw => channel * area + idx,
+            Self::Nhwc => idx * channels + channel,
+        }
+    }
+
+    /// Copy a map in this layout into `out` in NCHW order
+    ///
+    /// # Arguments
+    /// * `map` - Map in this layout (channels x area elements)
+    /// * `out` - Receives the NCHW map (same length)
+    /// * `channels` - Number of channels of the map
+    pub fn to_nchw(self, map: &[f32], out: &mut [f32], channels: usize) {
+        let area = map.len() / channels.max(1);
+        for channel in 0..channels {
+            for idx in 0..area {
+                out[channel * area + idx] = map[self.index(channel, idx, channels, area)];
+            }
+        }
+    }
+}
+
+/// Top-left corner of the search crop centered on a box
+pub fn crop_origin(rect: &[i32; 4], crop_size: i32) -> (i32, i32) {
+    (
+        rect[0] + (rect[2] - crop_size) / 2,
+        rect[1] + (rect[3] - crop_size) / 2,
+    )
+}
+
+/// `f32::floor` as i32 (not available in `core`)
+#[inline]
+fn floor(value: f32) -> i32 {
+    let truncated = value as i32;
+    if (truncated as f32) > value {
+        truncated - 1
+    } else {
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_peak_skips_neighbours() {
+        let mut arr = [0.0f32; 16];
+        arr[5] = 1.0; // (1, 1)
+        arr[6] = 0.9; // adjacent to the peak
+        arr[15] = 0.4; // (3, 3)
+        assert!((second_peak(&arr, 4, 5) - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_top_peaks() {
+        let mut arr = [0.0f32; 16];
+        arr[5] = 1.0;
+        arr[6] = 0.9; // suppressed by the first peak
+        arr[15] = 0.4;
+        arr[12] = 0.3;
+        let mut peaks = [(0, 0.0); 3];
+        assert_eq!(top_peaks(&arr, 4, &mut peaks), 3);
+        assert_eq!(peaks, [(5, 1.0), (15, 0.4), (12, 0.3)]);
+    }
+
+    #[test]
+    fn test_decode_box() {
+        let mut size_map = [0.0f32; 2 * 16];
+        let mut offset_map = [0.0f32; 2 * 16];
+        // Cell (y=2, x=1) of a 4x4 map
+        let idx = 2 * 4 + 1;
+        offset_map[idx] = 0.5;
+        offset_map[16 + idx] = 0.25;
+        size_map[idx] = 0.5;
+        size_map[16 + idx] = 0.25;
+
+        let crop_box = decode_box(&size_map, &offset_map, idx, 4);
+        assert_eq!(crop_box, CropBox { cx: 0.375, cy: 0.5625, w: 0.5, h: 0.25 });
+
+        let origin = crop_origin(&[100, 100, 20, 20], 80);
+        assert_eq!(origin, (70, 70));
+        assert_eq!(crop_box.to_image(origin, 80), [80, 105, 40, 20]);
+    }
+
+    #[test]
+    fn test_nhwc_layout_decodes_like_nchw() {
+        // Cell (y=2, x=1) of a 4x4 map, as in test_decode_box
+        let idx = 2 * 4 + 1;
+        let (mut size_nchw, mut offset_nchw) = ([0.1f32; 32], [0.0f32; 32]);
+        let (mut size_nhwc, mut offset_nhwc) = ([0.1f32; 32], [0.0f32; 32]);
+        for (channel, (size, offset)) in [(0.5, 0.5), (0.25, 0.25)].into_iter().enumerate() {
+            size_nchw[OutputLayout::Nchw.index(channel, idx, 2, 16)] = size;
+            offset_nchw[OutputLayout::Nchw.index(channel, idx, 2, 16)] = offset;
+            size_nhwc[OutputLayout::Nhwc.index(channel, idx, 2, 16)] = size;
+            offset_nhwc[OutputLayout::Nhwc.index(channel, idx, 2, 16)] = offset;
+        }
+        assert_eq!(OutputLayout::Nhwc.index(1, idx, 2, 16), 19);
+
+        let (mut size, mut offset) = ([0.0f32; 32], [0.0f32; 32]);
+        OutputLayout::Nhwc.to_nchw(&size_nhwc, &mut size, 2);
+        OutputLayout::Nhwc.to_nchw(&offset_nhwc, &mut offset, 2);
+        assert_eq!((size, offset), (size_nchw, offset_nchw));
+        assert_eq!(
+            decode_box(&size, &offset, idx, 4),
+            CropBox { cx: 0.375, cy: 0.5625, w: 0.5, h: 0.25 }
+        );
+
+        OutputLayout::Nchw.to_nchw(&size_nchw, &mut size, 2);
+        assert_eq!(size, size_nchw);
+    }
+
+    #[test]
+    fn test_find_max() {
+        let arr = [0.1, 0.5, 0.3, 0.9, 0.2];
+        let (idx, val) = find_max(&arr);
+        assert_eq!(idx, 3);
+        assert!((val - 0.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_decode_peak_without_allocation() {
+        let mut conf = [0.0f32; 16];
+        conf[9] = 0.8;
+        conf[3] = 0.2;
+        let window = [1.0f32; 16];
+        let size_map = [0.5f32; 32];
+        let offset_map = [0.5f32; 32];
+        let mut scratch = [0.0f32; 16];
+
+        let peak = decode_peak(&conf, &size_map, &offset_map, &window, &mut scratch, 4);
+        assert_eq!(peak.idx, 9);
+        assert!((peak.peak_ratio - 0.25).abs() < 1e-6);
+        assert_eq!(peak.crop_box.to_image((0, 0), 40), [5, 15, 20, 20]);
+        assert_eq!(floor(-0.5), -1);
+    }
+}
@@ -0,0 +1,132 @@
+//! Errors with the pipeline context they occurred in
+//!
+//! A bare "Failed to run inference" after hours of unattended running says
+//! little. `PipelineError` adds the stage, the frame index and the source (a
+//! camera or file name) to the underlying error, so the log line points at
+//! the frame that failed:
+//!
+//! ```text
+//! inference error on frame 183204 of cam2: Failed to run inference: ...
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+#[cfg(feature = "rknn")]
+use crate::rknn::RknnError;
+
+/// Pipeline stage an error came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Reading or decoding a frame
+    Capture,
+    /// Crop, resize, color conversion and normalization
+    Preprocess,
+    /// Model execution
+    Inference,
+    /// Decoding the model outputs
+    Postprocess,
+    /// Writing results (reports, telemetry, video)
+    Output,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Capture => "capture",
+            Self::Preprocess => "preprocess",
+            Self::Inference => "inference",
+            Self::Postprocess => "postprocess",
+            Self::Output => "output",
+        })
+    }
+}
+
+/// Error of one pipeline stage with the frame and source it happened on
+#[derive(Error, Debug)]
+#[error("{stage} error{}: {error}", location(.frame, .source_id))]
+pub struct PipelineError {
+    pub stage: Stage,
+    /// Index of the frame being processed
+    pub frame: Option<u64>,
+    /// Camera or file the frame came from
+    pub source_id: Option<Arc<str>>,
+    #[source]
+    pub error: Box<dyn Error + Send + Sync>,
+}
+
+impl PipelineError {
+    pub fn new(stage: Stage, error: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self {
+            stage,
+            frame: None,
+            source_id: None,
+            error: error.into(),
+        }
+    }
+
+    pub fn with_frame(mut self, frame: u64) -> Self {
+        self.frame = Some(frame);
+        self
+    }
+
+    pub fn with_source(mut self, source_id: impl Into<Arc<str>>) -> Self {
+        self.source_id = Some(source_id.into());
+        self
+    }
+}
+
+/// " on frame N of SOURCE", parts present only when known
+fn location(frame: &Option<u64>, source_id: &Option<Arc<str>>) -> String {
+    let mut location = String::new();
+    if let Some(frame) = frame {
+        location.push_str(&format!(" on frame {}", frame));
+    }
+    if let Some(source_id) = source_id {
+        location.push_str(&format!(" of {}", source_id));
+    }
+    location
+}
+
+#[cfg(feature = "rknn")]
+impl From<RknnError> for PipelineError {
+    fn from(error: RknnError) -> Self {
+        let stage = match error {
+            RknnError::OutputShape(_) => Stage::Postprocess,
+            _ => Stage::Inference,
+        };
+        Self::new(stage, error)
+    }
+}
+
+/// Attach pipeline context to any result, e.g.
+/// `source.read().at(Stage::Capture, frame)?`
+pub trait PipelineContext<T> {
+    fn at(self, stage: Stage, frame: u64) -> Result<T, PipelineError>;
+}
+
+impl<T, E: Into<Box<dyn Error + Send + Sync>>> PipelineContext<T> for Result<T, E> {
+    fn at(self, stage: Stage, frame: u64) -> Result<T, PipelineError> {
+        self.map_err(|error| PipelineError::new(stage, error).with_frame(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn test_message_and_source() {
+        let read: Result<(), io::Error> = Err(io::Error::other("device unplugged"));
+        let error = read.at(Stage::Capture, 42).unwrap_err().with_source("cam2");
+        assert_eq!(error.to_string(), "capture error on frame 42 of cam2: device unplugged");
+        assert_eq!(error.source().map(|e| e.to_string()).as_deref(), Some("device unplugged"));
+
+        let bare = PipelineError::new(Stage::Output, "disk full");
+        assert_eq!(bare.to_string(), "output error: disk full");
+    }
+}
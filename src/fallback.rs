@@ -0,0 +1,435 @@
+//! CPU correlation-filter tracker used while the NPU is unavailable
+//!
+//! A MOSSE filter on a small grayscale patch: a few 2D FFTs per frame, cheap
+//! enough to run on one core next to the control loop. It learns from the
+//! boxes the model reports, so it is ready to take over when inference fails
+//! or the latency budget skips a frame. It follows translation only; the box
+//! size is kept until the model is back.
+
+use std::f32::consts::PI;
+use std::ops::{Add, Mul, Sub};
+
+use ndarray::ArrayView3;
+
+use crate::postprocess::{hann2d, TrackingResult};
+use crate::preprocess::BBox;
+
+/// Fallback tracker configuration
+#[derive(Debug, Clone, Copy)]
+pub struct FallbackConfig {
+    /// Side of the correlation patch in pixels (power of two)
+    pub patch_size: usize,
+    /// Patch side relative to the larger box side
+    pub padding: f32,
+    /// Width of the Gaussian target response in patch pixels
+    pub sigma: f32,
+    /// Weight of the newest frame in the filter average
+    pub learning_rate: f32,
+    /// Peak-to-sidelobe ratio below which the target counts as lost
+    pub min_psr: f32,
+    /// Also track on frames the latency budget skips, instead of repeating
+    /// the previous box
+    pub on_budget_skip: bool,
+}
+
+impl Default for FallbackConfig {
+    fn default() -> Self {
+        Self {
+            patch_size: 64,
+            padding: 2.0,
+            sigma: 2.0,
+            learning_rate: 0.125,
+            min_psr: 7.0,
+            on_budget_skip: true,
+        }
+    }
+}
+
+/// Box found by the correlation filter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CorrelationMatch {
+    /// New box [x, y, w, h]; same size as the searched one
+    pub bbox: [i32; 4],
+    /// Peak-to-sidelobe ratio of the response
+    pub psr: f32,
+    /// PSR mapped to [0, 1]; `min_psr` maps to 0.5
+    pub score: f32,
+    /// Whether the PSR reaches `min_psr`
+    pub success: bool,
+}
+
+/// MOSSE correlation-filter tracker
+pub struct CorrelationTracker {
+    config: FallbackConfig,
+    window: Vec<f32>,
+    /// Spectrum of the Gaussian target response
+    target: Vec<Complex>,
+    /// Filter numerator and denominator, averaged over frames
+    numerator: Vec<Complex>,
+    denominator: Vec<f32>,
+    trained: bool,
+}
+
+impl CorrelationTracker {
+    /// Regularization of the filter denominator
+    const LAMBDA: f32 = 1e-2;
+    /// Half side of the peak region excluded from the sidelobe
+    const PEAK_EXCLUSION: usize = 5;
+
+    pub fn new(config: FallbackConfig) -> Self {
+        let n = config.patch_size.next_power_of_two().max(8);
+        let config = FallbackConfig {
+            patch_size: n,
+            ..config
+        };
+        let center = (n / 2) as f32;
+        let mut target: Vec<Complex> = (0..n * n)
+            .map(|idx| {
+                let (dy, dx) = ((idx / n) as f32 - center, (idx % n) as f32 - center);
+                let re = (-(dx * dx + dy * dy) / (2.0 * config.sigma * config.sigma)).exp();
+                Complex { re, im: 0.0 }
+            })
+            .collect();
+        fft2d(&mut target, n, false);
+
+        Self {
+            config,
+            window: hann2d(n, n),
+            target,
+            numerator: vec![Complex::default(); n * n],
+            denominator: vec![0.0; n * n],
+            trained: false,
+        }
+    }
+
+    pub fn config(&self) -> &FallbackConfig {
+        &self.config
+    }
+
+    /// Whether the filter has seen the target
+    pub fn is_trained(&self) -> bool {
+        self.trained
+    }
+
+    /// Train the filter from scratch on a box
+    pub fn init(&mut self, image: &ArrayView3<u8>, rect: &[i32; 4]) {
+        self.trained = false;
+        self.learn(image, rect);
+    }
+
+    /// Blend the appearance at a box into the filter
+    pub fn learn(&mut self, image: &ArrayView3<u8>, rect: &[i32; 4]) {
+        let spectrum = self.spectrum(image, rect);
+        let rate = if self.trained { self.config.learning_rate } else { 1.0 };
+        for (idx, f) in spectrum.iter().enumerate() {
+            let numerator = self.target[idx] * f.conj();
+            let denominator = f.norm_sqr();
+            self.numerator[idx] = self.numerator[idx].scale(1.0 - rate) + numerator.scale(rate);
+            self.denominator[idx] = self.denominator[idx] * (1.0 - rate) + denominator * rate;
+        }
+        self.trained = true;
+    }
+
+    /// Find the target near a box, without learning
+    ///
+    /// # Returns
+    /// * None before the filter is trained
+    pub fn track(&self, image: &ArrayView3<u8>, rect: &[i32; 4]) -> Option<CorrelationMatch> {
+        if !self.trained {
+            return None;
+        }
+        let n = self.config.patch_size;
+        let mut response = self.spectrum(image, rect);
+        for (idx, value) in response.iter_mut().enumerate() {
+            let filter = self.numerator[idx].scale(1.0 / (self.denominator[idx] + Self::LAMBDA));
+            *value = filter * *value;
+        }
+        fft2d(&mut response, n, true);
+        let response: Vec<f32> = response.iter().map(|value| value.re).collect();
+
+        let (peak, &peak_value) = response
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        let psr = peak_to_sidelobe(&response, n, peak, peak_value);
+
+        let step = self.patch_side(rect) / n as f32;
+        let dx = ((peak % n) as f32 - (n / 2) as f32) * step;
+        let dy = ((peak / n) as f32 - (n / 2) as f32) * step;
+        Some(CorrelationMatch {
+            bbox: [rect[0] + dx.round() as i32, rect[1] + dy.round() as i32, rect[2], rect[3]],
+            psr,
+            score: (psr / (2.0 * self.config.min_psr)).clamp(0.0, 1.0),
+            success: psr >= self.config.min_psr,
+        })
+    }
+
+    /// Forget the target
+    pub fn reset(&mut self) {
+        self.trained = false;
+    }
+
+    fn patch_side(&self, rect: &[i32; 4]) -> f32 {
+        self.config.padding * rect[2].max(rect[3]).max(1) as f32
+    }
+
+    /// Spectrum of the windowed, log-scaled grayscale patch around a box
+    fn spectrum(&self, image: &ArrayView3<u8>, rect: &[i32; 4]) -> Vec<Complex> {
+        let n = self.config.patch_size;
+        let (img_h, img_w, channels) = image.dim();
+        let side = self.patch_side(rect);
+        let step = side / n as f32;
+        let x0 = rect[0] as f32 + rect[2] as f32 / 2.0 - side / 2.0;
+        let y0 = rect[1] as f32 + rect[3] as f32 / 2.0 - side / 2.0;
+
+        // Nearest sample, edge pixels repeated outside the frame
+        let mut values = Vec::with_capacity(n * n);
+        for i in 0..n {
+            let y = ((y0 + (i as f32 + 0.5) * step) as i32).clamp(0, img_h as i32 - 1) as usize;
+            for j in 0..n {
+                let x =
+                    ((x0 + (j as f32 + 0.5) * step) as i32).clamp(0, img_w as i32 - 1) as usize;
+                let gray = if channels >= 3 {
+                    0.299 * image[[y, x, 0]] as f32
+                        + 0.587 * image[[y, x, 1]] as f32
+                        + 0.114 * image[[y, x, 2]] as f32
+                } else {
+                    image[[y, x, 0]] as f32
+                };
+                values.push((gray + 1.0).ln());
+            }
+        }
+
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>()
+            / values.len() as f32;
+        let std = variance.sqrt().max(1e-5);
+        let mut spectrum: Vec<Complex> = values
+            .iter()
+            .zip(&self.window)
+            .map(|(&v, &w)| Complex {
+                re: (v - mean) / std * w,
+                im: 0.0,
+            })
+            .collect();
+        fft2d(&mut spectrum, n, false);
+        spectrum
+    }
+}
+
+/// Standalone CPU tracker on the correlation filter
+///
+/// Keeps its own box, e.g. as an independent member of a `FusedTracker`.
+pub struct CorrelationTrack {
+    filter: CorrelationTracker,
+    rect: [i32; 4],
+}
+
+impl CorrelationTrack {
+    pub fn new(config: FallbackConfig) -> Self {
+        Self {
+            filter: CorrelationTracker::new(config),
+            rect: [0, 0, 0, 0],
+        }
+    }
+
+    pub fn init(&mut self, image: &ArrayView3<u8>, bbox: BBox) {
+        self.rect = bbox.to_array();
+        self.filter.init(image, &self.rect);
+    }
+
+    /// Track in a new frame, learning the new appearance on success
+    pub fn update(&mut self, image: &ArrayView3<u8>) -> TrackingResult {
+        let Some(found) = self.filter.track(image, &self.rect) else {
+            return TrackingResult::default();
+        };
+        if found.success {
+            self.rect = found.bbox;
+            self.filter.learn(image, &self.rect);
+        }
+        TrackingResult {
+            success: found.success,
+            bbox: self.rect,
+            score: found.score,
+            ..TrackingResult::default()
+        }
+    }
+
+    /// Move the box without retraining
+    pub fn set_bbox(&mut self, rect: [i32; 4]) {
+        self.rect = rect;
+    }
+
+    pub fn get_bbox(&self) -> [i32; 4] {
+        self.rect
+    }
+}
+
+/// (peak - sidelobe mean) / sidelobe std, the peak's neighbourhood excluded
+fn peak_to_sidelobe(response: &[f32], n: usize, peak: usize, peak_value: f32) -> f32 {
+    let (peak_y, peak_x) = (peak / n, peak % n);
+    let exclusion = CorrelationTracker::PEAK_EXCLUSION;
+    let (mut sum, mut sum_sq, mut count) = (0.0f32, 0.0f32, 0usize);
+    for (idx, &value) in response.iter().enumerate() {
+        if (idx / n).abs_diff(peak_y) <= exclusion && (idx % n).abs_diff(peak_x) <= exclusion {
+            continue;
+        }
+        sum += value;
+        sum_sq += value * value;
+        count += 1;
+    }
+    if count == 0 {
+        return 0.0;
+    }
+    let mean = sum / count as f32;
+    let std = (sum_sq / count as f32 - mean * mean).max(0.0).sqrt();
+    (peak_value - mean) / std.max(1e-6)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    fn conj(self) -> Self {
+        Self {
+            re: self.re,
+            im: -self.im,
+        }
+    }
+
+    fn norm_sqr(self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    fn scale(self, factor: f32) -> Self {
+        Self {
+            re: self.re * factor,
+            im: self.im * factor,
+        }
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            re: self.re - other.re,
+            im: self.im - other.im,
+        }
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+}
+
+/// In-place radix-2 FFT; the inverse is scaled by 1/len
+fn fft(data: &mut [Complex], inverse: bool) {
+    let len = data.len();
+    let mut j = 0;
+    for i in 1..len {
+        let mut bit = len >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut size = 2;
+    while size <= len {
+        let angle = sign * 2.0 * PI / size as f32;
+        for start in (0..len).step_by(size) {
+            for k in 0..size / 2 {
+                let twiddle = Complex {
+                    re: (angle * k as f32).cos(),
+                    im: (angle * k as f32).sin(),
+                };
+                let even = data[start + k];
+                let odd = data[start + k + size / 2] * twiddle;
+                data[start + k] = even + odd;
+                data[start + k + size / 2] = even - odd;
+            }
+        }
+        size <<= 1;
+    }
+
+    if inverse {
+        for value in data.iter_mut() {
+            *value = value.scale(1.0 / len as f32);
+        }
+    }
+}
+
+/// FFT of an n x n row-major array
+fn fft2d(data: &mut [Complex], n: usize, inverse: bool) {
+    for row in data.chunks_mut(n) {
+        fft(row, inverse);
+    }
+    let mut column = vec![Complex::default(); n];
+    for x in 0..n {
+        for y in 0..n {
+            column[y] = data[y * n + x];
+        }
+        fft(&mut column, inverse);
+        for y in 0..n {
+            data[y * n + x] = column[y];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_follows_translation() {
+        // Deterministic texture, shifted by (6, -4) in the second frame
+        let texture = |x: i32, y: i32| {
+            let seed = (x.wrapping_mul(73_856_093) ^ y.wrapping_mul(19_349_663)) as u32;
+            (seed.wrapping_mul(2_654_435_761) >> 24) as u8
+        };
+        let frame = |dx: i32, dy: i32| {
+            Array3::from_shape_fn((240, 320, 3), |(y, x, _)| {
+                texture((x as i32 - dx) / 3, (y as i32 - dy) / 3)
+            })
+        };
+        let rect = [140, 100, 40, 40];
+        let mut tracker = CorrelationTracker::new(FallbackConfig::default());
+        assert!(tracker.track(&frame(0, 0).view(), &rect).is_none());
+        tracker.init(&frame(0, 0).view(), &rect);
+
+        let found = tracker.track(&frame(6, -4).view(), &rect).unwrap();
+        assert!(found.success, "psr {}", found.psr);
+        assert!((found.bbox[0] - 146).abs() <= 2, "{:?}", found.bbox);
+        assert!((found.bbox[1] - 96).abs() <= 2, "{:?}", found.bbox);
+        assert_eq!(&found.bbox[2..], &rect[2..]);
+    }
+}
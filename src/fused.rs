@@ -0,0 +1,302 @@
+//! Redundant tracking of one target by several trackers
+//!
+//! `FusedTracker` runs every member on each frame, maps the member scores to
+//! probabilities with a per-member `Calibration`, and averages the boxes of
+//! the members that agree with the most confident one, weighted by those
+//! probabilities. A member that fails or returns an error only drops out of
+//! that frame, so e.g. a CPU `CorrelationTrack` keeps the track alive next to
+//! a `VitTrack` whose NPU stalls.
+
+use ndarray::ArrayView3;
+
+use crate::fallback::CorrelationTrack;
+use crate::postprocess::TrackingResult;
+use crate::preprocess::BBox;
+use crate::rknn::RknnError;
+use crate::tracker::VitTrack;
+
+/// Single-target tracker that can be a `FusedTracker` member
+pub trait TargetTracker {
+    fn init(&mut self, image: &ArrayView3<u8>, bbox: BBox);
+
+    fn update(&mut self, image: &ArrayView3<u8>) -> Result<TrackingResult, RknnError>;
+
+    /// Move the tracker to the fused box without retraining
+    fn set_bbox(&mut self, rect: [i32; 4]);
+}
+
+impl TargetTracker for VitTrack {
+    fn init(&mut self, image: &ArrayView3<u8>, bbox: BBox) {
+        VitTrack::init(self, image, bbox);
+    }
+
+    fn update(&mut self, image: &ArrayView3<u8>) -> Result<TrackingResult, RknnError> {
+        VitTrack::update(self, image)
+    }
+
+    fn set_bbox(&mut self, rect: [i32; 4]) {
+        VitTrack::set_bbox(self, rect);
+    }
+}
+
+impl TargetTracker for CorrelationTrack {
+    fn init(&mut self, image: &ArrayView3<u8>, bbox: BBox) {
+        CorrelationTrack::init(self, image, bbox);
+    }
+
+    fn update(&mut self, image: &ArrayView3<u8>) -> Result<TrackingResult, RknnError> {
+        Ok(CorrelationTrack::update(self, image))
+    }
+
+    fn set_bbox(&mut self, rect: [i32; 4]) {
+        CorrelationTrack::set_bbox(self, rect);
+    }
+}
+
+/// Mapping of a member's score to the probability that its box is right
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Calibration {
+    /// The score is used as is (clamped to [0, 1])
+    #[default]
+    Identity,
+    /// Logistic fit on labelled runs (Platt scaling):
+    /// p = 1 / (1 + exp(-(scale * score + offset)))
+    Platt { scale: f32, offset: f32 },
+}
+
+impl Calibration {
+    pub fn apply(&self, score: f32) -> f32 {
+        match *self {
+            Self::Identity => score.clamp(0.0, 1.0),
+            Self::Platt { scale, offset } => 1.0 / (1.0 + (-(scale * score + offset)).exp()),
+        }
+    }
+}
+
+/// Fusion configuration
+#[derive(Debug, Clone, Copy)]
+pub struct FusedTrackerConfig {
+    /// Fused confidence below which the frame counts as lost
+    pub min_confidence: f32,
+    /// Members whose box overlaps the most confident one by less than this
+    /// are left out of the average (they follow something else)
+    pub min_iou: f32,
+    /// Move every member to the fused box after a successful frame, so a
+    /// member that drifted or lost the target searches in the right place
+    pub feedback: bool,
+}
+
+impl Default for FusedTrackerConfig {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.5,
+            min_iou: 0.3,
+            feedback: true,
+        }
+    }
+}
+
+/// Outcome of one member on the last frame
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemberResult {
+    pub result: TrackingResult,
+    /// Calibrated confidence; 0 when the member failed or errored
+    pub confidence: f32,
+    /// Whether the member's box went into the fused box
+    pub used: bool,
+    pub error: bool,
+}
+
+struct Member {
+    tracker: Box<dyn TargetTracker + Send>,
+    calibration: Calibration,
+    last: MemberResult,
+}
+
+/// One target tracked by several trackers, boxes fused by confidence
+pub struct FusedTracker {
+    config: FusedTrackerConfig,
+    members: Vec<Member>,
+    rect_last: [i32; 4],
+}
+
+impl FusedTracker {
+    pub fn new(config: FusedTrackerConfig) -> Self {
+        Self {
+            config,
+            members: Vec::new(),
+            rect_last: [0, 0, 0, 0],
+        }
+    }
+
+    /// Add a member; call before `init`
+    pub fn push<T>(&mut self, tracker: T, calibration: Calibration)
+    where
+        T: TargetTracker + Send + 'static,
+    {
+        self.members.push(Member {
+            tracker: Box::new(tracker),
+            calibration,
+            last: MemberResult::default(),
+        });
+    }
+
+    pub fn init(&mut self, image: &ArrayView3<u8>, bbox: BBox) {
+        self.rect_last = bbox.to_array();
+        for member in &mut self.members {
+            member.tracker.init(image, bbox);
+            member.last = MemberResult::default();
+        }
+    }
+
+    /// Update every member and fuse their boxes
+    ///
+    /// The fused result carries the fused box and confidence (as `score`);
+    /// the other fields come from the most confident member.
+    ///
+    /// # Returns
+    /// * An error only when every member returned one
+    pub fn update(&mut self, image: &ArrayView3<u8>) -> Result<TrackingResult, RknnError> {
+        let mut first_error = None;
+        for member in &mut self.members {
+            member.last = match member.tracker.update(image) {
+                Ok(result) => MemberResult {
+                    result,
+                    confidence: if result.success {
+                        member.calibration.apply(result.score)
+                    } else {
+                        0.0
+                    },
+                    used: false,
+                    error: false,
+                },
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                    MemberResult {
+                        error: true,
+                        ..MemberResult::default()
+                    }
+                }
+            };
+        }
+        if let Some(error) = first_error
+            && self.members.iter().all(|member| member.last.error)
+        {
+            return Err(error);
+        }
+
+        let result = self.fuse();
+        if result.success {
+            self.rect_last = result.bbox;
+            if self.config.feedback {
+                for member in &mut self.members {
+                    member.tracker.set_bbox(result.bbox);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Member outcomes of the last frame, in `push` order
+    pub fn members(&self) -> impl Iterator<Item = &MemberResult> {
+        self.members.iter().map(|member| &member.last)
+    }
+
+    pub fn get_bbox(&self) -> [i32; 4] {
+        self.rect_last
+    }
+
+    fn fuse(&mut self) -> TrackingResult {
+        let best = self
+            .members
+            .iter()
+            .map(|member| member.last)
+            .filter(|last| last.confidence > 0.0)
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence));
+        let Some(best) = best else {
+            return TrackingResult {
+                bbox: self.rect_last,
+                ..TrackingResult::default()
+            };
+        };
+
+        let best_box = BBox::from_array(&best.result.bbox);
+        let (mut sum, mut weight, mut miss) = ([0.0f32; 4], 0.0f32, 1.0f32);
+        for member in &mut self.members {
+            let last = &mut member.last;
+            last.used = last.confidence > 0.0
+                && BBox::from_array(&last.result.bbox).iou(&best_box) >= self.config.min_iou;
+            if last.used {
+                for (sum, &value) in sum.iter_mut().zip(&last.result.bbox) {
+                    *sum += value as f32 * last.confidence;
+                }
+                weight += last.confidence;
+                // Agreeing members are independent evidence
+                miss *= 1.0 - last.confidence;
+            }
+        }
+
+        let confidence = 1.0 - miss;
+        let success = confidence >= self.config.min_confidence;
+        TrackingResult {
+            success,
+            bbox: if success {
+                sum.map(|value| (value / weight).round() as i32)
+            } else {
+                self.rect_last
+            },
+            score: confidence,
+            ..best.result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    /// Member replaying fixed results
+    struct Scripted(Result<([i32; 4], f32), ()>);
+
+    impl TargetTracker for Scripted {
+        fn init(&mut self, _image: &ArrayView3<u8>, _bbox: BBox) {}
+
+        fn update(&mut self, _image: &ArrayView3<u8>) -> Result<TrackingResult, RknnError> {
+            let (bbox, score) = self
+                .0
+                .map_err(|_| RknnError::InputError("scripted".into()))?;
+            Ok(TrackingResult {
+                success: score >= 0.25,
+                bbox,
+                score,
+                ..TrackingResult::default()
+            })
+        }
+
+        fn set_bbox(&mut self, _rect: [i32; 4]) {}
+    }
+
+    #[test]
+    fn test_weighted_fusion_skips_outliers_and_errors() {
+        let frame = Array3::<u8>::zeros((120, 160, 3));
+        let mut fused = FusedTracker::new(FusedTrackerConfig::default());
+        fused.push(Scripted(Ok(([100, 100, 40, 40], 0.75))), Calibration::Identity);
+        fused.push(Scripted(Ok(([104, 100, 40, 40], 0.25))), Calibration::Identity);
+        // Confident, but on a distractor elsewhere in the frame
+        fused.push(Scripted(Ok(([10, 10, 40, 40], 0.5))), Calibration::Identity);
+        fused.push(Scripted(Err(())), Calibration::Identity);
+        fused.init(&frame.view(), BBox::new(100, 100, 40, 40));
+
+        let result = fused.update(&frame.view()).unwrap();
+        assert!(result.success);
+        assert_eq!(result.bbox, [101, 100, 40, 40]);
+        assert!((result.score - (1.0 - 0.25 * 0.75)).abs() < 1e-6);
+        let used: Vec<bool> = fused.members().map(|m| m.used).collect();
+        assert_eq!(used, [true, true, false, false]);
+
+        let mut broken = FusedTracker::new(FusedTrackerConfig::default());
+        broken.push(Scripted(Err(())), Calibration::Identity);
+        assert!(broken.update(&frame.view()).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use ndarray::ArrayView3;
+
+/// Histogram-based candidate check configuration
+#[derive(Debug, Clone, Copy)]
+pub struct HistogramConfig {
+    /// Candidates below this Bhattacharyya coefficient are rejected
+    pub min_similarity: f32,
+    /// Weight of the similarity when ranking candidates (0 = score only)
+    pub weight: f32,
+}
+
+impl Default for HistogramConfig {
+    fn default() -> Self {
+        Self {
+            min_similarity: 0.5,
+            weight: 0.5,
+        }
+    }
+}
+
+/// Normalized RGB color histogram
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorHistogram {
+    bins: Vec<f32>,
+}
+
+impl ColorHistogram {
+    /// Bins per channel
+    pub const BINS: usize = 8;
+    const SHIFT: u32 = 8 - Self::BINS.trailing_zeros();
+
+    /// Histogram of an image region [x, y, w, h] (clipped to the image)
+    pub fn from_region(image: &ArrayView3<u8>, bbox: &[i32; 4]) -> Self {
+        let (img_h, img_w, _) = image.dim();
+        let [x, y, w, h] = *bbox;
+        let x1 = x.clamp(0, img_w as i32) as usize;
+        let y1 = y.clamp(0, img_h as i32) as usize;
+        let x2 = (x + w).clamp(0, img_w as i32) as usize;
+        let y2 = (y + h).clamp(0, img_h as i32) as usize;
+
+        let mut bins = vec![0.0f32; Self::BINS * Self::BINS * Self::BINS];
+        for yy in y1..y2 {
+            for xx in x1..x2 {
+                let r = (image[[yy, xx, 0]] >> Self::SHIFT) as usize;
+                let g = (image[[yy, xx, 1]] >> Self::SHIFT) as usize;
+                let b = (image[[yy, xx, 2]] >> Self::SHIFT) as usize;
+                bins[(r * Self::BINS + g) * Self::BINS + b] += 1.0;
+            }
+        }
+
+        let total: f32 = bins.iter().sum();
+        if total > 0.0 {
+            bins.iter_mut().for_each(|v| *v /= total);
+        }
+
+        Self { bins }
+    }
+
+    /// Bhattacharyya coefficient in [0, 1] (1 = identical distributions)
+    pub fn similarity(&self, other: &ColorHistogram) -> f32 {
+        self.bins
+            .iter()
+            .zip(&other.bins)
+            .map(|(a, b)| (a * b).sqrt())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_similarity() {
+        let mut image = Array3::<u8>::zeros((20, 40, 3));
+        for y in 0..20 {
+            for x in 0..20 {
+                image[[y, x, 0]] = 250;
+            }
+            for x in 20..40 {
+                image[[y, x, 2]] = 250;
+            }
+        }
+        let view = image.view();
+
+        let red = ColorHistogram::from_region(&view, &[0, 0, 20, 20]);
+        let red_again = ColorHistogram::from_region(&view, &[2, 2, 10, 10]);
+        let blue = ColorHistogram::from_region(&view, &[20, 0, 20, 20]);
+
+        assert!((red.similarity(&red_again) - 1.0).abs() < 1e-5);
+        assert!(red.similarity(&blue) < 1e-5);
+    }
+}
@@ -0,0 +1,311 @@
+//! MISB ST 0601 KLV metadata packets
+//!
+//! `KlvEncoder` turns a tracking result into a UAS Datalink Local Set packet
+//! ready to be muxed as the KLV data stream of a STANAG 4609 transport
+//! stream. Each packet carries:
+//!
+//! * Precision Time Stamp (tag 2) and LS version (tag 65)
+//! * Target Track Gate width and height (tags 43, 44)
+//! * Target Location latitude, longitude and elevation (tags 40-42), when the
+//!   result is geolocated from a pose log (`TrackingResult::geo`) or has a
+//!   ground-plane position and a `GeoOrigin` is configured
+//! * A VMTI Local Set (tag 74, MISB ST 0903) with the target's centroid and
+//!   box as pixel numbers and the score as confidence
+//! * Checksum (tag 1)
+//!
+//! Nothing is sent for the target on frames without it; the packet still
+//! carries the time stamp so the stream stays continuous.
+//!
+//! Camera intrinsics alone give the target direction relative to the sensor,
+//! not a geodetic position: they need the platform pose from a synced pose
+//! log (`VitTrackConfig::pose_sync`) to produce tags 40-42.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::postprocess::TrackingResult;
+
+/// Universal key of the UAS Datalink Local Set
+pub const UAS_LOCAL_SET_KEY: [u8; 16] = [
+    0x06, 0x0E, 0x2B, 0x34, 0x02, 0x0B, 0x01, 0x01, 0x0E, 0x01, 0x03, 0x01, 0x01, 0x00, 0x00, 0x00,
+];
+
+const TAG_CHECKSUM: u64 = 1;
+const TAG_TIMESTAMP: u64 = 2;
+const TAG_TARGET_LATITUDE: u64 = 40;
+const TAG_TARGET_LONGITUDE: u64 = 41;
+const TAG_TARGET_ELEVATION: u64 = 42;
+const TAG_GATE_WIDTH: u64 = 43;
+const TAG_GATE_HEIGHT: u64 = 44;
+const TAG_VERSION: u64 = 65;
+const TAG_VMTI: u64 = 74;
+
+const VMTI_VERSION: u64 = 4;
+const VMTI_TOTAL_TARGETS: u64 = 5;
+const VMTI_REPORTED_TARGETS: u64 = 6;
+const VMTI_FRAME_WIDTH: u64 = 8;
+const VMTI_FRAME_HEIGHT: u64 = 9;
+const VMTI_TARGET_SERIES: u64 = 101;
+const VTARGET_CENTROID: u64 = 1;
+const VTARGET_TOP_LEFT: u64 = 2;
+const VTARGET_BOTTOM_RIGHT: u64 = 3;
+const VTARGET_CONFIDENCE: u64 = 5;
+
+/// Mean Earth radius for the local tangent plane approximation
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Geodetic position of the ground-plane origin
+///
+/// The homography's world x axis points east and y north, in meters; over
+/// the few kilometers a camera sees the plane approximation holds.
+#[derive(Debug, Clone, Copy)]
+pub struct GeoOrigin {
+    /// Degrees, positive north
+    pub latitude: f64,
+    /// Degrees, positive east
+    pub longitude: f64,
+    /// Meters above the ellipsoid
+    pub altitude: f32,
+}
+
+impl GeoOrigin {
+    /// Latitude and longitude of a ground-plane point in meters
+    pub fn geodetic(&self, east: f32, north: f32) -> (f64, f64) {
+        let latitude = self.latitude + (north as f64 / EARTH_RADIUS).to_degrees();
+        let scale = EARTH_RADIUS * self.latitude.to_radians().cos().max(1e-6);
+        let longitude = self.longitude + (east as f64 / scale).to_degrees();
+        (latitude, longitude)
+    }
+}
+
+/// KLV encoder configuration
+#[derive(Debug, Clone, Copy)]
+pub struct KlvConfig {
+    /// UAS Datalink LS version reported in tag 65
+    pub version: u8,
+    /// Enables the target location tags for results with a world position
+    pub origin: Option<GeoOrigin>,
+    /// Add the VMTI Local Set with pixel coordinates
+    pub vmti: bool,
+}
+
+impl Default for KlvConfig {
+    fn default() -> Self {
+        Self {
+            version: 17,
+            origin: None,
+            vmti: true,
+        }
+    }
+}
+
+/// Builds MISB ST 0601 packets from tracking results
+pub struct KlvEncoder {
+    config: KlvConfig,
+}
+
+impl KlvEncoder {
+    pub fn new(config: KlvConfig) -> Self {
+        Self { config }
+    }
+
+    /// Encode one packet
+    ///
+    /// # Arguments
+    /// * `result` - Tracking result of the frame
+    /// * `frame_size` - Frame (width, height) in pixels
+    /// * `time` - UTC capture time of the frame
+    ///
+    /// # Returns
+    /// * Complete packet: key, BER length and local set ending with the checksum
+    pub fn encode(
+        &self,
+        result: &TrackingResult,
+        frame_size: (usize, usize),
+        time: SystemTime,
+    ) -> Vec<u8> {
+        let micros = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as u64;
+        let mut set = Vec::with_capacity(96);
+        push_item(&mut set, TAG_TIMESTAMP, &micros.to_be_bytes());
+        push_item(&mut set, TAG_VERSION, &[self.config.version]);
+
+        if result.success {
+            let [_, _, w, h] = result.bbox;
+            push_item(&mut set, TAG_GATE_WIDTH, &[track_gate(w)]);
+            push_item(&mut set, TAG_GATE_HEIGHT, &[track_gate(h)]);
+
+            if let Some((latitude, longitude, altitude)) = self.location(result) {
+                push_item(&mut set, TAG_TARGET_LATITUDE, &map_angle(latitude, 90.0));
+                push_item(&mut set, TAG_TARGET_LONGITUDE, &map_angle(longitude, 180.0));
+                push_item(&mut set, TAG_TARGET_ELEVATION, &map_elevation(altitude));
+            }
+        }
+        if self.config.vmti {
+            push_item(&mut set, TAG_VMTI, &vmti_set(result, frame_size));
+        }
+
+        let mut packet = Vec::with_capacity(set.len() + 24);
+        packet.extend_from_slice(&UAS_LOCAL_SET_KEY);
+        // The checksum item (tag, length, 2 bytes) is part of the set length
+        push_ber_length(&mut packet, set.len() + 4);
+        packet.extend_from_slice(&set);
+        packet.extend_from_slice(&[TAG_CHECKSUM as u8, 2]);
+        let checksum = checksum(&packet);
+        packet.extend_from_slice(&checksum.to_be_bytes());
+        packet
+    }
+
+    /// Geolocation from the platform pose, else from the ground plane
+    fn location(&self, result: &TrackingResult) -> Option<(f64, f64, f32)> {
+        if let Some(geo) = result.geo {
+            return Some((geo.latitude, geo.longitude, geo.altitude));
+        }
+        let (origin, world) = (self.config.origin?, result.world?);
+        let (latitude, longitude) = origin.geodetic(world.x, world.y);
+        Some((latitude, longitude, origin.altitude))
+    }
+}
+
+/// Nested MISB ST 0903 VMTI Local Set with zero or one target
+fn vmti_set(result: &TrackingResult, frame_size: (usize, usize)) -> Vec<u8> {
+    let (width, height) = frame_size;
+    let reported = u64::from(result.success);
+    let mut set = Vec::with_capacity(48);
+    push_item(&mut set, VMTI_VERSION, &uint_bytes(5));
+    push_item(&mut set, VMTI_TOTAL_TARGETS, &uint_bytes(reported));
+    push_item(&mut set, VMTI_REPORTED_TARGETS, &uint_bytes(reported));
+    push_item(&mut set, VMTI_FRAME_WIDTH, &uint_bytes(width as u64));
+    push_item(&mut set, VMTI_FRAME_HEIGHT, &uint_bytes(height as u64));
+    if !result.success || width == 0 || height == 0 {
+        return set;
+    }
+
+    // Pixel number: row * width + column + 1, clamped to the frame
+    let pixel = |x: i32, y: i32| {
+        let column = x.clamp(0, width as i32 - 1) as u64;
+        let row = y.clamp(0, height as i32 - 1) as u64;
+        uint_bytes(row * width as u64 + column + 1)
+    };
+    let [x, y, w, h] = result.bbox;
+    let mut target = Vec::with_capacity(24);
+    // The track id when tagged (VMTI ids start at 1); 1 for an untagged target
+    push_ber_oid(&mut target, result.tag.map_or(1, |tag| u64::from(tag.id).max(1)));
+    push_item(&mut target, VTARGET_CENTROID, &pixel(x + w / 2, y + h / 2));
+    push_item(&mut target, VTARGET_TOP_LEFT, &pixel(x, y));
+    push_item(&mut target, VTARGET_BOTTOM_RIGHT, &pixel(x + w - 1, y + h - 1));
+    let confidence = (result.score.clamp(0.0, 1.0) * 100.0).round() as u8;
+    push_item(&mut target, VTARGET_CONFIDENCE, &[confidence]);
+
+    let mut series = Vec::with_capacity(target.len() + 2);
+    push_ber_length(&mut series, target.len());
+    series.extend_from_slice(&target);
+    push_item(&mut set, VMTI_TARGET_SERIES, &series);
+    set
+}
+
+fn push_item(out: &mut Vec<u8>, tag: u64, value: &[u8]) {
+    push_ber_oid(out, tag);
+    push_ber_length(out, value.len());
+    out.extend_from_slice(value);
+}
+
+/// BER-OID: 7 bits per byte, high bit set on all but the last
+fn push_ber_oid(out: &mut Vec<u8>, value: u64) {
+    let groups = (64 - value.leading_zeros()).div_ceil(7).max(1);
+    for i in (0..groups).rev() {
+        let byte = (value >> (7 * i)) as u8 & 0x7F;
+        out.push(if i == 0 { byte } else { byte | 0x80 });
+    }
+}
+
+/// BER length: short form below 128, long form otherwise
+fn push_ber_length(out: &mut Vec<u8>, len: usize) {
+    if len < 128 {
+        out.push(len as u8);
+        return;
+    }
+    let bytes = uint_bytes(len as u64);
+    out.push(0x80 | bytes.len() as u8);
+    out.extend_from_slice(&bytes);
+}
+
+/// Big-endian unsigned integer without leading zero bytes
+fn uint_bytes(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = (value.leading_zeros() / 8).min(7) as usize;
+    bytes[skip..].to_vec()
+}
+
+/// Track gate size: 2 pixels per unit, 0 to 510 pixels
+fn track_gate(pixels: i32) -> u8 {
+    (pixels.max(0) / 2).min(255) as u8
+}
+
+/// Signed 32-bit mapping of [-range, range] degrees
+fn map_angle(degrees: f64, range: f64) -> [u8; 4] {
+    let scaled = (degrees.clamp(-range, range) / range * i32::MAX as f64).round();
+    (scaled as i32).to_be_bytes()
+}
+
+/// Unsigned 16-bit mapping of [-900, 19000] meters
+fn map_elevation(meters: f32) -> [u8; 2] {
+    let scaled = ((meters.clamp(-900.0, 19_000.0) + 900.0) / 19_900.0 * 65_535.0).round();
+    (scaled as u16).to_be_bytes()
+}
+
+/// 16-bit running sum, even bytes in the high half
+fn checksum(bytes: &[u8]) -> u16 {
+    bytes.iter().enumerate().fold(0u16, |sum, (i, &byte)| {
+        sum.wrapping_add((byte as u16) << (8 * ((i + 1) % 2)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::WorldTarget;
+    use std::time::Duration;
+
+    #[test]
+    fn test_packet_layout_and_checksum() {
+        let encoder = KlvEncoder::new(KlvConfig {
+            origin: Some(GeoOrigin {
+                latitude: 45.0,
+                longitude: 10.0,
+                altitude: 120.0,
+            }),
+            ..KlvConfig::default()
+        });
+        let result = TrackingResult {
+            success: true,
+            bbox: [100, 50, 40, 20],
+            score: 0.9,
+            world: Some(WorldTarget {
+                x: 100.0,
+                y: 200.0,
+                ..WorldTarget::default()
+            }),
+            ..TrackingResult::default()
+        };
+        let time = UNIX_EPOCH + Duration::from_micros(1_700_000_000_000_000);
+        let packet = encoder.encode(&result, (640, 480), time);
+
+        assert_eq!(packet[..16], UAS_LOCAL_SET_KEY);
+        assert_eq!(packet[16] as usize, packet.len() - 17);
+        // Time stamp first, checksum last
+        assert_eq!(packet[17..19], [2, 8]);
+        assert_eq!(packet[19..27], 1_700_000_000_000_000u64.to_be_bytes());
+        let (body, sum) = packet.split_at(packet.len() - 2);
+        assert_eq!(body[body.len() - 2..], [1, 2]);
+        assert_eq!(u16::from_be_bytes([sum[0], sum[1]]), checksum(body));
+        // Gate width 40 px and a latitude item
+        assert!(packet.windows(3).any(|w| w == [43, 1, 20]));
+        assert!(packet.windows(2).any(|w| w == [40, 4]));
+
+        let mut length = Vec::new();
+        push_ber_length(&mut length, 300);
+        assert_eq!(length, [0x82, 0x01, 0x2C]);
+        let mut oid = Vec::new();
+        push_ber_oid(&mut oid, 144);
+        assert_eq!(oid, [0x81, 0x10]);
+    }
+}
@@ -0,0 +1,120 @@
+//! Application identity attached to a track
+//!
+//! `TrackTag` is small and `Copy`, so it travels inside every
+//! `TrackingResult` and from there into the report CSV, KLV and RPC output.
+//! The label text and an arbitrary payload stay with the tracker
+//! (`VitTrack::label`), next to the results it produces.
+//!
+//! Overlays, snapshots and report thumbnails draw tagged tracks in
+//! `TrackTag::color`, which depends on the id only: a track keeps its color
+//! while coasting, lost and recovered, and an external UI can reproduce it.
+
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+/// Track id and class, copied into every result
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TrackTag {
+    pub id: u32,
+    pub class_id: Option<u32>,
+}
+
+impl TrackTag {
+    /// Display color (RGB) of the track
+    ///
+    /// Hue steps by the golden angle (137.508°) per id, so neighbouring ids
+    /// get well separated hues; saturation 0.85, value 0.95.
+    pub fn color(&self) -> [u8; 3] {
+        const GOLDEN_ANGLE: f64 = 137.50776405;
+        let hue = (self.id as f64 * GOLDEN_ANGLE).rem_euclid(360.0) / 60.0;
+        let (s, v) = (0.85, 0.95);
+        let f = hue.fract();
+        let (p, q, t) = (v * (1.0 - s), v * (1.0 - s * f), v * (1.0 - s * (1.0 - f)));
+        let rgb = match hue as u32 {
+            0 => [v, t, p],
+            1 => [q, v, p],
+            2 => [p, v, t],
+            3 => [p, q, v],
+            4 => [t, p, v],
+            _ => [v, p, q],
+        };
+        rgb.map(|c| (c * 255.0).round() as u8)
+    }
+}
+
+/// Identity and user payload of a track
+#[derive(Clone, Default)]
+pub struct TrackLabel {
+    pub tag: TrackTag,
+    /// Display name, e.g. "truck 3"
+    pub name: String,
+    data: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl TrackLabel {
+    pub fn new(id: u32, name: impl Into<String>) -> Self {
+        Self {
+            tag: TrackTag { id, class_id: None },
+            name: name.into(),
+            data: None,
+        }
+    }
+
+    pub fn with_class(mut self, class_id: u32) -> Self {
+        self.tag.class_id = Some(class_id);
+        self
+    }
+
+    /// Attach any value, e.g. a detector record or a `serde_json::Value`
+    pub fn with_data<T: Any + Send + Sync>(mut self, data: T) -> Self {
+        self.data = Some(Arc::new(data));
+        self
+    }
+
+    /// The attached value, if it is a `T`
+    pub fn data<T: Any>(&self) -> Option<&T> {
+        self.data.as_deref()?.downcast_ref()
+    }
+}
+
+impl fmt::Debug for TrackLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrackLabel")
+            .field("tag", &self.tag)
+            .field("name", &self.name)
+            .field("data", &self.data.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_data() {
+        struct Detection {
+            confidence: f32,
+        }
+        let label = TrackLabel::new(7, "truck")
+            .with_class(3)
+            .with_data(Detection { confidence: 0.9 });
+        assert_eq!(label.tag, TrackTag { id: 7, class_id: Some(3) });
+        assert_eq!(label.data::<Detection>().map(|d| d.confidence), Some(0.9));
+        assert!(label.data::<String>().is_none());
+        assert!(label.clone().data::<Detection>().is_some());
+    }
+
+    #[test]
+    fn test_color_is_stable_and_distinct() {
+        let color = |id| TrackTag { id, class_id: None }.color();
+        assert_eq!(color(0), [242, 36, 36]);
+        assert_eq!(color(5), color(5));
+        // The class does not change the color
+        assert_eq!(TrackTag { id: 5, class_id: Some(2) }.color(), color(5));
+        for id in 0..16 {
+            assert_ne!(color(id), color(id + 1));
+        }
+    }
+}
@@ -1,8 +1,100 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod decode;
+#[cfg(feature = "std")]
+pub mod budget;
+#[cfg(feature = "std")]
+pub mod camera;
+#[cfg(feature = "std")]
+pub mod control;
+#[cfg(feature = "std")]
+pub mod coords;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod fallback;
+#[cfg(feature = "rknn")]
+pub mod fused;
+#[cfg(feature = "std")]
+pub mod histogram;
+#[cfg(feature = "std")]
 pub mod preprocess;
+#[cfg(feature = "std")]
+pub mod preprocessor;
+#[cfg(feature = "std")]
+pub mod proposal;
+#[cfg(feature = "std")]
+pub mod klv;
+#[cfg(feature = "std")]
+pub mod label;
+#[cfg(feature = "std")]
+pub mod mask;
+#[cfg(feature = "std")]
+pub mod model_info;
+#[cfg(feature = "std")]
+pub mod motion;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+#[cfg(feature = "rknn")]
+pub mod pipeline;
+#[cfg(feature = "std")]
+pub mod pose;
+#[cfg(feature = "std")]
+pub mod postfix;
+#[cfg(feature = "std")]
 pub mod postprocess;
+#[cfg(feature = "std")]
+pub mod quality;
+#[cfg(feature = "std")]
+pub mod reload;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "rknn")]
 pub mod rknn;
+#[cfg(feature = "std")]
+pub mod roi;
+#[cfg(feature = "std")]
+pub mod rotation;
+#[cfg(feature = "rknn")]
+pub mod selftest;
+#[cfg(feature = "std")]
+pub mod snapshot;
+#[cfg(feature = "rknn")]
+pub mod soak;
+#[cfg(feature = "std")]
+pub mod stages;
+#[cfg(feature = "std")]
+pub mod stationary;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod subtitle;
+#[cfg(feature = "std")]
+pub mod template;
+#[cfg(feature = "std")]
+pub mod tensor_dump;
+#[cfg(feature = "std")]
+pub mod threshold;
+#[cfg(feature = "std")]
+pub mod trajectory;
+#[cfg(feature = "rknn")]
 pub mod tracker;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod watchdog;
+#[cfg(feature = "std")]
+pub mod world;
 
+#[cfg(feature = "std")]
+pub use camera::{AngularTarget, CameraIntrinsics};
+#[cfg(feature = "std")]
 pub use preprocess::BBox;
-pub use tracker::VitTrack;
-pub use postprocess::TrackingResult;
\ No newline at end of file
+#[cfg(feature = "std")]
+pub use rotation::RotatedBox;
+#[cfg(feature = "rknn")]
+pub use tracker::{VitTrack, VitTrackModel};
+#[cfg(feature = "std")]
+pub use postprocess::TrackingResult;
+#[cfg(feature = "std")]
+pub use world::{GroundPlane, WorldTarget};
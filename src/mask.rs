@@ -0,0 +1,175 @@
+//! Target segmentation from a mask head
+//!
+//! Some tracker exports also output a mask of the target over the search
+//! crop. `TargetMask` keeps that small probability map together with the frame
+//! region it covers; `contains` samples it bilinearly, so outlines follow the
+//! target at frame resolution rather than in mask-cell steps.
+
+use ndarray::ArrayViewMut3;
+
+use crate::postprocess::TrackingResult;
+use crate::stages::CropTransform;
+
+/// Mask head configuration
+#[derive(Debug, Clone)]
+pub struct MaskConfig {
+    /// Name of the mask output (listed in `VitTrackConfig::output_names`)
+    pub output: String,
+    /// Probability from which a pixel belongs to the target
+    pub threshold: f32,
+    /// The head emits logits; a sigmoid is applied first
+    pub logits: bool,
+}
+
+impl Default for MaskConfig {
+    fn default() -> Self {
+        Self {
+            output: "mask".into(),
+            threshold: 0.5,
+            logits: false,
+        }
+    }
+}
+
+/// Target probability over the search crop of one frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetMask {
+    /// Row-major probabilities, `side` x `side`
+    pub probs: Vec<f32>,
+    pub side: usize,
+    pub threshold: f32,
+    /// Frame region the mask covers
+    pub transform: CropTransform,
+}
+
+/// Tracking result with the target mask of the same frame
+#[derive(Debug, Clone, Default)]
+pub struct MaskedResult {
+    pub result: TrackingResult,
+    /// None when tracking failed or the frame had no mask
+    pub mask: Option<TargetMask>,
+}
+
+impl TargetMask {
+    /// Mask from a square mask head output
+    ///
+    /// # Returns
+    /// * None when the output is empty or not square
+    pub fn decode(values: &[f32], config: &MaskConfig, transform: CropTransform) -> Option<Self> {
+        let side = values.len().isqrt();
+        if side == 0 || side * side != values.len() {
+            return None;
+        }
+        let probs = if config.logits {
+            values.iter().map(|&v| 1.0 / (1.0 + (-v).exp())).collect()
+        } else {
+            values.to_vec()
+        };
+        Some(Self {
+            probs,
+            side,
+            threshold: config.threshold,
+            transform,
+        })
+    }
+
+    /// Frame pixels per mask cell
+    pub fn cell_size(&self) -> f32 {
+        self.transform.crop_size as f32 / self.side as f32
+    }
+
+    /// Probability at a frame pixel, bilinear between cell centers
+    pub fn prob_at(&self, x: i32, y: i32) -> f32 {
+        let (ox, oy) = self.transform.origin;
+        let cell = self.cell_size();
+        let last = (self.side - 1) as f32;
+        let u = (((x - ox) as f32 + 0.5) / cell - 0.5).clamp(0.0, last);
+        let v = (((y - oy) as f32 + 0.5) / cell - 0.5).clamp(0.0, last);
+        let (x0, y0) = (u as usize, v as usize);
+        let (x1, y1) = ((x0 + 1).min(self.side - 1), (y0 + 1).min(self.side - 1));
+        let (fx, fy) = (u - x0 as f32, v - y0 as f32);
+        let at = |cx: usize, cy: usize| self.probs[cy * self.side + cx];
+        let top = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * fx;
+        let bottom = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * fx;
+        top + (bottom - top) * fy
+    }
+
+    /// Whether a frame pixel is on the target
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let (ox, oy) = self.transform.origin;
+        let size = self.transform.crop_size;
+        (ox..ox + size).contains(&x)
+            && (oy..oy + size).contains(&y)
+            && self.prob_at(x, y) >= self.threshold
+    }
+
+    /// Mask cells at or above the threshold
+    pub fn area(&self) -> usize {
+        self.probs.iter().filter(|&&p| p >= self.threshold).count()
+    }
+
+    /// Draw the mask onto the frame it was decoded on
+    ///
+    /// # Arguments
+    /// * `image` - Frame in HWC RGB format
+    /// * `color` - Fill and outline color
+    /// * `alpha` - Fill opacity; 0 draws the outline only
+    pub fn overlay(&self, image: &mut ArrayViewMut3<u8>, color: [u8; 3], alpha: f32) {
+        let (img_h, img_w, _) = image.dim();
+        let (ox, oy) = self.transform.origin;
+        let size = self.transform.crop_size;
+        let alpha = alpha.clamp(0.0, 1.0);
+        for y in oy.max(0)..(oy + size).min(img_h as i32) {
+            for x in ox.max(0)..(ox + size).min(img_w as i32) {
+                if !self.contains(x, y) {
+                    continue;
+                }
+                let outline = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                    .iter()
+                    .any(|&(nx, ny)| !self.contains(nx, ny));
+                let weight = if outline { 1.0 } else { alpha };
+                for (c, &target) in color.iter().enumerate() {
+                    let pixel = &mut image[[y as usize, x as usize, c]];
+                    let blended = *pixel as f32 + (target as f32 - *pixel as f32) * weight;
+                    *pixel = blended.round() as u8;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_decode_contains_and_overlay() {
+        // 4x4 mask over a 40px crop at (10, 10): target in cells (1..3, 1..3)
+        let mut logits = vec![-10.0f32; 16];
+        for idx in [5, 6, 9, 10] {
+            logits[idx] = 10.0;
+        }
+        let config = MaskConfig {
+            logits: true,
+            ..MaskConfig::default()
+        };
+        let transform = CropTransform {
+            origin: (10, 10),
+            crop_size: 40,
+        };
+        let mask = TargetMask::decode(&logits, &config, transform).unwrap();
+        assert_eq!(mask.area(), 4);
+        assert!(mask.contains(30, 30));
+        assert!(mask.contains(21, 30));
+        assert!(!mask.contains(12, 12));
+        assert!(!mask.contains(60, 30));
+        assert!(TargetMask::decode(&logits[..15], &config, transform).is_none());
+
+        let mut frame = Array3::<u8>::zeros((60, 60, 3));
+        mask.overlay(&mut frame.view_mut(), [0, 255, 0], 0.0);
+        assert_eq!(frame[[30, 20, 1]], 255); // outline
+        assert_eq!(frame[[30, 30, 1]], 0); // interior, fill off
+        assert_eq!(frame[[12, 12, 1]], 0);
+    }
+}
@@ -0,0 +1,444 @@
+//! Input/output signature of a model, for checking a conversion
+//!
+//! Reads the input and output names and shapes of an ONNX file without an
+//! inference runtime (a minimal protobuf walk over the graph) and compares
+//! them with the signature of the converted model. A conversion that swapped
+//! the template and search inputs or changed the score map size still runs,
+//! it just returns garbage; `ModelInfo::cross_check` names the problem:
+//!
+//! ```ignore
+//! let onnx = ModelInfo::read_onnx("vittrack.onnx")?;
+//! for mismatch in onnx.cross_check(&ModelInfo::vittrack(128, 256, 16)) {
+//!     eprintln!("{}", mismatch);
+//! }
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ModelInfoError {
+    #[error("Failed to read model: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Malformed ONNX file: {0}")]
+    Malformed(String),
+}
+
+/// Name and shape of one model input or output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TensorInfo {
+    pub name: String,
+    /// Dimensions; -1 for symbolic or unknown ones
+    pub dims: Vec<i64>,
+}
+
+impl TensorInfo {
+    pub fn new(name: impl Into<String>, dims: &[i64]) -> Self {
+        Self {
+            name: name.into(),
+            dims: dims.to_vec(),
+        }
+    }
+
+    /// Whether both describe the same tensor, possibly in another layout
+    ///
+    /// Fully known shapes must have the same dimensions in any order (NCHW
+    /// in the ONNX file vs NHWC in the converted model); shapes with unknown
+    /// dimensions only need the same rank.
+    pub fn same_shape(&self, other: &TensorInfo) -> bool {
+        let known = |dims: &[i64]| dims.iter().all(|&d| d > 0);
+        if !known(&self.dims) || !known(&other.dims) {
+            return self.dims.len() == other.dims.len();
+        }
+        let mut a = self.dims.clone();
+        let mut b = other.dims.clone();
+        a.sort_unstable();
+        b.sort_unstable();
+        a == b
+    }
+}
+
+impl fmt::Display for TensorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dims: Vec<String> = self
+            .dims
+            .iter()
+            .map(|&d| if d > 0 { d.to_string() } else { "?".into() })
+            .collect();
+        write!(f, "{} [{}]", self.name, dims.join("x"))
+    }
+}
+
+/// Problem found by `ModelInfo::cross_check`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// Different number of inputs or outputs
+    Count {
+        what: &'static str,
+        source: usize,
+        converted: usize,
+    },
+    /// Template and search inputs appear in opposite order
+    SwappedInputs,
+    /// Input or output at `index` has another shape
+    Shape {
+        what: &'static str,
+        index: usize,
+        source: TensorInfo,
+        converted: TensorInfo,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Count {
+                what,
+                source,
+                converted,
+            } => write!(
+                f,
+                "{} count: source {}, converted {}",
+                what, source, converted
+            ),
+            Self::SwappedInputs => {
+                write!(f, "inputs swapped: template and search in reverse order")
+            }
+            Self::Shape {
+                what,
+                index,
+                source,
+                converted,
+            } => write!(
+                f,
+                "{} {}: source {}, converted {}",
+                what, index, source, converted
+            ),
+        }
+    }
+}
+
+/// Inputs and outputs of a model, in model order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelInfo {
+    pub inputs: Vec<TensorInfo>,
+    pub outputs: Vec<TensorInfo>,
+}
+
+impl ModelInfo {
+    /// Signature `RknnModel` feeds and reads
+    ///
+    /// NHWC template and search inputs, then the NCHW confidence, size and
+    /// offset maps. Compare with the tensor attributes of a converted model
+    /// (e.g. as printed by rknn-toolkit) or with the source ONNX file.
+    ///
+    /// # Arguments
+    /// * `template_size` - Template input side in pixels
+    /// * `search_size` - Search input side in pixels
+    /// * `score_size` - Side of the score map
+    pub fn vittrack(template_size: usize, search_size: usize, score_size: usize) -> Self {
+        let (t, s, m) = (template_size as i64, search_size as i64, score_size as i64);
+        Self {
+            inputs: vec![
+                TensorInfo::new("template", &[1, t, t, 3]),
+                TensorInfo::new("search", &[1, s, s, 3]),
+            ],
+            outputs: vec![
+                TensorInfo::new("conf_map", &[1, 1, m, m]),
+                TensorInfo::new("size_map", &[1, 2, m, m]),
+                TensorInfo::new("offset_map", &[1, 2, m, m]),
+            ],
+        }
+    }
+
+    /// Read the graph inputs and outputs of an ONNX file
+    pub fn read_onnx<P: AsRef<Path>>(path: P) -> Result<Self, ModelInfoError> {
+        Self::parse_onnx(&fs::read(path)?)
+    }
+
+    /// Parse the graph inputs and outputs of a serialized ONNX `ModelProto`
+    ///
+    /// Initializers listed as graph inputs (older exports) are left out.
+    pub fn parse_onnx(bytes: &[u8]) -> Result<Self, ModelInfoError> {
+        // ModelProto.graph = 7
+        let mut graph = None;
+        for field in Fields::new(bytes) {
+            if let (7, Value::Bytes(data)) = field? {
+                graph = Some(data);
+            }
+        }
+        let graph = graph.ok_or_else(|| ModelInfoError::Malformed("no graph".into()))?;
+
+        // GraphProto.initializer = 5, input = 11, output = 12
+        let (mut inputs, mut outputs, mut initializers) = (Vec::new(), Vec::new(), Vec::new());
+        for field in Fields::new(graph) {
+            match field? {
+                (5, Value::Bytes(data)) => initializers.push(initializer_name(data)?),
+                (11, Value::Bytes(data)) => inputs.push(value_info(data)?),
+                (12, Value::Bytes(data)) => outputs.push(value_info(data)?),
+                _ => {}
+            }
+        }
+        inputs.retain(|input| !initializers.contains(&input.name));
+        Ok(Self { inputs, outputs })
+    }
+
+    /// Compare this (source) signature with a converted model's
+    ///
+    /// # Returns
+    /// * Every mismatch found; empty when the signatures agree
+    pub fn cross_check(&self, converted: &ModelInfo) -> Vec<Mismatch> {
+        let mut mismatches = Vec::new();
+        if let ([a, b], [c, d]) = (&self.inputs[..], &converted.inputs[..]) {
+            let by_name = !a.name.is_empty() && a.name == d.name && b.name == c.name;
+            let by_shape = !a.same_shape(c) && a.same_shape(d) && b.same_shape(c);
+            if by_name || by_shape {
+                mismatches.push(Mismatch::SwappedInputs);
+                return mismatches;
+            }
+        }
+
+        let lists = [
+            ("input", &self.inputs, &converted.inputs),
+            ("output", &self.outputs, &converted.outputs),
+        ];
+        for (what, source, converted) in lists {
+            if source.len() != converted.len() {
+                mismatches.push(Mismatch::Count {
+                    what,
+                    source: source.len(),
+                    converted: converted.len(),
+                });
+            }
+            for (index, (a, b)) in source.iter().zip(converted).enumerate() {
+                if !a.same_shape(b) {
+                    mismatches.push(Mismatch::Shape {
+                        what,
+                        index,
+                        source: a.clone(),
+                        converted: b.clone(),
+                    });
+                }
+            }
+        }
+        mismatches
+    }
+}
+
+/// ValueInfoProto: name = 1, type = 2
+fn value_info(data: &[u8]) -> Result<TensorInfo, ModelInfoError> {
+    let mut info = TensorInfo::new("", &[]);
+    for field in Fields::new(data) {
+        match field? {
+            (1, Value::Bytes(name)) => info.name = string(name)?,
+            // TypeProto.tensor_type = 1 -> Tensor.shape = 2 -> TensorShapeProto.dim = 1
+            (2, Value::Bytes(type_proto)) => {
+                for tensor in nested(type_proto, 1) {
+                    for shape in nested(tensor?, 2) {
+                        for dim in nested(shape?, 1) {
+                            info.dims.push(dimension(dim?)?);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(info)
+}
+
+/// Dimension: dim_value = 1, dim_param = 2 (symbolic)
+fn dimension(data: &[u8]) -> Result<i64, ModelInfoError> {
+    let mut value = -1;
+    for field in Fields::new(data) {
+        if let (1, Value::Varint(v)) = field? {
+            value = v as i64;
+        }
+    }
+    Ok(value)
+}
+
+/// TensorProto.name = 8
+fn initializer_name(data: &[u8]) -> Result<String, ModelInfoError> {
+    for field in Fields::new(data) {
+        if let (8, Value::Bytes(name)) = field? {
+            return string(name);
+        }
+    }
+    Ok(String::new())
+}
+
+fn string(data: &[u8]) -> Result<String, ModelInfoError> {
+    String::from_utf8(data.to_vec()).map_err(|e| ModelInfoError::Malformed(e.to_string()))
+}
+
+/// Length-delimited submessages with field number `number`
+fn nested(data: &[u8], number: u32) -> impl Iterator<Item = Result<&[u8], ModelInfoError>> {
+    Fields::new(data).filter_map(move |field| match field {
+        Ok((n, Value::Bytes(bytes))) if n == number => Some(Ok(bytes)),
+        Ok(_) => None,
+        Err(e) => Some(Err(e)),
+    })
+}
+
+/// Protobuf field payload
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Iterator over the (field number, value) pairs of a protobuf message
+struct Fields<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn varint(&mut self) -> Result<u64, ModelInfoError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or_else(|| ModelInfoError::Malformed("truncated varint".into()))?;
+            self.pos += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ModelInfoError::Malformed("varint too long".into()))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ModelInfoError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len());
+        let end = end.ok_or_else(|| ModelInfoError::Malformed("truncated field".into()))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn field(&mut self) -> Result<(u32, Value<'a>), ModelInfoError> {
+        let key = self.varint()?;
+        let number = (key >> 3) as u32;
+        let value = match key & 7 {
+            0 => Value::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                Value::Fixed
+            }
+            2 => {
+                let len = self.varint()? as usize;
+                Value::Bytes(self.take(len)?)
+            }
+            5 => {
+                self.take(4)?;
+                Value::Fixed
+            }
+            wire => {
+                return Err(ModelInfoError::Malformed(format!(
+                    "unsupported wire type {}",
+                    wire
+                )));
+            }
+        };
+        Ok((number, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u32, Value<'a>), ModelInfoError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_err() {
+            // Stop after the first error
+            self.pos = self.data.len();
+        }
+        Some(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn message(number: u32, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint(u64::from(number) << 3 | 2, &mut out);
+        varint(payload.len() as u64, &mut out);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    /// ValueInfoProto of a float tensor with fixed dims
+    fn value_info(name: &str, dims: &[u64]) -> Vec<u8> {
+        let mut shape = Vec::new();
+        for &dim in dims {
+            let mut value = vec![1 << 3];
+            varint(dim, &mut value);
+            shape.extend(message(1, &value));
+        }
+        let mut tensor = vec![1 << 3, 1]; // elem_type = FLOAT
+        tensor.extend(message(2, &shape));
+        let mut info = message(1, name.as_bytes());
+        info.extend(message(2, &message(1, &tensor)));
+        info
+    }
+
+    #[test]
+    fn test_parse_and_detect_swapped_inputs() {
+        let mut graph = message(5, &message(8, b"weights"));
+        graph.extend(message(11, &value_info("template", &[1, 3, 128, 128])));
+        graph.extend(message(11, &value_info("search", &[1, 3, 256, 256])));
+        graph.extend(message(11, &value_info("weights", &[64])));
+        graph.extend(message(12, &value_info("conf_map", &[1, 1, 16, 16])));
+        graph.extend(message(12, &value_info("size_map", &[1, 2, 16, 16])));
+        graph.extend(message(12, &value_info("offset_map", &[1, 2, 16, 16])));
+        let mut model = vec![1 << 3, 8]; // ir_version
+        model.extend(message(7, &graph));
+
+        let onnx = ModelInfo::parse_onnx(&model).unwrap();
+        assert_eq!(onnx.inputs.len(), 2);
+        assert_eq!(onnx.inputs[1], TensorInfo::new("search", &[1, 3, 256, 256]));
+        assert_eq!(onnx.outputs[2].to_string(), "offset_map [1x2x16x16]");
+        assert!(
+            onnx.cross_check(&ModelInfo::vittrack(128, 256, 16))
+                .is_empty()
+        );
+
+        let mut swapped = ModelInfo::vittrack(128, 256, 16);
+        swapped.inputs.swap(0, 1);
+        assert_eq!(onnx.cross_check(&swapped), [Mismatch::SwappedInputs]);
+
+        let mismatches = onnx.cross_check(&ModelInfo::vittrack(128, 256, 20));
+        assert_eq!(mismatches.len(), 3);
+        assert_eq!(
+            mismatches[0].to_string(),
+            "output 0: source conf_map [1x1x16x16], converted conf_map [1x1x20x20]"
+        );
+        assert!(ModelInfo::parse_onnx(&model[..model.len() - 3]).is_err());
+    }
+}
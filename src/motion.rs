@@ -0,0 +1,311 @@
+/// Occlusion coasting configuration
+#[derive(Debug, Clone, Copy)]
+pub struct CoastingConfig {
+    /// Maximum number of frames to coast on the motion model
+    pub max_frames: usize,
+    /// Score must fall below this fraction of the previous score to count as occlusion
+    pub score_drop: f32,
+    /// Measurements required before the motion history is trusted
+    pub min_history: usize,
+    /// Search region growth per coasted frame (0.25 = +25% per frame)
+    pub search_growth: f32,
+    /// Kalman process noise (acceleration variance)
+    pub process_noise: f32,
+    /// Kalman measurement noise variance (px²)
+    pub measurement_noise: f32,
+}
+
+impl Default for CoastingConfig {
+    fn default() -> Self {
+        Self {
+            max_frames: 15,
+            score_drop: 0.5,
+            min_history: 5,
+            search_growth: 0.25,
+            process_noise: 1.0,
+            measurement_noise: 4.0,
+        }
+    }
+}
+
+/// Motion model run inside `VitTrack`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MotionModel {
+    /// No filtering; a Kalman filter still runs when coasting is enabled
+    #[default]
+    None,
+    /// Constant-velocity Kalman filter on the box center; results report the
+    /// filtered box and keep the decoded one in `bbox_raw`
+    Kalman {
+        /// Acceleration variance (px²/frame⁴ at the reference frame rate)
+        process_noise: f32,
+        /// Measurement noise variance (px²)
+        measurement_noise: f32,
+    },
+}
+
+impl MotionModel {
+    /// Kalman filter with the coasting defaults
+    pub fn kalman() -> Self {
+        let coasting = CoastingConfig::default();
+        Self::Kalman {
+            process_noise: coasting.process_noise,
+            measurement_noise: coasting.measurement_noise,
+        }
+    }
+}
+
+/// Largest accepted jump of the box center between frames
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaxDisplacement {
+    Pixels(f32),
+    /// Multiples of the larger side of the last box
+    BoxSizes(f32),
+}
+
+/// Guard against the box jumping onto a distractor
+///
+/// A peak further than the limit from the predicted position (motion model,
+/// else the last box) is replaced by the best of the next candidates within
+/// reach, or rejected when there is none. The limit grows with every frame
+/// since the last accepted box, so a target reappearing after a loss is not
+/// locked out.
+#[derive(Debug, Clone, Copy)]
+pub struct TeleportGuardConfig {
+    pub max_displacement: MaxDisplacement,
+    /// Score-map peaks considered, including the best one
+    pub candidates: usize,
+}
+
+impl Default for TeleportGuardConfig {
+    fn default() -> Self {
+        Self {
+            max_displacement: MaxDisplacement::BoxSizes(1.5),
+            candidates: 5,
+        }
+    }
+}
+
+impl TeleportGuardConfig {
+    /// Allowed center displacement in pixels
+    ///
+    /// # Arguments
+    /// * `bbox` - Last accepted box
+    /// * `frames` - Frames since the last accepted box (at least 1)
+    pub fn limit(&self, bbox: &[i32; 4], frames: u32) -> f32 {
+        let base = match self.max_displacement {
+            MaxDisplacement::Pixels(pixels) => pixels,
+            MaxDisplacement::BoxSizes(sizes) => sizes * bbox[2].max(bbox[3]) as f32,
+        };
+        base * frames.max(1) as f32
+    }
+}
+
+/// Constant-velocity Kalman filter on the box center
+///
+/// State is [cx, cy, vx, vy] in pixels and pixels/frame; the box size is
+/// carried along from the last measurement.
+#[derive(Debug, Clone)]
+pub struct KalmanFilter {
+    x: [f32; 4],
+    p: [[f32; 4]; 4],
+    /// Process noise (acceleration variance, px²/frame⁴)
+    q: f32,
+    /// Measurement noise variance (px²)
+    r: f32,
+    size: [f32; 2],
+    corrections: usize,
+}
+
+impl KalmanFilter {
+    /// Initial variance of the velocity components
+    const INITIAL_VELOCITY_VAR: f32 = 100.0;
+
+    pub fn new(bbox: &[i32; 4], q: f32, r: f32) -> Self {
+        let [x, y, w, h] = bbox.map(|v| v as f32);
+        let mut p = [[0.0f32; 4]; 4];
+        p[0][0] = r;
+        p[1][1] = r;
+        p[2][2] = Self::INITIAL_VELOCITY_VAR;
+        p[3][3] = Self::INITIAL_VELOCITY_VAR;
+
+        Self {
+            x: [x + w / 2.0, y + h / 2.0, 0.0, 0.0],
+            p,
+            q,
+            r,
+            size: [w, h],
+            corrections: 0,
+        }
+    }
+
+    /// Advance the state by one frame
+    pub fn predict(&mut self) {
+        let [cx, cy, vx, vy] = self.x;
+        self.x = [cx + vx, cy + vy, vx, vy];
+
+        // P = F P F^T with F = [[I, I], [0, I]]
+        let p = self.p;
+        let mut fp = [[0.0f32; 4]; 4];
+        for j in 0..4 {
+            fp[0][j] = p[0][j] + p[2][j];
+            fp[1][j] = p[1][j] + p[3][j];
+            fp[2][j] = p[2][j];
+            fp[3][j] = p[3][j];
+        }
+        for (row, f) in self.p.iter_mut().zip(&fp) {
+            *row = [f[0] + f[2], f[1] + f[3], f[2], f[3]];
+        }
+
+        // Discrete white-noise acceleration, dt = 1
+        for axis in 0..2 {
+            self.p[axis][axis] += self.q * 0.25;
+            self.p[axis][axis + 2] += self.q * 0.5;
+            self.p[axis + 2][axis] += self.q * 0.5;
+            self.p[axis + 2][axis + 2] += self.q;
+        }
+    }
+
+    /// Correct the state with a measured box [x, y, w, h]
+    pub fn correct(&mut self, bbox: &[i32; 4]) {
+        let [x, y, w, h] = bbox.map(|v| v as f32);
+        let z = [x + w / 2.0, y + h / 2.0];
+        let Some(s_inv) = self.innovation_inverse() else {
+            return;
+        };
+
+        // K = P H^T S^-1 (4x2)
+        let k = self.p.map(|row| {
+            [
+                row[0] * s_inv[0][0] + row[1] * s_inv[1][0],
+                row[0] * s_inv[0][1] + row[1] * s_inv[1][1],
+            ]
+        });
+
+        let innovation = [z[0] - self.x[0], z[1] - self.x[1]];
+        for (x, k) in self.x.iter_mut().zip(&k) {
+            *x += k[0] * innovation[0] + k[1] * innovation[1];
+        }
+
+        // P = (I - K H) P
+        let p = self.p;
+        for (row, k) in self.p.iter_mut().zip(&k) {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value -= k[0] * p[0][j] + k[1] * p[1][j];
+            }
+        }
+
+        self.size = [w, h];
+        self.corrections += 1;
+    }
+
+    /// Squared Mahalanobis distance of a measured box center from the prediction
+    ///
+    /// Follows a chi-square distribution with 2 degrees of freedom for
+    /// measurements that fit the model; large values are outliers.
+    pub fn mahalanobis(&self, bbox: &[i32; 4]) -> f32 {
+        let [x, y, w, h] = bbox.map(|v| v as f32);
+        let d = [x + w / 2.0 - self.x[0], y + h / 2.0 - self.x[1]];
+        let Some(s_inv) = self.innovation_inverse() else {
+            return f32::INFINITY;
+        };
+        d[0] * (s_inv[0][0] * d[0] + s_inv[0][1] * d[1])
+            + d[1] * (s_inv[1][0] * d[0] + s_inv[1][1] * d[1])
+    }
+
+    /// S^-1 with S = H P H^T + R (2x2, H selects the position)
+    fn innovation_inverse(&self) -> Option<[[f32; 2]; 2]> {
+        let s = [
+            [self.p[0][0] + self.r, self.p[0][1]],
+            [self.p[1][0], self.p[1][1] + self.r],
+        ];
+        let det = s[0][0] * s[1][1] - s[0][1] * s[1][0];
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        Some([
+            [s[1][1] / det, -s[0][1] / det],
+            [-s[1][0] / det, s[0][0] / det],
+        ])
+    }
+
+    /// State [cx, cy, vx, vy]
+    pub fn state(&self) -> [f32; 4] {
+        self.x
+    }
+
+    /// State covariance
+    pub fn covariance(&self) -> [[f32; 4]; 4] {
+        self.p
+    }
+
+    /// Current box estimate [x, y, w, h]
+    pub fn bbox(&self) -> [i32; 4] {
+        let [w, h] = self.size;
+        [
+            (self.x[0] - w / 2.0).round() as i32,
+            (self.x[1] - h / 2.0).round() as i32,
+            w.round() as i32,
+            h.round() as i32,
+        ]
+    }
+
+    /// Box expected `frames` frames ahead, without changing the state
+    pub fn predicted(&self, frames: f32) -> [i32; 4] {
+        let [cx, cy, vx, vy] = self.x;
+        let [w, h] = self.size;
+        [
+            (cx + vx * frames - w / 2.0).round() as i32,
+            (cy + vy * frames - h / 2.0).round() as i32,
+            w.round() as i32,
+            h.round() as i32,
+        ]
+    }
+
+    /// Velocity estimate (px/frame)
+    pub fn velocity(&self) -> (f32, f32) {
+        (self.x[2], self.x[3])
+    }
+
+    /// Number of measurements incorporated since init
+    pub fn corrections(&self) -> usize {
+        self.corrections
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_teleport_limit_grows_while_lost() {
+        let guard = TeleportGuardConfig::default();
+        assert_eq!(guard.limit(&[0, 0, 40, 20], 1), 60.0);
+        assert_eq!(guard.limit(&[0, 0, 40, 20], 3), 180.0);
+        let pixels = TeleportGuardConfig {
+            max_displacement: MaxDisplacement::Pixels(25.0),
+            ..guard
+        };
+        assert_eq!(pixels.limit(&[0, 0, 40, 20], 0), 25.0);
+    }
+
+    #[test]
+    fn test_learns_constant_velocity() {
+        let mut kalman = KalmanFilter::new(&[0, 0, 20, 20], 0.1, 1.0);
+        for i in 1..30 {
+            kalman.predict();
+            kalman.correct(&[i * 3, i * 2, 20, 20]);
+        }
+        let (vx, vy) = kalman.velocity();
+        assert!((vx - 3.0).abs() < 0.2);
+        assert!((vy - 2.0).abs() < 0.2);
+
+        assert_eq!(kalman.predicted(1.0)[..2], [90, 60]);
+
+        // Coasting continues the motion
+        kalman.predict();
+        let [x, y, _, _] = kalman.bbox();
+        assert!((x - 90).abs() <= 1);
+        assert!((y - 60).abs() <= 1);
+    }
+}
@@ -0,0 +1,61 @@
+//! ONNX Runtime CPU backend
+//!
+//! Runs the original VitTrack ONNX model when no NPU is present. Inputs use
+//! the same NHWC float32 layout as the RKNN model and are transposed to the
+//! NCHW layout the ONNX export expects.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use ort::session::Session;
+use ort::value::Tensor;
+
+/// VitTrack ONNX model on the CPU
+pub struct OnnxModel {
+    /// `Session::run` needs `&mut`; the RKNN backend runs through `&self`
+    session: Mutex<Session>,
+}
+
+impl OnnxModel {
+    /// Load an ONNX model from file
+    pub fn load<P: AsRef<Path>>(model_path: P) -> Result<Self, ort::Error> {
+        let session = Session::builder()?.commit_from_file(model_path)?;
+        Ok(Self {
+            session: Mutex::new(session),
+        })
+    }
+
+    /// Output tensor names in model order
+    pub fn output_names(&self) -> Vec<String> {
+        let session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        session.outputs.iter().map(|output| output.name.clone()).collect()
+    }
+
+    /// Run inference with template and search inputs
+    ///
+    /// # Arguments
+    /// * `template` - Template input as NHWC float32
+    /// * `search` - Search input as NHWC float32
+    ///
+    /// # Returns
+    /// * The model outputs in order (conf_map, size_map, offset_map)
+    pub fn run(&self, template: &[f32], search: &[f32]) -> Result<Vec<Vec<f32>>, ort::Error> {
+        let mut session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let outputs = session.run(ort::inputs![to_nchw(template)?, to_nchw(search)?])?;
+        outputs
+            .values()
+            .map(|value| Ok(value.try_extract_tensor::<f32>()?.1.to_vec()))
+            .collect()
+    }
+}
+
+/// Transpose a square 1xSxSx3 input to 1x3xSxS
+fn to_nchw(nhwc: &[f32]) -> Result<Tensor<f32>, ort::Error> {
+    let pixels = nhwc.len() / 3;
+    let size = pixels.isqrt();
+    let mut nchw = vec![0.0f32; nhwc.len()];
+    for (i, &value) in nhwc.iter().enumerate() {
+        nchw[(i % 3) * pixels + i / 3] = value;
+    }
+    Tensor::from_array(([1usize, 3, size, size], nchw))
+}
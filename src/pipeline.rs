@@ -0,0 +1,374 @@
+//! Tracking on a worker thread, decoupled from capture
+//!
+//! `PipelinedTracker` queues frames for a `VitTrack` running on its own
+//! thread, so capture never waits for the NPU. When frames arrive faster than
+//! they are tracked the oldest queued frame is dropped. `stats` reports how
+//! many frames per second are queued, processed and dropped: drops mean the
+//! NPU is the bottleneck, an idle queue at a low rate means the camera is.
+//!
+//! With `VitTrackConfig::deterministic` nothing is dropped: `push` waits for
+//! room in the queue instead, unless the worker has exited.
+
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use ndarray::Array3;
+
+use crate::error::PipelineError;
+use crate::postprocess::TrackingResult;
+use crate::preprocess::BBox;
+use crate::quality::TemplateQuality;
+use crate::tracker::VitTrack;
+
+/// Pipeline configuration
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    /// Frames waiting for the worker; 1 always tracks the newest frame
+    pub queue_len: usize,
+    /// Window over which the per-second rates are measured
+    pub stats_window: Duration,
+    /// Camera or file name reported with errors
+    pub source_id: Option<Arc<str>>,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            queue_len: 2,
+            stats_window: Duration::from_secs(1),
+            source_id: None,
+        }
+    }
+}
+
+/// Queue counters; rates cover the last completed `stats_window`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct QueueStats {
+    pub queued_per_sec: f32,
+    pub processed_per_sec: f32,
+    pub dropped_per_sec: f32,
+    /// Frames waiting right now
+    pub depth: usize,
+    pub total_queued: u64,
+    pub total_processed: u64,
+    pub total_dropped: u64,
+}
+
+/// Output of the worker for one submitted frame
+// Tracked is the common variant; boxing it would allocate on every frame
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum PipelineOutput {
+    Initialized {
+        frame: u64,
+        quality: TemplateQuality,
+    },
+    Tracked {
+        frame: u64,
+        /// Errors carry the frame number and `source_id`
+        result: Result<TrackingResult, PipelineError>,
+    },
+}
+
+enum Job {
+    Init(u64, Array3<u8>, BBox),
+    Track(u64, Array3<u8>),
+    Stop,
+}
+
+impl Job {
+    /// Only tracking frames may be dropped; init and stop always run
+    fn droppable(&self) -> bool {
+        matches!(self, Job::Track(..))
+    }
+}
+
+struct QueueState<T> {
+    jobs: VecDeque<T>,
+    window_start: Instant,
+    /// queued, processed, dropped in the current window
+    window: [u64; 3],
+    stats: QueueStats,
+    /// The worker has exited (or panicked); nothing is popped any more
+    closed: bool,
+}
+
+/// Bounded queue dropping the oldest droppable entry when full
+pub(crate) struct FrameQueue<T> {
+    state: Mutex<QueueState<T>>,
+    ready: Condvar,
+    /// Signalled on pop when `lossless`
+    space: Condvar,
+    capacity: usize,
+    window: Duration,
+    /// Wait for room instead of dropping
+    lossless: bool,
+}
+
+impl<T> FrameQueue<T> {
+    pub(crate) fn new(capacity: usize, window: Duration, lossless: bool) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                jobs: VecDeque::with_capacity(capacity.max(1) + 1),
+                window_start: Instant::now(),
+                window: [0; 3],
+                stats: QueueStats::default(),
+                closed: false,
+            }),
+            ready: Condvar::new(),
+            space: Condvar::new(),
+            capacity: capacity.max(1),
+            window,
+            lossless,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Add a job; `counted` jobs enter the frame statistics
+    ///
+    /// Once the queue is closed the job is discarded instead of waiting for
+    /// room that never comes.
+    pub(crate) fn push(&self, job: T, counted: bool, droppable: impl Fn(&T) -> bool) {
+        let mut state = self.lock();
+        while self.lossless && !state.closed && state.jobs.len() >= self.capacity {
+            state = self.space.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+        if state.closed {
+            return;
+        }
+        if state.jobs.len() >= self.capacity
+            && let Some(index) = state.jobs.iter().position(&droppable)
+        {
+            state.jobs.remove(index);
+            self.count(&mut state, 2);
+        }
+        state.jobs.push_back(job);
+        if counted {
+            self.count(&mut state, 0);
+        }
+        state.stats.depth = state.jobs.len();
+        self.ready.notify_one();
+    }
+
+    /// Wait for the next job
+    pub(crate) fn pop(&self) -> T {
+        let mut state = self.lock();
+        loop {
+            if let Some(job) = state.jobs.pop_front() {
+                state.stats.depth = state.jobs.len();
+                self.space.notify_one();
+                return job;
+            }
+            state = self.ready.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Mark the consumer gone and release waiting producers
+    pub(crate) fn close(&self) {
+        self.lock().closed = true;
+        self.space.notify_all();
+    }
+
+    pub(crate) fn processed(&self) {
+        let mut state = self.lock();
+        self.count(&mut state, 1);
+    }
+
+    pub(crate) fn stats(&self) -> QueueStats {
+        let mut state = self.lock();
+        self.roll(&mut state);
+        state.stats
+    }
+
+    fn count(&self, state: &mut QueueState<T>, counter: usize) {
+        self.roll(state);
+        state.window[counter] += 1;
+        match counter {
+            0 => state.stats.total_queued += 1,
+            1 => state.stats.total_processed += 1,
+            _ => state.stats.total_dropped += 1,
+        }
+    }
+
+    /// Close the current window once it is over
+    fn roll(&self, state: &mut QueueState<T>) {
+        let elapsed = state.window_start.elapsed();
+        if elapsed < self.window {
+            return;
+        }
+        let rate = |count: u64| count as f32 / elapsed.as_secs_f32();
+        state.stats.queued_per_sec = rate(state.window[0]);
+        state.stats.processed_per_sec = rate(state.window[1]);
+        state.stats.dropped_per_sec = rate(state.window[2]);
+        state.window = [0; 3];
+        state.window_start = Instant::now();
+    }
+}
+
+/// `VitTrack` running on a worker thread behind a frame queue
+pub struct PipelinedTracker {
+    queue: Arc<FrameQueue<Job>>,
+    outputs: Receiver<PipelineOutput>,
+    worker: Option<JoinHandle<VitTrack>>,
+    next_frame: u64,
+}
+
+impl PipelinedTracker {
+    pub fn new(tracker: VitTrack, config: PipelineConfig) -> Self {
+        let lossless = tracker.model().config().deterministic;
+        let queue = Arc::new(FrameQueue::new(config.queue_len, config.stats_window, lossless));
+        let (tx, outputs) = mpsc::channel();
+        let worker_queue = queue.clone();
+        let source_id = config.source_id;
+        let worker = thread::spawn(move || run_worker(tracker, &worker_queue, tx, source_id));
+
+        Self {
+            queue,
+            outputs,
+            worker: Some(worker),
+            next_frame: 0,
+        }
+    }
+
+    /// Queue an init on `image`; never dropped
+    ///
+    /// # Returns
+    /// * Frame number reported with the output
+    pub fn init(&mut self, image: Array3<u8>, bbox: BBox) -> u64 {
+        let frame = self.next_id();
+        self.queue.push(Job::Init(frame, image, bbox), true, Job::droppable);
+        frame
+    }
+
+    /// Queue a frame for tracking, dropping the oldest queued one if full
+    ///
+    /// # Returns
+    /// * Frame number reported with the output
+    pub fn push(&mut self, image: Array3<u8>) -> u64 {
+        let frame = self.next_id();
+        self.queue.push(Job::Track(frame, image), true, Job::droppable);
+        frame
+    }
+
+    /// Next output if one is ready
+    pub fn try_output(&self) -> Option<PipelineOutput> {
+        self.outputs.try_recv().ok()
+    }
+
+    /// Wait for the next output
+    pub fn recv_output(&self) -> Option<PipelineOutput> {
+        self.outputs.recv().ok()
+    }
+
+    /// Queue depth and throughput
+    pub fn stats(&self) -> QueueStats {
+        self.queue.stats()
+    }
+
+    /// Stop the worker after the queued frames and take the tracker back
+    pub fn shutdown(mut self) -> VitTrack {
+        self.stop().expect("pipeline worker panicked")
+    }
+
+    fn stop(&mut self) -> Option<VitTrack> {
+        let worker = self.worker.take()?;
+        self.queue.push(Job::Stop, false, Job::droppable);
+        worker.join().ok()
+    }
+
+    fn next_id(&mut self) -> u64 {
+        let frame = self.next_frame;
+        self.next_frame += 1;
+        frame
+    }
+}
+
+impl Drop for PipelinedTracker {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Closes the queue when the worker exits, including by panic
+struct CloseOnExit<'a, T>(&'a FrameQueue<T>);
+
+impl<T> Drop for CloseOnExit<'_, T> {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+fn run_worker(
+    mut tracker: VitTrack,
+    queue: &FrameQueue<Job>,
+    tx: Sender<PipelineOutput>,
+    source_id: Option<Arc<str>>,
+) -> VitTrack {
+    let _close = CloseOnExit(queue);
+    loop {
+        let output = match queue.pop() {
+            Job::Init(frame, image, bbox) => {
+                let quality = tracker.init(&image.view(), bbox);
+                PipelineOutput::Initialized { frame, quality }
+            }
+            Job::Track(frame, image) => {
+                let result = tracker.update(&image.view()).map_err(|error| {
+                    let mut error = PipelineError::from(error).with_frame(frame);
+                    error.source_id = source_id.clone();
+                    error
+                });
+                PipelineOutput::Tracked { frame, result }
+            }
+            Job::Stop => return tracker,
+        };
+        queue.processed();
+        // The receiver may be gone while the owner shuts down
+        let _ = tx.send(output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_drops_oldest_and_counts() {
+        let queue = FrameQueue::new(2, Duration::ZERO, false);
+        let droppable = |job: &u32| *job != 0;
+        for job in 0..4 {
+            queue.push(job, true, droppable);
+        }
+        // Job 0 is never dropped; 1 and 2 made room for the newer frames
+        assert_eq!(queue.pop(), 0);
+        assert_eq!(queue.pop(), 3);
+        queue.processed();
+
+        let stats = queue.stats();
+        assert_eq!(stats.total_queued, 4);
+        assert_eq!(stats.total_processed, 1);
+        assert_eq!(stats.total_dropped, 2);
+        assert_eq!(stats.depth, 0);
+    }
+
+    #[test]
+    fn test_lossless_push_after_worker_panic() {
+        let queue = Arc::new(FrameQueue::new(1, Duration::ZERO, true));
+        queue.push(0u32, true, |_| true);
+        let worker_queue = queue.clone();
+        let worker = thread::spawn(move || {
+            let _close = CloseOnExit(&*worker_queue);
+            panic!("worker failed");
+        });
+        assert!(worker.join().is_err());
+
+        // Full queue and no worker: returns instead of waiting for room
+        queue.push(1, true, |_| true);
+        assert_eq!(queue.stats().depth, 1);
+    }
+}
@@ -0,0 +1,249 @@
+//! Platform pose from an external IMU/GPS log
+//!
+//! A pose log is a CSV of timestamped platform positions and attitudes, one
+//! sample per line:
+//!
+//! ```text
+//! # time, latitude, longitude, altitude, roll, pitch, yaw
+//! 12:00:01.000, 45.07012, 7.68650, 310.5, 0.5, -30.0, 92.0
+//! 12:00:01.100, 45.07013, 7.68655, 310.4, 0.4, -30.1, 92.1
+//! ```
+//!
+//! Times are seconds or `HH:MM:SS.sss` timecode on the log clock; angles are
+//! degrees, yaw is the heading clockwise from north and pitch is positive
+//! nose up. `PoseSync` maps a frame timestamp onto the log clock with a fixed
+//! offset and interpolates the pose between the two surrounding samples.
+//! Together with camera intrinsics the pose geolocates the target.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::camera::AngularTarget;
+use crate::klv::GeoOrigin;
+
+/// Platform position and attitude at one instant
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PlatformPose {
+    /// Time on the log clock
+    pub time: Duration,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Meters above the ellipsoid
+    pub altitude: f32,
+    /// Degrees
+    pub roll: f32,
+    pub pitch: f32,
+    pub yaw: f32,
+}
+
+/// Geodetic target position
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GeoTarget {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Meters above the ellipsoid
+    pub altitude: f32,
+}
+
+impl PlatformPose {
+    /// Intersect the line of sight to the target with flat ground
+    ///
+    /// The camera looks along the platform's heading and pitch; roll is
+    /// ignored, which holds for gimbals that keep the horizon level.
+    ///
+    /// # Returns
+    /// * Ground point, or `None` when the line of sight does not reach the ground
+    pub fn geolocate(&self, angular: &AngularTarget, ground_altitude: f32) -> Option<GeoTarget> {
+        let depression = -(self.pitch.to_radians() + angular.elevation);
+        let height = self.altitude - ground_altitude;
+        // Near the horizon the distance is meaningless
+        if depression < 0.01 || height <= 0.0 {
+            return None;
+        }
+        let distance = height / depression.tan();
+        let heading = self.yaw.to_radians() + angular.azimuth;
+        let origin = GeoOrigin {
+            latitude: self.latitude,
+            longitude: self.longitude,
+            altitude: ground_altitude,
+        };
+        let (latitude, longitude) =
+            origin.geodetic(distance * heading.sin(), distance * heading.cos());
+        Some(GeoTarget {
+            latitude,
+            longitude,
+            altitude: ground_altitude,
+        })
+    }
+
+    /// Pose a fraction `t` of the way to `next`
+    fn lerp(&self, next: &Self, t: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        // Angles take the short way around
+        let angle = |a: f32, b: f32| a + ((b - a + 540.0).rem_euclid(360.0) - 180.0) * t;
+        Self {
+            time: self.time + (next.time - self.time).mul_f32(t),
+            latitude: self.latitude + (next.latitude - self.latitude) * t as f64,
+            longitude: self.longitude + (next.longitude - self.longitude) * t as f64,
+            altitude: mix(self.altitude, next.altitude),
+            roll: angle(self.roll, next.roll),
+            pitch: mix(self.pitch, next.pitch),
+            yaw: angle(self.yaw, next.yaw).rem_euclid(360.0),
+        }
+    }
+}
+
+/// Pose samples sorted by time
+#[derive(Debug, Clone, Default)]
+pub struct PoseLog {
+    poses: Vec<PlatformPose>,
+}
+
+impl PoseLog {
+    /// Parse `time, latitude, longitude, altitude, roll, pitch, yaw` lines
+    ///
+    /// `#` starts a comment and a header line starting with `time` is skipped.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut poses = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() || line.starts_with("time") {
+                continue;
+            }
+            let invalid = |what: &str| format!("line {}: {}: {}", number + 1, what, line);
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != 7 {
+                return Err(invalid("expected 7 fields"));
+            }
+            let time = parse_time(fields[0]).ok_or_else(|| invalid("bad time"))?;
+            let field = |i: usize| fields[i].parse::<f64>().map_err(|_| invalid("bad number"));
+            poses.push(PlatformPose {
+                time,
+                latitude: field(1)?,
+                longitude: field(2)?,
+                altitude: field(3)? as f32,
+                roll: field(4)? as f32,
+                pitch: field(5)? as f32,
+                yaw: field(6)? as f32,
+            });
+        }
+        poses.sort_by_key(|pose| pose.time);
+        Ok(Self { poses })
+    }
+
+    /// Read and parse a pose log file
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn len(&self) -> usize {
+        self.poses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.poses.is_empty()
+    }
+
+    /// Pose at `time` on the log clock
+    ///
+    /// # Arguments
+    /// * `time` - Log time
+    /// * `max_gap` - Longest gap between samples to interpolate across
+    ///
+    /// # Returns
+    /// * Interpolated pose, or `None` outside the log or inside a longer gap
+    pub fn at(&self, time: Duration, max_gap: Duration) -> Option<PlatformPose> {
+        let next = self.poses.partition_point(|pose| pose.time < time);
+        let after = self.poses.get(next)?;
+        if after.time == time {
+            return Some(*after);
+        }
+        let before = self.poses.get(next.checked_sub(1)?)?;
+        let gap = after.time - before.time;
+        if gap > max_gap {
+            return None;
+        }
+        let t = (time - before.time).as_secs_f32() / gap.as_secs_f32();
+        Some(before.lerp(after, t))
+    }
+}
+
+/// Seconds or `HH:MM:SS.sss`
+fn parse_time(field: &str) -> Option<Duration> {
+    let mut seconds = 0.0;
+    for part in field.split(':') {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    (seconds >= 0.0 && seconds.is_finite()).then(|| Duration::from_secs_f64(seconds))
+}
+
+/// Maps frame timestamps onto a pose log
+#[derive(Debug, Clone)]
+pub struct PoseSync {
+    pub log: Arc<PoseLog>,
+    /// Seconds added to a frame timestamp to get the log time (may be negative)
+    pub offset: f64,
+    /// Longest gap between samples to interpolate across
+    pub max_gap: Duration,
+    /// Ground altitude for the geolocation, meters above the ellipsoid
+    pub ground_altitude: f32,
+}
+
+impl PoseSync {
+    pub fn new(log: PoseLog, offset: f64) -> Self {
+        Self {
+            log: Arc::new(log),
+            offset,
+            max_gap: Duration::from_millis(500),
+            ground_altitude: 0.0,
+        }
+    }
+
+    /// Pose at the capture time of a frame
+    pub fn pose_at(&self, frame_time: Duration) -> Option<PlatformPose> {
+        let time = frame_time.as_secs_f64() + self.offset;
+        if time < 0.0 {
+            return None;
+        }
+        self.log.at(Duration::from_secs_f64(time), self.max_gap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolate_with_offset() {
+        let text = "time,lat,lon,alt,roll,pitch,yaw\n\
+                    00:01:00.0, 45.0, 7.0, 300, 0, -30, 350\n\
+                    00:01:00.2, 45.0002, 7.0, 310, 0, -30, 10 # turning\n\
+                    00:01:05.0, 45.1, 7.0, 310, 0, -30, 10\n";
+        let sync = PoseSync::new(PoseLog::parse(text).unwrap(), 59.0);
+
+        let pose = sync.pose_at(Duration::from_millis(1100)).unwrap();
+        assert!((pose.latitude - 45.0001).abs() < 1e-9);
+        assert!((pose.altitude - 305.0).abs() < 1e-3);
+        // Through north, not back through south
+        assert!(pose.yaw.abs() < 1e-3 || (pose.yaw - 360.0).abs() < 1e-3);
+        // Gap of 4.8 s is too long to interpolate
+        assert!(sync.pose_at(Duration::from_secs(3)).is_none());
+        assert!(sync.pose_at(Duration::from_secs(10)).is_none());
+
+        // 30 degrees down from 300 m: the target is 520 m north
+        let level = PlatformPose {
+            latitude: 45.0,
+            longitude: 7.0,
+            altitude: 300.0,
+            pitch: -30.0,
+            ..PlatformPose::default()
+        };
+        let target = level.geolocate(&AngularTarget::default(), 0.0).unwrap();
+        let north = (target.latitude - 45.0).to_radians() * 6_371_000.0;
+        assert!((north - 300.0 / 30f64.to_radians().tan()).abs() < 1.0);
+    }
+}
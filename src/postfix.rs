@@ -0,0 +1,282 @@
+//! Offline track clean-up
+//!
+//! Online results are causal: each box only uses the frames before it.
+//! Analytics on a finished run can use the whole track instead. `postfix`
+//! runs the constant-velocity Kalman filter forward, rejects measurements
+//! far outside its prediction, then smooths the states backwards with a
+//! Rauch-Tung-Striebel pass. Short gaps are filled from the smoothed motion;
+//! longer gaps split the track into independent segments.
+
+use crate::motion::KalmanFilter;
+
+type Matrix = [[f32; 4]; 4];
+
+/// Offline clean-up configuration
+#[derive(Debug, Clone, Copy)]
+pub struct PostfixConfig {
+    /// Longest run of missing frames filled in; longer gaps end the segment
+    pub max_gap: usize,
+    /// Measurements further than this many standard deviations from the
+    /// prediction are outliers
+    pub outlier_gate: f32,
+    /// Consecutive outliers after which the target is taken to have really
+    /// moved and a new segment starts
+    pub max_outliers: usize,
+    /// Kalman process noise (acceleration variance)
+    pub process_noise: f32,
+    /// Kalman measurement noise variance (px²)
+    pub measurement_noise: f32,
+}
+
+impl Default for PostfixConfig {
+    fn default() -> Self {
+        Self {
+            max_gap: 15,
+            outlier_gate: 5.0,
+            max_outliers: 3,
+            process_noise: 1.0,
+            measurement_noise: 4.0,
+        }
+    }
+}
+
+/// Origin of a cleaned box
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointKind {
+    /// Smoothed measurement
+    Measured,
+    /// Filled in across a gap
+    Interpolated,
+    /// Rejected measurement, replaced by the smoothed motion
+    Outlier,
+    /// No box: outside any segment
+    Missing,
+}
+
+/// Cleaned track, one entry per input frame
+#[derive(Debug, Clone, Default)]
+pub struct CleanTrack {
+    pub boxes: Vec<Option<[i32; 4]>>,
+    pub kinds: Vec<PointKind>,
+}
+
+/// One forward-filtered frame of a segment
+struct Step {
+    frame: usize,
+    predicted: ([f32; 4], Matrix),
+    filtered: ([f32; 4], Matrix),
+    /// Box size when the frame's measurement was accepted
+    size: Option<[f32; 2]>,
+}
+
+/// Smooth a finished track
+///
+/// # Arguments
+/// * `measurements` - Box [x, y, w, h] per frame, `None` where the target was lost
+/// * `config` - Gap, outlier and noise settings
+///
+/// # Returns
+/// * Cleaned boxes with the origin of each
+pub fn postfix(measurements: &[Option<[i32; 4]>], config: &PostfixConfig) -> CleanTrack {
+    let mut track = CleanTrack {
+        boxes: vec![None; measurements.len()],
+        kinds: vec![PointKind::Missing; measurements.len()],
+    };
+    let gate = config.outlier_gate * config.outlier_gate;
+    let mut segment: Vec<Step> = Vec::new();
+    let mut kalman: Option<KalmanFilter> = None;
+    let (mut missed, mut rejected) = (0, 0);
+
+    for (frame, measurement) in measurements.iter().enumerate() {
+        let Some(filter) = &mut kalman else {
+            if let Some(bbox) = measurement {
+                let filter =
+                    KalmanFilter::new(bbox, config.process_noise, config.measurement_noise);
+                segment.push(first_step(frame, &filter, bbox));
+                kalman = Some(filter);
+                (missed, rejected) = (0, 0);
+            }
+            continue;
+        };
+
+        filter.predict();
+        let predicted = (filter.state(), filter.covariance());
+        let mut size = None;
+        if let Some(bbox) = measurement {
+            if filter.mahalanobis(bbox) <= gate {
+                filter.correct(bbox);
+                size = Some([bbox[2] as f32, bbox[3] as f32]);
+                rejected = 0;
+            } else if rejected < config.max_outliers {
+                track.kinds[frame] = PointKind::Outlier;
+                rejected += 1;
+            } else {
+                // The target did move: start over from here
+                finish_segment(&mut segment, &mut track);
+                let filter =
+                    KalmanFilter::new(bbox, config.process_noise, config.measurement_noise);
+                segment.push(first_step(frame, &filter, bbox));
+                kalman = Some(filter);
+                (missed, rejected) = (0, 0);
+                continue;
+            }
+        }
+
+        missed = if size.is_some() { 0 } else { missed + 1 };
+        if missed > config.max_gap {
+            finish_segment(&mut segment, &mut track);
+            kalman = None;
+            continue;
+        }
+        segment.push(Step {
+            frame,
+            predicted,
+            filtered: (filter.state(), filter.covariance()),
+            size,
+        });
+    }
+    finish_segment(&mut segment, &mut track);
+    track
+}
+
+fn first_step(frame: usize, filter: &KalmanFilter, bbox: &[i32; 4]) -> Step {
+    let state = (filter.state(), filter.covariance());
+    Step {
+        frame,
+        predicted: state,
+        filtered: state,
+        size: Some([bbox[2] as f32, bbox[3] as f32]),
+    }
+}
+
+/// RTS pass over a segment and write its boxes into the track
+fn finish_segment(segment: &mut Vec<Step>, track: &mut CleanTrack) {
+    // Frames after the last measurement are extrapolation, not interpolation
+    let last = segment.iter().rposition(|step| step.size.is_some());
+    segment.truncate(last.map_or(0, |last| last + 1));
+    if segment.is_empty() {
+        return;
+    }
+
+    let mut smoothed = vec![[0.0f32; 4]; segment.len()];
+    let n = segment.len();
+    smoothed[n - 1] = segment[n - 1].filtered.0;
+    for k in (0..n - 1).rev() {
+        let (x_f, p_f) = &segment[k].filtered;
+        let (x_p, p_p) = &segment[k + 1].predicted;
+        // C = P_f F^T P_pred^-1; x_s = x_f + C (x_s[k+1] - x_pred)
+        let gain = match invert(p_p) {
+            Some(p_inv) => multiply(&multiply(p_f, &transpose(&TRANSITION)), &p_inv),
+            None => [[0.0; 4]; 4],
+        };
+        let diff: [f32; 4] = std::array::from_fn(|i| smoothed[k + 1][i] - x_p[i]);
+        smoothed[k] = std::array::from_fn(|i| {
+            x_f[i] + (0..4).map(|j| gain[i][j] * diff[j]).sum::<f32>()
+        });
+    }
+
+    // Sizes are interpolated linearly between accepted measurements
+    let mut previous: Option<(usize, [f32; 2])> = None;
+    for (k, step) in segment.iter().enumerate() {
+        let size = match step.size {
+            Some(size) => size,
+            None => {
+                let (i, before) = previous.unwrap_or((k, [0.0; 2]));
+                let (j, after) = segment[k..]
+                    .iter()
+                    .enumerate()
+                    .find_map(|(offset, s)| s.size.map(|size| (k + offset, size)))
+                    .unwrap_or((k, before));
+                let t = (k - i) as f32 / (j - i).max(1) as f32;
+                [0, 1].map(|c| before[c] + (after[c] - before[c]) * t)
+            }
+        };
+        if let Some(measured) = step.size {
+            previous = Some((k, measured));
+        }
+
+        let [cx, cy, _, _] = smoothed[k];
+        track.boxes[step.frame] = Some([
+            (cx - size[0] / 2.0).round() as i32,
+            (cy - size[1] / 2.0).round() as i32,
+            size[0].round() as i32,
+            size[1].round() as i32,
+        ]);
+        track.kinds[step.frame] = match (step.size, track.kinds[step.frame]) {
+            (Some(_), _) => PointKind::Measured,
+            (None, PointKind::Outlier) => PointKind::Outlier,
+            (None, _) => PointKind::Interpolated,
+        };
+    }
+    segment.clear();
+}
+
+/// Constant-velocity transition, dt = 1
+const TRANSITION: Matrix = [
+    [1.0, 0.0, 1.0, 0.0],
+    [0.0, 1.0, 0.0, 1.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..4).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn transpose(a: &Matrix) -> Matrix {
+    std::array::from_fn(|i| std::array::from_fn(|j| a[j][i]))
+}
+
+/// Gauss-Jordan inversion with partial pivoting
+fn invert(a: &Matrix) -> Option<Matrix> {
+    let mut m = *a;
+    let mut inv: Matrix = std::array::from_fn(|i| std::array::from_fn(|j| f32::from(i == j)));
+    for col in 0..4 {
+        let pivot = (col..4).max_by(|&r, &s| m[r][col].abs().total_cmp(&m[s][col].abs()))?;
+        if m[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        m.swap(col, pivot);
+        inv.swap(col, pivot);
+        let scale = 1.0 / m[col][col];
+        for j in 0..4 {
+            m[col][j] *= scale;
+            inv[col][j] *= scale;
+        }
+        for row in (0..4).filter(|&row| row != col) {
+            let factor = m[row][col];
+            for j in 0..4 {
+                m[row][j] -= factor * m[col][j];
+                inv[row][j] -= factor * inv[col][j];
+            }
+        }
+    }
+    Some(inv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fills_gaps_and_rejects_outliers() {
+        // Constant motion of 2 px/frame with a gap and a one-frame jump
+        let mut measurements: Vec<Option<[i32; 4]>> =
+            (0..40).map(|i| Some([i * 2, 100, 20, 20])).collect();
+        for frame in measurements.iter_mut().take(15).skip(10) {
+            *frame = None;
+        }
+        measurements[25] = Some([300, 300, 20, 20]);
+        measurements.extend([None; 30]);
+
+        let track = postfix(&measurements, &PostfixConfig::default());
+        assert_eq!(track.kinds[12], PointKind::Interpolated);
+        assert_eq!(track.kinds[25], PointKind::Outlier);
+        assert_eq!(track.kinds[39], PointKind::Measured);
+        assert_eq!(track.kinds[45], PointKind::Missing);
+        let [x, y, w, _] = track.boxes[12].unwrap();
+        assert!((x - 24).abs() <= 1 && (y - 100).abs() <= 1 && w == 20);
+        let [x, y, _, _] = track.boxes[25].unwrap();
+        assert!((x - 50).abs() <= 1 && (y - 100).abs() <= 1);
+    }
+}
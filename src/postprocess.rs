@@ -1,9 +1,67 @@
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::time::Duration;
+
+use crate::budget::Degradation;
+use crate::camera::AngularTarget;
+use crate::label::TrackTag;
+pub use crate::decode::{
+    apply_window, crop_origin, decode_box, decode_peak, find_max, second_peak, top_peaks, CropBox,
+    OutputLayout, Peak,
+};
+use crate::pose::{GeoTarget, PlatformPose};
+use crate::rotation::RotatedBox;
+use crate::template::TemplateEvent;
+use crate::watchdog::ReinitEvent;
+use crate::world::WorldTarget;
+
 /// Tracking result
 #[derive(Debug, Clone, Copy)]
 pub struct TrackingResult {
     pub success: bool,
     pub bbox: [i32; 4], // [x, y, w, h]
     pub score: f32,
+    /// Exponential moving average of `score` (equal to it unless
+    /// `VitTrackConfig::score_smoothing` is set)
+    pub score_smoothed: f32,
+    /// Decoded box before motion filtering (only with `MotionModel::Kalman`;
+    /// `bbox` is then the filtered box)
+    pub bbox_raw: Option<[i32; 4]>,
+    /// Angular offset and size (only when camera intrinsics are configured)
+    pub angular: Option<AngularTarget>,
+    /// Ground-plane position and speed (only when a homography is configured)
+    pub world: Option<WorldTarget>,
+    /// Oriented box (only when rotation estimation is enabled)
+    pub rotated: Option<RotatedBox>,
+    /// Box [x, y, w, h] relative to frame size (only when normalized output is enabled)
+    pub bbox_normalized: Option<[f32; 4]>,
+    /// Box in `VitTrackConfig::coordinates` (only when a convention is configured)
+    pub bbox_converted: Option<[f32; 4]>,
+    /// Degradation applied to stay within the latency budget
+    pub degradation: Degradation,
+    /// Set on the frame where the loss watchdog fired
+    pub reinit: Option<ReinitEvent>,
+    /// Box predicted by the motion model during an occlusion (success is false)
+    pub coasting: bool,
+    /// Repeated from the last inferred frame: stationary target, unchanged scene
+    pub cached: bool,
+    /// Second-best (non-adjacent) peak divided by the best one; close to 1 means a distractor
+    pub peak_ratio: f32,
+    /// Peak sharpness of the confidence map (average peak-to-correlation energy)
+    pub apce: f32,
+    /// Occlusion probability (only with `VitTrackConfig::occlusion`)
+    pub occlusion: Option<f32>,
+    /// The target looks occluded; template updates are paused and the search widened
+    pub occluded: bool,
+    /// Template update or rollback applied on this frame
+    pub template_event: Option<TemplateEvent>,
+    /// Capture time of the frame on the source clock (V4L2 buffer time, stream PTS)
+    pub timestamp: Option<Duration>,
+    /// Platform pose at the capture time (only when a pose log is synced)
+    pub platform: Option<PlatformPose>,
+    /// Geodetic target position from the pose and the camera intrinsics
+    pub geo: Option<GeoTarget>,
+    /// Track identity set with `VitTrack::set_label`
+    pub tag: Option<TrackTag>,
 }
 
 impl Default for TrackingResult {
@@ -12,10 +70,228 @@ impl Default for TrackingResult {
             success: false,
             bbox: [0, 0, 0, 0],
             score: 0.0,
+            score_smoothed: 0.0,
+            bbox_raw: None,
+            angular: None,
+            world: None,
+            rotated: None,
+            bbox_normalized: None,
+            bbox_converted: None,
+            degradation: Degradation::None,
+            reinit: None,
+            coasting: false,
+            cached: false,
+            peak_ratio: 0.0,
+            apce: 0.0,
+            occlusion: None,
+            occluded: false,
+            template_event: None,
+            timestamp: None,
+            platform: None,
+            geo: None,
+            tag: None,
+        }
+    }
+}
+
+impl TrackingResult {
+    /// Box [x, y, w, h] divided by frame width/height
+    pub fn normalized_bbox(&self, frame_w: usize, frame_h: usize) -> [f32; 4] {
+        let [x, y, w, h] = self.bbox;
+        let (fw, fh) = (frame_w as f32, frame_h as f32);
+        [x as f32 / fw, y as f32 / fh, w as f32 / fw, h as f32 / fh]
+    }
+
+    /// Result between two inferred frames, `t` in [0, 1] from `a` to `b`
+    ///
+    /// Box, scores and angles are interpolated linearly; oriented box and
+    /// world position are taken from the nearer frame. Per-frame events are
+    /// dropped and the result is marked `SkippedInference`.
+    pub fn interpolate(a: &Self, b: &Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |x: f32, y: f32| x + (y - x) * t;
+        let nearest = if t < 0.5 { a } else { b };
+
+        let bbox = [0, 1, 2, 3].map(|i| lerp(a.bbox[i] as f32, b.bbox[i] as f32).round() as i32);
+        let angular = match (a.angular, b.angular) {
+            (Some(p), Some(q)) => Some(AngularTarget {
+                azimuth: lerp(p.azimuth, q.azimuth),
+                elevation: lerp(p.elevation, q.elevation),
+                width: lerp(p.width, q.width),
+                height: lerp(p.height, q.height),
+            }),
+            _ => nearest.angular,
+        };
+        let bbox_normalized = match (a.bbox_normalized, b.bbox_normalized) {
+            (Some(p), Some(q)) => Some([0, 1, 2, 3].map(|i| lerp(p[i], q[i]))),
+            _ => nearest.bbox_normalized,
+        };
+        let bbox_converted = match (a.bbox_converted, b.bbox_converted) {
+            (Some(p), Some(q)) => Some([0, 1, 2, 3].map(|i| lerp(p[i], q[i]))),
+            _ => nearest.bbox_converted,
+        };
+        let occlusion = match (a.occlusion, b.occlusion) {
+            (Some(p), Some(q)) => Some(lerp(p, q)),
+            _ => nearest.occlusion,
+        };
+        let timestamp = match (a.timestamp, b.timestamp) {
+            (Some(p), Some(q)) => Some(p + q.saturating_sub(p).mul_f32(t)),
+            _ => None,
+        };
+
+        Self {
+            success: a.success && b.success,
+            bbox,
+            score: lerp(a.score, b.score),
+            score_smoothed: lerp(a.score_smoothed, b.score_smoothed),
+            bbox_raw: None,
+            angular,
+            world: nearest.world,
+            rotated: nearest.rotated,
+            bbox_normalized,
+            bbox_converted,
+            degradation: Degradation::SkippedInference,
+            reinit: None,
+            coasting: a.coasting || b.coasting,
+            cached: false,
+            peak_ratio: lerp(a.peak_ratio, b.peak_ratio),
+            apce: lerp(a.apce, b.apce),
+            occlusion,
+            occluded: a.occluded || b.occluded,
+            template_event: None,
+            timestamp,
+            platform: nearest.platform,
+            geo: nearest.geo,
+            tag: nearest.tag,
+        }
+    }
+}
+
+/// How the current score map is combined with the history
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FusionMode {
+    /// Exponential moving average of aligned maps
+    #[default]
+    Average,
+    /// Maximum of the current map and the decayed history
+    Max,
+}
+
+/// Score-map temporal fusion configuration
+#[derive(Debug, Clone, Copy)]
+pub struct FusionConfig {
+    pub mode: FusionMode,
+    /// Weight of the history (0 disables fusion, close to 1 is very sticky)
+    pub decay: f32,
+}
+
+impl Default for FusionConfig {
+    fn default() -> Self {
+        Self {
+            mode: FusionMode::Average,
+            decay: 0.3,
         }
     }
 }
 
+/// Score map together with the image region it covers
+#[derive(Debug, Clone)]
+pub struct FusedMap {
+    pub conf: Vec<f32>,
+    /// Top-left corner of the search crop in image pixels
+    pub origin: (f32, f32),
+    /// Search crop size in image pixels
+    pub crop_size: f32,
+}
+
+/// Temporal fusion of confidence maps, aligned by the search crop motion
+#[derive(Debug, Clone)]
+pub struct ScoreFusion {
+    config: FusionConfig,
+    history: Option<FusedMap>,
+}
+
+impl ScoreFusion {
+    pub fn new(config: FusionConfig) -> Self {
+        Self {
+            config,
+            history: None,
+        }
+    }
+
+    /// Fuse a new confidence map with the history (does not update the history)
+    ///
+    /// # Arguments
+    /// * `conf_map` - Current confidence map (score_size x score_size)
+    /// * `origin` - Top-left corner of the current search crop in image pixels
+    /// * `crop_size` - Current search crop size in image pixels
+    pub fn fuse(&self, conf_map: &[f32], origin: (f32, f32), crop_size: f32) -> FusedMap {
+        let mut conf = conf_map.to_vec();
+
+        if let Some(history) = &self.history {
+            let size = (conf.len() as f32).sqrt() as usize;
+            let decay = self.config.decay;
+
+            for y in 0..size {
+                for x in 0..size {
+                    // Cell center in image pixels, then in history cell coordinates
+                    let px = origin.0 + (x as f32 + 0.5) / size as f32 * crop_size;
+                    let py = origin.1 + (y as f32 + 0.5) / size as f32 * crop_size;
+                    let u = (px - history.origin.0) / history.crop_size * size as f32 - 0.5;
+                    let v = (py - history.origin.1) / history.crop_size * size as f32 - 0.5;
+
+                    let Some(previous) = sample_bilinear(&history.conf, size, u, v) else {
+                        continue;
+                    };
+
+                    let idx = y * size + x;
+                    conf[idx] = match self.config.mode {
+                        FusionMode::Average => (1.0 - decay) * conf[idx] + decay * previous,
+                        FusionMode::Max => conf[idx].max(decay * previous),
+                    };
+                }
+            }
+        }
+
+        FusedMap {
+            conf,
+            origin,
+            crop_size,
+        }
+    }
+
+    /// Keep a fused map as history for the next frame
+    pub fn commit(&mut self, fused: FusedMap) {
+        self.history = Some(fused);
+    }
+
+    pub fn reset(&mut self) {
+        self.history = None;
+    }
+}
+
+/// Bilinear sample of a square map; None outside the map
+fn sample_bilinear(map: &[f32], size: usize, u: f32, v: f32) -> Option<f32> {
+    let max = (size - 1) as f32;
+    if !(0.0..=max).contains(&u) || !(0.0..=max).contains(&v) {
+        return None;
+    }
+
+    let x0 = u.floor() as usize;
+    let y0 = v.floor() as usize;
+    let x1 = (x0 + 1).min(size - 1);
+    let y1 = (y0 + 1).min(size - 1);
+    let dx = u - x0 as f32;
+    let dy = v - y0 as f32;
+
+    Some(
+        map[y0 * size + x0] * (1.0 - dx) * (1.0 - dy)
+            + map[y0 * size + x1] * dx * (1.0 - dy)
+            + map[y1 * size + x0] * (1.0 - dx) * dy
+            + map[y1 * size + x1] * dx * dy,
+    )
+}
+
 /// Create 1D Hanning window (matching OpenCV implementation)
 pub fn hann1d(size: usize) -> Vec<f32> {
     let mut window = vec![0.0f32; size];
@@ -44,103 +320,516 @@ pub fn hann2d(rows: usize, cols: usize) -> Vec<f32> {
     window
 }
 
-/// Process model outputs
+/// Parameters of a score-map window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowSpec {
+    pub rows: usize,
+    pub cols: usize,
+    /// Blend with a flat window: 1 is the full Hann window, 0 is flat
+    pub influence: f32,
+}
+
+impl WindowSpec {
+    /// Full Hann window over a square score map
+    pub fn hann(score_size: usize) -> Self {
+        Self {
+            rows: score_size,
+            cols: score_size,
+            influence: 1.0,
+        }
+    }
+
+    /// Compute the window as a flat row-major array
+    pub fn build(&self) -> Vec<f32> {
+        let k = self.influence.clamp(0.0, 1.0);
+        let mut window = hann2d(self.rows, self.cols);
+        if k < 1.0 {
+            window.iter_mut().for_each(|w| *w = 1.0 - k + k * *w);
+        }
+        window
+    }
+}
+
+/// Windows in use in this process, shared by equal specs
+static WINDOWS: Mutex<Vec<(WindowSpec, Weak<[f32]>)>> = Mutex::new(Vec::new());
+
+/// Window for `spec` from the process-wide cache
+///
+/// Trackers with the same score size (or influence) share one allocation; a
+/// window is dropped once no tracker holds it.
+pub fn cached_window(spec: WindowSpec) -> Arc<[f32]> {
+    let mut windows = WINDOWS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(window) = windows
+        .iter()
+        .find(|(cached, _)| *cached == spec)
+        .and_then(|(_, window)| window.upgrade())
+    {
+        return window;
+    }
+    windows.retain(|(_, window)| window.strong_count() > 0);
+    let window: Arc<[f32]> = spec.build().into();
+    windows.push((spec, Arc::downgrade(&window)));
+    window
+}
+
+/// Move a square window by (dx, dy) cells, zero where it was shifted in
+pub fn shift_window(window: &[f32], size: usize, dx: i32, dy: i32) -> Vec<f32> {
+    let mut shifted = vec![0.0f32; size * size];
+    for r in 0..size {
+        for c in 0..size {
+            let (src_r, src_c) = (r as i32 - dy, c as i32 - dx);
+            if (0..size as i32).contains(&src_r) && (0..size as i32).contains(&src_c) {
+                shifted[r * size + c] = window[src_r as usize * size + src_c as usize];
+            }
+        }
+    }
+    shifted
+}
+
+/// Zero the outer `border` rings of a row-major map
+pub fn suppress_border(map: &mut [f32], rows: usize, cols: usize, border: usize) {
+    if border == 0 {
+        return;
+    }
+    for (r, row) in map.chunks_exact_mut(cols).take(rows).enumerate() {
+        if r < border || r + border >= rows {
+            row.fill(0.0);
+        } else {
+            let edge = border.min(cols);
+            row[..edge].fill(0.0);
+            row[cols - edge..].fill(0.0);
+        }
+    }
+}
+
+/// How the target position is read from the score map
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Localization {
+    /// Highest cell plus its predicted offset and size
+    #[default]
+    Argmax,
+    /// Expected box under a softmax over the windowed scores (smoother for slow targets)
+    SoftArgmax {
+        /// Softmax temperature in score units (smaller is closer to argmax)
+        temperature: f32,
+    },
+}
+
+/// Mapping of the raw confidence output to scores
+///
+/// Exports differ in what the confidence head emits (post-sigmoid
+/// probabilities, logits, ...); mapping them to one range keeps thresholds and
+/// calibration valid across models.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScoreTransform {
+    /// Outputs are already scores in [0, 1]
+    #[default]
+    None,
+    /// Outputs are logits: per-cell sigmoid
+    Sigmoid,
+    /// Outputs are logits: softmax over the whole map (cells sum to 1)
+    Softmax,
+}
+
+impl ScoreTransform {
+    /// Transform a confidence map in place
+    pub fn apply(&self, conf_map: &mut [f32]) {
+        match self {
+            Self::None => {}
+            Self::Sigmoid => {
+                for v in conf_map.iter_mut() {
+                    *v = 1.0 / (1.0 + (-*v).exp());
+                }
+            }
+            Self::Softmax => {
+                let max = conf_map.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+                let mut sum = 0.0;
+                for v in conf_map.iter_mut() {
+                    *v = (*v - max).exp();
+                    sum += *v;
+                }
+                if sum > 0.0 {
+                    conf_map.iter_mut().for_each(|v| *v /= sum);
+                }
+            }
+        }
+    }
+}
+
+/// Average peak-to-correlation energy of a confidence map
+///
+/// (max - min)² / mean((x - min)²): high for a single sharp peak, low for a
+/// flat or multi-modal map (occlusion, distractors).
+pub fn apce(conf_map: &[f32]) -> f32 {
+    let (min, max) = conf_map
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let energy = conf_map.iter().map(|&v| (v - min).powi(2)).sum::<f32>() / conf_map.len() as f32;
+    if energy > 0.0 {
+        (max - min).powi(2) / energy
+    } else {
+        0.0
+    }
+}
+
+/// Occlusion estimation configuration
+#[derive(Debug, Clone, Copy)]
+pub struct OcclusionConfig {
+    /// Probability from which the target counts as occluded
+    pub threshold: f32,
+    /// Weight of a new unoccluded frame in the reference APCE and score
+    pub learning_rate: f32,
+    /// Search region growth while occluded (0.5 = +50%)
+    pub search_growth: f32,
+}
+
+impl Default for OcclusionConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            learning_rate: 0.05,
+            search_growth: 0.5,
+        }
+    }
+}
+
+/// Occlusion estimate from the drop of APCE and score below their running
+/// averages over unoccluded frames
+#[derive(Debug, Clone)]
+pub struct OcclusionEstimator {
+    config: OcclusionConfig,
+    /// Reference (APCE, score)
+    reference: Option<(f32, f32)>,
+}
+
+impl OcclusionEstimator {
+    pub fn new(config: OcclusionConfig) -> Self {
+        Self {
+            config,
+            reference: None,
+        }
+    }
+
+    pub fn config(&self) -> &OcclusionConfig {
+        &self.config
+    }
+
+    pub fn reset(&mut self) {
+        self.reference = None;
+    }
+
+    /// Estimate the occlusion of a frame and learn from it when unoccluded
+    ///
+    /// # Returns
+    /// * Occlusion probability in [0, 1]: the larger relative drop of APCE
+    ///   or score; 0 until a successful frame set the reference
+    pub fn update(&mut self, success: bool, score: f32, apce: f32) -> f32 {
+        let probability = match self.reference {
+            Some((ref_apce, ref_score)) => {
+                let drop = |value: f32, reference: f32| {
+                    if reference > 0.0 { 1.0 - value / reference } else { 0.0 }
+                };
+                drop(apce, ref_apce).max(drop(score, ref_score)).clamp(0.0, 1.0)
+            }
+            None => 0.0,
+        };
+        if success && probability < self.config.threshold {
+            let rate = self.config.learning_rate;
+            self.reference = Some(match self.reference {
+                Some((a, s)) => (a + rate * (apce - a), s + rate * (score - s)),
+                None => (apce, score),
+            });
+        }
+        probability
+    }
+}
+
+/// Layout of a keypoint head output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeypointFormat {
+    /// One heatmap per keypoint (count x side x side); the peak is the position
+    #[default]
+    Heatmap,
+    /// (x, y) or (x, y, confidence) per keypoint, x and y relative to the
+    /// search crop (0..1)
+    Regression,
+}
+
+/// Keypoint head configuration
+#[derive(Debug, Clone)]
+pub struct KeypointConfig {
+    /// Name of the keypoint output (listed in `VitTrackConfig::output_names`)
+    pub output: String,
+    /// Keypoints per target, e.g. 5 for center + corners
+    pub count: usize,
+    pub format: KeypointFormat,
+}
+
+impl Default for KeypointConfig {
+    fn default() -> Self {
+        Self {
+            output: "keypoints".into(),
+            count: 5,
+            format: KeypointFormat::Heatmap,
+        }
+    }
+}
+
+/// Target keypoint in frame pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keypoint {
+    pub x: f32,
+    pub y: f32,
+    /// Heatmap peak or regressed confidence (1 when the head has none)
+    pub confidence: f32,
+}
+
+/// Decode a keypoint head output into frame pixels
+///
+/// # Arguments
+/// * `values` - Keypoint head output
+/// * `config` - Head layout and keypoint count
+/// * `origin` - Top-left corner of the search crop in frame pixels
+/// * `crop_size` - Search crop size in frame pixels
+///
+/// # Returns
+/// * Keypoints in head order; empty when the output does not match the layout
+pub fn decode_keypoints(
+    values: &[f32],
+    config: &KeypointConfig,
+    origin: (i32, i32),
+    crop_size: i32,
+) -> Vec<Keypoint> {
+    let count = config.count;
+    if count == 0 || values.is_empty() || !values.len().is_multiple_of(count) {
+        return Vec::new();
+    }
+    let stride = values.len() / count;
+    let to_frame = |u: f32, v: f32| {
+        (origin.0 as f32 + u * crop_size as f32, origin.1 as f32 + v * crop_size as f32)
+    };
+
+    match config.format {
+        KeypointFormat::Heatmap => {
+            let side = stride.isqrt();
+            if side * side != stride {
+                return Vec::new();
+            }
+            values
+                .chunks_exact(stride)
+                .map(|map| {
+                    let (idx, peak) = find_max(map);
+                    let (cx, cy) = (idx % side, idx / side);
+                    // Sub-cell position from a parabola through the peak and its neighbours
+                    let refine = |prev: Option<f32>, next: Option<f32>| match (prev, next) {
+                        (Some(prev), Some(next)) if prev + next - 2.0 * peak < 0.0 => {
+                            0.5 * (prev - next) / (prev + next - 2.0 * peak)
+                        }
+                        _ => 0.0,
+                    };
+                    let at = |x: usize, y: usize| map[y * side + x];
+                    let dx = refine(
+                        cx.checked_sub(1).map(|x| at(x, cy)),
+                        (cx + 1 < side).then(|| at(cx + 1, cy)),
+                    );
+                    let dy = refine(
+                        cy.checked_sub(1).map(|y| at(cx, y)),
+                        (cy + 1 < side).then(|| at(cx, cy + 1)),
+                    );
+                    let (x, y) = to_frame(
+                        (cx as f32 + 0.5 + dx) / side as f32,
+                        (cy as f32 + 0.5 + dy) / side as f32,
+                    );
+                    Keypoint { x, y, confidence: peak }
+                })
+                .collect()
+        }
+        KeypointFormat::Regression => {
+            if !(2..=3).contains(&stride) {
+                return Vec::new();
+            }
+            values
+                .chunks_exact(stride)
+                .map(|point| {
+                    let (x, y) = to_frame(point[0], point[1]);
+                    Keypoint {
+                        x,
+                        y,
+                        confidence: point.get(2).copied().unwrap_or(1.0),
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+/// Angle of the principal axis through the keypoints, in degrees from the
+/// image x axis towards y, in (-90, 90]
+///
+/// Keypoints are weighted by confidence; None when fewer than two reach
+/// `min_confidence` or they all coincide.
+pub fn keypoint_orientation(keypoints: &[Keypoint], min_confidence: f32) -> Option<f32> {
+    let points: Vec<&Keypoint> =
+        keypoints.iter().filter(|p| p.confidence >= min_confidence).collect();
+    let weight: f32 = points.iter().map(|p| p.confidence).sum();
+    if points.len() < 2 || weight <= 0.0 {
+        return None;
+    }
+    let mx = points.iter().map(|p| p.confidence * p.x).sum::<f32>() / weight;
+    let my = points.iter().map(|p| p.confidence * p.y).sum::<f32>() / weight;
+    let (mut sxx, mut syy, mut sxy) = (0.0f32, 0.0f32, 0.0f32);
+    for p in &points {
+        let (dx, dy) = (p.x - mx, p.y - my);
+        sxx += p.confidence * dx * dx;
+        syy += p.confidence * dy * dy;
+        sxy += p.confidence * dx * dy;
+    }
+    if sxx + syy <= f32::EPSILON {
+        return None;
+    }
+    let angle = 0.5 * (2.0 * sxy).atan2(sxx - syy).to_degrees();
+    Some(if angle <= -90.0 { angle + 180.0 } else { angle })
+}
+
+/// Expected box over all cells, weighted by softmax(score / temperature)
+///
+/// # Arguments
+/// * `conf_windowed` - Windowed confidence map (score_size x score_size)
+/// * `size_map` - Size map (2 x score_size x score_size)
+/// * `offset_map` - Offset map (2 x score_size x score_size)
+/// * `score_size` - Side of the score map
+/// * `temperature` - Softmax temperature
+pub fn soft_argmax(
+    conf_windowed: &[f32],
+    size_map: &[f32],
+    offset_map: &[f32],
+    score_size: usize,
+    temperature: f32,
+) -> CropBox {
+    let (_, max_score) = find_max(conf_windowed);
+    let temperature = temperature.max(f32::EPSILON);
+
+    let mut total = 0.0f32;
+    let mut expected = CropBox { cx: 0.0, cy: 0.0, w: 0.0, h: 0.0 };
+    for (idx, &score) in conf_windowed.iter().enumerate() {
+        let weight = ((score - max_score) / temperature).exp();
+        let cell = decode_box(size_map, offset_map, idx, score_size);
+        expected.cx += weight * cell.cx;
+        expected.cy += weight * cell.cy;
+        expected.w += weight * cell.w;
+        expected.h += weight * cell.h;
+        total += weight;
+    }
+
+    CropBox {
+        cx: expected.cx / total,
+        cy: expected.cy / total,
+        w: expected.w / total,
+        h: expected.h / total,
+    }
+}
+
+/// Process model outputs of a 16x16 score map
+///
+/// The size and offset maps are indexed NCHW; reorder maps of NHWC exports
+/// with `OutputLayout::to_nchw` first (`RknnModel` does this itself). Use
+/// `process_outputs_with` for other score sizes.
 ///
 /// # Arguments
 /// * `conf_map` - Confidence map (256 elements, 16x16)
 /// * `size_map` - Size map (512 elements, 2x16x16)
 /// * `offset_map` - Offset map (512 elements, 2x16x16)
 /// * `hanning` - Hanning window (256 elements, 16x16)
-/// * `rect_last` - Previous bounding box [x, y, w, h]
+/// * `rect_last` - Previous bounding box [x, y, w, h] the search crop was centered on
 /// * `crop_size` - Crop size in original image pixels
 /// * `threshold` - Score threshold
 ///
 /// # Returns
-/// * Tracking result with updated bounding box
+/// * Tracking result with the decoded box (`rect_last` on failure); the caller
+///   decides whether to adopt it
 pub fn process_outputs(
     conf_map: &[f32],
     size_map: &[f32],
     offset_map: &[f32],
     hanning: &[f32],
-    rect_last: &mut [i32; 4],
+    rect_last: &[i32; 4],
     crop_size: i32,
     threshold: f32,
 ) -> TrackingResult {
-    const SCORE_SIZE: usize = 16;
+    process_outputs_with(
+        conf_map,
+        size_map,
+        offset_map,
+        hanning,
+        rect_last,
+        crop_size,
+        threshold,
+        16,
+        Localization::Argmax,
+    )
+}
 
-    // Apply Hanning window
-    let mut conf_windowed = vec![0.0f32; SCORE_SIZE * SCORE_SIZE];
-    for i in 0..conf_windowed.len() {
-        conf_windowed[i] = conf_map[i] * hanning[i];
-    }
+/// Process model outputs with a chosen score size and localization mode
+///
+/// Same as `process_outputs`; the maps are `score_size` x `score_size`
+/// (`size_map` and `offset_map` twice that) and `localization` selects how
+/// the box is decoded.
+#[allow(clippy::too_many_arguments)]
+pub fn process_outputs_with(
+    conf_map: &[f32],
+    size_map: &[f32],
+    offset_map: &[f32],
+    hanning: &[f32],
+    rect_last: &[i32; 4],
+    crop_size: i32,
+    threshold: f32,
+    score_size: usize,
+    localization: Localization,
+) -> TrackingResult {
+    let area = score_size * score_size;
+    debug_assert!(conf_map.len() == area && hanning.len() == area);
+    debug_assert!(size_map.len() == 2 * area && offset_map.len() == 2 * area);
 
-    // Find max location
-    let (max_idx, max_score) = find_max(&conf_windowed);
-    let max_loc_y = max_idx / SCORE_SIZE;
-    let max_loc_x = max_idx % SCORE_SIZE;
+    let mut conf_windowed = vec![0.0f32; area];
+    let peak = decode_peak(
+        conf_map,
+        size_map,
+        offset_map,
+        hanning,
+        &mut conf_windowed,
+        score_size,
+    );
+    let (max_score, peak_ratio) = (peak.score, peak.peak_ratio);
+    let apce = apce(conf_map);
 
     if max_score >= threshold {
-        // Get predictions at max location
-        // offset_map layout: [2, 16, 16] -> index = channel * 256 + y * 16 + x
-        let offset_x = offset_map[0 * 256 + max_loc_y * SCORE_SIZE + max_loc_x];
-        let offset_y = offset_map[1 * 256 + max_loc_y * SCORE_SIZE + max_loc_x];
-
-        // size_map layout: [2, 16, 16]
-        let size_w = size_map[0 * 256 + max_loc_y * SCORE_SIZE + max_loc_x];
-        let size_h = size_map[1 * 256 + max_loc_y * SCORE_SIZE + max_loc_x];
-
-        // Normalized coordinates [0, 1]
-        let cx = (max_loc_x as f32 + offset_x) / SCORE_SIZE as f32;
-        let cy = (max_loc_y as f32 + offset_y) / SCORE_SIZE as f32;
-
-        // Update rectangle
-        update_rect(rect_last, cx, cy, size_w, size_h, crop_size);
+        let crop_box = match localization {
+            Localization::Argmax => peak.crop_box,
+            Localization::SoftArgmax { temperature } => {
+                soft_argmax(&conf_windowed, size_map, offset_map, score_size, temperature)
+            }
+        };
 
         TrackingResult {
             success: true,
-            bbox: *rect_last,
+            bbox: crop_box.to_image(crop_origin(rect_last, crop_size), crop_size),
             score: max_score,
+            peak_ratio,
+            apce,
+            ..TrackingResult::default()
         }
     } else {
         TrackingResult {
             success: false,
             bbox: *rect_last,
             score: max_score,
+            peak_ratio,
+            apce,
+            ..TrackingResult::default()
         }
     }
 }
 
-/// Find maximum value and its index
-fn find_max(arr: &[f32]) -> (usize, f32) {
-    let mut max_idx = 0;
-    let mut max_val = f32::NEG_INFINITY;
-
-    for (idx, &val) in arr.iter().enumerate() {
-        if val > max_val {
-            max_val = val;
-            max_idx = idx;
-        }
-    }
-
-    (max_idx, max_val)
-}
-
-/// Update rectangle based on predictions (matching OpenCV logic)
-fn update_rect(rect: &mut [i32; 4], cx: f32, cy: f32, w: f32, h: f32, crop_size: i32) {
-    // Origin of crop in original image
-    let x0 = rect[0] + (rect[2] - crop_size) / 2;
-    let y0 = rect[1] + (rect[3] - crop_size) / 2;
-
-    // Convert normalized coords to image coords
-    let x1 = cx - w / 2.0;
-    let y1 = cy - h / 2.0;
-
-    rect[0] = (x1 * crop_size as f32 + x0 as f32).floor() as i32;
-    rect[1] = (y1 * crop_size as f32 + y0 as f32).floor() as i32;
-    rect[2] = (w * crop_size as f32).floor() as i32;
-    rect[3] = (h * crop_size as f32).floor() as i32;
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,10 +851,198 @@ mod tests {
     }
 
     #[test]
-    fn test_find_max() {
-        let arr = vec![0.1, 0.5, 0.3, 0.9, 0.2];
-        let (idx, val) = find_max(&arr);
-        assert_eq!(idx, 3);
-        assert!((val - 0.9).abs() < 1e-6);
+    fn test_suppress_border() {
+        let mut map = vec![1.0f32; 36];
+        suppress_border(&mut map, 6, 6, 2);
+        let kept: Vec<usize> = (0..36).filter(|&i| map[i] > 0.0).collect();
+        assert_eq!(kept, [14, 15, 20, 21]);
+    }
+
+    #[test]
+    fn test_cached_window_is_shared() {
+        let a = cached_window(WindowSpec::hann(18));
+        let b = cached_window(WindowSpec::hann(18));
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(&a[..], &hann2d(18, 18)[..]);
+
+        let flat = cached_window(WindowSpec {
+            influence: 0.0,
+            ..WindowSpec::hann(18)
+        });
+        assert!(!Arc::ptr_eq(&a, &flat));
+        assert!(flat.iter().all(|&w| w == 1.0));
+    }
+
+    #[test]
+    fn test_normalized_bbox() {
+        let result = TrackingResult {
+            bbox: [480, 270, 96, 54],
+            ..Default::default()
+        };
+        assert_eq!(result.normalized_bbox(1920, 1080), [0.25, 0.25, 0.05, 0.05]);
+    }
+
+    #[test]
+    fn test_score_transforms() {
+        let mut logits = [0.0f32, 2.0, -2.0, 0.0];
+        ScoreTransform::Sigmoid.apply(&mut logits);
+        assert_eq!(logits[0], 0.5);
+        assert!((logits[1] + logits[2] - 1.0).abs() < 1e-6);
+
+        let mut logits = [1.0f32, 1.0, 3.0, 1.0];
+        ScoreTransform::Softmax.apply(&mut logits);
+        assert!((logits.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+        assert!(logits[2] > logits[0] && logits[0] == logits[3]);
+    }
+
+    #[test]
+    fn test_interpolated_timestamp() {
+        let a = TrackingResult {
+            timestamp: Some(Duration::from_millis(1000)),
+            ..Default::default()
+        };
+        let b = TrackingResult {
+            timestamp: Some(Duration::from_millis(1040)),
+            ..Default::default()
+        };
+        let mid = TrackingResult::interpolate(&a, &b, 0.25);
+        assert_eq!(mid.timestamp, Some(Duration::from_millis(1010)));
+    }
+
+    #[test]
+    fn test_fusion_follows_crop_motion() {
+        let mut fusion = ScoreFusion::new(FusionConfig {
+            mode: FusionMode::Max,
+            decay: 1.0,
+        });
+
+        // Peak at cell (8, 8) of a crop at origin (0, 0), 16 px per cell
+        let mut previous = vec![0.0f32; 256];
+        previous[8 * 16 + 8] = 1.0;
+        fusion.commit(fusion.fuse(&previous, (0.0, 0.0), 256.0));
+
+        // Crop moved right by two cells: the peak appears at cell (8, 6)
+        let fused = fusion.fuse(&vec![0.0f32; 256], (32.0, 0.0), 256.0);
+        assert!((fused.conf[8 * 16 + 6] - 1.0).abs() < 1e-6);
+        assert_eq!(fused.conf[8 * 16 + 8], 0.0);
+    }
+
+    #[test]
+    fn test_process_outputs_is_pure() {
+        let mut conf_map = vec![0.0f32; 256];
+        conf_map[8 * 16 + 8] = 1.0;
+        let mut size_map = vec![0.0f32; 512];
+        size_map[8 * 16 + 8] = 0.25;
+        size_map[256 + 8 * 16 + 8] = 0.25;
+        let mut offset_map = vec![0.0f32; 512];
+        offset_map[8 * 16 + 8] = 1.0;
+        let hanning = vec![1.0f32; 256];
+        let rect_last = [100, 100, 32, 32];
+
+        let result =
+            process_outputs(&conf_map, &size_map, &offset_map, &hanning, &rect_last, 128, 0.5);
+        assert!(result.success);
+        assert_eq!(result.bbox, [108, 100, 32, 32]);
+
+        let lost =
+            process_outputs(&conf_map, &size_map, &offset_map, &hanning, &rect_last, 128, 2.0);
+        assert!(!lost.success);
+        assert_eq!(lost.bbox, rect_last);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_process_outputs_score_size() {
+        // 20x20 map over a 200px crop at (20, 20): peak at cell (13, 4)
+        let (size, area) = (20, 400);
+        let peak = 4 * size + 13;
+        let mut conf_map = vec![0.0f32; area];
+        conf_map[peak] = 1.0;
+        let mut size_map = vec![0.0f32; 2 * area];
+        size_map[peak] = 0.2;
+        size_map[area + peak] = 0.2;
+        let offset_map = vec![0.5f32; 2 * area];
+        let hanning = vec![1.0f32; area];
+
+        let result = process_outputs_with(
+            &conf_map,
+            &size_map,
+            &offset_map,
+            &hanning,
+            &[100, 100, 40, 40],
+            200,
+            0.5,
+            size,
+            Localization::Argmax,
+        );
+        assert!(result.success);
+        assert_eq!(result.bbox, [135, 45, 40, 40]);
+    }
+
+    #[test]
+    fn test_soft_argmax_vs_argmax() {
+        let size_map = vec![0.25f32; 512];
+        let offset_map = vec![0.5f32; 512];
+
+        // Single sharp peak: both modes agree
+        let mut conf = vec![0.0f32; 256];
+        conf[5 * 16 + 9] = 1.0;
+        let hard = decode_box(&size_map, &offset_map, 5 * 16 + 9, 16);
+        let soft = soft_argmax(&conf, &size_map, &offset_map, 16, 0.01);
+        assert!((hard.cx - soft.cx).abs() < 1e-4 && (hard.cy - soft.cy).abs() < 1e-4);
+
+        // Two equal neighbouring peaks: argmax snaps to one cell, soft-argmax lands between
+        conf[5 * 16 + 10] = 1.0;
+        let soft = soft_argmax(&conf, &size_map, &offset_map, 16, 0.01);
+        assert!((soft.cx - 10.0 / 16.0).abs() < 1e-4);
+        assert!((soft.w - 0.25).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_apce_occlusion() {
+        let mut sharp = vec![0.0f32; 256];
+        sharp[40] = 1.0;
+        let flat: Vec<f32> = (0..256).map(|i| 0.4 + 0.1 * (i % 3) as f32).collect();
+        assert!(apce(&sharp) > 10.0 * apce(&flat));
+
+        let mut estimator = OcclusionEstimator::new(OcclusionConfig::default());
+        assert_eq!(estimator.update(true, 0.9, apce(&sharp)), 0.0);
+        assert!(estimator.update(true, 0.85, apce(&sharp)) < 0.1);
+        // Occluder: flat map, weaker peak
+        let occluded = estimator.update(true, 0.6, apce(&flat));
+        assert!(occluded > 0.9);
+        // The occluded frame did not lower the reference
+        assert!((estimator.update(true, 0.6, apce(&flat)) - occluded).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_decode_keypoints() {
+        // Two 4x4 heatmaps over a 64px crop at (100, 50): 16px cells
+        let mut heatmaps = vec![0.0f32; 32];
+        heatmaps[2 * 4 + 1] = 0.9; // cell (1, 2), symmetric neighbours
+        heatmaps[2 * 4] = 0.3;
+        heatmaps[2 * 4 + 2] = 0.3;
+        heatmaps[16 + 3] = 0.8; // corner cell (3, 0): no refinement at the border
+        heatmaps[16 + 2] = 0.4;
+        let config = KeypointConfig {
+            count: 2,
+            ..KeypointConfig::default()
+        };
+        let points = decode_keypoints(&heatmaps, &config, (100, 50), 64);
+        assert_eq!(points.len(), 2);
+        assert!((points[0].x - 124.0).abs() < 1e-4 && (points[0].y - 90.0).abs() < 1e-4);
+        assert!((points[0].confidence - 0.9).abs() < 1e-6);
+        assert_eq!((points[1].x, points[1].y), (156.0, 58.0));
+        assert!(decode_keypoints(&heatmaps[..30], &config, (100, 50), 64).is_empty());
+
+        let config = KeypointConfig {
+            count: 2,
+            format: KeypointFormat::Regression,
+            ..KeypointConfig::default()
+        };
+        let points = decode_keypoints(&[0.25, 0.25, 1.0, 0.75, 0.75, 0.5], &config, (0, 0), 100);
+        assert_eq!(points[1], Keypoint { x: 75.0, y: 75.0, confidence: 0.5 });
+        let angle = keypoint_orientation(&points, 0.0).unwrap();
+        assert!((angle - 45.0).abs() < 1e-3);
+        assert_eq!(keypoint_orientation(&points, 0.9), None);
+    }
+}
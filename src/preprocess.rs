@@ -1,3 +1,4 @@
+use half::f16;
 use ndarray::{Array3, ArrayView3};
 
 /// ImageNet mean values (RGB order)
@@ -5,6 +6,157 @@ pub const MEAN: [f32; 3] = [0.485, 0.456, 0.406];
 /// ImageNet std values (RGB order)
 pub const STD: [f32; 3] = [0.229, 0.224, 0.225];
 
+/// Element type of the input tensors sent to the NPU
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InputType {
+    #[default]
+    Float32,
+    Float16,
+    /// Raw RGB pixels for models with mean/std fused into the graph
+    Uint8,
+    /// Normalized and quantized in one fixed-point pass for int8 models
+    Int8 { zero_point: i32, scale: f32 },
+}
+
+/// Preprocessed NHWC input tensor
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InputTensor {
+    Float32(Vec<f32>),
+    Float16(Vec<f16>),
+    Uint8(Vec<u8>),
+    Int8(Vec<i8>),
+}
+
+impl InputTensor {
+    /// Normalize a cropped RGB image into a tensor of the given type
+    pub fn from_image(image: &Array3<u8>, input_type: InputType) -> Self {
+        match input_type {
+            InputType::Float32 => Self::Float32(preprocess_nhwc(image)),
+            InputType::Float16 => Self::Float16(preprocess_nhwc_f16(image)),
+            // Crop is already HWC RGB, no normalization needed
+            InputType::Uint8 => Self::Uint8(image.iter().copied().collect()),
+            InputType::Int8 { zero_point, scale } => {
+                Self::Int8(preprocess_nhwc_i8(image, &Int8Coefficients::new(zero_point, scale)))
+            }
+        }
+    }
+
+    /// Tensor from a crop with samples already scaled to [0, 1]
+    ///
+    /// Used for high-bit-depth sources, which skip the 8-bit stage entirely for
+    /// float inputs; uint8/int8 inputs are quantized by the model input type.
+    pub fn from_unit_image(image: &Array3<f32>, input_type: InputType) -> Self {
+        let normalized = image
+            .indexed_iter()
+            .map(|((_, _, ch), &v)| (v - MEAN[ch]) / STD[ch]);
+        match input_type {
+            InputType::Float32 => Self::Float32(normalized.collect()),
+            InputType::Float16 => Self::Float16(normalized.map(f16::from_f32).collect()),
+            InputType::Uint8 => Self::Uint8(
+                image
+                    .iter()
+                    .map(|&v| (v * 255.0).round().clamp(0.0, 255.0) as u8)
+                    .collect(),
+            ),
+            InputType::Int8 { zero_point, scale } => Self::Int8(
+                normalized
+                    .map(|v| ((v / scale).round() as i32 + zero_point).clamp(-128, 127) as i8)
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Normalize a cropped RGB image, stretching its contrast first
+    ///
+    /// `Normalization::Fixed` is the same as `from_image`.
+    pub fn from_image_normalized(
+        image: &Array3<u8>,
+        input_type: InputType,
+        normalization: Normalization,
+    ) -> Self {
+        if normalization == Normalization::Fixed {
+            return Self::from_image(image, input_type);
+        }
+        let mut unit = image.mapv(|v| v as f32 / 255.0);
+        normalization.apply(&mut unit);
+        Self::from_unit_image(&unit, input_type)
+    }
+
+    /// Element type name, for diagnostics
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Float32(_) => "float32",
+            Self::Float16(_) => "float16",
+            Self::Uint8(_) => "uint8",
+            Self::Int8(_) => "int8",
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Float32(data) => data.len(),
+            Self::Float16(data) => data.len(),
+            Self::Uint8(data) => data.len(),
+            Self::Int8(data) => data.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Size of the data in bytes
+    pub fn byte_len(&self) -> usize {
+        match self {
+            Self::Float32(data) => data.len() * 4,
+            Self::Float16(data) => data.len() * 2,
+            Self::Uint8(data) => data.len(),
+            Self::Int8(data) => data.len(),
+        }
+    }
+
+    /// Weighted average with another tensor of the same type and length
+    ///
+    /// # Arguments
+    /// * `other` - Tensor blended in
+    /// * `weight` - Weight of `other` (0 keeps `self`, 1 gives `other`)
+    ///
+    /// # Returns
+    /// * None when the element types or lengths differ
+    pub fn blend(&self, other: &Self, weight: f32) -> Option<Self> {
+        if self.len() != other.len() {
+            return None;
+        }
+        let mix = |a: f32, b: f32| a + (b - a) * weight;
+        Some(match (self, other) {
+            (Self::Float32(a), Self::Float32(b)) => {
+                Self::Float32(a.iter().zip(b).map(|(&a, &b)| mix(a, b)).collect())
+            }
+            (Self::Float16(a), Self::Float16(b)) => Self::Float16(
+                a.iter()
+                    .zip(b)
+                    .map(|(a, b)| f16::from_f32(mix(a.to_f32(), b.to_f32())))
+                    .collect(),
+            ),
+            (Self::Uint8(a), Self::Uint8(b)) => Self::Uint8(
+                a.iter()
+                    .zip(b)
+                    .map(|(&a, &b)| mix(a as f32, b as f32).round() as u8)
+                    .collect(),
+            ),
+            (Self::Int8(a), Self::Int8(b)) => Self::Int8(
+                a.iter()
+                    .zip(b)
+                    .map(|(&a, &b)| mix(a as f32, b as f32).round() as i8)
+                    .collect(),
+            ),
+            _ => return None,
+        })
+    }
+}
+
 /// Bounding box [x, y, width, height]
 #[derive(Debug, Clone, Copy, Default)]
 pub struct BBox {
@@ -40,6 +192,17 @@ impl BBox {
     pub fn center(&self) -> (i32, i32) {
         (self.x + self.width / 2, self.y + self.height / 2)
     }
+
+    /// Intersection over union with another box
+    pub fn iou(&self, other: &BBox) -> f32 {
+        let x1 = self.x.max(other.x);
+        let y1 = self.y.max(other.y);
+        let x2 = (self.x + self.width).min(other.x + other.width);
+        let y2 = (self.y + self.height).min(other.y + other.height);
+        let inter = ((x2 - x1).max(0) * (y2 - y1).max(0)) as f32;
+        let union = self.area() + other.area() - inter;
+        if union > 0.0 { inter / union } else { 0.0 }
+    }
 }
 
 /// Crop and preprocess image for RKNN
@@ -59,12 +222,26 @@ pub fn crop_and_preprocess(
     factor: u32,
     output_size: usize,
 ) -> (Vec<f32>, i32) {
+    let (resized, crop_sz) = crop_resized(image, bbox, factor, output_size);
+    (preprocess_nhwc(&resized), crop_sz)
+}
+
+/// Crop around bbox and resize without normalization
+///
+/// # Returns
+/// * Cropped image as Array3<u8> (output_size x output_size x 3)
+/// * Crop size in original image pixels
+pub fn crop_resized(
+    image: &ArrayView3<u8>,
+    bbox: &BBox,
+    factor: u32,
+    output_size: usize,
+) -> (Array3<u8>, i32) {
     let (img_h, img_w, _channels) = image.dim();
     let img_h = img_h as i32;
     let img_w = img_w as i32;
 
-    // Calculate crop size: sqrt(area) * factor
-    let crop_sz = (bbox.area().sqrt() * factor as f32).ceil() as i32;
+    let crop_sz = crop_size(bbox, factor);
 
     // Calculate crop coordinates centered on bbox
     let x1 = bbox.x + (bbox.width - crop_sz) / 2;
@@ -107,11 +284,219 @@ pub fn crop_and_preprocess(
         }
     }
 
-    // Resize and preprocess
+    // Resize
     let resized = resize_bilinear(&crop, output_size, output_size);
-    let preprocessed = preprocess_nhwc(&resized);
 
-    (preprocessed, crop_sz)
+    (resized, crop_sz)
+}
+
+/// Per-crop contrast stretch applied before the model mean/std
+///
+/// Thermal cameras rescale the whole frame with their AGC, so the target's
+/// absolute intensity swings from frame to frame. Stretching each crop to its
+/// own range keeps template and search crops comparable.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Normalization {
+    /// Samples used as is (visible-light footage)
+    #[default]
+    Fixed,
+    /// Crop minimum maps to 0, maximum to 1
+    MinMax,
+    /// Given quantiles (0-1) of the crop map to 0 and 1, ignoring hot spots
+    Percentile { low: f32, high: f32 },
+}
+
+impl Normalization {
+    /// Preset for thermal footage
+    pub const THERMAL: Self = Self::Percentile {
+        low: 0.02,
+        high: 0.98,
+    };
+
+    /// Stretch samples in [0, 1] in place
+    pub fn apply(&self, image: &mut Array3<f32>) {
+        let (low, high) = match *self {
+            Self::Fixed => return,
+            Self::MinMax => image
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v))),
+            Self::Percentile { low, high } => {
+                let mut values: Vec<f32> = image.iter().copied().collect();
+                (quantile(&mut values, low), quantile(&mut values, high))
+            }
+        };
+        if low > high {
+            // Empty crop
+            return;
+        }
+
+        // Flat crops stay flat instead of amplifying noise
+        let span = (high - low).max(1.0 / 255.0);
+        image.mapv_inplace(|v| ((v - low) / span).clamp(0.0, 1.0));
+    }
+}
+
+/// Value at quantile q (0-1), partially reordering `values`
+fn quantile(values: &mut [f32], q: f32) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let idx = ((values.len() - 1) as f32 * q.clamp(0.0, 1.0)).round() as usize;
+    *values.select_nth_unstable_by(idx, f32::total_cmp).1
+}
+
+/// Mapping of high-bit-depth samples to [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleRange {
+    /// Sample value mapped to 0
+    pub low: u16,
+    /// Sample value mapped to 1
+    pub high: u16,
+}
+
+impl SampleRange {
+    /// Full range of a `bits`-bit source (10 -> 0..=1023)
+    pub fn bits(bits: u32) -> Self {
+        Self {
+            low: 0,
+            high: ((1u32 << bits.clamp(1, 16)) - 1) as u16,
+        }
+    }
+
+    #[inline]
+    pub fn normalize(&self, value: u16) -> f32 {
+        let span = self.high.saturating_sub(self.low).max(1) as f32;
+        ((value.saturating_sub(self.low)) as f32 / span).min(1.0)
+    }
+}
+
+/// Crop around bbox from a 10/16-bit frame and resize, keeping full precision
+///
+/// Mono frames (1 channel) are replicated to RGB.
+///
+/// # Returns
+/// * Cropped image (output_size x output_size x 3) with samples in [0, 1]
+/// * Crop size in original image pixels
+pub fn crop_resized_u16(
+    image: &ArrayView3<u16>,
+    bbox: &BBox,
+    factor: u32,
+    output_size: usize,
+    range: SampleRange,
+) -> (Array3<f32>, i32) {
+    let (img_h, img_w, channels) = image.dim();
+    let crop_sz = crop_size(bbox, factor);
+    let x1 = bbox.x + (bbox.width - crop_sz) / 2;
+    let y1 = bbox.y + (bbox.height - crop_sz) / 2;
+
+    // Bilinear sampling straight from the frame; outside is zero padding
+    let scale = crop_sz as f32 / output_size as f32;
+    let sample = |y: i32, x: i32, c: usize| -> f32 {
+        if y < 0 || x < 0 || y >= img_h as i32 || x >= img_w as i32 {
+            return 0.0;
+        }
+        let c = if channels == 1 { 0 } else { c };
+        range.normalize(image[[y as usize, x as usize, c]])
+    };
+
+    let resized = Array3::from_shape_fn((output_size, output_size, 3), |(y, x, c)| {
+        let src_y = y as f32 * scale;
+        let src_x = x as f32 * scale;
+        let (y0, x0) = (src_y.floor() as i32, src_x.floor() as i32);
+        let (dy, dx) = (src_y - y0 as f32, src_x - x0 as f32);
+        let (y0, x0) = (y0 + y1, x0 + x1);
+
+        sample(y0, x0, c) * (1.0 - dx) * (1.0 - dy)
+            + sample(y0, x0 + 1, c) * dx * (1.0 - dy)
+            + sample(y0 + 1, x0, c) * (1.0 - dx) * dy
+            + sample(y0 + 1, x0 + 1, c) * dx * dy
+    });
+
+    (resized, crop_sz)
+}
+
+/// Crop size in image pixels: sqrt(area) * factor
+pub fn crop_size(bbox: &BBox, factor: u32) -> i32 {
+    (bbox.area().sqrt() * factor as f32).ceil() as i32
+}
+
+/// Crop and preprocess, downscaling the whole frame first when the crop is large
+///
+/// When the crop exceeds `max_crop` pixels the frame is box-downscaled by an
+/// integer factor and the crop is taken from the smaller image with adjusted
+/// coordinates. The returned crop size is still in original image pixels.
+///
+/// # Arguments
+/// * `image` - Input image as Array3<u8> in HWC format
+/// * `bbox` - Bounding box to crop around
+/// * `factor` - Crop factor (2 for template, 4 for search)
+/// * `output_size` - Output size (128 for template, 256 for search)
+/// * `max_crop` - Crop size above which the frame is downscaled first
+pub fn crop_and_preprocess_downscaled(
+    image: &ArrayView3<u8>,
+    bbox: &BBox,
+    factor: u32,
+    output_size: usize,
+    max_crop: i32,
+) -> (Vec<f32>, i32) {
+    let (resized, crop_sz) = crop_resized_downscaled(image, bbox, factor, output_size, max_crop);
+    (preprocess_nhwc(&resized), crop_sz)
+}
+
+/// Same as `crop_resized`, downscaling the frame first when the crop is large
+pub fn crop_resized_downscaled(
+    image: &ArrayView3<u8>,
+    bbox: &BBox,
+    factor: u32,
+    output_size: usize,
+    max_crop: i32,
+) -> (Array3<u8>, i32) {
+    let crop_sz = crop_size(bbox, factor);
+    if max_crop <= 0 || crop_sz <= max_crop {
+        return crop_resized(image, bbox, factor, output_size);
+    }
+
+    let scale = (crop_sz + max_crop - 1) / max_crop;
+    let small = downscale(image, scale as usize);
+    let small_bbox = BBox::new(
+        bbox.x / scale,
+        bbox.y / scale,
+        (bbox.width / scale).max(1),
+        (bbox.height / scale).max(1),
+    );
+
+    let (resized, _small_crop) = crop_resized(&small.view(), &small_bbox, factor, output_size);
+
+    (resized, crop_sz)
+}
+
+/// Downscale image by an integer factor using box averaging
+fn downscale(image: &ArrayView3<u8>, scale: usize) -> Array3<u8> {
+    let (h, w, channels) = image.dim();
+    let new_h = (h / scale).max(1);
+    let new_w = (w / scale).max(1);
+    let mut small = Array3::<u8>::zeros((new_h, new_w, channels));
+
+    for y in 0..new_h {
+        let y_end = ((y + 1) * scale).min(h);
+        for x in 0..new_w {
+            let x_end = ((x + 1) * scale).min(w);
+            for c in 0..channels {
+                let mut sum = 0u32;
+                let mut count = 0u32;
+                for sy in y * scale..y_end {
+                    for sx in x * scale..x_end {
+                        sum += image[[sy, sx, c]] as u32;
+                        count += 1;
+                    }
+                }
+                small[[y, x, c]] = (sum / count.max(1)) as u8;
+            }
+        }
+    }
+
+    small
 }
 
 /// Resize image using bilinear interpolation
@@ -159,12 +544,100 @@ fn resize_bilinear(image: &Array3<u8>, new_h: usize, new_w: usize) -> Array3<u8>
     resized
 }
 
+/// Fixed-point (Q16) per-channel coefficients mapping u8 pixels to int8
+///
+/// q = round(((p / 255 - mean) / std) / scale) + zero_point
+///   = (p * mul + add) >> 16
+#[derive(Debug, Clone, Copy)]
+pub struct Int8Coefficients {
+    mul: [i32; 3],
+    add: [i32; 3],
+}
+
+impl Int8Coefficients {
+    const SHIFT: i32 = 16;
+
+    pub fn new(zero_point: i32, scale: f32) -> Self {
+        let one = (1i32 << Self::SHIFT) as f32;
+        let mut mul = [0i32; 3];
+        let mut add = [0i32; 3];
+
+        for ch in 0..3 {
+            let gain = 1.0 / (255.0 * STD[ch] * scale);
+            let offset = zero_point as f32 - MEAN[ch] / (STD[ch] * scale);
+            mul[ch] = (gain * one).round() as i32;
+            // Fold the rounding half into the offset
+            add[ch] = (offset * one).round() as i32 + (1 << (Self::SHIFT - 1));
+        }
+
+        Self { mul, add }
+    }
+
+    #[inline]
+    pub fn apply(&self, pixel: u8, ch: usize) -> i8 {
+        let q = (pixel as i32 * self.mul[ch] + self.add[ch]) >> Self::SHIFT;
+        q.clamp(i8::MIN as i32, i8::MAX as i32) as i8
+    }
+}
+
+/// Normalize and quantize image to NHWC int8 with integer arithmetic only
+/// Input: RGB HWC uint8
+/// Output: RGB NHWC int8 (as flat Vec)
+pub fn preprocess_nhwc_i8(image: &Array3<u8>, coefficients: &Int8Coefficients) -> Vec<i8> {
+    let (h, w, c) = image.dim();
+    let mut output = vec![0i8; h * w * c];
+
+    for y in 0..h {
+        for x in 0..w {
+            for ch in 0..3 {
+                output[y * w * 3 + x * 3 + ch] = coefficients.apply(image[[y, x, ch]], ch);
+            }
+        }
+    }
+
+    output
+}
+
+/// Preprocess image to NHWC float16 format with ImageNet normalization
+/// Input: RGB HWC uint8
+/// Output: RGB NHWC float16 normalized (as flat Vec)
+pub fn preprocess_nhwc_f16(image: &Array3<u8>) -> Vec<f16> {
+    let (h, w, c) = image.dim();
+    let mut output = vec![f16::ZERO; h * w * c];
+
+    for y in 0..h {
+        for x in 0..w {
+            for ch in 0..3 {
+                let value = image[[y, x, ch]] as f32 / 255.0;
+                let normalized = (value - MEAN[ch]) / STD[ch];
+                output[y * w * 3 + x * 3 + ch] = f16::from_f32(normalized);
+            }
+        }
+    }
+
+    output
+}
+
 /// Preprocess image to NHWC float32 format with ImageNet normalization
 /// Input: RGB HWC uint8
 /// Output: RGB NHWC float32 normalized (as flat Vec)
-fn preprocess_nhwc(image: &Array3<u8>) -> Vec<f32> {
+pub fn preprocess_nhwc(image: &Array3<u8>) -> Vec<f32> {
     let (h, w, c) = image.dim();
-    let mut output = vec![0.0f32; 1 * h * w * c];
+    let mut output = vec![0.0f32; h * w * c];
+    preprocess_nhwc_into(image, &mut output);
+    output
+}
+
+/// Preprocess image to NHWC float32 into a caller-provided buffer
+///
+/// Writes the normalized crop straight into the model input memory (e.g.
+/// `rknn::InputBuffers`) instead of a temporary Vec.
+///
+/// # Panics
+/// * When `output` does not hold exactly h * w * 3 values
+pub fn preprocess_nhwc_into(image: &Array3<u8>, output: &mut [f32]) {
+    let (h, w, _) = image.dim();
+    assert_eq!(output.len(), h * w * 3, "output must hold h * w * 3 values");
 
     for y in 0..h {
         for x in 0..w {
@@ -180,14 +653,59 @@ fn preprocess_nhwc(image: &Array3<u8>) -> Vec<f32> {
             }
         }
     }
-
-    output
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_u16_crop_matches_range() {
+        // Uniform 10-bit mono frame
+        let image = Array3::<u16>::from_elem((64, 64, 1), 612);
+        let bbox = BBox::new(24, 24, 16, 16);
+        let (crop, crop_sz) = crop_resized_u16(&image.view(), &bbox, 2, 8, SampleRange::bits(10));
+        assert_eq!(crop_sz, 32);
+        assert_eq!(crop.dim(), (8, 8, 3));
+        assert!(crop.iter().all(|&v| (v - 612.0 / 1023.0).abs() < 1e-6));
+
+        // Unit-range tensors match the 8-bit path for 8-bit values
+        let unit = Array3::<f32>::from_elem((2, 2, 3), 100.0 / 255.0);
+        let bytes = Array3::<u8>::from_elem((2, 2, 3), 100);
+        let (InputTensor::Float32(a), InputTensor::Float32(b)) = (
+            InputTensor::from_unit_image(&unit, InputType::Float32),
+            InputTensor::from_image(&bytes, InputType::Float32),
+        ) else {
+            panic!("expected float32 tensors");
+        };
+        assert!(a.iter().zip(&b).all(|(x, y)| (x - y).abs() < 1e-5));
+    }
+
+    #[test]
+    fn test_normalization_removes_gain_and_offset() {
+        let crop = Array3::from_shape_fn((16, 16, 3), |(y, x, _)| (40 + 4 * y + 2 * x) as u8);
+        let agc = crop.mapv(|v| v / 2 + 60);
+
+        for normalization in [Normalization::MinMax, Normalization::THERMAL] {
+            let (InputTensor::Float32(a), InputTensor::Float32(b)) = (
+                InputTensor::from_image_normalized(&crop, InputType::Float32, normalization),
+                InputTensor::from_image_normalized(&agc, InputType::Float32, normalization),
+            ) else {
+                panic!("expected float32 tensors");
+            };
+            assert!(a.iter().zip(&b).all(|(x, y)| (x - y).abs() < 0.1));
+        }
+    }
+
+    #[test]
+    fn test_tensor_blend() {
+        let a = InputTensor::Uint8(vec![0, 100, 200]);
+        let b = InputTensor::Uint8(vec![100, 100, 0]);
+        assert!(matches!(a.blend(&b, 0.25), Some(InputTensor::Uint8(v)) if v == [25, 100, 150]));
+        assert!(a.blend(&InputTensor::Float32(vec![0.0; 3]), 0.5).is_none());
+        assert!(a.blend(&InputTensor::Uint8(vec![0; 2]), 0.5).is_none());
+    }
+
     #[test]
     fn test_bbox() {
         let bbox = BBox::new(100, 100, 50, 50);
@@ -201,6 +719,75 @@ mod tests {
         assert_eq!(crop_sz, 100);
     }
 
+    #[test]
+    fn test_downscale() {
+        let mut image = Array3::<u8>::zeros((4, 4, 3));
+        image[[0, 0, 0]] = 200;
+        image[[1, 1, 0]] = 200;
+        let small = downscale(&image.view(), 2);
+        assert_eq!(small.dim(), (2, 2, 3));
+        assert_eq!(small[[0, 0, 0]], 100);
+        assert_eq!(small[[1, 1, 0]], 0);
+    }
+
+    #[test]
+    fn test_downscaled_crop_size() {
+        let image = Array3::<u8>::zeros((1080, 1920, 3));
+        let bbox = BBox::new(800, 400, 300, 300);
+        let (result, crop_sz) =
+            crop_and_preprocess_downscaled(&image.view(), &bbox, 4, 256, 512);
+        assert_eq!(result.len(), 256 * 256 * 3);
+        assert_eq!(crop_sz, 1200);
+    }
+
+    #[test]
+    fn test_f16_matches_f32() {
+        let mut image = Array3::<u8>::zeros((8, 8, 3));
+        image[[3, 4, 1]] = 200;
+        let f32_data = preprocess_nhwc(&image);
+        let f16_data = preprocess_nhwc_f16(&image);
+        for (a, b) in f32_data.iter().zip(&f16_data) {
+            assert!((a - b.to_f32()).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_uint8_tensor_is_raw_hwc() {
+        let mut image = Array3::<u8>::zeros((4, 4, 3));
+        image[[1, 2, 0]] = 10;
+        image[[1, 2, 2]] = 30;
+        let tensor = InputTensor::from_image(&image, InputType::Uint8);
+        let InputTensor::Uint8(data) = tensor else {
+            panic!("expected uint8 tensor");
+        };
+        assert_eq!(data.len(), 4 * 4 * 3);
+        assert_eq!(data[(4 + 2) * 3], 10);
+        assert_eq!(data[(4 + 2) * 3 + 2], 30);
+    }
+
+    #[test]
+    fn test_int8_matches_float_quantization() {
+        let (zero_point, scale) = (-10, 0.0187);
+        let coefficients = Int8Coefficients::new(zero_point, scale);
+        for ch in 0..3 {
+            for pixel in 0..=255u8 {
+                let normalized = (pixel as f32 / 255.0 - MEAN[ch]) / STD[ch];
+                let expected =
+                    ((normalized / scale).round() as i32 + zero_point).clamp(-128, 127);
+                let actual = coefficients.apply(pixel, ch) as i32;
+                assert!((actual - expected).abs() <= 1, "ch {} pixel {}", ch, pixel);
+            }
+        }
+    }
+
+    #[test]
+    fn test_preprocess_into_buffer() {
+        let image = Array3::from_shape_fn((4, 5, 3), |(y, x, c)| (y * 40 + x * 7 + c * 3) as u8);
+        let mut buffer = vec![0.0f32; 4 * 5 * 3];
+        preprocess_nhwc_into(&image, &mut buffer);
+        assert_eq!(buffer, preprocess_nhwc(&image));
+    }
+
     #[test]
     fn test_preprocess_shape() {
         // let image = ArrayView3::<u8>::((480, 640, 3));
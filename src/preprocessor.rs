@@ -0,0 +1,270 @@
+//! Pluggable crop + resize + normalize stage
+//!
+//! `VitTrack` produces its search tensors through a `Preprocessor` taken from
+//! the config, so hardware paths (RGA, GPU) can be added by implementing the
+//! trait without touching the tracker.
+
+use std::fmt;
+use std::sync::Arc;
+
+use half::f16;
+use ndarray::ArrayView3;
+
+use crate::preprocess::{
+    crop_resized, crop_size, preprocess_nhwc_into, BBox, InputTensor, InputType, Normalization,
+    MEAN, STD,
+};
+
+/// Crop geometry and tensor format requested from a preprocessor
+#[derive(Debug, Clone, Copy)]
+pub struct CropSpec {
+    /// Context factor: crop side is sqrt(area) * factor
+    pub factor: u32,
+    /// Output side in pixels
+    pub output_size: usize,
+    pub input_type: InputType,
+    pub normalization: Normalization,
+}
+
+/// Crop around a box, resize and convert to a model input tensor
+pub trait Preprocessor: fmt::Debug + Send + Sync {
+    /// # Returns
+    /// * Input tensor (output_size x output_size x 3, NHWC)
+    /// * Crop size in image pixels
+    fn crop_tensor(
+        &self,
+        image: &ArrayView3<u8>,
+        bbox: &BBox,
+        spec: &CropSpec,
+    ) -> (InputTensor, i32);
+
+    /// Float32 crop written into caller memory (e.g. `rknn::InputBuffers`)
+    ///
+    /// `spec.input_type` is ignored. The default copies the `crop_tensor`
+    /// result; implementations can write `output` directly.
+    ///
+    /// # Returns
+    /// * Crop size in image pixels
+    fn crop_into(
+        &self,
+        image: &ArrayView3<u8>,
+        bbox: &BBox,
+        spec: &CropSpec,
+        output: &mut [f32],
+    ) -> i32 {
+        let spec = CropSpec {
+            input_type: InputType::Float32,
+            ..*spec
+        };
+        match self.crop_tensor(image, bbox, &spec) {
+            (InputTensor::Float32(data), crop_size) => {
+                output.copy_from_slice(&data);
+                crop_size
+            }
+            (other, _) => panic!("{} returned {} for float32", self.name(), other.type_name()),
+        }
+    }
+
+    /// Short name, for logs and runtime selection
+    fn name(&self) -> &'static str;
+}
+
+/// Reference path: 8-bit crop, then tensor conversion
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuPreprocessor;
+
+impl Preprocessor for CpuPreprocessor {
+    fn crop_tensor(
+        &self,
+        image: &ArrayView3<u8>,
+        bbox: &BBox,
+        spec: &CropSpec,
+    ) -> (InputTensor, i32) {
+        let (crop, crop_size) = crop_resized(image, bbox, spec.factor, spec.output_size);
+        let tensor = InputTensor::from_image_normalized(&crop, spec.input_type, spec.normalization);
+        (tensor, crop_size)
+    }
+
+    fn crop_into(
+        &self,
+        image: &ArrayView3<u8>,
+        bbox: &BBox,
+        spec: &CropSpec,
+        output: &mut [f32],
+    ) -> i32 {
+        let (crop, crop_size) = crop_resized(image, bbox, spec.factor, spec.output_size);
+        if spec.normalization == Normalization::Fixed {
+            preprocess_nhwc_into(&crop, output);
+        } else {
+            let tensor =
+                InputTensor::from_image_normalized(&crop, InputType::Float32, spec.normalization);
+            match tensor {
+                InputTensor::Float32(data) => output.copy_from_slice(&data),
+                _ => unreachable!("float32 requested"),
+            }
+        }
+        crop_size
+    }
+
+    fn name(&self) -> &'static str {
+        "cpu"
+    }
+}
+
+/// Single pass from the frame to a float tensor
+///
+/// Interpolation taps are computed once per row and column and the output is
+/// written directly, without the intermediate crop and resized images. The
+/// result is identical to `CpuPreprocessor`; other input types and
+/// normalizations fall back to it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FusedPreprocessor;
+
+impl Preprocessor for FusedPreprocessor {
+    fn crop_tensor(
+        &self,
+        image: &ArrayView3<u8>,
+        bbox: &BBox,
+        spec: &CropSpec,
+    ) -> (InputTensor, i32) {
+        let is_float = matches!(spec.input_type, InputType::Float32 | InputType::Float16);
+        if !is_float || spec.normalization != Normalization::Fixed {
+            return CpuPreprocessor.crop_tensor(image, bbox, spec);
+        }
+
+        let size = spec.output_size;
+        let mut output = vec![0.0f32; size * size * 3];
+        let crop_sz = fused_crop(image, bbox, spec, &mut output);
+        let tensor = match spec.input_type {
+            InputType::Float16 => {
+                InputTensor::Float16(output.into_iter().map(f16::from_f32).collect())
+            }
+            _ => InputTensor::Float32(output),
+        };
+        (tensor, crop_sz)
+    }
+
+    fn crop_into(
+        &self,
+        image: &ArrayView3<u8>,
+        bbox: &BBox,
+        spec: &CropSpec,
+        output: &mut [f32],
+    ) -> i32 {
+        if spec.normalization != Normalization::Fixed {
+            return CpuPreprocessor.crop_into(image, bbox, spec, output);
+        }
+        fused_crop(image, bbox, spec, output)
+    }
+
+    fn name(&self) -> &'static str {
+        "fused"
+    }
+}
+
+/// `FusedPreprocessor` float32 path: fixed normalization written into `output`
+fn fused_crop(image: &ArrayView3<u8>, bbox: &BBox, spec: &CropSpec, output: &mut [f32]) -> i32 {
+    let (img_h, img_w, _) = image.dim();
+    let crop_sz = crop_size(bbox, spec.factor);
+    let size = spec.output_size;
+    if crop_sz > 0 {
+        let x1 = bbox.x + (bbox.width - crop_sz) / 2;
+        let y1 = bbox.y + (bbox.height - crop_sz) / 2;
+        let rows = taps(y1, crop_sz as usize, size, img_h);
+        let cols = taps(x1, crop_sz as usize, size, img_w);
+        let gain = [0, 1, 2].map(|c| 1.0 / (255.0 * STD[c]));
+        let bias = [0, 1, 2].map(|c| -MEAN[c] / STD[c]);
+
+        let pixel = |y: Option<usize>, x: Option<usize>, c: usize| match (y, x) {
+            (Some(y), Some(x)) => image[[y, x, c]] as f32,
+            _ => 0.0,
+        };
+        for (row, &(y0, y1, dy)) in output.chunks_exact_mut(size * 3).zip(&rows) {
+            for (out, &(x0, x1, dx)) in row.chunks_exact_mut(3).zip(&cols) {
+                for c in 0..3 {
+                    let value = pixel(y0, x0, c) * (1.0 - dx) * (1.0 - dy)
+                        + pixel(y0, x1, c) * dx * (1.0 - dy)
+                        + pixel(y1, x0, c) * (1.0 - dx) * dy
+                        + pixel(y1, x1, c) * dx * dy;
+                    out[c] = value.round().clamp(0.0, 255.0) * gain[c] + bias[c];
+                }
+            }
+        }
+    } else {
+        // Empty crop is all zero pixels
+        for px in output.chunks_exact_mut(3) {
+            for c in 0..3 {
+                px[c] = -MEAN[c] / STD[c];
+            }
+        }
+    }
+    crop_sz
+}
+
+/// Bilinear taps along one axis: frame indices (None outside) and fraction
+///
+/// Same sampling as `crop_resized`: the crop starting at `start` with side
+/// `crop` is zero padded and resized to `out` samples.
+fn taps(
+    start: i32,
+    crop: usize,
+    out: usize,
+    limit: usize,
+) -> Vec<(Option<usize>, Option<usize>, f32)> {
+    let scale = crop as f32 / out as f32;
+    let to_frame = |i: usize| {
+        let p = start + i as i32;
+        (p >= 0 && (p as usize) < limit).then_some(p as usize)
+    };
+    (0..out)
+        .map(|i| {
+            let src = i as f32 * scale;
+            let i0 = (src.floor() as usize).min(crop - 1);
+            let i1 = (i0 + 1).min(crop - 1);
+            (to_frame(i0), to_frame(i1), src - i0 as f32)
+        })
+        .collect()
+}
+
+/// Look up a preprocessor by name ("cpu", "fused")
+pub fn by_name(name: &str) -> Option<Arc<dyn Preprocessor>> {
+    match name {
+        "cpu" => Some(Arc::new(CpuPreprocessor)),
+        "fused" => Some(Arc::new(FusedPreprocessor)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_fused_matches_cpu() {
+        let image = Array3::from_shape_fn((60, 80, 3), |(y, x, c)| (3 * x + 5 * y + 40 * c) as u8);
+        // Partly outside the frame to exercise the padding
+        let bbox = BBox::new(60, -5, 20, 14);
+        let spec = CropSpec {
+            factor: 4,
+            output_size: 32,
+            input_type: InputType::Float32,
+            normalization: Normalization::Fixed,
+        };
+
+        let (cpu, cpu_size) = CpuPreprocessor.crop_tensor(&image.view(), &bbox, &spec);
+        let (fused, fused_size) = FusedPreprocessor.crop_tensor(&image.view(), &bbox, &spec);
+        assert_eq!(cpu_size, fused_size);
+        let (InputTensor::Float32(cpu), InputTensor::Float32(fused)) = (cpu, fused) else {
+            panic!("expected float32 tensors");
+        };
+        assert!(cpu.iter().zip(&fused).all(|(a, b)| (a - b).abs() < 1e-5));
+
+        // Writing into caller memory gives the same tensors
+        let mut output = vec![0.0f32; 32 * 32 * 3];
+        assert_eq!(CpuPreprocessor.crop_into(&image.view(), &bbox, &spec, &mut output), cpu_size);
+        assert_eq!(output, cpu);
+        assert_eq!(FusedPreprocessor.crop_into(&image.view(), &bbox, &spec, &mut output), cpu_size);
+        assert_eq!(output, fused);
+    }
+}
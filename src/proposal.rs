@@ -0,0 +1,205 @@
+use ndarray::{Array2, ArrayView3};
+
+use crate::preprocess::BBox;
+
+/// Motion proposal configuration
+#[derive(Debug, Clone, Copy)]
+pub struct MotionProposalConfig {
+    /// Integer downscale factor applied before background modeling
+    pub downscale: usize,
+    /// Background adaptation rate per frame (0..1)
+    pub learning_rate: f32,
+    /// Foreground when the pixel deviates by more than this many standard deviations
+    pub k: f32,
+    /// Lower bound of the per-pixel standard deviation (luma levels)
+    pub min_std: f32,
+    /// Minimum proposal area in full-resolution pixels
+    pub min_area: i32,
+    /// Frames used to learn the background before proposing
+    pub warmup_frames: usize,
+}
+
+impl Default for MotionProposalConfig {
+    fn default() -> Self {
+        Self {
+            downscale: 4,
+            learning_rate: 0.05,
+            k: 3.0,
+            min_std: 8.0,
+            min_area: 400,
+            warmup_frames: 10,
+        }
+    }
+}
+
+/// Moving-object proposals from a per-pixel Gaussian background model
+///
+/// Intended for fixed cameras: the strongest proposal can be passed to
+/// `VitTrack::init` when no detector is available.
+#[derive(Debug, Clone)]
+pub struct MotionProposals {
+    config: MotionProposalConfig,
+    mean: Option<Array2<f32>>,
+    var: Array2<f32>,
+    frames: usize,
+}
+
+impl MotionProposals {
+    pub fn new(config: MotionProposalConfig) -> Self {
+        Self {
+            config,
+            mean: None,
+            var: Array2::zeros((0, 0)),
+            frames: 0,
+        }
+    }
+
+    /// Update the background with a frame and return proposals, largest first
+    ///
+    /// # Arguments
+    /// * `image` - Input image in HWC format (3 channels)
+    pub fn update(&mut self, image: &ArrayView3<u8>) -> Vec<BBox> {
+        let luma = downscaled_luma(image, self.config.downscale.max(1));
+
+        let (mean, var) = match &mut self.mean {
+            Some(mean) if mean.dim() == luma.dim() => (mean, &mut self.var),
+            _ => {
+                // First frame or resolution change: restart the model
+                let min_var = self.config.min_std * self.config.min_std;
+                self.var = Array2::from_elem(luma.dim(), min_var);
+                self.frames = 0;
+                (self.mean.insert(luma.clone()), &mut self.var)
+            }
+        };
+
+        let alpha = self.config.learning_rate;
+        let min_var = self.config.min_std * self.config.min_std;
+        let k_sq = self.config.k * self.config.k;
+        let mut foreground = Array2::<bool>::from_elem(luma.dim(), false);
+        ndarray::Zip::from(&mut foreground)
+            .and(mean)
+            .and(var)
+            .and(&luma)
+            .for_each(|fg, m, v, &x| {
+                let d = x - *m;
+                *fg = d * d > k_sq * v.max(min_var);
+                // Foreground pixels adapt slowly so stopped objects fade in gradually
+                let rate = if *fg { alpha * 0.1 } else { alpha };
+                *m += rate * d;
+                *v += rate * (d * d - *v);
+            });
+
+        self.frames += 1;
+        if self.frames <= self.config.warmup_frames {
+            return Vec::new();
+        }
+
+        let scale = self.config.downscale.max(1) as i32;
+        let mut boxes: Vec<BBox> = connected_boxes(&foreground)
+            .into_iter()
+            .map(|[x, y, w, h]| BBox::new(x * scale, y * scale, w * scale, h * scale))
+            .filter(|b| b.width * b.height >= self.config.min_area)
+            .collect();
+        boxes.sort_by_key(|b| std::cmp::Reverse(b.width * b.height));
+        boxes
+    }
+
+    /// Forget the background model
+    pub fn reset(&mut self) {
+        self.mean = None;
+        self.frames = 0;
+    }
+}
+
+/// Box-averaged luminance at 1/factor resolution
+fn downscaled_luma(image: &ArrayView3<u8>, factor: usize) -> Array2<f32> {
+    let (h, w, _) = image.dim();
+    let (out_h, out_w) = ((h / factor).max(1), (w / factor).max(1));
+    let norm = 1.0 / (factor * factor) as f32;
+
+    Array2::from_shape_fn((out_h, out_w), |(oy, ox)| {
+        let mut sum = 0.0f32;
+        for y in (oy * factor..(oy + 1) * factor).map(|y| y.min(h - 1)) {
+            for x in (ox * factor..(ox + 1) * factor).map(|x| x.min(w - 1)) {
+                sum += 0.299 * image[[y, x, 0]] as f32
+                    + 0.587 * image[[y, x, 1]] as f32
+                    + 0.114 * image[[y, x, 2]] as f32;
+            }
+        }
+        sum * norm
+    })
+}
+
+/// Bounding boxes [x, y, w, h] of 8-connected foreground components
+fn connected_boxes(mask: &Array2<bool>) -> Vec<[i32; 4]> {
+    let (h, w) = mask.dim();
+    let mut visited = Array2::<bool>::from_elem((h, w), false);
+    let mut boxes = Vec::new();
+    let mut stack = Vec::new();
+
+    for start_y in 0..h {
+        for start_x in 0..w {
+            if !mask[[start_y, start_x]] || visited[[start_y, start_x]] {
+                continue;
+            }
+
+            let (mut x1, mut y1, mut x2, mut y2) = (start_x, start_y, start_x, start_y);
+            visited[[start_y, start_x]] = true;
+            stack.push((start_y, start_x));
+            while let Some((y, x)) = stack.pop() {
+                x1 = x1.min(x);
+                y1 = y1.min(y);
+                x2 = x2.max(x);
+                y2 = y2.max(y);
+
+                for ny in y.saturating_sub(1)..(y + 2).min(h) {
+                    for nx in x.saturating_sub(1)..(x + 2).min(w) {
+                        if mask[[ny, nx]] && !visited[[ny, nx]] {
+                            visited[[ny, nx]] = true;
+                            stack.push((ny, nx));
+                        }
+                    }
+                }
+            }
+
+            boxes.push([
+                x1 as i32,
+                y1 as i32,
+                (x2 - x1 + 1) as i32,
+                (y2 - y1 + 1) as i32,
+            ]);
+        }
+    }
+
+    boxes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_proposes_moving_square() {
+        let mut proposals = MotionProposals::new(MotionProposalConfig {
+            warmup_frames: 3,
+            ..Default::default()
+        });
+        let background = Array3::<u8>::from_elem((120, 160, 3), 60);
+        for _ in 0..3 {
+            assert!(proposals.update(&background.view()).is_empty());
+        }
+
+        let mut frame = background.clone();
+        for y in 40..80 {
+            for x in 80..120 {
+                for c in 0..3 {
+                    frame[[y, x, c]] = 220;
+                }
+            }
+        }
+        let boxes = proposals.update(&frame.view());
+        assert_eq!(boxes.len(), 1);
+        assert_eq!(boxes[0].to_array(), [80, 40, 40, 40]);
+    }
+}
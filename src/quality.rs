@@ -0,0 +1,163 @@
+use ndarray::Array3;
+
+/// Reason a template selection is likely to track poorly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateWarning {
+    /// Nearly uniform intensity inside the box (sky, wall)
+    LowContrast,
+    /// Little edge/texture energy inside the box
+    LowTexture,
+    /// Box content is indistinguishable from its surroundings
+    LowForeground,
+}
+
+/// Template quality metrics computed at initialization
+#[derive(Debug, Clone, Default)]
+pub struct TemplateQuality {
+    /// Standard deviation of luminance inside the box
+    pub contrast: f32,
+    /// Mean gradient magnitude inside the box
+    pub texture: f32,
+    /// Fraction of box pixels that differ from the surrounding context
+    pub foreground: f32,
+    /// Combined quality in [0, 1]
+    pub score: f32,
+    pub warnings: Vec<TemplateWarning>,
+}
+
+impl TemplateQuality {
+    const MIN_CONTRAST: f32 = 8.0;
+    const MIN_TEXTURE: f32 = 4.0;
+    const MIN_FOREGROUND: f32 = 0.2;
+    /// Color distance from the context mean above which a pixel is foreground
+    const FOREGROUND_DISTANCE: f32 = 20.0;
+
+    pub fn is_poor(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+}
+
+/// Analyze a template crop
+///
+/// # Arguments
+/// * `crop` - Resized template crop (RGB HWC), target centered
+/// * `box_size` - Target (width, height) in crop pixels
+pub fn template_quality(crop: &Array3<u8>, box_size: (usize, usize)) -> TemplateQuality {
+    let (h, w, _) = crop.dim();
+    let box_w = box_size.0.clamp(1, w);
+    let box_h = box_size.1.clamp(1, h);
+    let x1 = (w - box_w) / 2;
+    let y1 = (h - box_h) / 2;
+    let (x2, y2) = (x1 + box_w, y1 + box_h);
+    let inside = |y: usize, x: usize| y >= y1 && y < y2 && x >= x1 && x < x2;
+
+    let luma = |y: usize, x: usize| {
+        0.299 * crop[[y, x, 0]] as f32
+            + 0.587 * crop[[y, x, 1]] as f32
+            + 0.114 * crop[[y, x, 2]] as f32
+    };
+
+    // Contrast and texture inside the box
+    let (mut sum, mut sum_sq, mut grad) = (0.0f32, 0.0f32, 0.0f32);
+    for y in y1..y2 {
+        for x in x1..x2 {
+            let v = luma(y, x);
+            sum += v;
+            sum_sq += v * v;
+
+            let gx = luma(y, (x + 1).min(w - 1)) - luma(y, x.saturating_sub(1));
+            let gy = luma((y + 1).min(h - 1), x) - luma(y.saturating_sub(1), x);
+            grad += 0.5 * gx.hypot(gy);
+        }
+    }
+    let count = (box_w * box_h) as f32;
+    let mean = sum / count;
+    let contrast = (sum_sq / count - mean * mean).max(0.0).sqrt();
+    let texture = grad / count;
+
+    // Mean color of the context outside the box
+    let mut context = [0.0f32; 3];
+    let mut context_count = 0usize;
+    for y in 0..h {
+        for x in 0..w {
+            if !inside(y, x) {
+                for c in 0..3 {
+                    context[c] += crop[[y, x, c]] as f32;
+                }
+                context_count += 1;
+            }
+        }
+    }
+
+    let foreground = if context_count == 0 {
+        1.0
+    } else {
+        context.iter_mut().for_each(|c| *c /= context_count as f32);
+        let mut differing = 0usize;
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let dist_sq: f32 = (0..3)
+                    .map(|c| (crop[[y, x, c]] as f32 - context[c]).powi(2))
+                    .sum();
+                if dist_sq.sqrt() > TemplateQuality::FOREGROUND_DISTANCE {
+                    differing += 1;
+                }
+            }
+        }
+        differing as f32 / count
+    };
+
+    let mut warnings = Vec::new();
+    if contrast < TemplateQuality::MIN_CONTRAST {
+        warnings.push(TemplateWarning::LowContrast);
+    }
+    if texture < TemplateQuality::MIN_TEXTURE {
+        warnings.push(TemplateWarning::LowTexture);
+    }
+    if foreground < TemplateQuality::MIN_FOREGROUND {
+        warnings.push(TemplateWarning::LowForeground);
+    }
+
+    let score = ((contrast / (4.0 * TemplateQuality::MIN_CONTRAST)).min(1.0)
+        + (texture / (4.0 * TemplateQuality::MIN_TEXTURE)).min(1.0)
+        + foreground)
+        / 3.0;
+
+    TemplateQuality {
+        contrast,
+        texture,
+        foreground,
+        score,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_template_is_poor() {
+        let crop = Array3::<u8>::from_elem((128, 128, 3), 180);
+        let quality = template_quality(&crop, (64, 64));
+        assert!(quality.is_poor());
+        assert!(quality.warnings.contains(&TemplateWarning::LowContrast));
+        assert!(quality.warnings.contains(&TemplateWarning::LowForeground));
+    }
+
+    #[test]
+    fn test_textured_object_is_good() {
+        let mut crop = Array3::<u8>::from_elem((128, 128, 3), 40);
+        for y in 32..96 {
+            for x in 32..96 {
+                let v = if (x / 4 + y / 4) % 2 == 0 { 250 } else { 120 };
+                for c in 0..3 {
+                    crop[[y, x, c]] = v;
+                }
+            }
+        }
+        let quality = template_quality(&crop, (64, 64));
+        assert!(!quality.is_poor(), "{:?}", quality);
+        assert!(quality.score > 0.5);
+    }
+}
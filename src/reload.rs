@@ -0,0 +1,156 @@
+//! Runtime settings reloaded while tracking
+//!
+//! Thresholds and search settings live in a small `key = value` file that can
+//! be edited in the field. `FileWatcher` notices the change and
+//! `VitTrack::apply_settings` applies it between frames, keeping the track.
+//!
+//! ```text
+//! # runtime.conf
+//! score_threshold = 0.3
+//! search_factor = 5
+//! window_influence = default
+//! score_smoothing = 0.3
+//! ```
+//!
+//! `default` (or a missing key) restores the value from `VitTrackConfig`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Tracker settings that can change without rebuilding the tracker
+///
+/// `None` keeps the configured value.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuntimeSettings {
+    pub score_threshold: Option<f32>,
+    pub search_factor: Option<u32>,
+    /// Weight of the Hann window: 0 disables it, 1 is the full window
+    pub window_influence: Option<f32>,
+    /// Weight of the newest score in `TrackingResult::score_smoothed`
+    pub score_smoothing: Option<f32>,
+}
+
+impl RuntimeSettings {
+    /// Parse `key = value` lines; `#` starts a comment
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut settings = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |what: &str| format!("line {}: {}: {}", number + 1, what, line);
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid("expected key = value"))?;
+            let value = value.trim();
+            let value = (value != "default").then_some(value);
+
+            match key.trim() {
+                "score_threshold" => {
+                    let threshold = parse_value(value).map_err(|_| invalid("bad number"))?;
+                    settings.score_threshold = threshold;
+                }
+                "search_factor" => {
+                    let factor = parse_value(value).map_err(|_| invalid("bad integer"))?;
+                    settings.search_factor = factor;
+                }
+                "window_influence" => {
+                    let influence: Option<f32> =
+                        parse_value(value).map_err(|_| invalid("bad number"))?;
+                    if influence.is_some_and(|v| !(0.0..=1.0).contains(&v)) {
+                        return Err(invalid("window_influence must be in [0, 1]"));
+                    }
+                    settings.window_influence = influence;
+                }
+                "score_smoothing" => {
+                    let alpha: Option<f32> =
+                        parse_value(value).map_err(|_| invalid("bad number"))?;
+                    if alpha.is_some_and(|v| !(0.0..=1.0).contains(&v)) {
+                        return Err(invalid("score_smoothing must be in [0, 1]"));
+                    }
+                    settings.score_smoothing = alpha;
+                }
+                _ => return Err(invalid("unknown setting")),
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Read and parse a settings file
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(value: Option<&str>) -> Result<Option<T>, T::Err> {
+    value.map(str::parse).transpose()
+}
+
+/// Polls a file's modification time
+#[derive(Debug, Clone)]
+pub struct FileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl FileWatcher {
+    /// The first `changed` call reports an existing file as changed
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            modified: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// File modified since the last call
+    ///
+    /// A missing file never counts as changed, so deleting it keeps the
+    /// settings in effect.
+    pub fn changed(&mut self) -> io::Result<bool> {
+        let modified = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata.modified()?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        if self.modified == Some(modified) {
+            return Ok(false);
+        }
+        self.modified = Some(modified);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_settings() {
+        let text = "# field tuning\nscore_threshold = 0.3\nsearch_factor=5 # wider\n\n\
+                    window_influence = default\nscore_smoothing = 0.25\n";
+        let settings = RuntimeSettings::parse(text).unwrap();
+        assert_eq!(
+            settings,
+            RuntimeSettings {
+                score_threshold: Some(0.3),
+                search_factor: Some(5),
+                window_influence: None,
+                score_smoothing: Some(0.25),
+            }
+        );
+
+        let err = RuntimeSettings::parse("score_threshold = 0.3\nthreshold = 1").unwrap_err();
+        assert!(err.starts_with("line 2: unknown setting"));
+        assert!(RuntimeSettings::parse("window_influence = 2").is_err());
+        assert!(RuntimeSettings::parse("score_smoothing = -0.5").is_err());
+    }
+}
@@ -0,0 +1,431 @@
+//! Standalone HTML run report
+//!
+//! `RunReport` collects the result of every frame and writes a single HTML
+//! file with no external resources: the session summary, the score timeline
+//! as inline SVG, the telemetry CSV as a download link and, optionally,
+//! thumbnails of the frames around each loss of track. The file can be mailed
+//! or attached to a ticket and opened in any browser.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use ndarray::{s, Array3, ArrayView3};
+
+use crate::postprocess::TrackingResult;
+use crate::snapshot::{box_color, draw_box};
+
+/// Report configuration
+#[derive(Debug, Clone)]
+pub struct ReportConfig {
+    pub title: String,
+    /// Keep thumbnails of the frame before and the frame of each loss
+    pub thumbnails: bool,
+    /// Thumbnail width in pixels (frames are downsampled by whole steps)
+    pub thumbnail_width: usize,
+    /// Thumbnails kept; later losses are listed without images
+    pub max_thumbnails: usize,
+    /// Drawn as a dashed line on the score timeline
+    pub score_threshold: Option<f32>,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self {
+            title: String::from("Tracking report"),
+            thumbnails: true,
+            thumbnail_width: 240,
+            max_thumbnails: 20,
+            score_threshold: None,
+        }
+    }
+}
+
+/// Figures of a whole session
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SessionSummary {
+    pub frames: usize,
+    /// Frames with `success`
+    pub tracked: usize,
+    /// Tracking -> lost transitions
+    pub losses: usize,
+    /// Longest run of lost frames
+    pub longest_loss: usize,
+    /// Mean score over the tracked frames
+    pub mean_score: f32,
+    /// First to last frame timestamp, when the frames carry one
+    pub duration: Option<Duration>,
+}
+
+struct Thumbnail {
+    frame: usize,
+    /// 24-bit BMP, which browsers display from a data URI
+    bmp: Vec<u8>,
+}
+
+/// Collects a session and renders it as a self-contained HTML file
+pub struct RunReport {
+    config: ReportConfig,
+    results: Vec<TrackingResult>,
+    thumbnails: Vec<Thumbnail>,
+    /// Thumbnail of the previous frame, kept until the next one
+    previous: Option<Thumbnail>,
+    was_tracking: bool,
+}
+
+impl RunReport {
+    pub fn new(config: ReportConfig) -> Self {
+        Self {
+            config,
+            results: Vec::new(),
+            thumbnails: Vec::new(),
+            previous: None,
+            was_tracking: false,
+        }
+    }
+
+    /// Record the result of a frame
+    ///
+    /// # Arguments
+    /// * `result` - Tracking result of the frame
+    /// * `image` - Frame the result was computed on (RGB HWC); only needed for thumbnails
+    pub fn observe(&mut self, result: &TrackingResult, image: Option<&ArrayView3<u8>>) {
+        let frame = self.results.len();
+        let lost = self.was_tracking && !result.success;
+        self.was_tracking = result.success;
+        self.results.push(*result);
+
+        let Some(image) = image.filter(|_| self.config.thumbnails) else {
+            return;
+        };
+        let thumbnail = Thumbnail {
+            frame,
+            bmp: encode_bmp(&downsample(image, result, self.config.thumbnail_width)),
+        };
+        if lost && self.thumbnails.len() < self.config.max_thumbnails {
+            self.thumbnails.extend(self.previous.take());
+            self.thumbnails.push(thumbnail);
+        } else {
+            self.previous = Some(thumbnail);
+        }
+    }
+
+    pub fn results(&self) -> &[TrackingResult] {
+        &self.results
+    }
+
+    pub fn summary(&self) -> SessionSummary {
+        let tracked: Vec<f32> =
+            self.results.iter().filter(|r| r.success).map(|r| r.score).collect();
+        let (mut losses, mut longest_loss, mut run) = (0, 0, 0);
+        let mut was_tracking = false;
+        for result in &self.results {
+            if result.success {
+                run = 0;
+            } else {
+                losses += usize::from(was_tracking);
+                run += 1;
+                longest_loss = longest_loss.max(run);
+            }
+            was_tracking = result.success;
+        }
+        let first = self.results.iter().find_map(|r| r.timestamp);
+        let last = self.results.iter().rev().find_map(|r| r.timestamp);
+
+        SessionSummary {
+            frames: self.results.len(),
+            tracked: tracked.len(),
+            losses,
+            longest_loss,
+            mean_score: tracked.iter().sum::<f32>() / tracked.len().max(1) as f32,
+            duration: first.zip(last).map(|(first, last)| last.saturating_sub(first)),
+        }
+    }
+
+    /// Per-frame telemetry: frame, timestamp, success, score, peak ratio, box,
+    /// track tag
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "frame,timestamp_ms,success,coasting,score,peak_ratio,x,y,w,h,track_id,class_id"
+        )?;
+        for (i, result) in self.results.iter().enumerate() {
+            let timestamp = result
+                .timestamp
+                .map(|t| format!("{:.1}", t.as_secs_f64() * 1000.0))
+                .unwrap_or_default();
+            let [x, y, w, h] = result.bbox;
+            let (track_id, class_id) = match result.tag {
+                Some(tag) => (tag.id.to_string(), tag.class_id.map(|c| c.to_string())),
+                None => (String::new(), None),
+            };
+            writeln!(
+                out,
+                "{},{},{},{},{:.4},{:.4},{},{},{},{},{},{}",
+                i,
+                timestamp,
+                result.success,
+                result.coasting,
+                result.score,
+                result.peak_ratio,
+                x,
+                y,
+                w,
+                h,
+                track_id,
+                class_id.unwrap_or_default()
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Render the report
+    pub fn to_html(&self) -> String {
+        let summary = self.summary();
+        let title = escape(&self.config.title);
+        let mut csv = Vec::new();
+        // Writing into a Vec cannot fail
+        let _ = self.write_csv(&mut csv);
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title>\n\
+             <style>body{{font-family:sans-serif;margin:2em;color:#222}}\
+             table{{border-collapse:collapse}}td,th{{padding:4px 12px;text-align:left}}\
+             tr:nth-child(even){{background:#f2f2f2}}figure{{display:inline-block;margin:8px}}\
+             img{{image-rendering:pixelated;border:1px solid #ccc}}</style>\n\
+             </head><body>\n<h1>{title}</h1>\n"
+        );
+
+        html.push_str("<h2>Summary</h2>\n<table>\n");
+        let tracked = 100.0 * summary.tracked as f32 / summary.frames.max(1) as f32;
+        let rows = [
+            ("Frames", summary.frames.to_string()),
+            ("Tracked", format!("{} ({:.1} %)", summary.tracked, tracked)),
+            ("Losses", summary.losses.to_string()),
+            ("Longest loss", format!("{} frames", summary.longest_loss)),
+            ("Mean score (tracked)", format!("{:.3}", summary.mean_score)),
+            (
+                "Duration",
+                summary
+                    .duration
+                    .map(|d| format!("{:.1} s", d.as_secs_f32()))
+                    .unwrap_or_else(|| String::from("-")),
+            ),
+        ];
+        for (name, value) in rows {
+            let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, value);
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Score timeline</h2>\n");
+        html.push_str("<p>Score per frame; shaded spans are frames without the target.</p>\n");
+        html.push_str(&self.timeline_svg());
+
+        if !self.thumbnails.is_empty() {
+            html.push_str("<h2>Loss events</h2>\n");
+            for thumbnail in &self.thumbnails {
+                let result = &self.results[thumbnail.frame];
+                let _ = writeln!(
+                    html,
+                    "<figure><img src=\"data:image/bmp;base64,{}\" alt=\"frame {}\">\
+                     <figcaption>frame {}: {} (score {:.2})</figcaption></figure>",
+                    base64(&thumbnail.bmp),
+                    thumbnail.frame,
+                    thumbnail.frame,
+                    if result.success { "before loss" } else { "lost" },
+                    result.score
+                );
+            }
+        }
+
+        let _ = write!(
+            html,
+            "<h2>Telemetry</h2>\n<p><a download=\"telemetry.csv\" \
+             href=\"data:text/csv;base64,{}\">Download telemetry.csv</a> \
+             ({} frames)</p>\n</body></html>\n",
+            base64(&csv),
+            summary.frames
+        );
+        html
+    }
+
+    /// Write the report to `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_html())
+    }
+
+    fn timeline_svg(&self) -> String {
+        const WIDTH: f32 = 900.0;
+        const HEIGHT: f32 = 200.0;
+        let span = self.results.len().max(2) - 1;
+        let x = |frame: usize| frame as f32 / span as f32 * WIDTH;
+        let y = |score: f32| HEIGHT - score.clamp(0.0, 1.0) * HEIGHT;
+
+        let mut svg = format!(
+            "<svg viewBox=\"0 0 {WIDTH} {HEIGHT}\" width=\"100%\" \
+             preserveAspectRatio=\"none\" style=\"max-width:{WIDTH}px;height:{HEIGHT}px;\
+             border:1px solid #ccc\">\n"
+        );
+        let mut lost_from = None;
+        for (i, result) in self.results.iter().enumerate() {
+            match (result.success, lost_from) {
+                (false, None) => lost_from = Some(i),
+                (true, Some(from)) => {
+                    push_span(&mut svg, x(from), x(i));
+                    lost_from = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(from) = lost_from {
+            push_span(&mut svg, x(from), WIDTH);
+        }
+
+        if let Some(threshold) = self.config.score_threshold {
+            let _ = writeln!(
+                svg,
+                "<line x1=\"0\" x2=\"{WIDTH}\" y1=\"{0:.1}\" y2=\"{0:.1}\" stroke=\"#888\" \
+                 stroke-dasharray=\"6 4\"/>",
+                y(threshold)
+            );
+        }
+
+        let points: Vec<String> = self
+            .results
+            .iter()
+            .enumerate()
+            .map(|(i, r)| format!("{:.1},{:.1}", x(i), y(r.score)))
+            .collect();
+        let _ = writeln!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"#1f77b4\" stroke-width=\"1.5\" \
+             vector-effect=\"non-scaling-stroke\" points=\"{}\"/>\n</svg>",
+            points.join(" ")
+        );
+        svg
+    }
+}
+
+fn push_span(svg: &mut String, from: f32, to: f32) {
+    let _ = writeln!(
+        svg,
+        "<rect x=\"{:.1}\" y=\"0\" width=\"{:.1}\" height=\"100%\" fill=\"#f8d0d0\"/>",
+        from,
+        (to - from).max(1.0)
+    );
+}
+
+/// Every n-th pixel so the width fits, with the box drawn in
+fn downsample(image: &ArrayView3<u8>, result: &TrackingResult, width: usize) -> Array3<u8> {
+    let step = image.dim().1.div_ceil(width.max(1)).max(1);
+    let mut frame = image.slice(s![..;step, ..;step, ..]).to_owned();
+    draw_box(&mut frame, &result.bbox.map(|v| v / step as i32), box_color(result));
+    frame
+}
+
+/// Uncompressed top-down 24-bit BMP of an RGB image
+fn encode_bmp(image: &Array3<u8>) -> Vec<u8> {
+    let (h, w, _) = image.dim();
+    let stride = (w * 3).div_ceil(4) * 4;
+    let size = 54 + stride * h;
+    let mut bmp = Vec::with_capacity(size);
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&(size as u32).to_le_bytes());
+    bmp.extend_from_slice(&0u32.to_le_bytes());
+    bmp.extend_from_slice(&54u32.to_le_bytes());
+    bmp.extend_from_slice(&40u32.to_le_bytes());
+    bmp.extend_from_slice(&(w as i32).to_le_bytes());
+    // Negative height: rows are stored top to bottom
+    bmp.extend_from_slice(&(-(h as i32)).to_le_bytes());
+    bmp.extend_from_slice(&1u16.to_le_bytes());
+    bmp.extend_from_slice(&24u16.to_le_bytes());
+    // No compression, default image size, resolution and palette
+    bmp.extend_from_slice(&[0; 24]);
+    for row in image.outer_iter() {
+        for pixel in row.outer_iter() {
+            bmp.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+        }
+        bmp.resize(bmp.len() + stride - w * 3, 0);
+    }
+    bmp
+}
+
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let byte = |i: usize| chunk.get(i).copied().unwrap_or(0) as u32;
+        let bits = byte(0) << 16 | byte(1) << 8 | byte(2);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(bits >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_summary_and_thumbnails() {
+        let mut report = RunReport::new(ReportConfig {
+            title: String::from("Gate <north>"),
+            thumbnail_width: 20,
+            ..ReportConfig::default()
+        });
+        let image = Array3::<u8>::zeros((30, 40, 3));
+        let tracking = TrackingResult {
+            success: true,
+            bbox: [10, 10, 8, 8],
+            score: 0.8,
+            ..TrackingResult::default()
+        };
+        let lost = TrackingResult {
+            success: false,
+            score: 0.1,
+            ..tracking
+        };
+        let frames = [tracking, tracking, lost, lost, lost, tracking, lost];
+        for result in &frames {
+            report.observe(result, Some(&image.view()));
+        }
+
+        let summary = report.summary();
+        assert_eq!(summary.frames, 7);
+        assert_eq!(summary.tracked, 3);
+        assert_eq!(summary.losses, 2);
+        assert_eq!(summary.longest_loss, 3);
+        assert!((summary.mean_score - 0.8).abs() < 1e-6);
+
+        let html = report.to_html();
+        assert!(html.contains("<title>Gate &lt;north&gt;</title>"));
+        assert!(html.contains("<svg"));
+        // Frame before and frame of both losses
+        assert_eq!(html.matches("data:image/bmp;base64,").count(), 4);
+        assert!(html.contains("data:text/csv;base64,"));
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(b""), "");
+    }
+}
@@ -1,6 +1,19 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+use ndarray::ArrayView4;
 use rknn_rs::prelude::{Rknn, RknnInput, RknnTensorFormat, RknnTensorType};
 use thiserror::Error;
 
+#[cfg(feature = "onnx")]
+use crate::onnx::OnnxModel;
+use crate::decode::OutputLayout;
+use crate::preprocess::InputTensor;
+
 #[derive(Error, Debug)]
 pub enum RknnError {
     #[error("Failed to load model: {0}")]
@@ -11,10 +24,93 @@ pub enum RknnError {
     RunError(String),
     #[error("Failed to get outputs: {0}")]
     OutputError(String),
+    #[error("Unexpected output shape: {0}")]
+    OutputShape(String),
+    #[error("No NPU device: {0}")]
+    NoDevice(String),
+}
+
+/// Where inference runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Device {
+    /// NPU when the driver is present, otherwise the CPU backend
+    #[default]
+    Auto,
+    /// RKNN runtime on the NPU
+    Npu,
+    /// ONNX Runtime on the CPU (needs the `onnx` feature)
+    Cpu,
+}
+
+/// Check that the RKNPU kernel driver is loaded
+///
+/// # Returns
+/// * Why the NPU is unusable, if it is
+pub fn detect_npu() -> Result<(), String> {
+    const NODES: [&str; 3] = ["/sys/module/rknpu", "/dev/rknpu", "/sys/kernel/debug/rknpu"];
+    if NODES.iter().any(|node| Path::new(node).exists()) {
+        return Ok(());
+    }
+
+    // Recent drivers register as a DRM device instead
+    if !rknpu_drm_nodes().is_empty() {
+        return Ok(());
+    }
+
+    Err("RKNPU driver not found (no /dev/rknpu, /sys/module/rknpu or rknpu DRM device)".into())
+}
+
+/// `/dev/dri` nodes of the rknpu DRM driver
+fn rknpu_drm_nodes() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            fs::read_link(entry.path().join("device/driver"))
+                .is_ok_and(|driver| driver.file_name().is_some_and(|name| name == "rknpu"))
+        })
+        .map(|entry| Path::new("/dev/dri").join(entry.file_name()))
+        .collect()
+}
+
+/// NPU memory mapped into this process, in bytes
+///
+/// Covers what the runtime allocates for all contexts: weights, internal
+/// buffers and input/output tensors, i.e. the totals of
+/// `rknn_query(RKNN_QUERY_MEM_SIZE)`, which the bindings don't expose. Read
+/// from the NPU device mappings in `/proc/self/maps`.
+///
+/// # Returns
+/// * `None` without NPU mappings (no NPU, or the CPU backend)
+pub fn npu_mapped_bytes() -> Option<u64> {
+    let mut devices = rknpu_drm_nodes();
+    devices.push(PathBuf::from("/dev/rknpu"));
+    let maps = fs::read_to_string("/proc/self/maps").ok()?;
+
+    let mut total = None;
+    for line in maps.lines() {
+        // start-end perms offset dev inode path
+        let mut fields = line.split_whitespace();
+        let (Some(range), Some(path)) = (fields.next(), fields.nth(4)) else {
+            continue;
+        };
+        if !devices.iter().any(|device| device.as_os_str() == path) {
+            continue;
+        }
+        if let Some((start, end)) = range.split_once('-')
+            && let (Ok(start), Ok(end)) =
+                (u64::from_str_radix(start, 16), u64::from_str_radix(end, 16))
+        {
+            *total.get_or_insert(0) += end.saturating_sub(start);
+        }
+    }
+    total
 }
 
 /// RKNN Model outputs for VitTrack
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct VitTrackOutputs {
     /// Confidence map (1x1x16x16 = 256 elements)
     pub conf_map: Vec<f32>,
@@ -22,20 +118,451 @@ pub struct VitTrackOutputs {
     pub size_map: Vec<f32>,
     /// Offset map (1x2x16x16 = 512 elements)
     pub offset_map: Vec<f32>,
+    /// Auxiliary outputs in model order (see `RknnModel::with_output_names`)
+    pub extra: Vec<NamedOutput>,
+}
+
+/// Model output besides the tracking heads, e.g. a quality or mask branch
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedOutput {
+    pub name: Arc<str>,
+    pub data: Vec<f32>,
+}
+
+impl VitTrackOutputs {
+    /// Preallocated buffers for reuse across frames
+    pub fn new() -> Self {
+        Self {
+            conf_map: Vec::with_capacity(256),
+            size_map: Vec::with_capacity(512),
+            offset_map: Vec::with_capacity(512),
+            extra: Vec::new(),
+        }
+    }
+
+    /// Output by tensor name: a tracking head or an auxiliary output
+    pub fn get(&self, name: &str) -> Option<&[f32]> {
+        match name {
+            "conf_map" => Some(&self.conf_map),
+            "size_map" => Some(&self.size_map),
+            "offset_map" => Some(&self.offset_map),
+            _ => self
+                .extra
+                .iter()
+                .find(|output| &*output.name == name)
+                .map(|output| &output.data[..]),
+        }
+    }
+
+    /// Buffer of tracking head `head` (0 = conf, 1 = size, 2 = offset)
+    fn head_mut(&mut self, head: usize) -> &mut Vec<f32> {
+        match head {
+            0 => &mut self.conf_map,
+            1 => &mut self.size_map,
+            _ => &mut self.offset_map,
+        }
+    }
+
+    /// Zero-copy views of the buffers, shaped like the model outputs (NCHW)
+    ///
+    /// # Arguments
+    /// * `score_size` - Side of the score map
+    pub fn views(&self, score_size: usize) -> Result<OutputViews<'_>, RknnError> {
+        Ok(OutputViews {
+            conf_map: map_view("conf_map", &self.conf_map, 1, score_size)?,
+            size_map: map_view("size_map", &self.size_map, 2, score_size)?,
+            offset_map: map_view("offset_map", &self.offset_map, 2, score_size)?,
+        })
+    }
+}
+
+fn map_view<'a>(
+    name: &str,
+    data: &'a [f32],
+    channels: usize,
+    score_size: usize,
+) -> Result<ArrayView4<'a, f32>, RknnError> {
+    let expected = channels * score_size * score_size;
+    if data.len() != expected {
+        return Err(RknnError::OutputShape(format!(
+            "{} has {} elements, expected {}",
+            name,
+            data.len(),
+            expected
+        )));
+    }
+    ArrayView4::from_shape((1, channels, score_size, score_size), data)
+        .map_err(|e| RknnError::OutputShape(e.to_string()))
+}
+
+/// Borrowed views of `VitTrackOutputs`
+#[derive(Debug, Clone)]
+pub struct OutputViews<'a> {
+    /// 1 x 1 x S x S
+    pub conf_map: ArrayView4<'a, f32>,
+    /// 1 x 2 x S x S (width, height)
+    pub size_map: ArrayView4<'a, f32>,
+    /// 1 x 2 x S x S (x, y)
+    pub offset_map: ArrayView4<'a, f32>,
+}
+
+/// Float32 model inputs filled in place and handed to the runtime without copies
+///
+/// Preprocess straight into `template_mut` / `search_mut` (e.g. with
+/// `preprocess_nhwc_into`), then call `RknnModel::inference_buffers_into`.
+/// The template only has to be written again when it changes.
+pub struct InputBuffers {
+    inputs: Vec<RknnInput<f32>>,
+}
+
+impl InputBuffers {
+    /// # Arguments
+    /// * `template_size` - Template input side in pixels
+    /// * `search_size` - Search input side in pixels
+    pub fn new(template_size: usize, search_size: usize) -> Self {
+        let input = |index, size: usize| RknnInput {
+            index,
+            buf: vec![0.0f32; size * size * 3],
+            pass_through: false,
+            type_: RknnTensorType::Float32,
+            fmt: RknnTensorFormat::NHWC,
+        };
+        Self {
+            inputs: vec![input(0, template_size), input(1, search_size)],
+        }
+    }
+
+    /// Template input, NHWC
+    pub fn template_mut(&mut self) -> &mut [f32] {
+        &mut self.inputs[0].buf
+    }
+
+    /// Search input, NHWC
+    pub fn search_mut(&mut self) -> &mut [f32] {
+        &mut self.inputs[1].buf
+    }
+
+    /// Memory held by both inputs
+    pub fn byte_len(&self) -> usize {
+        self.inputs.iter().map(|input| input.buf.len() * size_of::<f32>()).sum()
+    }
+}
+
+/// Affine quantization parameters of an int8 output tensor
+#[derive(Debug, Clone, Copy)]
+pub struct OutputQuantization {
+    pub zero_point: i32,
+    pub scale: f32,
+}
+
+impl OutputQuantization {
+    #[inline]
+    pub fn dequantize(&self, value: i8) -> f32 {
+        (value as i32 - self.zero_point) as f32 * self.scale
+    }
+}
+
+/// NPU arbiter shared by every model running on the same cores
+///
+/// Registered models run one inference at a time, highest priority first and
+/// in arrival order within a priority. An inference is never interrupted:
+/// batch jobs (detector over tiles, classifier over crops) split their work
+/// and check `NpuTurn::should_yield` between parts, so a waiting tracker gets
+/// the NPU after at most one part.
+#[derive(Clone, Default)]
+pub struct NpuScheduler {
+    inner: Arc<SchedulerInner>,
+}
+
+#[derive(Default)]
+struct SchedulerInner {
+    state: Mutex<SchedulerState>,
+    turn_ended: Condvar,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    busy: bool,
+    next_ticket: u64,
+    /// (priority, ticket) of the clients waiting for a turn
+    waiting: Vec<(u8, u64)>,
+}
+
+impl SchedulerState {
+    fn next(&self) -> Option<(u8, u64)> {
+        self.waiting.iter().copied().max_by_key(|&(priority, ticket)| (priority, Reverse(ticket)))
+    }
+}
+
+impl NpuScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle for one model
+    ///
+    /// # Arguments
+    /// * `priority` - Higher runs first; give the tracker more than batch models
+    pub fn client(&self, priority: u8) -> NpuClient {
+        NpuClient {
+            scheduler: self.clone(),
+            priority,
+        }
+    }
+
+    /// Inferences currently waiting for the NPU
+    pub fn waiting(&self) -> usize {
+        self.lock().waiting.len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SchedulerState> {
+        self.inner.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A model's registration with an `NpuScheduler`
+#[derive(Clone)]
+pub struct NpuClient {
+    scheduler: NpuScheduler,
+    priority: u8,
+}
+
+impl fmt::Debug for NpuClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NpuClient").field("priority", &self.priority).finish()
+    }
+}
+
+impl NpuClient {
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// Block until this client may use the NPU
+    ///
+    /// The turn lasts until the returned guard is dropped.
+    pub fn acquire(&self) -> NpuTurn<'_> {
+        let mut state = self.scheduler.lock();
+        let me = (self.priority, state.next_ticket);
+        state.next_ticket += 1;
+        state.waiting.push(me);
+        while state.busy || state.next() != Some(me) {
+            state = self
+                .scheduler
+                .inner
+                .turn_ended
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        state.waiting.retain(|&waiter| waiter != me);
+        state.busy = true;
+        NpuTurn { client: self }
+    }
+}
+
+/// Exclusive use of the NPU, released on drop
+pub struct NpuTurn<'a> {
+    client: &'a NpuClient,
+}
+
+impl NpuTurn<'_> {
+    /// A higher-priority client is waiting; end the turn at the next preemption point
+    pub fn should_yield(&self) -> bool {
+        let priority = self.client.priority;
+        self.client.scheduler.lock().waiting.iter().any(|&(p, _)| p > priority)
+    }
+}
+
+impl Drop for NpuTurn<'_> {
+    fn drop(&mut self) {
+        self.client.scheduler.lock().busy = false;
+        self.client.scheduler.inner.turn_ended.notify_all();
+    }
+}
+
+/// RKNN contexts currently alive in this process
+static LIVE_CONTEXTS: AtomicUsize = AtomicUsize::new(0);
+
+enum Backend {
+    Npu(Rknn),
+    #[cfg(feature = "onnx")]
+    Cpu(OnnxModel),
 }
 
 /// RKNN Model wrapper for VitTrack
+///
+/// The context and its NPU memory are released when the model is dropped or
+/// closed; `live_contexts` counts the open ones for leak checks.
+///
+/// Without an NPU the model can run on the CPU through ONNX Runtime (`onnx`
+/// feature), loading the `.onnx` file next to the `.rknn` one.
 pub struct RknnModel {
-    rknn: Rknn,
+    backend: Backend,
+    /// Turns taken around every NPU inference when shared with other models
+    npu: Option<NpuClient>,
+    output_quantization: Option<[OutputQuantization; 3]>,
+    /// Expected element counts of conf_map, size_map, offset_map
+    output_lens: [usize; 3],
+    /// Names of all outputs fetched per inference, in model order
+    output_names: Vec<Arc<str>>,
+    /// Positions of conf_map, size_map and offset_map in `output_names`
+    heads: [usize; 3],
+    /// Layout the model emits the size and offset maps in
+    output_layout: OutputLayout,
+    /// Size of the loaded model file
+    file_bytes: u64,
 }
 
 impl RknnModel {
+    /// Output names in model order, used in error messages
+    const OUTPUT_NAMES: [&'static str; 3] = ["conf_map", "size_map", "offset_map"];
+
     /// Load RKNN model from file
-    pub fn load<P: AsRef<std::path::Path>>(model_path: P) -> Result<Self, RknnError> {
-        let rknn = Rknn::rknn_init(model_path)
-            .map_err(|e| RknnError::LoadError(e.to_string()))?;
+    ///
+    /// Falls back to the CPU backend when no NPU is found; see `load_on`.
+    pub fn load<P: AsRef<Path>>(model_path: P) -> Result<Self, RknnError> {
+        Self::load_on(model_path, Device::Auto)
+    }
+
+    /// Load the model for a given device
+    ///
+    /// # Arguments
+    /// * `model_path` - RKNN model; the CPU backend loads the same path with an `.onnx` extension
+    /// * `device` - `Auto` picks the NPU when `detect_npu` finds it
+    ///
+    /// # Returns
+    /// * `RknnError::NoDevice` when the NPU is missing and no CPU backend is compiled in
+    pub fn load_on<P: AsRef<Path>>(model_path: P, device: Device) -> Result<Self, RknnError> {
+        let model_path = model_path.as_ref();
+        let device = match device {
+            Device::Auto => match detect_npu() {
+                Ok(()) => Device::Npu,
+                Err(_) if cfg!(feature = "onnx") => Device::Cpu,
+                Err(reason) => {
+                    return Err(RknnError::NoDevice(format!(
+                        "{}; build with the `onnx` feature for the CPU backend",
+                        reason
+                    )));
+                }
+            },
+            device => device,
+        };
+
+        let file_bytes = |path: &Path| fs::metadata(path).map_or(0, |m| m.len());
+        let (backend, file_bytes) = match device {
+            #[cfg(feature = "onnx")]
+            Device::Cpu => {
+                let path = model_path.with_extension("onnx");
+                let model = OnnxModel::load(&path)
+                    .map_err(|e| RknnError::LoadError(format!("{}: {}", path.display(), e)))?;
+                (Backend::Cpu(model), file_bytes(&path))
+            }
+            #[cfg(not(feature = "onnx"))]
+            Device::Cpu => {
+                return Err(RknnError::NoDevice(
+                    "CPU backend not compiled in (`onnx` feature)".into(),
+                ));
+            }
+            _ => {
+                let rknn = Rknn::rknn_init(model_path)
+                    .map_err(|e| RknnError::LoadError(e.to_string()))?;
+                LIVE_CONTEXTS.fetch_add(1, Ordering::SeqCst);
+                (Backend::Npu(rknn), file_bytes(model_path))
+            }
+        };
+        let output_names = query_output_names(&backend)?;
+        let heads = heads_by_name(&output_names)?;
+
+        Ok(Self {
+            backend,
+            npu: None,
+            output_quantization: None,
+            output_lens: output_lens(16),
+            output_names,
+            heads,
+            output_layout: OutputLayout::Nchw,
+            file_bytes,
+        })
+    }
+
+    /// Release the context now instead of at the end of the scope
+    ///
+    /// Same as dropping the model; makes the release point explicit in
+    /// services that swap models.
+    pub fn close(self) {
+        drop(self);
+    }
+
+    /// Number of RKNN contexts alive in this process
+    pub fn live_contexts() -> usize {
+        LIVE_CONTEXTS.load(Ordering::SeqCst)
+    }
 
-        Ok(Self { rknn })
+    /// Device the model runs on (`Npu` or `Cpu`)
+    pub fn device(&self) -> Device {
+        match self.backend {
+            Backend::Npu(_) => Device::Npu,
+            #[cfg(feature = "onnx")]
+            Backend::Cpu(_) => Device::Cpu,
+        }
+    }
+
+    /// Size of the model file (weights and graph), in bytes
+    pub fn file_bytes(&self) -> u64 {
+        self.file_bytes
+    }
+
+    /// Expect outputs for a score map of `score_size` x `score_size` (default 16)
+    pub fn with_score_size(mut self, score_size: usize) -> Self {
+        self.output_lens = output_lens(score_size);
+        self
+    }
+
+    /// Layout of the size and offset maps the model emits (default NCHW)
+    ///
+    /// NHWC maps are reordered to NCHW after every inference, so
+    /// `VitTrackOutputs` always holds NCHW maps.
+    pub fn with_output_layout(mut self, layout: OutputLayout) -> Self {
+        self.output_layout = layout;
+        self
+    }
+
+    /// Name the model outputs in model order, overriding the queried names
+    ///
+    /// For exports whose tensor names do not identify the heads. Every named
+    /// output is fetched on each inference. The tracking heads are found by
+    /// name (`conf_map`, `size_map`, `offset_map`); the others end up in
+    /// `VitTrackOutputs::extra`. With `with_output_quantization`
+    /// only the heads are dequantized; auxiliary int8 outputs keep their raw
+    /// values.
+    ///
+    /// # Returns
+    /// * `RknnError::LoadError` when a head is missing from `names`
+    pub fn with_output_names<S: AsRef<str>>(mut self, names: &[S]) -> Result<Self, RknnError> {
+        let names: Vec<Arc<str>> = names.iter().map(|name| Arc::from(name.as_ref())).collect();
+        for (head, expected) in self.heads.iter_mut().zip(Self::OUTPUT_NAMES) {
+            *head = names
+                .iter()
+                .position(|name| &**name == expected)
+                .ok_or_else(|| RknnError::LoadError(format!("no {} output named", expected)))?;
+        }
+        self.output_names = names;
+        Ok(self)
+    }
+
+    /// Share the NPU with other models through a scheduler
+    pub fn with_scheduler(mut self, client: NpuClient) -> Self {
+        self.npu = Some(client);
+        self
+    }
+
+    /// Fetch raw int8 outputs and dequantize them on the CPU
+    ///
+    /// Skips the runtime float conversion (`want_float = false`). Parameters are
+    /// given in output order: conf_map, size_map, offset_map.
+    pub fn with_output_quantization(mut self, quantization: [OutputQuantization; 3]) -> Self {
+        self.output_quantization = Some(quantization);
+        self
     }
 
     /// Run inference with template and search inputs
@@ -51,48 +578,569 @@ impl RknnModel {
         template: &[f32],
         search: &[f32],
     ) -> Result<VitTrackOutputs, RknnError> {
-        // Create inputs
+        let mut outputs = VitTrackOutputs::new();
+        #[cfg(feature = "onnx")]
+        if let Backend::Cpu(model) = &self.backend {
+            self.run_cpu(model, template, search, &mut outputs)?;
+            return Ok(outputs);
+        }
+        let _turn = self.npu.as_ref().map(NpuClient::acquire);
+        self.run_copied(template, search, RknnTensorType::Float32)?;
+        self.read_outputs(&mut outputs)?;
+        Ok(outputs)
+    }
+
+    /// Run inference with preprocessed tensors of any supported input type
+    ///
+    /// Template and search must share the same element type.
+    pub fn inference_tensors(
+        &self,
+        template: &InputTensor,
+        search: &InputTensor,
+    ) -> Result<VitTrackOutputs, RknnError> {
+        let mut outputs = VitTrackOutputs::new();
+        self.inference_tensors_into(template, search, &mut outputs)?;
+        Ok(outputs)
+    }
+
+    /// Run inference writing results into caller-provided buffers
+    ///
+    /// Results are copied into the existing buffers, which only grow when
+    /// the output sizes do. The runtime binding itself still returns newly
+    /// allocated output Vecs on every call.
+    pub fn inference_tensors_into(
+        &self,
+        template: &InputTensor,
+        search: &InputTensor,
+        outputs: &mut VitTrackOutputs,
+    ) -> Result<(), RknnError> {
+        #[cfg(feature = "onnx")]
+        if let Backend::Cpu(model) = &self.backend {
+            let (InputTensor::Float32(t), InputTensor::Float32(s)) = (template, search) else {
+                return Err(RknnError::InputError(format!(
+                    "CPU backend needs float32 inputs, got {}",
+                    template.type_name()
+                )));
+            };
+            return self.run_cpu(model, t, s, outputs);
+        }
+
+        let _turn = self.npu.as_ref().map(NpuClient::acquire);
+        match (template, search) {
+            (InputTensor::Float32(t), InputTensor::Float32(s)) => {
+                self.run_copied(t, s, RknnTensorType::Float32)
+            }
+            (InputTensor::Float16(t), InputTensor::Float16(s)) => {
+                self.run_copied(t, s, RknnTensorType::Float16)
+            }
+            (InputTensor::Uint8(t), InputTensor::Uint8(s)) => {
+                self.run_copied(t, s, RknnTensorType::Uint8)
+            }
+            (InputTensor::Int8(t), InputTensor::Int8(s)) => {
+                self.run_copied(t, s, RknnTensorType::Int8)
+            }
+            _ => Err(RknnError::InputError(format!(
+                "Mismatched input types: template {}, search {}",
+                template.type_name(),
+                search.type_name()
+            ))),
+        }?;
+
+        self.read_outputs(outputs)
+    }
+
+    /// Run inference on inputs preprocessed in place
+    ///
+    /// Nothing is copied on the way to the runtime; see `InputBuffers`.
+    pub fn inference_buffers_into(
+        &self,
+        inputs: &mut InputBuffers,
+        outputs: &mut VitTrackOutputs,
+    ) -> Result<(), RknnError> {
+        #[cfg(feature = "onnx")]
+        if let Backend::Cpu(model) = &self.backend {
+            let [template, search] = &inputs.inputs[..] else { unreachable!() };
+            return self.run_cpu(model, &template.buf, &search.buf, outputs);
+        }
+
+        let _turn = self.npu.as_ref().map(NpuClient::acquire);
+        self.run(&mut inputs.inputs)?;
+        self.read_outputs(outputs)
+    }
+
+    /// Run a single-input model, e.g. the template encoder of a two-model export
+    ///
+    /// # Arguments
+    /// * `input` - NHWC input tensor
+    ///
+    /// # Returns
+    /// * The first output as float32, in the runtime's (NCHW) order
+    pub fn encode(&self, input: &InputTensor) -> Result<Vec<f32>, RknnError> {
+        if self.device() != Device::Npu {
+            return Err(RknnError::RunError("template encoder needs the NPU backend".into()));
+        }
+
+        let _turn = self.npu.as_ref().map(NpuClient::acquire);
+        match input {
+            InputTensor::Float32(buf) => self.run_one(buf, RknnTensorType::Float32),
+            InputTensor::Float16(buf) => self.run_one(buf, RknnTensorType::Float16),
+            InputTensor::Uint8(buf) => self.run_one(buf, RknnTensorType::Uint8),
+            InputTensor::Int8(buf) => self.run_one(buf, RknnTensorType::Int8),
+        }?;
+        self.rknn()
+            .outputs_get::<f32>(1)
+            .map_err(|e| RknnError::OutputError(e.to_string()))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| RknnError::OutputShape("template encoder has no output".into()))
+    }
+
+    /// Run the search model of a two-model export on cached template features
+    ///
+    /// # Arguments
+    /// * `features` - Output of `encode` for the template encoder
+    /// * `search` - Search input; must be float32
+    pub fn inference_features_into(
+        &self,
+        features: &[f32],
+        search: &InputTensor,
+        outputs: &mut VitTrackOutputs,
+    ) -> Result<(), RknnError> {
+        let InputTensor::Float32(search) = search else {
+            return Err(RknnError::InputError(format!(
+                "Template features need a float32 search input, got {}",
+                search.type_name()
+            )));
+        };
+        if self.device() != Device::Npu {
+            return Err(RknnError::RunError("template features need the NPU backend".into()));
+        }
+
+        let _turn = self.npu.as_ref().map(NpuClient::acquire);
+        // Features go back in the order the encoder produced them
         let mut inputs = vec![
             RknnInput {
                 index: 0,
-                buf: template.to_vec(),
+                buf: features.to_vec(),
                 pass_through: false,
                 type_: RknnTensorType::Float32,
-                fmt: RknnTensorFormat::NHWC,
+                fmt: RknnTensorFormat::NCHW,
             },
             RknnInput {
                 index: 1,
-                buf: search.to_vec(),
+                buf: search.clone(),
                 pass_through: false,
                 type_: RknnTensorType::Float32,
                 fmt: RknnTensorFormat::NHWC,
             },
         ];
+        self.run(&mut inputs)?;
+        self.read_outputs(outputs)
+    }
 
-        // Set inputs
-        self.rknn
-            .inputs_set(&mut inputs)
-            .map_err(|e| RknnError::InputError(e.to_string()))?;
+    /// RKNN context; the CPU backend returns before reaching the NPU paths
+    fn rknn(&self) -> &Rknn {
+        match &self.backend {
+            Backend::Npu(rknn) => rknn,
+            #[cfg(feature = "onnx")]
+            Backend::Cpu(_) => unreachable!("NPU path on the CPU backend"),
+        }
+    }
 
-        // Run inference
-        self.rknn
-            .run()
+    #[cfg(feature = "onnx")]
+    fn run_cpu(
+        &self,
+        model: &OnnxModel,
+        template: &[f32],
+        search: &[f32],
+        outputs: &mut VitTrackOutputs,
+    ) -> Result<(), RknnError> {
+        let raw = model
+            .run(template, search)
             .map_err(|e| RknnError::RunError(e.to_string()))?;
+        self.check_outputs(&raw)?;
+        self.copy_outputs(&raw, outputs, |_, v| v);
+        Ok(())
+    }
 
-        // Get outputs (3 outputs for VitTrack)
-        let outputs = self.rknn
-            .outputs_get::<f32>(3)
-            .map_err(|e| RknnError::OutputError(e.to_string()))?;
+    /// Hand the inputs to the runtime and run inference
+    fn run<T>(&self, inputs: &mut [RknnInput<T>]) -> Result<(), RknnError> {
+        self.rknn()
+            .inputs_set(inputs)
+            .map_err(|e| RknnError::InputError(e.to_string()))?;
+        self.rknn()
+            .run()
+            .map_err(|e| RknnError::RunError(e.to_string()))
+    }
 
-        // Extract output data
-        let conf_map = outputs[0].to_vec();
-        let size_map = outputs[1].to_vec();
-        let offset_map = outputs[2].to_vec();
+    /// Single NHWC input copied from a borrowed slice
+    fn run_one<T: Clone>(&self, input: &[T], type_: RknnTensorType) -> Result<(), RknnError> {
+        self.run(&mut [nhwc_input(0, input, type_)])
+    }
 
-        Ok(VitTrackOutputs {
-            conf_map,
-            size_map,
-            offset_map,
-        })
+    /// Template and search inputs copied from borrowed slices
+    ///
+    /// The runtime takes owned buffers, so the slice-based entry points pay
+    /// one copy per input; `InputBuffers` avoids it.
+    fn run_copied<T: Clone>(
+        &self,
+        template: &[T],
+        search: &[T],
+        type_: RknnTensorType,
+    ) -> Result<(), RknnError> {
+        self.run(&mut [nhwc_input(0, template, type_), nhwc_input(1, search, type_)])
+    }
+
+    /// Copy (and dequantize if configured) the VitTrack outputs into buffers
+    fn read_outputs(&self, outputs: &mut VitTrackOutputs) -> Result<(), RknnError> {
+        let count = self.output_names.len();
+        match &self.output_quantization {
+            Some(quantization) => {
+                let raw = self
+                    .rknn()
+                    .outputs_get::<i8>(count)
+                    .map_err(|e| RknnError::OutputError(e.to_string()))?;
+                self.check_outputs(&raw)?;
+                self.copy_outputs(&raw, outputs, |head, v| match head {
+                    Some(head) => quantization[head].dequantize(v),
+                    None => f32::from(v),
+                });
+            }
+            None => {
+                let raw = self
+                    .rknn()
+                    .outputs_get::<f32>(count)
+                    .map_err(|e| RknnError::OutputError(e.to_string()))?;
+                self.check_outputs(&raw)?;
+                self.copy_outputs(&raw, outputs, |_, v| v);
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy runtime outputs into `outputs` with this model's head mapping
+    fn copy_outputs<T: Copy>(
+        &self,
+        raw: &[Vec<T>],
+        outputs: &mut VitTrackOutputs,
+        value: impl Fn(Option<usize>, T) -> f32,
+    ) {
+        let heads = OutputHeads {
+            names: &self.output_names,
+            heads: &self.heads,
+            layout: self.output_layout,
+        };
+        heads.copy(raw, outputs, value);
+    }
+
+    /// Validate output count and lengths before they are indexed
+    fn check_outputs<T>(&self, raw: &[Vec<T>]) -> Result<(), RknnError> {
+        if raw.len() < self.output_names.len() {
+            return Err(RknnError::OutputShape(format!(
+                "expected {} outputs, got {}",
+                self.output_names.len(),
+                raw.len()
+            )));
+        }
+        let lens = self.heads.map(|index| raw[index].len());
+        check_output_lens(&lens, &self.output_lens)
+    }
+}
+
+/// Output tensor names in model order, as reported by the runtime
+///
+/// RKNN: `rknn_query` with `RKNN_QUERY_IN_OUT_NUM`, then the name from each
+/// output's `RKNN_QUERY_OUTPUT_ATTR`.
+fn query_output_names(backend: &Backend) -> Result<Vec<Arc<str>>, RknnError> {
+    match backend {
+        Backend::Npu(rknn) => {
+            let count = rknn
+                .io_num()
+                .map_err(|e| RknnError::LoadError(e.to_string()))?
+                .n_output;
+            (0..count)
+                .map(|index| {
+                    let attr = rknn
+                        .output_attr(index)
+                        .map_err(|e| RknnError::LoadError(e.to_string()))?;
+                    Ok(Arc::from(attr.name))
+                })
+                .collect()
+        }
+        #[cfg(feature = "onnx")]
+        Backend::Cpu(model) => Ok(model.output_names().into_iter().map(Arc::from).collect()),
+    }
+}
+
+/// Positions of conf_map, size_map and offset_map among the model outputs
+///
+/// Heads are found by tensor name; exports with other names are read in
+/// the default order, heads first. Override with `with_output_names`.
+fn heads_by_name(names: &[Arc<str>]) -> Result<[usize; 3], RknnError> {
+    if names.len() < RknnModel::OUTPUT_NAMES.len() {
+        return Err(RknnError::LoadError(format!(
+            "model has {} outputs, expected at least 3",
+            names.len()
+        )));
+    }
+    let position = |head: &str| names.iter().position(|name| &**name == head);
+    Ok(match RknnModel::OUTPUT_NAMES.map(position) {
+        [Some(conf), Some(size), Some(offset)] => [conf, size, offset],
+        _ => [0, 1, 2],
+    })
+}
+
+/// Where the tracking heads are among the runtime outputs
+struct OutputHeads<'a> {
+    names: &'a [Arc<str>],
+    /// Output index of conf, size and offset map
+    heads: &'a [usize; 3],
+    layout: OutputLayout,
+}
+
+impl OutputHeads<'_> {
+    /// Copy runtime outputs into the buffers of `outputs`, keeping their memory
+    ///
+    /// Head maps are written in the NCHW order the decoders index; auxiliary
+    /// outputs reuse the `extra` entries of the previous frame.
+    ///
+    /// # Arguments
+    /// * `value` - Converts an element of head `Some(head)` or of an
+    ///   auxiliary output (`None`) to f32
+    fn copy<T: Copy>(
+        &self,
+        raw: &[Vec<T>],
+        outputs: &mut VitTrackOutputs,
+        value: impl Fn(Option<usize>, T) -> f32,
+    ) {
+        let mut extra = 0;
+        for (index, (name, data)) in self.names.iter().zip(raw).enumerate() {
+            match self.heads.iter().position(|&head| head == index) {
+                Some(head) => {
+                    // conf_map has one channel, size and offset maps two
+                    let channels = if head == 0 { 1 } else { 2 };
+                    let area = data.len() / channels;
+                    let buffer = outputs.head_mut(head);
+                    buffer.clear();
+                    for channel in 0..channels {
+                        buffer.extend((0..area).map(|idx| {
+                            let v = data[self.layout.index(channel, idx, channels, area)];
+                            value(Some(head), v)
+                        }));
+                    }
+                }
+                None => {
+                    if extra == outputs.extra.len() {
+                        outputs.extra.push(NamedOutput {
+                            name: name.clone(),
+                            data: Vec::with_capacity(data.len()),
+                        });
+                    }
+                    let output = &mut outputs.extra[extra];
+                    output.name = name.clone();
+                    output.data.clear();
+                    output.data.extend(data.iter().map(|&v| value(None, v)));
+                    extra += 1;
+                }
+            }
+        }
+        outputs.extra.truncate(extra);
+    }
+}
+
+/// NHWC runtime input holding a copy of `buf`
+fn nhwc_input<T: Clone>(index: usize, buf: &[T], type_: RknnTensorType) -> RknnInput<T> {
+    RknnInput {
+        index,
+        buf: buf.to_vec(),
+        pass_through: false,
+        type_,
+        fmt: RknnTensorFormat::NHWC,
+    }
+}
+
+/// Element counts of the 3 outputs for a given score map size
+fn output_lens(score_size: usize) -> [usize; 3] {
+    let area = score_size * score_size;
+    [area, 2 * area, 2 * area]
+}
+
+impl Drop for RknnModel {
+    fn drop(&mut self) {
+        // `backend` is dropped right after this, destroying the context
+        if matches!(self.backend, Backend::Npu(_)) {
+            LIVE_CONTEXTS.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+fn check_output_lens(actual: &[usize], expected: &[usize; 3]) -> Result<(), RknnError> {
+    if actual.len() < expected.len() {
+        return Err(RknnError::OutputShape(format!(
+            "expected {} outputs, got {}",
+            expected.len(),
+            actual.len()
+        )));
+    }
+
+    for ((name, &len), &expected_len) in RknnModel::OUTPUT_NAMES.iter().zip(actual).zip(expected) {
+        if len != expected_len {
+            return Err(RknnError::OutputShape(format!(
+                "{} has {} elements, expected {}",
+                name, len, expected_len
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Model the hardware tests load; they are ignored off the device
+#[cfg(test)]
+pub(crate) const TEST_MODEL: &str = "models/object_tracking_vittrack_2023sep.rknn";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn npu_model() -> RknnModel {
+        RknnModel::load_on(TEST_MODEL, Device::Npu).unwrap()
+    }
+
+    #[test]
+    fn test_check_output_lens() {
+        let expected = output_lens(16);
+        assert!(check_output_lens(&[256, 512, 512], &expected).is_ok());
+
+        let err = check_output_lens(&[256, 512], &expected).unwrap_err();
+        assert!(matches!(err, RknnError::OutputShape(_)));
+
+        let err = check_output_lens(&[256, 512, 128], &expected).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unexpected output shape: offset_map has 128 elements, expected 512"
+        );
+    }
+
+    #[test]
+    #[ignore = "needs RKNPU"]
+    fn test_load_close_cycles_release_contexts() {
+        // The first context also maps the runtime's shared buffers
+        npu_model().close();
+        let contexts = RknnModel::live_contexts();
+        let mapped = npu_mapped_bytes().expect("no NPU mappings");
+        for _ in 0..50 {
+            let model = npu_model();
+            assert_eq!(RknnModel::live_contexts(), contexts + 1);
+            model.close();
+        }
+        assert_eq!(RknnModel::live_contexts(), contexts);
+        assert!(npu_mapped_bytes().unwrap_or(0) <= mapped, "NPU memory leaked");
+    }
+
+    #[test]
+    #[ignore = "needs RKNPU"]
+    fn test_model_file_bytes() {
+        let model = npu_model();
+        assert_eq!(model.file_bytes(), fs::metadata(TEST_MODEL).unwrap().len());
+        assert!(model.file_bytes() > 0);
+    }
+
+    #[test]
+    fn test_scheduler_priority_order() {
+        let scheduler = NpuScheduler::new();
+        let batch = scheduler.client(10);
+        let turn = batch.acquire();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let mut handles = Vec::new();
+        for priority in [10, 200] {
+            let client = scheduler.client(priority);
+            let order = order.clone();
+            handles.push(std::thread::spawn(move || {
+                let _turn = client.acquire();
+                order.lock().unwrap().push(priority);
+            }));
+            while scheduler.waiting() < handles.len() {
+                std::thread::yield_now();
+            }
+        }
+
+        assert!(turn.should_yield());
+        drop(turn);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), [200, 10]);
+    }
+
+    #[test]
+    fn test_output_views() {
+        let outputs = VitTrackOutputs {
+            conf_map: (0..256).map(|v| v as f32).collect(),
+            size_map: vec![0.5; 512],
+            offset_map: vec![0.25; 512],
+            ..VitTrackOutputs::default()
+        };
+        let views = outputs.views(16).unwrap();
+        assert_eq!(views.size_map.dim(), (1, 2, 16, 16));
+        assert_eq!(views.conf_map[[0, 0, 2, 3]], 35.0);
+        assert!(outputs.views(8).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_heads_by_name() {
+        let names = |list: &[&str]| list.iter().map(|&name| Arc::from(name)).collect::<Vec<_>>();
+        let aux = names(&["quality", "size_map", "conf_map", "offset_map", "mask"]);
+        assert_eq!(heads_by_name(&aux).unwrap(), [2, 1, 3]);
+        // Unnamed exports keep the default order
+        assert_eq!(heads_by_name(&names(&["out0", "out1", "out2", "out3"])).unwrap(), [0, 1, 2]);
+        assert!(heads_by_name(&names(&["conf_map", "size_map"])).is_err());
+    }
+
+    #[test]
+    fn test_outputs_copied_into_buffers() {
+        let names = ["quality", "conf_map", "size_map", "offset_map"].map(Arc::from);
+        let heads = OutputHeads {
+            names: &names,
+            heads: &[1, 2, 3],
+            layout: OutputLayout::Nhwc,
+        };
+        // 2x2 score map; the 2-channel maps interleave their channels (NHWC)
+        let raw = vec![
+            vec![0.9],
+            vec![0.1, 0.2, 0.3, 0.4],
+            vec![1.0, 5.0, 2.0, 6.0, 3.0, 7.0, 4.0, 8.0],
+            vec![0.0; 8],
+        ];
+        let mut outputs = VitTrackOutputs::new();
+        heads.copy(&raw, &mut outputs, |_, v| v);
+        let buffers = (outputs.size_map.as_ptr(), outputs.extra[0].data.as_ptr());
+        heads.copy(&raw, &mut outputs, |_, v| v);
+
+        assert_eq!(outputs.size_map, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+        assert_eq!(outputs.get("quality"), Some(&[0.9][..]));
+        // Second frame reuses the memory of the first
+        assert_eq!((outputs.size_map.as_ptr(), outputs.extra[0].data.as_ptr()), buffers);
+    }
+
+    #[test]
+    #[ignore = "needs RKNPU"]
+    fn test_named_outputs() {
+        assert!(npu_model().with_output_names(&["conf_map", "size_map"]).is_err());
+
+        let model = npu_model()
+            .with_output_names(&["quality", "size_map", "conf_map", "offset_map"])
+            .unwrap();
+        let raw = vec![vec![0.9], vec![0.5; 512], vec![0.1; 256], vec![0.25; 512]];
+        assert!(model.check_outputs(&raw[..3]).is_err());
+        model.check_outputs(&raw).unwrap();
+
+        let mut outputs = VitTrackOutputs::new();
+        model.copy_outputs(&raw, &mut outputs, |_, v| v);
+        assert_eq!(outputs.conf_map, [0.1; 256]);
+        assert_eq!(outputs.get("offset_map"), Some(&[0.25; 512][..]));
+        assert_eq!(outputs.get("quality"), Some(&[0.9][..]));
+        assert_eq!(outputs.get("mask"), None);
+    }
+}
@@ -0,0 +1,382 @@
+//! Target region hints for camera auto-exposure and auto-focus
+//!
+//! `RoiHints` turns tracking results into a metering region and hands it to a
+//! `RoiSink` only when it moves, so the camera exposes and focuses for the
+//! target instead of the whole scene. On loss the region is held for a few
+//! frames, then metering goes back to the full frame.
+//!
+//! `EncoderRoi` gives the same target region to a video encoder as a
+//! block-aligned rectangle or a per-block QP offset map, so the streamed
+//! target keeps its detail at low bitrates.
+
+use std::io;
+
+use crate::postprocess::TrackingResult;
+use crate::preprocess::BBox;
+
+/// Receives metering regions in frame pixels
+pub trait RoiSink {
+    /// `None` returns metering to the whole frame
+    fn set_roi(&mut self, roi: Option<BBox>) -> io::Result<()>;
+}
+
+impl<F: FnMut(Option<BBox>) -> io::Result<()>> RoiSink for F {
+    fn set_roi(&mut self, roi: Option<BBox>) -> io::Result<()> {
+        self(roi)
+    }
+}
+
+/// ROI hint configuration
+#[derive(Debug, Clone, Copy)]
+pub struct RoiHintConfig {
+    /// Region side relative to the target box (context around the target)
+    pub padding: f32,
+    /// Smallest region side in pixels; tiny regions make metering noisy
+    pub min_size: i32,
+    /// Frames the last region is kept after a loss
+    pub hold_frames: u32,
+    /// Region changes below this many pixels (any edge) are not sent
+    pub min_change: i32,
+}
+
+impl Default for RoiHintConfig {
+    fn default() -> Self {
+        Self {
+            padding: 1.5,
+            min_size: 64,
+            hold_frames: 15,
+            min_change: 8,
+        }
+    }
+}
+
+/// Tracks the region last sent to a sink
+#[derive(Debug, Clone)]
+pub struct RoiHints {
+    config: RoiHintConfig,
+    sent: Option<BBox>,
+    lost_frames: u32,
+}
+
+impl RoiHints {
+    pub fn new(config: RoiHintConfig) -> Self {
+        Self {
+            config,
+            sent: None,
+            lost_frames: 0,
+        }
+    }
+
+    /// Metering region for a result, clamped to the frame
+    ///
+    /// # Arguments
+    /// * `bbox` - Target box
+    /// * `frame_size` - Frame (width, height) in pixels
+    pub fn region(&self, bbox: &BBox, (width, height): (usize, usize)) -> BBox {
+        let (width, height) = (width as i32, height as i32);
+        let side = |v: i32, limit: i32| {
+            ((v as f32 * self.config.padding).round() as i32).max(self.config.min_size).min(limit)
+        };
+        let (w, h) = (side(bbox.width, width), side(bbox.height, height));
+        let (cx, cy) = bbox.center();
+        let x = (cx - w / 2).clamp(0, width - w);
+        let y = (cy - h / 2).clamp(0, height - h);
+        BBox::new(x, y, w, h)
+    }
+
+    /// Send the region for this frame if it changed
+    ///
+    /// # Returns
+    /// * The region now in effect (`None` for the whole frame)
+    pub fn update(
+        &mut self,
+        result: &TrackingResult,
+        frame_size: (usize, usize),
+        sink: &mut impl RoiSink,
+    ) -> io::Result<Option<BBox>> {
+        let roi = if result.success || result.coasting {
+            self.lost_frames = 0;
+            Some(self.region(&BBox::from_array(&result.bbox), frame_size))
+        } else {
+            self.lost_frames = self.lost_frames.saturating_add(1);
+            if self.lost_frames <= self.config.hold_frames {
+                return Ok(self.sent);
+            }
+            None
+        };
+
+        let changed = match (&self.sent, &roi) {
+            (Some(sent), Some(roi)) => {
+                let (a, b) = (sent.to_array(), roi.to_array());
+                let edges = |r: [i32; 4]| [r[0], r[1], r[0] + r[2], r[1] + r[3]];
+                let moved = edges(a).into_iter().zip(edges(b));
+                moved.map(|(a, b)| (a - b).abs()).any(|d| d >= self.config.min_change)
+            }
+            (None, None) => false,
+            _ => true,
+        };
+        if changed {
+            sink.set_roi(roi)?;
+            self.sent = roi;
+        }
+        Ok(self.sent)
+    }
+
+    /// Forget the sent region (e.g. after the sink was reopened)
+    pub fn reset(&mut self) {
+        self.sent = None;
+        self.lost_frames = 0;
+    }
+}
+
+/// Encoder ROI configuration
+#[derive(Debug, Clone, Copy)]
+pub struct EncoderRoiConfig {
+    /// Region side relative to the target box
+    pub margin: f32,
+    /// Coding block side in pixels (16 for H.264 macroblocks, 64 for HEVC CTUs)
+    pub block_size: i32,
+    /// QP offset inside the region; negative spends more bits on the target
+    pub qp_delta: i8,
+}
+
+impl Default for EncoderRoiConfig {
+    fn default() -> Self {
+        Self {
+            margin: 1.3,
+            block_size: 16,
+            qp_delta: -8,
+        }
+    }
+}
+
+/// Target region for an encoder ROI / QP-map API
+#[derive(Debug, Clone, Copy)]
+pub struct EncoderRoi {
+    /// Region in frame pixels, aligned to coding blocks and clamped to the frame
+    pub rect: BBox,
+    pub qp_delta: i8,
+    pub block_size: i32,
+}
+
+impl EncoderRoi {
+    /// Region for one frame, `None` while the target is lost
+    ///
+    /// # Arguments
+    /// * `result` - Tracking result of the frame being encoded
+    /// * `frame_size` - Frame (width, height) in pixels
+    pub fn from_result(
+        result: &TrackingResult,
+        (width, height): (usize, usize),
+        config: &EncoderRoiConfig,
+    ) -> Option<Self> {
+        if !(result.success || result.coasting) {
+            return None;
+        }
+
+        let block = config.block_size.max(1);
+        let [x, y, w, h] = result.bbox.map(|v| v as f32);
+        let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+        let (mw, mh) = (w * config.margin / 2.0, h * config.margin / 2.0);
+        // Grow outwards to block boundaries
+        let x1 = ((cx - mw).floor() as i32).div_euclid(block) * block;
+        let y1 = ((cy - mh).floor() as i32).div_euclid(block) * block;
+        let x2 = ((cx + mw).ceil() as i32 + block - 1).div_euclid(block) * block;
+        let y2 = ((cy + mh).ceil() as i32 + block - 1).div_euclid(block) * block;
+        let (x1, y1) = (x1.max(0), y1.max(0));
+        let (x2, y2) = (x2.min(width as i32), y2.min(height as i32));
+        if x2 <= x1 || y2 <= y1 {
+            return None;
+        }
+
+        Some(Self {
+            rect: BBox::new(x1, y1, x2 - x1, y2 - y1),
+            qp_delta: config.qp_delta,
+            block_size: block,
+        })
+    }
+
+    /// Per-block QP offsets, row-major, 0 outside the region
+    ///
+    /// # Returns
+    /// * Map of ceil(width / block) x ceil(height / block) entries
+    pub fn qp_map(&self, (width, height): (usize, usize)) -> Vec<i8> {
+        let block = self.block_size as usize;
+        let (cols, rows) = (width.div_ceil(block), height.div_ceil(block));
+        let [x, y, w, h] = self.rect.to_array().map(|v| v.max(0) as usize);
+        let (bx1, by1) = (x / block, y / block);
+        let (bx2, by2) = ((x + w).div_ceil(block), (y + h).div_ceil(block));
+
+        let mut map = vec![0i8; cols * rows];
+        for row in by1..by2.min(rows) {
+            map[row * cols + bx1.min(cols)..row * cols + bx2.min(cols)].fill(self.qp_delta);
+        }
+        map
+    }
+}
+
+/// Auto functions steered by the UVC region of interest
+#[cfg(feature = "v4l2")]
+pub mod uvc_auto {
+    pub const EXPOSURE: u16 = 1 << 0;
+    pub const IRIS: u16 = 1 << 1;
+    pub const WHITE_BALANCE: u16 = 1 << 2;
+    pub const FOCUS: u16 = 1 << 3;
+}
+
+/// Sink writing the UVC region-of-interest controls of a V4L2 device
+///
+/// Uses `V4L2_CID_UVC_REGION_OF_INTEREST_RECT` / `_AUTO` (Linux 6.0+, UVC
+/// 1.5 cameras). Cameras behind the Rockchip ISP meter through rkaiq
+/// instead; feed those from a closure sink.
+#[cfg(feature = "v4l2")]
+pub struct V4l2RoiSink {
+    file: std::fs::File,
+    /// `uvc_auto` bits enabled while a region is set
+    pub auto_controls: u16,
+}
+
+#[cfg(feature = "v4l2")]
+impl V4l2RoiSink {
+    const CID_ROI_RECT: u32 = 0x009a_1901;
+    const CID_ROI_AUTO: u32 = 0x009a_1902;
+    /// _IOWR('V', 72, struct v4l2_ext_controls)
+    const VIDIOC_S_EXT_CTRLS: libc::c_ulong = 0xc020_5648;
+
+    /// Open a video device (e.g. `/dev/video0`) for exposure and focus hints
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> io::Result<Self> {
+        let file = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(Self {
+            file,
+            auto_controls: uvc_auto::EXPOSURE | uvc_auto::FOCUS,
+        })
+    }
+}
+
+/// struct v4l2_rect
+#[cfg(feature = "v4l2")]
+#[repr(C)]
+struct V4l2Rect {
+    left: i32,
+    top: i32,
+    width: u32,
+    height: u32,
+}
+
+/// struct v4l2_ext_control (packed; the union holds the value or a pointer)
+#[cfg(feature = "v4l2")]
+#[repr(C, packed)]
+struct V4l2ExtControl {
+    id: u32,
+    size: u32,
+    reserved2: u32,
+    value: u64,
+}
+
+/// struct v4l2_ext_controls
+#[cfg(feature = "v4l2")]
+#[repr(C)]
+struct V4l2ExtControls {
+    which: u32,
+    count: u32,
+    error_idx: u32,
+    request_fd: i32,
+    reserved: u32,
+    controls: *mut V4l2ExtControl,
+}
+
+#[cfg(feature = "v4l2")]
+impl RoiSink for V4l2RoiSink {
+    fn set_roi(&mut self, roi: Option<BBox>) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        let mut rect = roi.map(|roi| V4l2Rect {
+            left: roi.x,
+            top: roi.y,
+            width: roi.width.max(0) as u32,
+            height: roi.height.max(0) as u32,
+        });
+        let mut controls = Vec::with_capacity(2);
+        if let Some(rect) = &mut rect {
+            controls.push(V4l2ExtControl {
+                id: Self::CID_ROI_RECT,
+                size: std::mem::size_of::<V4l2Rect>() as u32,
+                reserved2: 0,
+                value: rect as *mut V4l2Rect as u64,
+            });
+        }
+        // Clearing the auto bits hands metering back to the whole frame
+        let auto = if roi.is_some() { self.auto_controls } else { 0 };
+        controls.push(V4l2ExtControl {
+            id: Self::CID_ROI_AUTO,
+            size: 0,
+            reserved2: 0,
+            value: auto as u64,
+        });
+        let mut request = V4l2ExtControls {
+            which: 0,
+            count: controls.len() as u32,
+            error_idx: 0,
+            request_fd: 0,
+            reserved: 0,
+            controls: controls.as_mut_ptr(),
+        };
+
+        // SAFETY: request, controls and rect outlive the call and match the kernel layout.
+        let ret = unsafe {
+            libc::ioctl(self.file.as_raw_fd(), Self::VIDIOC_S_EXT_CTRLS, &mut request)
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracked(bbox: [i32; 4]) -> TrackingResult {
+        TrackingResult {
+            success: true,
+            bbox,
+            score: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_roi_sent_on_change_and_released_after_hold() {
+        let config = RoiHintConfig {
+            hold_frames: 2,
+            ..RoiHintConfig::default()
+        };
+        let mut hints = RoiHints::new(config);
+        let mut sent = Vec::new();
+        let mut sink = |roi: Option<BBox>| {
+            sent.push(roi.map(|r| r.to_array()));
+            Ok(())
+        };
+        let frame = (640, 480);
+
+        hints.update(&tracked([600, 10, 40, 40]), frame, &mut sink).unwrap();
+        hints.update(&tracked([602, 11, 40, 40]), frame, &mut sink).unwrap();
+        for _ in 0..3 {
+            hints.update(&TrackingResult::default(), frame, &mut sink).unwrap();
+        }
+
+        // Padded to 64 px and clamped inside the frame; the 2 px move is not sent
+        assert_eq!(sent, [Some([576, 0, 64, 64]), None]);
+    }
+
+    #[test]
+    fn test_encoder_roi_block_aligned() {
+        let config = EncoderRoiConfig::default();
+        let roi = EncoderRoi::from_result(&tracked([40, 20, 20, 20]), (64, 48), &config).unwrap();
+        // 26 px around the center (50, 30), grown to 16 px blocks
+        assert_eq!(roi.rect.to_array(), [32, 16, 32, 32]);
+        assert_eq!(roi.qp_map((64, 48)), [0, 0, 0, 0, 0, 0, -8, -8, 0, 0, -8, -8]);
+        assert!(EncoderRoi::from_result(&TrackingResult::default(), (64, 48), &config).is_none());
+    }
+}
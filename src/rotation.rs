@@ -0,0 +1,153 @@
+use ndarray::ArrayView3;
+
+/// Rotated bounding box
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RotatedBox {
+    /// Center in image pixels
+    pub cx: f32,
+    pub cy: f32,
+    /// Size along the major axis
+    pub length: f32,
+    /// Size along the minor axis
+    pub breadth: f32,
+    /// Angle of the major axis in radians, counter-clockwise from the image x axis
+    pub angle: f32,
+}
+
+/// Minimum |cos(2θ)| for solving the rotated box size from its axis-aligned bounds
+const MIN_COS_2THETA: f32 = 0.2;
+
+/// Estimate target orientation inside an axis-aligned box
+///
+/// Uses the gradient structure tensor of the box region: the dominant gradient
+/// direction is perpendicular to the long edges of the target. The rotated box
+/// size is then solved so that it is circumscribed by the axis-aligned box.
+///
+/// # Arguments
+/// * `image` - Input image as Array3<u8> in HWC format
+/// * `bbox` - Axis-aligned box [x, y, w, h]
+pub fn estimate_rotated_box(image: &ArrayView3<u8>, bbox: &[i32; 4]) -> RotatedBox {
+    let (img_h, img_w, channels) = image.dim();
+    let [x, y, w, h] = *bbox;
+
+    let axis_aligned = RotatedBox {
+        cx: x as f32 + w as f32 / 2.0,
+        cy: y as f32 + h as f32 / 2.0,
+        length: w.max(h) as f32,
+        breadth: w.min(h) as f32,
+        angle: if w >= h {
+            0.0
+        } else {
+            std::f32::consts::FRAC_PI_2
+        },
+    };
+
+    if w <= 0 || h <= 0 {
+        return axis_aligned;
+    }
+
+    // Valid region, one pixel inside for central differences
+    let x1 = x.max(1) as usize;
+    let y1 = y.max(1) as usize;
+    let x2 = ((x + w).max(0) as usize).min(img_w.saturating_sub(1));
+    let y2 = ((y + h).max(0) as usize).min(img_h.saturating_sub(1));
+
+    if x2 <= x1 || y2 <= y1 {
+        return axis_aligned;
+    }
+
+    let gray = |yy: usize, xx: usize| -> f32 {
+        (0..channels)
+            .map(|c| image[[yy, xx, c]] as f32)
+            .sum::<f32>()
+            / channels as f32
+    };
+
+    let (mut jxx, mut jyy, mut jxy) = (0.0f32, 0.0f32, 0.0f32);
+    for yy in y1..y2 {
+        for xx in x1..x2 {
+            let gx = (gray(yy, xx + 1) - gray(yy, xx - 1)) * 0.5;
+            let gy = (gray(yy + 1, xx) - gray(yy - 1, xx)) * 0.5;
+            jxx += gx * gx;
+            jyy += gy * gy;
+            jxy += gx * gy;
+        }
+    }
+
+    if jxx + jyy <= f32::EPSILON {
+        return axis_aligned;
+    }
+
+    // Dominant gradient orientation; the major axis is perpendicular to it.
+    // Image y points down, so negate to get a counter-clockwise angle.
+    let gradient_angle = 0.5 * (2.0 * jxy).atan2(jxx - jyy);
+    let mut angle = -(gradient_angle + std::f32::consts::FRAC_PI_2);
+    // Wrap into (-pi/2, pi/2]
+    while angle <= -std::f32::consts::FRAC_PI_2 {
+        angle += std::f32::consts::PI;
+    }
+    while angle > std::f32::consts::FRAC_PI_2 {
+        angle -= std::f32::consts::PI;
+    }
+
+    let (sin, cos) = (angle.sin().abs(), angle.cos().abs());
+    let cos2 = cos * cos - sin * sin;
+    if cos2.abs() < MIN_COS_2THETA {
+        return axis_aligned;
+    }
+
+    let (wf, hf) = (w as f32, h as f32);
+    let length = (wf * cos - hf * sin) / cos2;
+    let breadth = (hf * cos - wf * sin) / cos2;
+
+    if length <= 0.0 || breadth <= 0.0 {
+        return axis_aligned;
+    }
+
+    // Keep `angle` pointing along the longer side
+    let (length, breadth, angle) = if length >= breadth {
+        (length, breadth, angle)
+    } else if angle > 0.0 {
+        (breadth, length, angle - std::f32::consts::FRAC_PI_2)
+    } else {
+        (breadth, length, angle + std::f32::consts::FRAC_PI_2)
+    };
+
+    RotatedBox {
+        length,
+        breadth,
+        angle,
+        ..axis_aligned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_horizontal_bar() {
+        // Bright horizontal bar: major axis along x
+        let mut image = Array3::<u8>::zeros((64, 64, 3));
+        for y in 28..36 {
+            for x in 8..56 {
+                for c in 0..3 {
+                    image[[y, x, c]] = 255;
+                }
+            }
+        }
+
+        let rotated = estimate_rotated_box(&image.view(), &[6, 26, 52, 12]);
+        assert!(rotated.angle.abs() < 0.1);
+        assert!(rotated.length > rotated.breadth);
+    }
+
+    #[test]
+    fn test_flat_region_is_axis_aligned() {
+        let image = Array3::<u8>::zeros((32, 32, 3));
+        let rotated = estimate_rotated_box(&image.view(), &[4, 4, 10, 20]);
+        assert_eq!(rotated.length, 20.0);
+        assert_eq!(rotated.breadth, 10.0);
+    }
+}
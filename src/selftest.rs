@@ -0,0 +1,212 @@
+//! On-device self-test for manufacturing and deployment checks
+//!
+//! Reports the NPU driver and runtime versions, loads the model, tracks a
+//! synthetic target moved by a known offset and measures the update latency.
+//! The expected box is the embedded reference, so the test does not depend on
+//! the exact output values of a particular model conversion.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use ndarray::Array3;
+
+use crate::preprocess::BBox;
+use crate::tracker::VitTrack;
+
+/// Self-test limits
+#[derive(Debug, Clone)]
+pub struct SelftestConfig {
+    /// Timed updates after the functional check
+    pub iterations: usize,
+    /// Mean update latency above this fails
+    pub max_latency: Duration,
+    /// Minimum overlap of the tracked box with the reference box
+    pub min_iou: f32,
+    /// RKNN runtime library scanned for its version string
+    pub runtime_library: PathBuf,
+    /// Files tried in order for the NPU driver version
+    pub driver_version_files: Vec<PathBuf>,
+}
+
+impl Default for SelftestConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 20,
+            max_latency: Duration::from_millis(50),
+            min_iou: 0.5,
+            runtime_library: PathBuf::from("/usr/lib/librknnrt.so"),
+            driver_version_files: vec![
+                PathBuf::from("/sys/kernel/debug/rknpu/version"),
+                PathBuf::from("/sys/module/rknpu/version"),
+            ],
+        }
+    }
+}
+
+/// Outcome of one self-test step
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Pass/fail report, printable as one line per check
+#[derive(Debug, Clone, Default)]
+pub struct SelftestReport {
+    pub checks: Vec<Check>,
+}
+
+impl SelftestReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    pub(crate) fn push(&mut self, name: &'static str, passed: bool, detail: String) {
+        self.checks.push(Check {
+            name,
+            passed,
+            detail,
+        });
+    }
+}
+
+impl fmt::Display for SelftestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let status = if check.passed { "PASS" } else { "FAIL" };
+            writeln!(f, "[{}] {}: {}", status, check.name, check.detail)?;
+        }
+        write!(f, "RESULT: {}", if self.passed() { "PASS" } else { "FAIL" })
+    }
+}
+
+/// Run the self-test against a model file
+pub fn run<P: AsRef<Path>>(model_path: P, config: &SelftestConfig) -> SelftestReport {
+    let mut report = SelftestReport::default();
+
+    let driver = config
+        .driver_version_files
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .map(|version| version.trim().to_string());
+    report.push(
+        "driver",
+        driver.is_some(),
+        driver.unwrap_or_else(|| "NPU driver version not found".into()),
+    );
+    let runtime = runtime_version(&config.runtime_library);
+    report.push(
+        "runtime",
+        runtime.is_some(),
+        runtime.unwrap_or_else(|| format!("no version in {}", config.runtime_library.display())),
+    );
+
+    let start = Instant::now();
+    let mut tracker = match VitTrack::new(model_path.as_ref()) {
+        Ok(tracker) => tracker,
+        Err(e) => {
+            report.push("load", false, e.to_string());
+            return report;
+        }
+    };
+    let load_ms = start.elapsed().as_secs_f32() * 1e3;
+    let device = tracker.model().device();
+    report.push("load", true, format!("{:.0} ms on {:?}", load_ms, device));
+
+    // Known input: textured target moved by a fixed offset
+    let initial = [300, 200, 48, 40];
+    let moved = [312, 208, 48, 40];
+    tracker.init(&synthetic_frame(&initial).view(), BBox::from_array(&initial));
+    let frame = synthetic_frame(&moved);
+    let result = match tracker.update(&frame.view()) {
+        Ok(result) => result,
+        Err(e) => {
+            report.push("inference", false, e.to_string());
+            return report;
+        }
+    };
+    let finite = tracker.score_map().iter().all(|v| v.is_finite());
+    report.push(
+        "outputs",
+        finite,
+        format!("{} confidence values, all finite: {}", tracker.score_map().len(), finite),
+    );
+    let overlap = BBox::from_array(&result.bbox).iou(&BBox::from_array(&moved));
+    report.push(
+        "reference",
+        result.success && overlap >= config.min_iou,
+        format!(
+            "box {:?} vs {:?}, IoU {:.2}, score {:.3}",
+            result.bbox, moved, overlap, result.score
+        ),
+    );
+
+    let mut latencies = Vec::with_capacity(config.iterations);
+    for _ in 0..config.iterations {
+        let start = Instant::now();
+        if let Err(e) = tracker.update(&frame.view()) {
+            report.push("latency", false, e.to_string());
+            return report;
+        }
+        latencies.push(start.elapsed());
+    }
+    if let Some(max) = latencies.iter().max() {
+        let mean = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+        report.push(
+            "latency",
+            mean <= config.max_latency,
+            format!(
+                "mean {:.1} ms, max {:.1} ms (limit {:.1} ms)",
+                mean.as_secs_f32() * 1e3,
+                max.as_secs_f32() * 1e3,
+                config.max_latency.as_secs_f32() * 1e3
+            ),
+        );
+    }
+
+    report
+}
+
+/// Version string embedded in librknnrt ("librknnrt version: 2.3.0 (...)")
+pub fn runtime_version(library: &Path) -> Option<String> {
+    const MARKER: &[u8] = b"librknnrt version: ";
+    let bytes = fs::read(library).ok()?;
+    let start = bytes.windows(MARKER.len()).position(|w| w == MARKER)? + MARKER.len();
+    let end = bytes[start..]
+        .iter()
+        .position(|&b| b == 0 || b == b'\n')
+        .map_or(bytes.len(), |len| start + len);
+    Some(String::from_utf8_lossy(&bytes[start..end]).trim().to_string())
+}
+
+/// Gray frame with a checkerboard target at `bbox`
+pub(crate) fn synthetic_frame(bbox: &[i32; 4]) -> Array3<u8> {
+    let [bx, by, bw, bh] = *bbox;
+    Array3::from_shape_fn((480, 640, 3), |(y, x, c)| {
+        let (x, y) = (x as i32, y as i32);
+        if (bx..bx + bw).contains(&x) && (by..by + bh).contains(&y) {
+            let cell = ((x - bx) / 8 + (y - by) / 8) % 2;
+            [[220, 40, 40], [40, 40, 220]][cell as usize][c]
+        } else {
+            110
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_version_parsing() {
+        let path = std::env::temp_dir().join(format!("librknnrt_{}.so", std::process::id()));
+        let library = b"\x7fELF\0\0librknnrt version: 2.3.0 (c949ad889d@2024-11-07)\0tail";
+        fs::write(&path, library).unwrap();
+        let version = runtime_version(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(version.as_deref(), Some("2.3.0 (c949ad889d@2024-11-07)"));
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use ndarray::{s, Array3, ArrayView3};
+
+use crate::postprocess::TrackingResult;
+
+/// Snapshot-on-loss configuration
+#[derive(Debug, Clone)]
+pub struct SnapshotConfig {
+    /// Directory receiving `loss_000`, `loss_001`, ...
+    pub dir: PathBuf,
+    /// Frames kept before the loss (including the lost frame)
+    pub frames: usize,
+    /// Snapshot directories kept; the oldest is overwritten
+    pub max_snapshots: usize,
+    /// Keep every n-th pixel of the frames (memory is frames * size / n^2)
+    pub downsample: usize,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("diagnostics"),
+            frames: 15,
+            max_snapshots: 10,
+            downsample: 2,
+        }
+    }
+}
+
+/// Saves the frames leading up to a loss of track
+///
+/// Feed every frame with its result; when tracking turns into lost, the
+/// buffered frames (annotated with the box), their results and the current
+/// score map are written to a new snapshot directory:
+///
+/// * `frame_NN.ppm` - annotated frames, oldest first
+/// * `results.csv` - frame, success, score, peak ratio and box
+/// * `score_map.pgm` / `score_map.csv` - confidence map of the lost frame
+pub struct LossSnapshots {
+    config: SnapshotConfig,
+    frames: VecDeque<(Array3<u8>, TrackingResult)>,
+    was_tracking: bool,
+    written: usize,
+}
+
+impl LossSnapshots {
+    pub fn new(config: SnapshotConfig) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(config.frames.max(1)),
+            config,
+            was_tracking: false,
+            written: 0,
+        }
+    }
+
+    /// Record a frame and write a snapshot on the tracking -> lost transition
+    ///
+    /// # Arguments
+    /// * `image` - Frame the result was computed on (RGB HWC)
+    /// * `result` - Tracking result of the frame
+    /// * `score_map` - Confidence map of the frame (`VitTrack::score_map`)
+    ///
+    /// # Returns
+    /// * Directory of the snapshot written on this frame, if any
+    pub fn observe(
+        &mut self,
+        image: &ArrayView3<u8>,
+        result: &TrackingResult,
+        score_map: &[f32],
+    ) -> io::Result<Option<PathBuf>> {
+        let step = self.config.downsample.max(1);
+        let mut frame = image.slice(s![..;step, ..;step, ..]).to_owned();
+        draw_box(&mut frame, &result.bbox.map(|v| v / step as i32), box_color(result));
+        if self.frames.len() == self.config.frames.max(1) {
+            self.frames.pop_front();
+        }
+        self.frames.push_back((frame, *result));
+
+        let lost = self.was_tracking && !result.success;
+        self.was_tracking = result.success;
+        if !lost {
+            return Ok(None);
+        }
+
+        let slot = self.written % self.config.max_snapshots.max(1);
+        self.written += 1;
+        let dir = self.config.dir.join(format!("loss_{:03}", slot));
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        self.write(&dir, score_map)?;
+        Ok(Some(dir))
+    }
+
+    fn write(&self, dir: &Path, score_map: &[f32]) -> io::Result<()> {
+        let mut results = BufWriter::new(fs::File::create(dir.join("results.csv"))?);
+        writeln!(results, "frame,success,score,peak_ratio,x,y,w,h")?;
+        for (i, (frame, result)) in self.frames.iter().enumerate() {
+            write_ppm(&dir.join(format!("frame_{:02}.ppm", i)), frame)?;
+            let [x, y, w, h] = result.bbox;
+            writeln!(
+                results,
+                "{},{},{:.4},{:.4},{},{},{},{}",
+                i, result.success, result.score, result.peak_ratio, x, y, w, h
+            )?;
+        }
+        results.flush()?;
+
+        let size = (score_map.len() as f32).sqrt() as usize;
+        let mut csv = BufWriter::new(fs::File::create(dir.join("score_map.csv"))?);
+        for row in score_map.chunks(size.max(1)) {
+            let row: Vec<String> = row.iter().map(|v| format!("{:.4}", v)).collect();
+            writeln!(csv, "{}", row.join(","))?;
+        }
+        csv.flush()?;
+
+        let max = score_map.iter().cloned().fold(f32::MIN_POSITIVE, f32::max);
+        let mut pgm = BufWriter::new(fs::File::create(dir.join("score_map.pgm"))?);
+        write!(pgm, "P5\n{} {}\n255\n", size, size)?;
+        let pixels: Vec<u8> = score_map
+            .iter()
+            .take(size * size)
+            .map(|&v| (v.max(0.0) / max * 255.0).round() as u8)
+            .collect();
+        pgm.write_all(&pixels)?;
+        pgm.flush()
+    }
+}
+
+/// Outline color: the track color when tagged, else green when tracking and
+/// red when lost
+pub(crate) fn box_color(result: &TrackingResult) -> [u8; 3] {
+    match result.tag {
+        Some(tag) => tag.color(),
+        None if result.success => [0, 255, 0],
+        None => [255, 0, 0],
+    }
+}
+
+/// Outline a box in place
+pub(crate) fn draw_box(frame: &mut Array3<u8>, bbox: &[i32; 4], color: [u8; 3]) {
+    let (h, w, _) = frame.dim();
+    let [x, y, bw, bh] = *bbox;
+    let (x2, y2) = (x + bw.max(1) - 1, y + bh.max(1) - 1);
+    let mut put = |px: i32, py: i32| {
+        if px >= 0 && py >= 0 && (px as usize) < w && (py as usize) < h {
+            for (c, &value) in color.iter().enumerate() {
+                frame[[py as usize, px as usize, c]] = value;
+            }
+        }
+    };
+    for px in x..=x2 {
+        put(px, y);
+        put(px, y2);
+    }
+    for py in y..=y2 {
+        put(x, py);
+        put(x2, py);
+    }
+}
+
+/// Binary PPM (P6), viewable without extra dependencies
+fn write_ppm(path: &Path, frame: &Array3<u8>) -> io::Result<()> {
+    let (h, w, _) = frame.dim();
+    let mut file = BufWriter::new(fs::File::create(path)?);
+    write!(file, "P6\n{} {}\n255\n", w, h)?;
+    let pixels: Vec<u8> = frame.iter().copied().collect();
+    file.write_all(&pixels)?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_written_on_loss() {
+        let dir = std::env::temp_dir().join(format!("vit_snapshot_{}", std::process::id()));
+        let mut snapshots = LossSnapshots::new(SnapshotConfig {
+            dir: dir.clone(),
+            frames: 3,
+            max_snapshots: 2,
+            downsample: 2,
+        });
+        let image = Array3::<u8>::zeros((40, 60, 3));
+        let tracking = TrackingResult {
+            success: true,
+            bbox: [10, 10, 20, 10],
+            score: 0.7,
+            ..TrackingResult::default()
+        };
+        let lost = TrackingResult {
+            success: false,
+            ..tracking
+        };
+        let score_map = vec![0.5f32; 16];
+
+        for _ in 0..5 {
+            let written = snapshots.observe(&image.view(), &tracking, &score_map).unwrap();
+            assert!(written.is_none());
+        }
+        let written = snapshots.observe(&image.view(), &lost, &score_map).unwrap();
+        let snapshot = written.expect("snapshot on loss");
+        assert!(snapshot.join("frame_02.ppm").is_file());
+        assert!(!snapshot.join("frame_03.ppm").exists());
+        assert!(snapshot.join("score_map.pgm").is_file());
+        // Still lost: no new snapshot
+        let written = snapshots.observe(&image.view(), &lost, &score_map).unwrap();
+        assert!(written.is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
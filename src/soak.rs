@@ -0,0 +1,360 @@
+//! Burn-in (soak) test for firmware qualification
+//!
+//! Loops a recorded sequence, or a synthetic target circling the frame, for
+//! hours and samples the process at a fixed interval: resident memory,
+//! update latency, inference errors, SoC temperature and the NPU's frequency
+//! cap. At the end each criterion becomes a pass/fail check in the same
+//! format as the self-test.
+//!
+//! Thermal throttling shows up as the devfreq `max_freq` of the NPU dropping
+//! below its value at the start; the thermal framework lowers it when a trip
+//! point is crossed.
+
+use std::borrow::Cow;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use ndarray::Array3;
+
+use crate::preprocess::BBox;
+use crate::selftest::{synthetic_frame, SelftestReport};
+use crate::tracker::VitTrack;
+
+/// Soak test limits
+#[derive(Debug, Clone)]
+pub struct SoakConfig {
+    pub duration: Duration,
+    /// Interval between samples; latency is averaged over each interval
+    pub sample_interval: Duration,
+    /// Resident memory growth from the first to the last sample above this fails
+    pub max_memory_growth: u64,
+    /// Mean latency of the last interval over the first one above this fails
+    pub max_latency_drift: f32,
+    /// Inference errors above this fail
+    pub max_errors: u64,
+    /// Hottest thermal zone above this (°C) fails
+    pub max_temperature: f32,
+    /// Scanned for `thermal_zone*/temp`
+    pub thermal_dir: PathBuf,
+    /// Scanned for an `*npu*` device with `max_freq`
+    pub devfreq_dir: PathBuf,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(4 * 3600),
+            sample_interval: Duration::from_secs(60),
+            max_memory_growth: 16 << 20,
+            max_latency_drift: 1.25,
+            max_errors: 0,
+            max_temperature: 85.0,
+            thermal_dir: PathBuf::from("/sys/class/thermal"),
+            devfreq_dir: PathBuf::from("/sys/class/devfreq"),
+        }
+    }
+}
+
+/// Frames the soak test loops over
+#[derive(Debug, Clone)]
+pub enum SoakInput {
+    /// Textured target circling the frame, generated on the fly
+    Synthetic,
+    /// Recorded frames (RGB HWC), tracked from `init` at the start of each loop
+    Recorded { frames: Vec<Array3<u8>>, init: BBox },
+}
+
+/// Process state at the end of one sample interval
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SoakSample {
+    pub elapsed: Duration,
+    /// Updates since the start
+    pub frames: u64,
+    /// Inference errors since the start
+    pub errors: u64,
+    /// Resident memory in bytes
+    pub rss: Option<u64>,
+    pub mean_latency: Duration,
+    pub max_latency: Duration,
+    /// Hottest thermal zone in °C
+    pub temperature: Option<f32>,
+    /// NPU frequency cap in Hz
+    pub npu_max_freq: Option<u64>,
+}
+
+/// Samples and the pass/fail verdict
+#[derive(Debug, Clone, Default)]
+pub struct SoakReport {
+    pub samples: Vec<SoakSample>,
+    /// First inference error messages
+    pub errors: Vec<String>,
+    pub checks: SelftestReport,
+}
+
+impl SoakReport {
+    pub fn passed(&self) -> bool {
+        self.checks.passed()
+    }
+
+    /// One line per sample
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "elapsed_s,frames,errors,rss_bytes,mean_latency_ms,max_latency_ms,\
+             temperature_c,npu_max_freq_hz"
+        )?;
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        for sample in &self.samples {
+            writeln!(
+                out,
+                "{:.0},{},{},{},{:.2},{:.2},{},{}",
+                sample.elapsed.as_secs_f32(),
+                sample.frames,
+                sample.errors,
+                optional(sample.rss.map(|v| v.to_string())),
+                sample.mean_latency.as_secs_f32() * 1e3,
+                sample.max_latency.as_secs_f32() * 1e3,
+                optional(sample.temperature.map(|v| format!("{:.1}", v))),
+                optional(sample.npu_max_freq.map(|v| v.to_string())),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Run the soak test
+///
+/// # Arguments
+/// * `tracker` - Loaded tracker; re-initialized at the start of every loop
+/// * `input` - Frames to loop over
+/// * `config` - Duration, sampling and limits
+/// * `on_sample` - Called with every sample, e.g. for progress output
+pub fn run(
+    tracker: &mut VitTrack,
+    input: &SoakInput,
+    config: &SoakConfig,
+    mut on_sample: impl FnMut(&SoakSample),
+) -> SoakReport {
+    const SYNTHETIC_PERIOD: usize = 120;
+    const MAX_MESSAGES: usize = 10;
+    let period = match input {
+        SoakInput::Synthetic => SYNTHETIC_PERIOD,
+        SoakInput::Recorded { frames, .. } => frames.len(),
+    };
+    let mut report = SoakReport::default();
+    if period == 0 {
+        report.checks.push("input", false, "no frames".into());
+        return report;
+    }
+
+    let start = Instant::now();
+    let mut window_start = start;
+    let mut latencies: Vec<Duration> = Vec::new();
+    let (mut frames, mut errors) = (0u64, 0u64);
+    let mut index = 0;
+    let mut reinit = true;
+    while start.elapsed() < config.duration {
+        let (frame, bbox) = match input {
+            SoakInput::Synthetic => {
+                let bbox = circling_box(index, SYNTHETIC_PERIOD);
+                (Cow::Owned(synthetic_frame(&bbox)), BBox::from_array(&bbox))
+            }
+            SoakInput::Recorded { frames, init } => (Cow::Borrowed(&frames[index]), *init),
+        };
+        if index == 0 || reinit {
+            tracker.init(&frame.view(), bbox);
+            reinit = false;
+        }
+
+        let timer = Instant::now();
+        match tracker.update(&frame.view()) {
+            Ok(_) => latencies.push(timer.elapsed()),
+            Err(e) => {
+                errors += 1;
+                if report.errors.len() < MAX_MESSAGES {
+                    report.errors.push(format!("frame {}: {}", frames, e));
+                }
+                reinit = true;
+            }
+        }
+        frames += 1;
+        index = (index + 1) % period;
+
+        if window_start.elapsed() >= config.sample_interval {
+            let sample = SoakSample {
+                elapsed: start.elapsed(),
+                frames,
+                errors,
+                rss: resident_memory(),
+                mean_latency: mean(&latencies),
+                max_latency: latencies.iter().max().copied().unwrap_or_default(),
+                temperature: max_temperature(&config.thermal_dir),
+                npu_max_freq: npu_max_freq(&config.devfreq_dir),
+            };
+            on_sample(&sample);
+            report.samples.push(sample);
+            latencies.clear();
+            window_start = Instant::now();
+        }
+    }
+
+    report.checks = evaluate(&report.samples, config);
+    report
+}
+
+/// Pass/fail checks over the samples
+pub fn evaluate(samples: &[SoakSample], config: &SoakConfig) -> SelftestReport {
+    let mut checks = SelftestReport::default();
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        checks.push("samples", false, "no complete sample interval".into());
+        return checks;
+    };
+    checks.push(
+        "duration",
+        true,
+        format!("{:.1} h, {} frames", last.elapsed.as_secs_f32() / 3600.0, last.frames),
+    );
+    checks.push(
+        "errors",
+        last.errors <= config.max_errors,
+        format!("{} inference errors (limit {})", last.errors, config.max_errors),
+    );
+
+    match (first.rss, last.rss) {
+        (Some(start), Some(end)) => {
+            let growth = end.saturating_sub(start);
+            checks.push(
+                "memory",
+                growth <= config.max_memory_growth,
+                format!(
+                    "RSS {:.1} -> {:.1} MiB (growth limit {:.1} MiB)",
+                    start as f32 / 1048576.0,
+                    end as f32 / 1048576.0,
+                    config.max_memory_growth as f32 / 1048576.0
+                ),
+            );
+        }
+        _ => checks.push("memory", false, "resident memory not available".into()),
+    }
+
+    let drift = last.mean_latency.as_secs_f32() / first.mean_latency.as_secs_f32().max(1e-6);
+    checks.push(
+        "latency",
+        drift <= config.max_latency_drift,
+        format!(
+            "mean {:.1} -> {:.1} ms, x{:.2} (limit x{:.2})",
+            first.mean_latency.as_secs_f32() * 1e3,
+            last.mean_latency.as_secs_f32() * 1e3,
+            drift,
+            config.max_latency_drift
+        ),
+    );
+
+    let hottest = samples.iter().filter_map(|s| s.temperature).reduce(f32::max);
+    checks.push(
+        "thermal",
+        hottest.is_none_or(|t| t <= config.max_temperature),
+        match hottest {
+            Some(t) => format!("max {:.1} °C (limit {:.1} °C)", t, config.max_temperature),
+            None => "no thermal zones readable".into(),
+        },
+    );
+
+    let throttled = match first.npu_max_freq {
+        Some(initial) => samples
+            .iter()
+            .filter(|s| s.npu_max_freq.is_some_and(|f| f < initial))
+            .count(),
+        None => 0,
+    };
+    checks.push(
+        "throttling",
+        throttled == 0,
+        match first.npu_max_freq {
+            Some(initial) => format!(
+                "NPU capped below {} MHz in {} of {} samples",
+                initial / 1_000_000,
+                throttled,
+                samples.len()
+            ),
+            None => "NPU devfreq not found".into(),
+        },
+    );
+    checks
+}
+
+/// Box of a 48x40 target on a circle around the center of a 640x480 frame
+fn circling_box(index: usize, period: usize) -> [i32; 4] {
+    let angle = index as f32 / period as f32 * std::f32::consts::TAU;
+    let (cx, cy) = (320.0 + 150.0 * angle.cos(), 240.0 + 120.0 * angle.sin());
+    [cx as i32 - 24, cy as i32 - 20, 48, 40]
+}
+
+fn mean(latencies: &[Duration]) -> Duration {
+    latencies.iter().sum::<Duration>() / latencies.len().max(1) as u32
+}
+
+/// `VmRSS` of this process
+fn resident_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Hottest `thermal_zone*/temp`, reported in millidegrees
+fn max_temperature(thermal_dir: &Path) -> Option<f32> {
+    fs::read_dir(thermal_dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("thermal_zone"))
+        .filter_map(|entry| fs::read_to_string(entry.path().join("temp")).ok())
+        .filter_map(|text| text.trim().parse::<f32>().ok())
+        .map(|millidegrees| millidegrees / 1000.0)
+        .reduce(f32::max)
+}
+
+/// `max_freq` of the NPU devfreq device (e.g. `fdab0000.npu`)
+fn npu_max_freq(devfreq_dir: &Path) -> Option<u64> {
+    fs::read_dir(devfreq_dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().contains("npu"))
+        .find_map(|entry| fs::read_to_string(entry.path().join("max_freq")).ok())
+        .and_then(|text| text.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_flags_leak_and_throttling() {
+        let sample = |minutes: u64, rss: u64, latency_ms: u64, freq: u64| SoakSample {
+            elapsed: Duration::from_secs(minutes * 60),
+            frames: minutes * 1800,
+            rss: Some(rss << 20),
+            mean_latency: Duration::from_millis(latency_ms),
+            max_latency: Duration::from_millis(latency_ms * 2),
+            temperature: Some(70.0),
+            npu_max_freq: Some(freq),
+            ..SoakSample::default()
+        };
+        let config = SoakConfig::default();
+
+        let stable = [sample(1, 80, 20, 1_000_000_000), sample(60, 82, 21, 1_000_000_000)];
+        assert!(evaluate(&stable, &config).passed());
+
+        let failing = [sample(1, 80, 20, 1_000_000_000), sample(60, 200, 30, 800_000_000)];
+        let checks = evaluate(&failing, &config);
+        let failed: Vec<&str> = checks
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.name)
+            .collect();
+        assert_eq!(failed, ["memory", "latency", "throttling"]);
+    }
+}
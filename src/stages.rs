@@ -0,0 +1,261 @@
+//! Intermediate products of one tracking step
+//!
+//! `VitTrack::update` runs crop -> normalize -> inference -> decode in one
+//! call. These types hold the product of each stage together with the frame
+//! region it describes, so a custom pipeline can replace one stage (e.g. a
+//! hardware crop) and keep the crate's implementation of the others:
+//!
+//! ```ignore
+//! let crop = SearchCrop::new(isp_crop, tracker.next_crop());
+//! let search = crop.normalize(InputType::Float32, Normalization::Fixed);
+//! let outputs = tracker.infer(&search)?.expect("initialized");
+//! let candidates = DecodedCandidates::decode(
+//!     &outputs.conf_map, &outputs.size_map, &outputs.offset_map,
+//!     tracker.score_window(), 16, search.transform, 5,
+//! );
+//! let result = candidates.to_result(0.3, &last_box);
+//! ```
+
+use ndarray::{Array3, ArrayView3};
+
+use crate::decode::{apply_window, crop_origin, decode_box, top_peaks, CropBox};
+use crate::postprocess::TrackingResult;
+use crate::preprocess::{crop_resized, crop_size, BBox, InputTensor, InputType, Normalization};
+
+/// Frame region covered by an externally produced search crop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropTransform {
+    /// Top-left corner of the square region in frame pixels
+    pub origin: (i32, i32),
+    /// Side of the region in frame pixels (before resizing to `search_size`)
+    pub crop_size: i32,
+}
+
+impl CropTransform {
+    /// Region `update` would crop around `bbox` with the given search factor
+    pub fn around(bbox: &BBox, search_factor: u32) -> Self {
+        let crop_size = crop_size(bbox, search_factor);
+        Self {
+            origin: crop_origin(&bbox.to_array(), crop_size),
+            crop_size,
+        }
+    }
+}
+
+/// Resized RGB search crop and the frame region it was taken from
+#[derive(Debug, Clone)]
+pub struct SearchCrop {
+    /// `search_size` x `search_size` x 3 pixels
+    pub pixels: Array3<u8>,
+    pub transform: CropTransform,
+}
+
+impl SearchCrop {
+    pub fn new(pixels: Array3<u8>, transform: CropTransform) -> Self {
+        Self { pixels, transform }
+    }
+
+    /// Crop around a box the way `update` does, padding outside the frame
+    ///
+    /// # Arguments
+    /// * `image` - Frame in HWC RGB format
+    /// * `bbox` - Box to search around
+    /// * `search_factor` - Crop side in units of the box size (usually 4)
+    /// * `search_size` - Side of the resized crop (usually 256)
+    pub fn around(
+        image: &ArrayView3<u8>,
+        bbox: &BBox,
+        search_factor: u32,
+        search_size: usize,
+    ) -> Self {
+        let (pixels, crop_size) = crop_resized(image, bbox, search_factor, search_size);
+        let transform = CropTransform {
+            origin: crop_origin(&bbox.to_array(), crop_size),
+            crop_size,
+        };
+        Self { pixels, transform }
+    }
+
+    /// Convert to a model input tensor
+    pub fn normalize(
+        &self,
+        input_type: InputType,
+        normalization: Normalization,
+    ) -> NormalizedTensor {
+        NormalizedTensor {
+            tensor: InputTensor::from_image_normalized(&self.pixels, input_type, normalization),
+            transform: self.transform,
+        }
+    }
+}
+
+/// Search tensor ready for inference and the frame region it covers
+#[derive(Debug, Clone)]
+pub struct NormalizedTensor {
+    pub tensor: InputTensor,
+    pub transform: CropTransform,
+}
+
+impl NormalizedTensor {
+    pub fn new(tensor: InputTensor, transform: CropTransform) -> Self {
+        Self { tensor, transform }
+    }
+}
+
+/// One local peak of the windowed score map
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+    /// Flat index of the score map cell (y * score_size + x)
+    pub idx: usize,
+    /// Windowed score
+    pub score: f32,
+    /// Box normalized to the search crop
+    pub crop_box: CropBox,
+    /// Box in frame pixels [x, y, w, h]
+    pub bbox: [i32; 4],
+}
+
+/// Candidate boxes decoded from the model heads, best first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DecodedCandidates {
+    pub candidates: Vec<Candidate>,
+}
+
+impl DecodedCandidates {
+    pub fn new(candidates: Vec<Candidate>) -> Self {
+        Self { candidates }
+    }
+
+    /// Window the confidence map and decode its highest local peaks
+    ///
+    /// The peaks are those of `top_peaks`, so the best candidate is the box
+    /// `update` would report with argmax localization.
+    ///
+    /// # Arguments
+    /// * `conf_map` - Confidence map (score_size x score_size)
+    /// * `size_map` - Size map (2 x score_size x score_size)
+    /// * `offset_map` - Offset map (2 x score_size x score_size)
+    /// * `window` - Window applied to the confidence map
+    /// * `score_size` - Side of the score map
+    /// * `transform` - Frame region of the search crop
+    /// * `max_candidates` - Number of peaks to decode
+    pub fn decode(
+        conf_map: &[f32],
+        size_map: &[f32],
+        offset_map: &[f32],
+        window: &[f32],
+        score_size: usize,
+        transform: CropTransform,
+        max_candidates: usize,
+    ) -> Self {
+        let mut windowed = vec![0.0f32; score_size * score_size];
+        apply_window(conf_map, window, &mut windowed);
+        let mut peaks = vec![(0, 0.0f32); max_candidates];
+        let found = top_peaks(&windowed, score_size, &mut peaks);
+
+        let candidates = peaks[..found]
+            .iter()
+            .map(|&(idx, score)| {
+                let crop_box = decode_box(size_map, offset_map, idx, score_size);
+                Candidate {
+                    idx,
+                    score,
+                    crop_box,
+                    bbox: crop_box.to_image(transform.origin, transform.crop_size),
+                }
+            })
+            .collect();
+        Self { candidates }
+    }
+
+    pub fn best(&self) -> Option<&Candidate> {
+        self.candidates.first()
+    }
+
+    /// Second-best candidate score divided by the best one
+    pub fn peak_ratio(&self) -> f32 {
+        match &self.candidates[..] {
+            [best, second, ..] if best.score > 0.0 => second.score.max(0.0) / best.score,
+            _ => 0.0,
+        }
+    }
+
+    /// Tracking result for the best candidate
+    ///
+    /// # Arguments
+    /// * `threshold` - Score below which the frame counts as lost
+    /// * `rect_last` - Box reported when lost
+    pub fn to_result(&self, threshold: f32, rect_last: &[i32; 4]) -> TrackingResult {
+        let Some(best) = self.best() else {
+            return TrackingResult {
+                bbox: *rect_last,
+                ..TrackingResult::default()
+            };
+        };
+        let success = best.score >= threshold;
+        TrackingResult {
+            success,
+            bbox: if success { best.bbox } else { *rect_last },
+            score: best.score,
+            peak_ratio: self.peak_ratio(),
+            ..TrackingResult::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postprocess::{hann2d, process_outputs};
+
+    #[test]
+    fn test_decode_matches_process_outputs() {
+        let conf_map: Vec<f32> = (0..256)
+            .map(|i| match i {
+                100 => 0.9,
+                170 => 0.6,
+                _ => 0.05,
+            })
+            .collect();
+        let size_map: Vec<f32> = (0..512).map(|i| 0.1 + (i % 7) as f32 * 0.02).collect();
+        let offset_map: Vec<f32> = (0..512).map(|i| (i % 5) as f32 * 0.1).collect();
+        let window = hann2d(16, 16);
+        let rect = [300, 200, 40, 30];
+
+        let expected = process_outputs(
+            &conf_map,
+            &size_map,
+            &offset_map,
+            &window,
+            &rect,
+            crop_size(&BBox::from_array(&rect), 4),
+            0.3,
+        );
+        let transform = CropTransform::around(&BBox::from_array(&rect), 4);
+        let candidates =
+            DecodedCandidates::decode(&conf_map, &size_map, &offset_map, &window, 16, transform, 3);
+        let result = candidates.to_result(0.3, &rect);
+
+        assert_eq!(candidates.candidates.len(), 3);
+        assert_eq!(candidates.best().map(|c| c.idx), Some(100));
+        assert_eq!(candidates.candidates[1].idx, 170);
+        assert!(result.success);
+        assert_eq!(result.bbox, expected.bbox);
+        assert_eq!(result.score, expected.score);
+        assert!((result.peak_ratio - expected.peak_ratio).abs() < 1e-6);
+        assert!(!candidates.to_result(1.0, &rect).success);
+    }
+
+    #[test]
+    fn test_search_crop_covers_transform() {
+        let frame = Array3::from_shape_fn((240, 320, 3), |(y, x, _)| ((x + y) % 256) as u8);
+        let bbox = BBox::new(100, 80, 32, 32);
+        let crop = SearchCrop::around(&frame.view(), &bbox, 4, 256);
+        assert_eq!(crop.pixels.dim(), (256, 256, 3));
+        assert_eq!(crop.transform, CropTransform::around(&bbox, 4));
+
+        let search = crop.normalize(InputType::Uint8, Normalization::Fixed);
+        assert_eq!(search.tensor.len(), 256 * 256 * 3);
+        assert_eq!(search.transform, crop.transform);
+    }
+}
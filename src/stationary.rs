@@ -0,0 +1,218 @@
+//! Reuse work while a parked target sits in an unchanged scene
+//!
+//! Surveillance cameras spend most of their time looking at targets that do
+//! not move. When the motion model predicts no movement and a sparse sample
+//! of the search region matches the last inferred frame, the tracker can
+//! either reuse the preprocessed search tensors or skip inference and repeat
+//! the previous result (flagged `cached`).
+
+use ndarray::ArrayView3;
+
+use crate::preprocess::InputTensor;
+
+/// Stationary-scene cache configuration
+#[derive(Debug, Clone, Copy)]
+pub struct StationaryConfig {
+    /// Mean absolute difference (0-255) of the sampled pixels below which the
+    /// search region counts as unchanged
+    pub max_diff: f32,
+    /// Sample every n-th pixel in both directions
+    pub sample_step: usize,
+    /// Predicted speed (px/frame) above which the target counts as moving
+    pub max_velocity: f32,
+    /// Run inference at least every this many frames
+    pub max_frames: usize,
+    /// Repeat the previous result instead of only reusing the search tensors
+    pub skip_inference: bool,
+}
+
+impl Default for StationaryConfig {
+    fn default() -> Self {
+        Self {
+            max_diff: 2.0,
+            sample_step: 4,
+            max_velocity: 0.5,
+            max_frames: 30,
+            skip_inference: true,
+        }
+    }
+}
+
+/// Sparse pixel sample of a frame region
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionSignature {
+    region: [i32; 4],
+    samples: Vec<u8>,
+}
+
+impl RegionSignature {
+    /// Sample `region` [x, y, w, h] of `image`, clipped to the frame
+    pub fn sample(image: &ArrayView3<u8>, region: [i32; 4], step: usize) -> Self {
+        let (img_h, img_w, channels) = image.dim();
+        let x0 = region[0].clamp(0, img_w as i32) as usize;
+        let y0 = region[1].clamp(0, img_h as i32) as usize;
+        let x1 = (region[0] + region[2]).clamp(0, img_w as i32) as usize;
+        let y1 = (region[1] + region[3]).clamp(0, img_h as i32) as usize;
+        let step = step.max(1);
+
+        let mut samples = Vec::new();
+        for y in (y0..y1).step_by(step) {
+            for x in (x0..x1).step_by(step) {
+                samples.extend((0..channels).map(|c| image[[y, x, c]]));
+            }
+        }
+        Self { region, samples }
+    }
+
+    /// Mean absolute difference to another sample of the same region
+    ///
+    /// # Returns
+    /// * None when the regions differ or nothing was sampled
+    pub fn mean_abs_diff(&self, other: &Self) -> Option<f32> {
+        if self.region != other.region
+            || self.samples.len() != other.samples.len()
+            || self.samples.is_empty()
+        {
+            return None;
+        }
+        let total: u64 = self
+            .samples
+            .iter()
+            .zip(&other.samples)
+            .map(|(&a, &b)| a.abs_diff(b) as u64)
+            .sum();
+        Some(total as f32 / self.samples.len() as f32)
+    }
+}
+
+/// Search tensor prepared for one crop
+struct CachedTensor {
+    key: ([i32; 4], Option<i32>),
+    tensor: InputTensor,
+    crop_size: i32,
+}
+
+/// Reference sample of the last inferred frame and its search tensors
+pub struct StationaryCache {
+    config: StationaryConfig,
+    reference: Option<RegionSignature>,
+    tensors: Vec<CachedTensor>,
+    /// Consecutive frames found unchanged
+    frames: usize,
+    unchanged: bool,
+}
+
+impl StationaryCache {
+    pub fn new(config: StationaryConfig) -> Self {
+        Self {
+            config,
+            reference: None,
+            tensors: Vec::new(),
+            frames: 0,
+            unchanged: false,
+        }
+    }
+
+    pub fn config(&self) -> &StationaryConfig {
+        &self.config
+    }
+
+    /// Compare the search region of a new frame with the reference
+    ///
+    /// A changed region (or a moving target) becomes the new reference and
+    /// drops the cached tensors.
+    ///
+    /// # Arguments
+    /// * `image` - New frame
+    /// * `region` - Frame region the next search covers
+    /// * `speed` - Predicted target speed in px/frame
+    ///
+    /// # Returns
+    /// * true when the frame may reuse the previous work
+    pub fn check(&mut self, image: &ArrayView3<u8>, region: [i32; 4], speed: f32) -> bool {
+        let signature = RegionSignature::sample(image, region, self.config.sample_step);
+        let diff = self
+            .reference
+            .as_ref()
+            .and_then(|reference| reference.mean_abs_diff(&signature));
+        self.unchanged = speed <= self.config.max_velocity
+            && self.frames < self.config.max_frames
+            && diff.is_some_and(|diff| diff <= self.config.max_diff);
+
+        if self.unchanged {
+            self.frames += 1;
+        } else {
+            self.reference = Some(signature);
+            self.tensors.clear();
+            self.frames = 0;
+        }
+        self.unchanged
+    }
+
+    /// Search tensor and crop size kept for a crop, if the scene is unchanged
+    pub fn tensor(&self, rect: [i32; 4], max_crop: Option<i32>) -> Option<(&InputTensor, i32)> {
+        if !self.unchanged {
+            return None;
+        }
+        self.tensors
+            .iter()
+            .find(|cached| cached.key == (rect, max_crop))
+            .map(|cached| (&cached.tensor, cached.crop_size))
+    }
+
+    /// Keep the search tensor of a crop of the reference frame
+    pub fn store(
+        &mut self,
+        rect: [i32; 4],
+        max_crop: Option<i32>,
+        tensor: &InputTensor,
+        crop_size: i32,
+    ) {
+        if self.tensors.iter().all(|cached| cached.key != (rect, max_crop)) {
+            self.tensors.push(CachedTensor {
+                key: (rect, max_crop),
+                tensor: tensor.clone(),
+                crop_size,
+            });
+        }
+    }
+
+    /// Bytes held by the cached tensors and the reference sample
+    pub fn memory_bytes(&self) -> usize {
+        self.tensors.iter().map(|cached| cached.tensor.byte_len()).sum::<usize>()
+            + self.reference.as_ref().map_or(0, |reference| reference.samples.len())
+    }
+
+    /// Forget the reference, e.g. after `init`
+    pub fn reset(&mut self) {
+        self.reference = None;
+        self.tensors.clear();
+        self.frames = 0;
+        self.unchanged = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_unchanged_until_scene_changes() {
+        let mut frame = Array3::from_shape_fn((120, 160, 3), |(y, x, c)| (x + 2 * y + c) as u8);
+        let region = [40, 30, 64, 64];
+        let mut cache = StationaryCache::new(StationaryConfig::default());
+
+        // The first frame only sets the reference
+        assert!(!cache.check(&frame.view(), region, 0.0));
+        assert!(cache.check(&frame.view(), region, 0.0));
+        assert!(!cache.check(&frame.view(), region, 3.0));
+        assert!(cache.check(&frame.view(), region, 0.0));
+
+        // Something drives into the region
+        frame.slice_mut(ndarray::s![40..80, 50..90, ..]).fill(255);
+        assert!(!cache.check(&frame.view(), region, 0.0));
+        assert!(cache.check(&frame.view(), region, 0.0));
+        assert!(!cache.check(&frame.view(), [0, 0, 64, 64], 0.0));
+    }
+}
@@ -0,0 +1,262 @@
+use std::fmt;
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Capture source that can be (re)opened
+pub trait FrameSource {
+    type Frame;
+
+    /// Open or reopen the source, releasing any previous connection
+    fn open(&mut self) -> io::Result<()>;
+
+    /// Read the next frame; Ok(None) when no frame is available right now
+    fn read(&mut self) -> io::Result<Option<Self::Frame>>;
+}
+
+/// Health change of a monitored source
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// No frame within the timeout (stalled, unplugged or ended)
+    Stalled { since: Duration },
+    /// Read failed
+    Error { message: String },
+    /// Reopen attempt failed; retried after `retry_in`
+    ReconnectFailed {
+        attempt: u32,
+        message: String,
+        retry_in: Duration,
+    },
+    /// Frames are flowing again
+    Reconnected { attempt: u32, downtime: Duration },
+}
+
+impl fmt::Display for StreamEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stalled { since } => write!(f, "no frame for {:.1} s", since.as_secs_f32()),
+            Self::Error { message } => write!(f, "read error: {}", message),
+            Self::ReconnectFailed {
+                attempt,
+                message,
+                retry_in,
+            } => write!(
+                f,
+                "reconnect attempt {} failed ({}), retrying in {:.1} s",
+                attempt,
+                message,
+                retry_in.as_secs_f32()
+            ),
+            Self::Reconnected { attempt, downtime } => write!(
+                f,
+                "reconnected after {} attempt(s), down {:.1} s",
+                attempt,
+                downtime.as_secs_f32()
+            ),
+        }
+    }
+}
+
+/// Receiver of stream events (closures implement it)
+pub trait StreamEventSink {
+    fn on_event(&mut self, event: &StreamEvent);
+}
+
+impl<F: FnMut(&StreamEvent)> StreamEventSink for F {
+    fn on_event(&mut self, event: &StreamEvent) {
+        self(event)
+    }
+}
+
+/// Reconnect policy
+#[derive(Debug, Clone, Copy)]
+pub struct ReconnectConfig {
+    /// Source is considered stalled without a frame for this long
+    pub frame_timeout: Duration,
+    /// Delay before the first retry, doubled after each failure
+    pub retry_delay: Duration,
+    /// Upper bound of the retry delay
+    pub max_retry_delay: Duration,
+    /// Give up after this many failed reopen attempts (None retries forever)
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            frame_timeout: Duration::from_secs(2),
+            retry_delay: Duration::from_millis(500),
+            max_retry_delay: Duration::from_secs(10),
+            max_attempts: None,
+        }
+    }
+}
+
+/// Source wrapper that detects stalls and reopens the source
+///
+/// `next_frame` blocks until a frame arrives, reconnecting as needed, so a
+/// capture loop survives camera unplug/replug and RTSP drops.
+pub struct ReconnectingSource<S: FrameSource> {
+    source: S,
+    config: ReconnectConfig,
+    sinks: Vec<Box<dyn StreamEventSink>>,
+    last_frame: Instant,
+}
+
+impl<S: FrameSource> ReconnectingSource<S> {
+    /// Wrap an already opened source
+    pub fn new(source: S, config: ReconnectConfig) -> Self {
+        Self {
+            source,
+            config,
+            sinks: Vec::new(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    pub fn add_sink<K: StreamEventSink + 'static>(&mut self, sink: K) {
+        self.sinks.push(Box::new(sink));
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Next frame, reconnecting while the source is stalled or failing
+    ///
+    /// # Returns
+    /// * Error only when `max_attempts` reopen attempts failed
+    pub fn next_frame(&mut self) -> io::Result<S::Frame> {
+        loop {
+            match self.source.read() {
+                Ok(Some(frame)) => {
+                    self.last_frame = Instant::now();
+                    return Ok(frame);
+                }
+                Ok(None) => {
+                    let since = self.last_frame.elapsed();
+                    if since < self.config.frame_timeout {
+                        thread::sleep(Duration::from_millis(5));
+                        continue;
+                    }
+                    self.emit(&StreamEvent::Stalled { since });
+                }
+                Err(e) => self.emit(&StreamEvent::Error {
+                    message: e.to_string(),
+                }),
+            }
+            self.reconnect()?;
+        }
+    }
+
+    fn reconnect(&mut self) -> io::Result<()> {
+        let down_since = self.last_frame;
+        let mut delay = self.config.retry_delay;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match self.source.open() {
+                Ok(()) => {
+                    // Restart the stall timer so the reopened source gets a full timeout
+                    self.last_frame = Instant::now();
+                    self.emit(&StreamEvent::Reconnected {
+                        attempt,
+                        downtime: down_since.elapsed(),
+                    });
+                    return Ok(());
+                }
+                Err(e) => {
+                    if self.config.max_attempts.is_some_and(|max| attempt >= max) {
+                        return Err(e);
+                    }
+                    self.emit(&StreamEvent::ReconnectFailed {
+                        attempt,
+                        message: e.to_string(),
+                        retry_in: delay,
+                    });
+                    thread::sleep(delay);
+                    delay = (delay * 2).min(self.config.max_retry_delay);
+                }
+            }
+        }
+    }
+
+    fn emit(&mut self, event: &StreamEvent) {
+        for sink in &mut self.sinks {
+            sink.on_event(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Yields `frames` frames, then fails until reopened `failed_opens` + 1 times
+    struct FlakySource {
+        frames: u32,
+        failed_opens: u32,
+    }
+
+    impl FrameSource for FlakySource {
+        type Frame = u32;
+
+        fn open(&mut self) -> io::Result<()> {
+            if self.failed_opens > 0 {
+                self.failed_opens -= 1;
+                return Err(io::Error::new(io::ErrorKind::NotFound, "unplugged"));
+            }
+            self.frames = 1;
+            Ok(())
+        }
+
+        fn read(&mut self) -> io::Result<Option<u32>> {
+            if self.frames == 0 {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "gone"));
+            }
+            self.frames -= 1;
+            Ok(Some(self.frames))
+        }
+    }
+
+    #[test]
+    fn test_reconnects_after_failure() {
+        let config = ReconnectConfig {
+            retry_delay: Duration::from_millis(1),
+            ..ReconnectConfig::default()
+        };
+        let source = FlakySource {
+            frames: 1,
+            failed_opens: 2,
+        };
+        let mut stream = ReconnectingSource::new(source, config);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+        stream.add_sink(move |event: &StreamEvent| log.lock().unwrap().push(event.clone()));
+
+        assert_eq!(stream.next_frame().unwrap(), 0);
+        assert_eq!(stream.next_frame().unwrap(), 0);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 4);
+        assert!(matches!(events[0], StreamEvent::Error { .. }));
+        assert!(matches!(events[2], StreamEvent::ReconnectFailed { attempt: 2, .. }));
+        assert!(matches!(events[3], StreamEvent::Reconnected { attempt: 3, .. }));
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let config = ReconnectConfig {
+            retry_delay: Duration::from_millis(1),
+            max_attempts: Some(2),
+            ..ReconnectConfig::default()
+        };
+        let source = FlakySource {
+            frames: 0,
+            failed_opens: 5,
+        };
+        let mut stream = ReconnectingSource::new(source, config);
+        assert!(stream.next_frame().is_err());
+    }
+}
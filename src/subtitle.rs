@@ -0,0 +1,186 @@
+//! Tracking data as a subtitle sidecar
+//!
+//! Writes one cue per frame with the box and score, as SubRip (`.srt`) or
+//! WebVTT (`.vtt`). Saved next to the recording under the same name, most
+//! players show the tracking data over the original video without
+//! re-encoding it.
+//!
+//! ```text
+//! 1
+//! 00:00:00,000 --> 00:00:00,033
+//! score 0.82 [412 220 64 48]
+//! ```
+//!
+//! Cue times come from `TrackingResult::timestamp` relative to the first
+//! frame, or from the frame rate when the frames carry no timestamp.
+
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::postprocess::TrackingResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    WebVtt,
+}
+
+impl SubtitleFormat {
+    /// `.vtt` is WebVTT, anything else SubRip
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("vtt") => SubtitleFormat::WebVtt,
+            _ => SubtitleFormat::Srt,
+        }
+    }
+}
+
+/// Streams tracking results into subtitle cues
+///
+/// A cue lasts until the next frame, so each cue is written when the next
+/// result arrives; `finish` writes the last one.
+pub struct SubtitleWriter<W: Write> {
+    out: W,
+    format: SubtitleFormat,
+    frame_interval: Duration,
+    /// Timestamp of the first frame, the zero of the cue times
+    origin: Option<Duration>,
+    pending: Option<(Duration, TrackingResult)>,
+    frames: u32,
+    cues: usize,
+}
+
+impl SubtitleWriter<BufWriter<fs::File>> {
+    /// Create a sidecar file, in the format given by its extension
+    ///
+    /// # Arguments
+    /// * `path` - `.srt` or `.vtt` file
+    /// * `fps` - Frame rate used when the frames carry no timestamp
+    pub fn create<P: AsRef<Path>>(path: P, fps: f32) -> io::Result<Self> {
+        let format = SubtitleFormat::from_path(&path);
+        Self::new(BufWriter::new(fs::File::create(path)?), format, fps)
+    }
+}
+
+impl<W: Write> SubtitleWriter<W> {
+    pub fn new(mut out: W, format: SubtitleFormat, fps: f32) -> io::Result<Self> {
+        if format == SubtitleFormat::WebVtt {
+            writeln!(out, "WEBVTT\n")?;
+        }
+        Ok(Self {
+            out,
+            format,
+            frame_interval: Duration::from_secs_f32(1.0 / fps.max(1.0)),
+            origin: None,
+            pending: None,
+            frames: 0,
+            cues: 0,
+        })
+    }
+
+    /// Add the result of the next frame
+    pub fn push(&mut self, result: &TrackingResult) -> io::Result<()> {
+        let start = match result.timestamp {
+            Some(timestamp) => timestamp.saturating_sub(*self.origin.get_or_insert(timestamp)),
+            None => self.frame_interval * self.frames,
+        };
+        self.frames += 1;
+        if let Some((previous, pending)) = self.pending.take() {
+            // Out-of-order timestamps still give the cue a visible length
+            let end = start.max(previous + Duration::from_millis(1));
+            self.write_cue(previous, end, &pending)?;
+        }
+        self.pending = Some((start, *result));
+        Ok(())
+    }
+
+    /// Write the last cue and flush
+    ///
+    /// # Returns
+    /// * The underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some((start, pending)) = self.pending.take() {
+            self.write_cue(start, start + self.frame_interval, &pending)?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn write_cue(
+        &mut self,
+        start: Duration,
+        end: Duration,
+        result: &TrackingResult,
+    ) -> io::Result<()> {
+        self.cues += 1;
+        let [x, y, w, h] = result.bbox;
+        let state = match (result.success, result.coasting) {
+            (true, _) => "score",
+            (false, true) => "coasting, score",
+            (false, false) => "lost, score",
+        };
+        writeln!(
+            self.out,
+            "{}\n{} --> {}\n{} {:.2} [{} {} {} {}]\n",
+            self.cues,
+            self.time(start),
+            self.time(end),
+            state,
+            result.score,
+            x,
+            y,
+            w,
+            h
+        )
+    }
+
+    /// `HH:MM:SS,mmm` for SubRip, `HH:MM:SS.mmm` for WebVTT
+    fn time(&self, time: Duration) -> String {
+        let millis = time.as_millis();
+        let separator = match self.format {
+            SubtitleFormat::Srt => ',',
+            SubtitleFormat::WebVtt => '.',
+        };
+        format!(
+            "{:02}:{:02}:{:02}{}{:03}",
+            millis / 3_600_000,
+            millis / 60_000 % 60,
+            millis / 1000 % 60,
+            separator,
+            millis % 1000
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cues_follow_timestamps() {
+        let mut writer = SubtitleWriter::new(Vec::new(), SubtitleFormat::WebVtt, 30.0).unwrap();
+        let tracking = TrackingResult {
+            success: true,
+            bbox: [412, 220, 64, 48],
+            score: 0.82,
+            timestamp: Some(Duration::from_millis(61_000)),
+            ..TrackingResult::default()
+        };
+        let lost = TrackingResult {
+            success: false,
+            score: 0.1,
+            timestamp: Some(Duration::from_millis(61_500)),
+            ..tracking
+        };
+        writer.push(&tracking).unwrap();
+        writer.push(&lost).unwrap();
+        let text = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        assert!(text.starts_with("WEBVTT\n\n1\n00:00:00.000 --> 00:00:00.500\n"));
+        assert!(text.contains("score 0.82 [412 220 64 48]"));
+        assert!(text.contains("2\n00:00:00.500 --> 00:00:00.533\nlost, score 0.10"));
+        assert_eq!(SubtitleFormat::from_path("run.SRT"), SubtitleFormat::Srt);
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+
+use crate::preprocess::InputTensor;
+
+/// Template update and drift rollback configuration
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateUpdateConfig {
+    /// Minimum number of frames between template updates
+    pub interval: usize,
+    /// Only update on frames scoring at least this much
+    pub min_score: f32,
+    /// Number of earlier templates kept for rollback
+    pub history: usize,
+    /// Frames after an update during which drift is checked
+    pub drift_window: usize,
+    /// A frame is degraded when its score falls below this fraction of the update score
+    pub drift_drop: f32,
+    /// Degraded frames within the window that trigger a rollback
+    pub drift_frames: usize,
+    /// How a newly captured template replaces the current one
+    pub strategy: TemplateUpdateStrategy,
+}
+
+/// How template updates combine the new crop with the current template
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TemplateUpdateStrategy {
+    /// The new crop becomes the template
+    #[default]
+    Replace,
+    /// The template becomes a running average of the crops; `rate` is the
+    /// weight of the new crop. Slower to adapt, but a single bad crop cannot
+    /// take over the template
+    Blend { rate: f32 },
+}
+
+impl Default for TemplateUpdateConfig {
+    fn default() -> Self {
+        Self {
+            interval: 30,
+            min_score: 0.6,
+            history: 3,
+            drift_window: 10,
+            drift_drop: 0.5,
+            drift_frames: 3,
+            strategy: TemplateUpdateStrategy::Replace,
+        }
+    }
+}
+
+impl TemplateUpdateStrategy {
+    /// Template that results from capturing `captured`
+    pub fn apply(&self, current: Option<&InputTensor>, captured: InputTensor) -> InputTensor {
+        match (self, current) {
+            (Self::Blend { rate }, Some(current)) => {
+                current.blend(&captured, rate.clamp(0.0, 1.0)).unwrap_or(captured)
+            }
+            _ => captured,
+        }
+    }
+}
+
+/// Template update applied during `VitTrack::update`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateEvent {
+    /// A new template was captured from the current frame
+    Updated,
+    /// Drift was detected and an earlier template restored
+    RolledBack,
+}
+
+/// Stored template with the metrics it was captured with
+#[derive(Debug, Clone)]
+pub struct TemplateSnapshot {
+    pub template: InputTensor,
+    /// Tracking score of the frame the template was cropped from
+    pub score: f32,
+    /// Template quality score at capture (see `quality::template_quality`)
+    pub quality: f32,
+}
+
+/// Recent templates and the drift check after each update
+#[derive(Debug, Clone)]
+pub struct TemplateHistory {
+    config: TemplateUpdateConfig,
+    snapshots: VecDeque<TemplateSnapshot>,
+    frames_since_update: usize,
+    degraded_frames: usize,
+}
+
+impl TemplateHistory {
+    pub fn new(config: TemplateUpdateConfig) -> Self {
+        Self {
+            config,
+            snapshots: VecDeque::with_capacity(config.history + 1),
+            frames_since_update: 0,
+            degraded_frames: 0,
+        }
+    }
+
+    /// Start over from the initial template
+    pub fn reset(&mut self, initial: TemplateSnapshot) {
+        self.snapshots.clear();
+        self.snapshots.push_back(initial);
+        self.frames_since_update = 0;
+        self.degraded_frames = 0;
+    }
+
+    /// Whether a new template should be captured on this frame
+    pub fn should_update(&self, success: bool, score: f32) -> bool {
+        success
+            && score >= self.config.min_score
+            && self.frames_since_update >= self.config.interval
+            && self.degraded_frames == 0
+    }
+
+    /// Store a newly captured template, dropping the oldest beyond the history
+    pub fn push(&mut self, snapshot: TemplateSnapshot) {
+        if self.snapshots.len() > self.config.history {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+        self.frames_since_update = 0;
+        self.degraded_frames = 0;
+    }
+
+    /// Record a frame tracked with the current template
+    ///
+    /// # Returns
+    /// * Template to restore when the frame confirms drift after an update
+    pub fn observe(&mut self, success: bool, score: f32) -> Option<&InputTensor> {
+        self.frames_since_update += 1;
+        if self.snapshots.len() < 2 || self.frames_since_update > self.config.drift_window {
+            return None;
+        }
+
+        let reference = self.snapshots.back()?.score;
+        if !success || score < reference * self.config.drift_drop {
+            self.degraded_frames += 1;
+        }
+        if self.degraded_frames < self.config.drift_frames.max(1) {
+            return None;
+        }
+
+        self.snapshots.pop_back();
+        // Do not immediately re-capture the template that just drifted
+        self.frames_since_update = 0;
+        self.degraded_frames = 0;
+        self.snapshots.back().map(|snapshot| &snapshot.template)
+    }
+
+    pub fn strategy(&self) -> TemplateUpdateStrategy {
+        self.config.strategy
+    }
+
+    /// Currently stored snapshots, oldest first
+    pub fn snapshots(&self) -> impl Iterator<Item = &TemplateSnapshot> {
+        self.snapshots.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(id: f32, score: f32) -> TemplateSnapshot {
+        TemplateSnapshot {
+            template: InputTensor::Float32(vec![id]),
+            score,
+            quality: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_rollback_after_drift() {
+        let mut history = TemplateHistory::new(TemplateUpdateConfig {
+            interval: 2,
+            history: 2,
+            drift_window: 5,
+            drift_frames: 2,
+            ..Default::default()
+        });
+        history.reset(snapshot(0.0, 1.0));
+
+        // No rollback target before the first update
+        for _ in 0..2 {
+            assert!(history.observe(false, 0.0).is_none());
+        }
+        assert!(history.should_update(true, 0.8));
+        history.push(snapshot(1.0, 0.8));
+        assert!(!history.should_update(true, 0.8));
+
+        // Collapse right after the update restores the initial template
+        assert!(history.observe(true, 0.7).is_none());
+        assert!(history.observe(true, 0.2).is_none());
+        let restored = history.observe(false, 0.0).cloned();
+        assert!(matches!(restored, Some(InputTensor::Float32(v)) if v == [0.0]));
+        assert_eq!(history.snapshots().count(), 1);
+    }
+}
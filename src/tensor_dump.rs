@@ -0,0 +1,223 @@
+//! Raw NPU input capture for offline model debugging
+//!
+//! Keeps the last template/search pairs exactly as passed to `inputs_set` and
+//! writes them as `.npy` (NHWC, in the model input type) when a dump is
+//! triggered. Replaying the same inputs through the ONNX model off-device
+//! separates quantization error from preprocessing and tracking logic.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::preprocess::InputTensor;
+
+/// Tensor dump configuration
+#[derive(Debug, Clone)]
+pub struct TensorDumpConfig {
+    /// Directory receiving `dump_000`, `dump_001`, ...
+    pub dir: PathBuf,
+    /// Inferences kept before a trigger (multi-scale search runs several per frame)
+    pub inferences: usize,
+    /// Dump on the tracking -> lost transition
+    pub on_loss: bool,
+    /// Also dump every n-th frame
+    pub every_nth: Option<u64>,
+    /// Dump directories kept; the oldest is overwritten
+    pub max_dumps: usize,
+}
+
+impl Default for TensorDumpConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("tensors"),
+            inferences: 8,
+            on_loss: true,
+            every_nth: None,
+            max_dumps: 10,
+        }
+    }
+}
+
+struct Recorded {
+    frame: u64,
+    index: usize,
+    template: InputTensor,
+    search: InputTensor,
+}
+
+/// Ring buffer of recent NPU inputs, written out on loss or on request
+///
+/// Each dump directory holds `frame_FFFFFF_K_template.npy` and
+/// `frame_FFFFFF_K_search.npy` for the K-th inference of frame F.
+pub struct TensorRecorder {
+    config: TensorDumpConfig,
+    template_shape: [usize; 4],
+    search_shape: [usize; 4],
+    recorded: VecDeque<Recorded>,
+    frame: u64,
+    frame_inferences: usize,
+    was_tracking: bool,
+    requested: bool,
+    written: usize,
+    last_dump: Option<io::Result<PathBuf>>,
+}
+
+impl TensorRecorder {
+    /// # Arguments
+    /// * `template_size` - Template input side in pixels
+    /// * `search_size` - Search input side in pixels
+    pub fn new(config: TensorDumpConfig, template_size: usize, search_size: usize) -> Self {
+        Self {
+            recorded: VecDeque::with_capacity(config.inferences.max(1)),
+            config,
+            template_shape: [1, template_size, template_size, 3],
+            search_shape: [1, search_size, search_size, 3],
+            frame: 0,
+            frame_inferences: 0,
+            was_tracking: false,
+            requested: false,
+            written: 0,
+            last_dump: None,
+        }
+    }
+
+    /// Keep the inputs of one inference of the current frame
+    pub fn record(&mut self, template: &InputTensor, search: &InputTensor) {
+        if self.recorded.len() == self.config.inferences.max(1) {
+            self.recorded.pop_front();
+        }
+        self.recorded.push_back(Recorded {
+            frame: self.frame,
+            index: self.frame_inferences,
+            template: template.clone(),
+            search: search.clone(),
+        });
+        self.frame_inferences += 1;
+    }
+
+    /// Bytes held by the recorded tensors
+    pub fn memory_bytes(&self) -> usize {
+        self.recorded
+            .iter()
+            .map(|recorded| recorded.template.byte_len() + recorded.search.byte_len())
+            .sum()
+    }
+
+    /// Dump at the end of the current frame
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    /// Close the current frame, writing a dump if one was triggered
+    ///
+    /// # Returns
+    /// * Directory of the dump written on this frame, if any
+    pub fn end_frame(&mut self, success: bool) -> io::Result<Option<PathBuf>> {
+        let lost = self.config.on_loss && self.was_tracking && !success;
+        let periodic = self.config.every_nth.is_some_and(|n| self.frame.is_multiple_of(n.max(1)));
+        let trigger = lost || periodic || self.requested;
+        self.was_tracking = success;
+        self.requested = false;
+        self.frame += 1;
+        self.frame_inferences = 0;
+        if !trigger || self.recorded.is_empty() {
+            return Ok(None);
+        }
+
+        let slot = self.written % self.config.max_dumps.max(1);
+        self.written += 1;
+        let dir = self.config.dir.join(format!("dump_{:03}", slot));
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        for recorded in &self.recorded {
+            let name = format!("frame_{:06}_{}", recorded.frame, recorded.index);
+            let template = dir.join(format!("{}_template.npy", name));
+            write_npy(&template, &recorded.template, &self.template_shape)?;
+            let search = dir.join(format!("{}_search.npy", name));
+            write_npy(&search, &recorded.search, &self.search_shape)?;
+        }
+        Ok(Some(dir))
+    }
+
+    /// Store the outcome of `end_frame` for `take_last_dump`
+    #[cfg(feature = "rknn")]
+    pub(crate) fn end_frame_logged(&mut self, success: bool) {
+        match self.end_frame(success) {
+            Ok(Some(dir)) => self.last_dump = Some(Ok(dir)),
+            Ok(None) => {}
+            Err(e) => self.last_dump = Some(Err(e)),
+        }
+    }
+
+    /// Directory (or error) of the latest dump not yet taken
+    pub fn take_last_dump(&mut self) -> Option<io::Result<PathBuf>> {
+        self.last_dump.take()
+    }
+}
+
+/// Write a tensor as a NumPy `.npy` file (format 1.0, C order)
+pub fn write_npy(path: &Path, tensor: &InputTensor, shape: &[usize]) -> io::Result<()> {
+    let (descr, data): (&str, Vec<u8>) = match tensor {
+        InputTensor::Float32(v) => ("<f4", v.iter().flat_map(|x| x.to_le_bytes()).collect()),
+        InputTensor::Float16(v) => ("<f2", v.iter().flat_map(|x| x.to_le_bytes()).collect()),
+        InputTensor::Uint8(v) => ("|u1", v.clone()),
+        InputTensor::Int8(v) => ("|i1", v.iter().map(|&x| x as u8).collect()),
+    };
+    let dims: Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+    let trailing = if dims.len() == 1 { "," } else { "" };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': ({}{}), }}",
+        descr,
+        dims.join(", "),
+        trailing
+    );
+    // Magic, version and length take 10 bytes; pad the total to 64 bytes
+    let padding = (64 - (10 + header.len() + 1) % 64) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    let mut file = BufWriter::new(fs::File::create(path)?);
+    file.write_all(b"\x93NUMPY\x01\x00")?;
+    file.write_all(&(header.len() as u16).to_le_bytes())?;
+    file.write_all(header.as_bytes())?;
+    file.write_all(&data)?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump_on_loss() {
+        let dir = std::env::temp_dir().join(format!("vit_tensors_{}", std::process::id()));
+        let config = TensorDumpConfig {
+            dir: dir.clone(),
+            inferences: 2,
+            ..TensorDumpConfig::default()
+        };
+        let mut recorder = TensorRecorder::new(config, 2, 4);
+        let template = InputTensor::Int8(vec![-1; 12]);
+        let search = InputTensor::Int8(vec![1; 48]);
+
+        for success in [true, true] {
+            recorder.record(&template, &search);
+            assert!(recorder.end_frame(success).unwrap().is_none());
+        }
+        recorder.record(&template, &search);
+        let dump = recorder.end_frame(false).unwrap().expect("dump on loss");
+        assert!(!dump.join("frame_000000_0_search.npy").exists());
+        let bytes = fs::read(dump.join("frame_000002_0_search.npy")).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(&bytes[..6], b"\x93NUMPY");
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+        assert!(header.contains("'descr': '|i1'") && header.contains("(1, 4, 4, 3)"));
+        assert_eq!(bytes.len(), 10 + header_len + 48);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+
+/// Adaptive threshold configuration
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveThresholdConfig {
+    /// Number of recent successful scores kept
+    pub window: usize,
+    /// Threshold is mean - k * std of the window
+    pub k: f32,
+    /// Lower bound of the adaptive threshold
+    pub min_threshold: f32,
+    /// Scores required before adapting (fixed threshold is used until then)
+    pub min_samples: usize,
+}
+
+impl Default for AdaptiveThresholdConfig {
+    fn default() -> Self {
+        Self {
+            window: 60,
+            k: 3.0,
+            min_threshold: 0.1,
+            min_samples: 10,
+        }
+    }
+}
+
+/// Success threshold derived from the running score distribution
+#[derive(Debug, Clone)]
+pub struct AdaptiveThreshold {
+    config: AdaptiveThresholdConfig,
+    scores: VecDeque<f32>,
+}
+
+impl AdaptiveThreshold {
+    pub fn new(config: AdaptiveThresholdConfig) -> Self {
+        Self {
+            config,
+            scores: VecDeque::with_capacity(config.window),
+        }
+    }
+
+    /// Current threshold, falling back to `fixed` until enough scores are seen
+    pub fn threshold(&self, fixed: f32) -> f32 {
+        if self.scores.len() < self.config.min_samples.max(1) {
+            return fixed;
+        }
+
+        let n = self.scores.len() as f32;
+        let mean = self.scores.iter().sum::<f32>() / n;
+        let var = self.scores.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / n;
+
+        (mean - self.config.k * var.sqrt()).max(self.config.min_threshold)
+    }
+
+    /// Add the score of a successful frame
+    pub fn push(&mut self, score: f32) {
+        if self.scores.len() == self.config.window {
+            self.scores.pop_front();
+        }
+        self.scores.push_back(score);
+    }
+
+    pub fn reset(&mut self) {
+        self.scores.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adapts_to_score_range() {
+        let mut threshold = AdaptiveThreshold::new(AdaptiveThresholdConfig {
+            window: 4,
+            k: 1.0,
+            min_threshold: 0.05,
+            min_samples: 2,
+        });
+        assert_eq!(threshold.threshold(0.25), 0.25);
+
+        for score in [0.8, 0.9, 0.8, 0.9] {
+            threshold.push(score);
+        }
+        assert!((threshold.threshold(0.25) - 0.8).abs() < 1e-5);
+
+        // Low-score scene: threshold follows down to the floor
+        for _ in 0..4 {
+            threshold.push(0.02);
+        }
+        assert_eq!(threshold.threshold(0.25), 0.05);
+    }
+}
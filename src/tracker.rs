@@ -1,8 +1,46 @@
-use ndarray::{ArrayView3};
+use ndarray::{s, Array3, ArrayView3};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::postprocess::{hann2d, process_outputs, TrackingResult};
-use crate::preprocess::{crop_and_preprocess, BBox};
-use crate::rknn::{RknnError, RknnModel};
+use crate::budget::{Degradation, LatencyBudget};
+use crate::camera::CameraIntrinsics;
+use crate::coords::Convention;
+use crate::fallback::{CorrelationTracker, FallbackConfig};
+use crate::histogram::{ColorHistogram, HistogramConfig};
+use crate::label::TrackLabel;
+use crate::mask::{MaskConfig, MaskedResult, TargetMask};
+use crate::motion::{CoastingConfig, KalmanFilter, MotionModel, TeleportGuardConfig};
+use crate::pose::PoseSync;
+use crate::postprocess::{
+    apply_window, cached_window, crop_origin, decode_box, decode_keypoints, process_outputs_with,
+    shift_window, suppress_border, top_peaks, FusedMap, FusionConfig, Keypoint, KeypointConfig,
+    Localization, OcclusionConfig, OcclusionEstimator, OutputLayout, ScoreFusion, ScoreTransform,
+    TrackingResult, WindowSpec,
+};
+use crate::preprocess::{
+    crop_resized, crop_resized_downscaled, crop_resized_u16, crop_size, BBox, InputTensor,
+    InputType, Normalization, SampleRange,
+};
+use crate::preprocessor::{CpuPreprocessor, CropSpec, Preprocessor};
+use crate::quality::{template_quality, TemplateQuality};
+use crate::reload::RuntimeSettings;
+use crate::rknn::{
+    Device, InputBuffers, NpuClient, OutputQuantization, OutputViews, RknnError, RknnModel,
+    VitTrackOutputs,
+};
+use crate::rotation::estimate_rotated_box;
+use crate::stages::NormalizedTensor;
+pub use crate::stages::CropTransform;
+use crate::stationary::{StationaryCache, StationaryConfig};
+use crate::template::{
+    TemplateEvent, TemplateHistory, TemplateSnapshot, TemplateUpdateConfig,
+};
+use crate::tensor_dump::{TensorDumpConfig, TensorRecorder};
+use crate::threshold::{AdaptiveThreshold, AdaptiveThresholdConfig};
+use crate::watchdog::{sweep_rects, LossWatchdog, RedetectionConfig, ReinitAction, ReinitEvent};
+use crate::world::GroundPlane;
 
 /// VitTrack configuration
 #[derive(Debug, Clone)]
@@ -13,6 +51,123 @@ pub struct VitTrackConfig {
     pub template_factor: u32,
     pub search_factor: u32,
     pub score_threshold: f32,
+    /// Optional camera intrinsics for angular target output
+    pub camera: Option<CameraIntrinsics>,
+    /// Optional ground-plane homography for world-coordinate output
+    pub ground_plane: Option<GroundPlane>,
+    /// Estimate target orientation and report a rotated box
+    pub estimate_rotation: bool,
+    /// Also report the box in [0, 1] coordinates relative to the frame
+    pub normalized_output: bool,
+    /// Also report the box in this coordinate convention (`bbox_converted`)
+    pub coordinates: Option<Convention>,
+    /// Scales of the previous box to search at (best response wins)
+    pub search_scales: Vec<f32>,
+    /// Downscale the frame first when the search crop exceeds this size (pixels)
+    pub max_search_crop: Option<i32>,
+    /// Element type of the input tensors (must match the converted model)
+    pub input_type: InputType,
+    /// Quantization of int8 outputs; when set, outputs are dequantized on the CPU
+    pub output_quantization: Option<[OutputQuantization; 3]>,
+    /// Layout of the size and offset maps of the exported model
+    pub output_layout: OutputLayout,
+    /// Names of all model outputs in model order, for exports with auxiliary
+    /// heads (see `RknnModel::with_output_names`); read them with `output`
+    pub output_names: Option<Vec<String>>,
+    /// Decode the target mask from a segmentation head listed in
+    /// `output_names`; read it with `mask` or `update_masked`
+    pub mask: Option<MaskConfig>,
+    /// Decode target keypoints from a keypoint head listed in `output_names`;
+    /// read them with `keypoints`
+    pub keypoints: Option<KeypointConfig>,
+    /// Per-frame latency budget; when exceeded the pipeline degrades gracefully
+    pub latency_budget: Option<Duration>,
+    /// Faster model with the same inputs/outputs, used as a budget degradation step
+    pub fast_model_path: Option<PathBuf>,
+    /// Template encoder of a two-model export (NPU only)
+    ///
+    /// The encoder runs once per template; its output replaces the template
+    /// input of the main (search + fusion) model, so each frame only runs the
+    /// search branch. Inputs are switched to float32.
+    pub template_encoder_path: Option<PathBuf>,
+    /// Track with a CPU correlation filter when inference fails (and, with
+    /// `on_budget_skip`, on frames the budget skips); such results are
+    /// flagged `Degradation::Fallback`
+    pub fallback: Option<FallbackConfig>,
+    /// Trigger recovery after the target has been lost for this long
+    pub lost_timeout: Option<Duration>,
+    /// Recovery action taken when `lost_timeout` expires
+    pub reinit_action: ReinitAction,
+    /// Most search crops one global sweep runs; larger frames are swept on a
+    /// coarser, downscaled grid and the best hit is refined at full size
+    pub sweep_max_tiles: usize,
+    /// Search a growing region for a few frames right after a loss
+    pub redetection: Option<RedetectionConfig>,
+    /// Derive the success threshold from recent scores instead of `score_threshold`
+    pub adaptive_threshold: Option<AdaptiveThresholdConfig>,
+    /// Weight of the newest score in `TrackingResult::score_smoothed`
+    /// (exponential moving average; 1 = no smoothing)
+    pub score_smoothing: Option<f32>,
+    /// Coast on the motion model through short occlusions
+    pub coasting: Option<CoastingConfig>,
+    /// Estimate occlusion from the peak sharpness (APCE) and score; while
+    /// occluded, template updates pause and the search widens
+    pub occlusion: Option<OcclusionConfig>,
+    /// Filter the reported box with a motion model
+    pub motion_model: MotionModel,
+    /// Reject peaks implausibly far from the predicted position
+    pub teleport_guard: Option<TeleportGuardConfig>,
+    /// Fuse the confidence map with a decayed history before peak picking
+    pub score_fusion: Option<FusionConfig>,
+    /// Check re-detection candidates against the initial target's color histogram
+    pub redetect_histogram: Option<HistogramConfig>,
+    /// Periodically refresh the template, rolling back when drift follows an update
+    pub template_update: Option<TemplateUpdateConfig>,
+    /// How the target position is decoded from the score map
+    pub localization: Localization,
+    /// Mapping of the raw confidence output to scores, before windowing
+    pub score_transform: ScoreTransform,
+    /// Outer rings of the score map zeroed before peak picking; border peaks
+    /// are mostly partially visible distractors entering the crop
+    pub score_border: usize,
+    /// Number of recent frames kept for `VitTrack::score_history`
+    pub score_history_len: usize,
+    /// Sample every n-th pixel when cropping (2 treats a 4K frame as 1080p);
+    /// results stay in full-resolution coordinates
+    pub frame_downsample: usize,
+    /// Per-crop contrast stretch before the model mean/std
+    pub normalization: Normalization,
+    /// Undistort template and search crops through the `camera` lens model;
+    /// ignores `frame_downsample` and `max_search_crop`
+    pub undistort_crops: bool,
+    /// Crop + resize + normalize stage for the search crops
+    pub preprocessor: Arc<dyn Preprocessor>,
+    /// Shift the search crop to stay inside the frame near the borders,
+    /// moving the cosine window with the target instead of zero padding
+    pub border_keep_out: bool,
+    /// Record the raw NPU inputs and dump them as .npy around loss events
+    pub tensor_dump: Option<TensorDumpConfig>,
+    /// Keep a crop of the last frame scoring at least this, used by
+    /// `VitTrack::swap_model` to regenerate the template
+    pub keyframe_score: Option<f32>,
+    /// Inference device; `Auto` falls back to the CPU backend without an NPU
+    pub device: Device,
+    /// Take NPU turns through a scheduler shared with other models
+    pub npu_client: Option<NpuClient>,
+    /// Reproducible runs: no latency budget or adaptive threshold, the loss
+    /// watchdog follows the frame timestamps (or 30 FPS) instead of the wall
+    /// clock, and `PipelinedTracker` never drops frames
+    pub deterministic: bool,
+    /// Expected frames per second; the window influence, teleport guard and
+    /// Kalman process noise are tuned at `REFERENCE_FRAME_RATE` and scaled to
+    /// this rate. None keeps them as configured
+    pub frame_rate: Option<f32>,
+    /// Attach the platform pose from an IMU/GPS log to every timestamped
+    /// result and, with `camera`, geolocate the target
+    pub pose_sync: Option<PoseSync>,
+    /// Reuse the search tensors, or the whole result, while the target is
+    /// stationary and its search region unchanged
+    pub stationary: Option<StationaryConfig>,
 }
 
 impl Default for VitTrackConfig {
@@ -24,19 +179,440 @@ impl Default for VitTrackConfig {
             template_factor: 2,
             search_factor: 4,
             score_threshold: 0.25,
+            camera: None,
+            ground_plane: None,
+            estimate_rotation: false,
+            normalized_output: false,
+            coordinates: None,
+            search_scales: vec![1.0],
+            max_search_crop: None,
+            input_type: InputType::Float32,
+            output_quantization: None,
+            output_layout: OutputLayout::Nchw,
+            output_names: None,
+            mask: None,
+            keypoints: None,
+            latency_budget: None,
+            fast_model_path: None,
+            template_encoder_path: None,
+            fallback: None,
+            lost_timeout: None,
+            reinit_action: ReinitAction::GlobalSweep,
+            sweep_max_tiles: 16,
+            redetection: None,
+            adaptive_threshold: None,
+            score_smoothing: None,
+            coasting: None,
+            occlusion: None,
+            motion_model: MotionModel::None,
+            teleport_guard: None,
+            score_fusion: None,
+            redetect_histogram: None,
+            template_update: None,
+            localization: Localization::Argmax,
+            score_transform: ScoreTransform::None,
+            score_border: 0,
+            score_history_len: 256,
+            frame_downsample: 1,
+            normalization: Normalization::Fixed,
+            undistort_crops: false,
+            preprocessor: Arc::new(CpuPreprocessor),
+            border_keep_out: false,
+            tensor_dump: None,
+            keyframe_score: Some(0.5),
+            device: Device::Auto,
+            npu_client: None,
+            deterministic: false,
+            frame_rate: None,
+            pose_sync: None,
+            stationary: None,
         }
     }
 }
 
-/// VitTrack tracker using RKNN
-pub struct VitTrack {
+/// Frame rate the motion-related defaults are tuned for
+pub const REFERENCE_FRAME_RATE: f32 = 30.0;
+
+impl VitTrackConfig {
+    /// Latency budget of a tracker; none in deterministic mode, where
+    /// wall-clock degradation would make runs differ
+    fn build_budget(&self, fast_model: bool) -> Option<LatencyBudget> {
+        self.latency_budget
+            .filter(|_| !self.deterministic)
+            .map(|budget| LatencyBudget::new(budget, fast_model))
+    }
+
+    /// Adaptive threshold of a tracker; none in deterministic mode
+    fn build_threshold(&self) -> Option<AdaptiveThreshold> {
+        self.adaptive_threshold
+            .filter(|_| !self.deterministic)
+            .map(AdaptiveThreshold::new)
+    }
+
+    /// Frame interval relative to `REFERENCE_FRAME_RATE` (3 at 10 FPS, 0.5 at 60 FPS)
+    pub fn frame_interval_ratio(&self) -> f32 {
+        self.frame_rate
+            .filter(|&fps| fps > 0.0)
+            .map_or(1.0, |fps| REFERENCE_FRAME_RATE / fps)
+    }
+
+    /// Kalman (process, measurement) noise: from `motion_model`, else from
+    /// `coasting`; None when neither needs a filter
+    pub fn kalman_noise(&self) -> Option<(f32, f32)> {
+        match (self.motion_model, &self.coasting) {
+            (
+                MotionModel::Kalman {
+                    process_noise,
+                    measurement_noise,
+                },
+                _,
+            ) => Some((process_noise, measurement_noise)),
+            (MotionModel::None, Some(coasting)) => {
+                Some((coasting.process_noise, coasting.measurement_noise))
+            }
+            (MotionModel::None, None) => None,
+        }
+    }
+
+    /// Hann window influence scaled to the frame rate
+    ///
+    /// Below the reference rate the target moves further between frames, so
+    /// the window flattens in proportion; it is never sharpened above 1.
+    pub fn window_influence(&self, influence: f32) -> f32 {
+        (influence / self.frame_interval_ratio().max(1.0)).clamp(0.0, 1.0)
+    }
+
+    /// Preset for thermal footage
+    ///
+    /// Stretches each crop between its 2nd and 98th percentile so global AGC
+    /// changes do not shift the scores.
+    pub fn thermal() -> Self {
+        Self {
+            normalization: Normalization::THERMAL,
+            ..Self::default()
+        }
+    }
+}
+
+/// Per-frame score metrics kept by the tracker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreSample {
+    pub success: bool,
+    pub score: f32,
+    /// Second-best peak divided by the best one
+    pub peak_ratio: f32,
+    /// Success threshold in effect for the frame
+    pub threshold: f32,
+}
+
+/// Approximate memory held by a tracker, in bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Model files (weights and graph) of the shared model
+    pub model_bytes: u64,
+    /// NPU memory mapped by the runtime for all contexts in the process
+    /// (see `rknn::npu_mapped_bytes`); `None` on the CPU backend
+    pub npu_bytes: Option<u64>,
+    /// Templates, crops, output and history buffers of this tracker
+    pub buffer_bytes: u64,
+}
+
+impl ResourceUsage {
+    /// Sum of all parts; NPU memory already includes the loaded weights
+    pub fn total(&self) -> u64 {
+        self.npu_bytes.unwrap_or(self.model_bytes) + self.buffer_bytes
+    }
+}
+
+/// Best decoded box of `VitTrack::update_raw`, no threshold applied
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawDetection {
+    pub bbox: [i32; 4], // [x, y, w, h]
+    pub score: f32,
+    /// Second-best peak divided by the best one
+    pub peak_ratio: f32,
+}
+
+/// The part of `VitTrackConfig` a saved track depends on
+///
+/// Sizes, factors, threshold, input type, scales, normalization and the
+/// frame rate behind the window; a track resumed under different values
+/// would match or score differently from the one that was saved.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateConfig {
+    pub template_size: usize,
+    pub search_size: usize,
+    pub score_size: usize,
+    pub template_factor: u32,
+    pub search_factor: u32,
+    pub score_threshold: f32,
+    pub input_type: InputType,
+    pub search_scales: Vec<f32>,
+    pub normalization: Normalization,
+    pub frame_downsample: usize,
+    pub frame_rate: Option<f32>,
+}
+
+impl StateConfig {
+    pub fn from_config(config: &VitTrackConfig) -> Self {
+        Self {
+            template_size: config.template_size,
+            search_size: config.search_size,
+            score_size: config.score_size,
+            template_factor: config.template_factor,
+            search_factor: config.search_factor,
+            score_threshold: config.score_threshold,
+            input_type: config.input_type,
+            search_scales: config.search_scales.clone(),
+            normalization: config.normalization,
+            frame_downsample: config.frame_downsample,
+            frame_rate: config.frame_rate,
+        }
+    }
+
+    /// Names of the fields that differ from `other`
+    pub fn differences(&self, other: &Self) -> Vec<&'static str> {
+        let fields = [
+            ("template_size", self.template_size != other.template_size),
+            ("search_size", self.search_size != other.search_size),
+            ("score_size", self.score_size != other.score_size),
+            ("template_factor", self.template_factor != other.template_factor),
+            ("search_factor", self.search_factor != other.search_factor),
+            ("score_threshold", self.score_threshold != other.score_threshold),
+            ("input_type", self.input_type != other.input_type),
+            ("search_scales", self.search_scales != other.search_scales),
+            ("normalization", self.normalization != other.normalization),
+            ("frame_downsample", self.frame_downsample != other.frame_downsample),
+            ("frame_rate", self.frame_rate != other.frame_rate),
+        ];
+        fields.into_iter().filter(|&(_, differs)| differs).map(|(name, _)| name).collect()
+    }
+}
+
+/// Checkpoint of a track, see `VitTrack::save_state`
+///
+/// With the `serde` feature it can be written to disk and read back after a
+/// process restart. The application rebuilds the tracker from its own
+/// configuration, then restores the state; `config` records the values the
+/// track was saved under so a mismatch is caught. The track label, the
+/// keyframe and the motion, score and loss history are not saved.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackerState {
+    /// Template tensor the track matches against
+    pub template: InputTensor,
+    /// Last box [x, y, w, h]
+    pub rect_last: [i32; 4],
+    /// Runtime overrides in effect (threshold, search factor, window)
+    pub settings: RuntimeSettings,
+    /// Configuration the track was saved under
+    pub config: StateConfig,
+}
+
+/// Side of the keyframe crop, in target boxes
+const KEYFRAME_FACTOR: u32 = 4;
+/// Keyframe crop resolution
+const KEYFRAME_SIZE: usize = 256;
+
+/// Region around a confident box, kept to regenerate the template
+struct Keyframe {
+    /// Crop around the box (undistorted when crops go through the lens model)
+    crop: Array3<u8>,
+    /// Box in crop pixels
+    bbox: BBox,
+}
+
+/// Auxiliary head outputs of the best search so far in a frame
+struct HeadCandidate {
+    score: f32,
+    /// Box decoded by that search, matched against the frame result
+    bbox: [i32; 4],
+    mask: Option<TargetMask>,
+    keypoints: Vec<Keypoint>,
+}
+
+/// How a search decides success
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SearchMode {
+    /// `update`: success above the threshold; caches, tensor dumps and
+    /// auxiliary heads follow the search
+    Track(f32),
+    /// `update_raw`: every peak is a success and nothing but the output
+    /// buffers is touched
+    Raw,
+}
+
+impl SearchMode {
+    fn threshold(self) -> f32 {
+        match self {
+            Self::Track(threshold) => threshold,
+            Self::Raw => f32::NEG_INFINITY,
+        }
+    }
+}
+
+/// Model shared between trackers: RKNN context(s), window and configuration
+pub struct VitTrackModel {
     config: VitTrackConfig,
     model: RknnModel,
-    hanning: Vec<f32>,
-    template: Option<Vec<f32>>,
+    fast_model: Option<RknnModel>,
+    template_encoder: Option<RknnModel>,
+    hanning: Arc<[f32]>,
+}
+
+impl VitTrackModel {
+    /// Load the RKNN model(s) for a configuration
+    ///
+    /// On the CPU backend the inputs are switched to float32, the only type
+    /// the ONNX model takes.
+    ///
+    /// # Arguments
+    /// * `model_path` - Path to RKNN model file
+    /// * `config` - Configuration shared by all trackers using this model
+    pub fn load<P: AsRef<std::path::Path>>(
+        model_path: P,
+        mut config: VitTrackConfig,
+    ) -> Result<Self, RknnError> {
+        let mut model = RknnModel::load_on(model_path, config.device)?
+            .with_score_size(config.score_size)
+            .with_output_layout(config.output_layout);
+        if model.device() == Device::Cpu {
+            config.input_type = InputType::Float32;
+        }
+        if let Some(quantization) = config.output_quantization {
+            model = model.with_output_quantization(quantization);
+        }
+        if let Some(names) = &config.output_names {
+            model = model.with_output_names(names)?;
+        }
+        if let Some(client) = &config.npu_client {
+            model = model.with_scheduler(client.clone());
+        }
+        let fast_model = match &config.fast_model_path {
+            Some(path) => {
+                let mut fast_model = RknnModel::load_on(path, model.device())?
+                    .with_score_size(config.score_size)
+                    .with_output_layout(config.output_layout);
+                if let Some(quantization) = config.output_quantization {
+                    fast_model = fast_model.with_output_quantization(quantization);
+                }
+                if let Some(names) = &config.output_names {
+                    fast_model = fast_model.with_output_names(names)?;
+                }
+                if let Some(client) = &config.npu_client {
+                    fast_model = fast_model.with_scheduler(client.clone());
+                }
+                Some(fast_model)
+            }
+            None => None,
+        };
+        let template_encoder = match &config.template_encoder_path {
+            Some(_) if model.device() != Device::Npu => {
+                return Err(RknnError::LoadError(
+                    "template encoder needs the NPU backend".into(),
+                ));
+            }
+            Some(path) => {
+                // Features are float; both inputs of the search model share a type
+                config.input_type = InputType::Float32;
+                let mut encoder = RknnModel::load_on(path, Device::Npu)?;
+                if let Some(client) = &config.npu_client {
+                    encoder = encoder.with_scheduler(client.clone());
+                }
+                Some(encoder)
+            }
+            None => None,
+        };
+        let hanning = cached_window(WindowSpec {
+            influence: config.window_influence(1.0),
+            ..WindowSpec::hann(config.score_size)
+        });
+
+        Ok(Self {
+            config,
+            model,
+            fast_model,
+            template_encoder,
+            hanning,
+        })
+    }
+
+    pub fn config(&self) -> &VitTrackConfig {
+        &self.config
+    }
+
+    /// Device the model runs on
+    pub fn device(&self) -> Device {
+        self.model.device()
+    }
+
+    /// Per-tracker input memory for float32 models fed without copies
+    fn build_inputs(&self) -> Option<InputBuffers> {
+        (self.config.input_type == InputType::Float32 && self.template_encoder.is_none())
+            .then(|| InputBuffers::new(self.config.template_size, self.config.search_size))
+    }
+}
+
+/// VitTrack tracker using RKNN
+///
+/// Holds the per-object tracking state; the model is shared through an `Arc`,
+/// so one `VitTrackModel` can serve many trackers.
+pub struct VitTrack {
+    shared: Arc<VitTrackModel>,
+    budget: Option<LatencyBudget>,
+    fallback: Option<CorrelationTracker>,
+    /// Inference error that handed the last frame to the fallback
+    fallback_error: Option<RknnError>,
+    outputs: VitTrackOutputs,
+    template: Option<InputTensor>,
     rect_last: [i32; 4],
+    world_last: Option<(f32, f32)>,
+    result_last: TrackingResult,
+    watchdog: Option<LossWatchdog>,
+    threshold: Option<AdaptiveThreshold>,
+    kalman: Option<KalmanFilter>,
+    coast_frames: usize,
+    /// Updates since the last successful frame
+    missed_frames: u32,
+    /// Moving average behind `TrackingResult::score_smoothed`
+    score_smoothed: Option<f32>,
+    /// Target mask of the last successful frame
+    mask: Option<TargetMask>,
+    /// Target keypoints of the last successful frame
+    keypoints: Vec<Keypoint>,
+    head_candidate: Option<HeadCandidate>,
+    fusion: Option<ScoreFusion>,
+    occlusion: Option<OcclusionEstimator>,
+    histogram: Option<ColorHistogram>,
+    template_history: Option<TemplateHistory>,
+    reinit_callback: Option<ReinitCallback>,
+    search_factor: Option<u32>,
+    score_threshold: Option<f32>,
+    window_influence: Option<f32>,
+    score_smoothing: Option<f32>,
+    /// Template encoder output, computed on first use after a template change
+    template_features: Option<Vec<f32>>,
+    /// Float32 model inputs; the search crop is preprocessed straight into them
+    inputs: Option<InputBuffers>,
+    /// `inputs` holds the current template, written on first use after a change
+    inputs_template: bool,
+    /// Hann window blended by `set_window_influence`
+    window: Option<Arc<[f32]>>,
+    frame_timestamp: Option<Duration>,
+    tensor_recorder: Option<TensorRecorder>,
+    keyframe: Option<Keyframe>,
+    score_history: VecDeque<ScoreSample>,
+    stationary: Option<StationaryCache>,
+    label: Option<TrackLabel>,
+    /// Watchdog clock in deterministic mode
+    clock_origin: Instant,
+    updates: u32,
 }
 
+/// Application callback returning a new init box for the current frame
+type ReinitCallback = Box<dyn FnMut(&ArrayView3<u8>) -> Option<BBox> + Send>;
+
 impl VitTrack {
     /// Create new VitTrack tracker
     ///
@@ -51,16 +627,72 @@ impl VitTrack {
         model_path: P,
         config: VitTrackConfig,
     ) -> Result<Self, RknnError> {
-        let model = RknnModel::load(model_path)?;
-        let hanning = hann2d(config.score_size, config.score_size);
+        Ok(Self::with_model(Arc::new(VitTrackModel::load(model_path, config)?)))
+    }
 
-        Ok(Self {
-            config,
-            model,
-            hanning,
+    /// Create a tracker on a shared model (no model loading, cheap)
+    pub fn with_model(shared: Arc<VitTrackModel>) -> Self {
+        let config = &shared.config;
+        let budget = config.build_budget(shared.fast_model.is_some());
+        let watchdog = config.lost_timeout.map(LossWatchdog::new);
+        let threshold = config.build_threshold();
+        let fusion = config.score_fusion.map(ScoreFusion::new);
+        let occlusion = config.occlusion.map(OcclusionEstimator::new);
+        let template_history = config.template_update.map(TemplateHistory::new);
+        let tensor_recorder = config.tensor_dump.clone().map(|dump| {
+            TensorRecorder::new(dump, config.template_size, config.search_size)
+        });
+        let score_history = VecDeque::with_capacity(config.score_history_len);
+        let stationary = config.stationary.map(StationaryCache::new);
+        let fallback = config.fallback.map(CorrelationTracker::new);
+        let inputs = shared.build_inputs();
+
+        Self {
+            shared,
+            budget,
+            fallback,
+            fallback_error: None,
+            outputs: VitTrackOutputs::new(),
             template: None,
             rect_last: [0, 0, 0, 0],
-        })
+            world_last: None,
+            result_last: TrackingResult::default(),
+            watchdog,
+            threshold,
+            kalman: None,
+            coast_frames: 0,
+            missed_frames: 0,
+            score_smoothed: None,
+            mask: None,
+            keypoints: Vec::new(),
+            head_candidate: None,
+            fusion,
+            occlusion,
+            histogram: None,
+            template_history,
+            reinit_callback: None,
+            search_factor: None,
+            score_threshold: None,
+            window_influence: None,
+            score_smoothing: None,
+            template_features: None,
+            inputs,
+            inputs_template: false,
+            window: None,
+            frame_timestamp: None,
+            tensor_recorder,
+            keyframe: None,
+            score_history,
+            stationary,
+            label: None,
+            clock_origin: Instant::now(),
+            updates: 0,
+        }
+    }
+
+    /// Model shared by this tracker
+    pub fn model(&self) -> &Arc<VitTrackModel> {
+        &self.shared
     }
 
     /// Initialize tracker with bounding box
@@ -68,22 +700,281 @@ impl VitTrack {
     /// # Arguments
     /// * `image` - Input image as Array3<u8> in HWC BGR format
     /// * `bbox` - Initial bounding box
-    pub fn init(&mut self, image: &ArrayView3<u8>, bbox: BBox) {
-        self.rect_last = bbox.to_array();
+    ///
+    /// # Returns
+    /// * Quality of the selected template (warnings indicate a poor selection)
+    pub fn init(&mut self, image: &ArrayView3<u8>, bbox: BBox) -> TemplateQuality {
+        self.reset_state(bbox);
+        self.histogram = self
+            .shared
+            .config
+            .redetect_histogram
+            .map(|_| ColorHistogram::from_region(image, &self.rect_last));
+
+        self.refresh_template(image, bbox)
+    }
+
+    /// Replace the template without touching the tracking state
+    ///
+    /// Usable at any time, e.g. to lock in a new appearance after the target
+    /// turned around. The image may be an earlier frame. The box, motion
+    /// model and score history are kept; the new template becomes the base
+    /// that template updates roll back to.
+    ///
+    /// # Arguments
+    /// * `image` - Frame showing the target, HWC RGB
+    /// * `bbox` - Target box in `image`
+    ///
+    /// # Returns
+    /// * Quality of the new template
+    pub fn refresh_template(&mut self, image: &ArrayView3<u8>, bbox: BBox) -> TemplateQuality {
+        let (template, quality) = self.crop_template(image, &bbox);
+        if let Some(history) = &mut self.template_history {
+            history.reset(TemplateSnapshot {
+                template: template.clone(),
+                score: 1.0,
+                quality: quality.score,
+            });
+        }
+        self.set_template(Some(template));
+        if self.shared.config.keyframe_score.is_some() {
+            self.keyframe = Some(self.crop_keyframe(image, &bbox));
+        }
+        if let Some(cache) = &mut self.stationary {
+            cache.reset();
+        }
+        if let Some(fallback) = &mut self.fallback {
+            fallback.init(image, &bbox.to_array());
+        }
+
+        quality
+    }
 
-        let (template, _crop_size) = crop_and_preprocess(
+    /// Initialize from a 10/16-bit frame (mono or RGB)
+    ///
+    /// # Arguments
+    /// * `image` - Input image in HWC format, 1 or 3 channels
+    /// * `bbox` - Initial bounding box
+    /// * `range` - Sample values mapped to black and white
+    pub fn init_u16(&mut self, image: &ArrayView3<u16>, bbox: BBox, range: SampleRange) {
+        let (mut template, _) = crop_resized_u16(
             image,
             &bbox,
-            self.config.template_factor,
-            self.config.template_size,
+            self.shared.config.template_factor,
+            self.shared.config.template_size,
+            range,
+        );
+        self.shared.config.normalization.apply(&mut template);
+        let template = InputTensor::from_unit_image(&template, self.shared.config.input_type);
+        self.init_with_tensor(template, bbox);
+    }
+
+    /// Initialize from a template tensor prepared by the caller
+    ///
+    /// The template must be cropped around `bbox` with `template_factor` and
+    /// resized to `template_size`.
+    pub fn init_with_tensor(&mut self, template: InputTensor, bbox: BBox) {
+        self.reset_state(bbox);
+        self.histogram = None;
+        if let Some(history) = &mut self.template_history {
+            history.reset(TemplateSnapshot {
+                template: template.clone(),
+                score: 1.0,
+                // Not measured without the 8-bit crop
+                quality: 0.0,
+            });
+        }
+        self.set_template(Some(template));
+    }
+
+    /// Reset all per-track state to a new initial box
+    fn reset_state(&mut self, bbox: BBox) {
+        self.rect_last = bbox.to_array();
+        self.world_last = None;
+        self.result_last = TrackingResult::default();
+        self.score_history.clear();
+        if let Some(watchdog) = &mut self.watchdog {
+            watchdog.reset();
+        }
+        if let Some(threshold) = &mut self.threshold {
+            threshold.reset();
+        }
+        // Acceleration variance per frame² scales with the frame interval⁴
+        let ratio = self.shared.config.frame_interval_ratio();
+        self.kalman = self.shared.config.kalman_noise().map(|(process, measurement)| {
+            KalmanFilter::new(&self.rect_last, process * ratio.powi(4), measurement)
+        });
+        self.coast_frames = 0;
+        self.missed_frames = 0;
+        self.score_smoothed = None;
+        self.mask = None;
+        self.keypoints.clear();
+        self.head_candidate = None;
+        if let Some(fusion) = &mut self.fusion {
+            fusion.reset();
+        }
+        if let Some(occlusion) = &mut self.occlusion {
+            occlusion.reset();
+        }
+        self.keyframe = None;
+        if let Some(cache) = &mut self.stationary {
+            cache.reset();
+        }
+        if let Some(fallback) = &mut self.fallback {
+            fallback.reset();
+        }
+        self.fallback_error = None;
+    }
+
+    /// Camera whose distortion is removed from the crops, if enabled
+    fn lens(&self) -> Option<&CameraIntrinsics> {
+        let config = &self.shared.config;
+        config.camera.as_ref().filter(|camera| config.undistort_crops && camera.is_distorted())
+    }
+
+    /// Convert an 8-bit crop with the configured input type and normalization
+    fn to_tensor(&self, crop: &Array3<u8>) -> InputTensor {
+        let config = &self.shared.config;
+        InputTensor::from_image_normalized(crop, config.input_type, config.normalization)
+    }
+
+    /// Crop the template around a box and rate it
+    fn crop_template(&self, image: &ArrayView3<u8>, bbox: &BBox) -> (InputTensor, TemplateQuality) {
+        let (template, crop_size, bbox) = match self.lens() {
+            Some(camera) => {
+                let bbox = BBox::from_array(&camera.undistort_rect(&bbox.to_array()));
+                let (template, crop_size) = camera.crop_undistorted(
+                    image,
+                    &bbox,
+                    self.shared.config.template_factor,
+                    self.shared.config.template_size,
+                );
+                (template, crop_size, bbox)
+            }
+            None => {
+                let step = self.shared.config.frame_downsample.max(1);
+                let (template, crop_size) = crop_resized(
+                    &downsampled(image, step),
+                    &scale_down(bbox, step),
+                    self.shared.config.template_factor,
+                    self.shared.config.template_size,
+                );
+                (template, crop_size * step as i32, *bbox)
+            }
+        };
+
+        self.template_tensor(&template, crop_size, &bbox)
+    }
+
+    /// Model input and quality of a template crop
+    ///
+    /// # Arguments
+    /// * `crop_size` - Side of the cropped region in the pixels of `bbox`
+    fn template_tensor(
+        &self,
+        template: &Array3<u8>,
+        crop_size: i32,
+        bbox: &BBox,
+    ) -> (InputTensor, TemplateQuality) {
+        let scale = self.shared.config.template_size as f32 / crop_size.max(1) as f32;
+        let box_size = (
+            (bbox.width as f32 * scale).round() as usize,
+            (bbox.height as f32 * scale).round() as usize,
+        );
+        let quality = template_quality(template, box_size);
+
+        (self.to_tensor(template), quality)
+    }
+
+    /// Crop around `bbox` wide enough for any template factor up to `KEYFRAME_FACTOR`
+    fn crop_keyframe(&self, image: &ArrayView3<u8>, bbox: &BBox) -> Keyframe {
+        let (crop, crop_size, bbox) = match self.lens() {
+            Some(camera) => {
+                let bbox = BBox::from_array(&camera.undistort_rect(&bbox.to_array()));
+                let (crop, crop_size) =
+                    camera.crop_undistorted(image, &bbox, KEYFRAME_FACTOR, KEYFRAME_SIZE);
+                (crop, crop_size, bbox)
+            }
+            None => {
+                let (crop, crop_size) = crop_resized(image, bbox, KEYFRAME_FACTOR, KEYFRAME_SIZE);
+                (crop, crop_size, *bbox)
+            }
+        };
+
+        // The box is centered in the crop
+        let scale = KEYFRAME_SIZE as f32 / crop_size.max(1) as f32;
+        let w = ((bbox.width as f32 * scale).round() as i32).max(1);
+        let h = ((bbox.height as f32 * scale).round() as i32).max(1);
+        let size = KEYFRAME_SIZE as i32;
+        Keyframe {
+            crop,
+            bbox: BBox::new((size - w) / 2, (size - h) / 2, w, h),
+        }
+    }
+
+    /// Continue the current track on another model (e.g. fast <-> accurate)
+    ///
+    /// The template is regenerated from the last high-confidence frame (see
+    /// `keyframe_score`) at the new model's template size and input type, so
+    /// the track survives the swap. Box, motion model and loss state are kept;
+    /// per-model state (score fusion, template history, latency budget)
+    /// starts over.
+    ///
+    /// # Returns
+    /// * Quality of the regenerated template; None when there was no track
+    ///   or no keyframe, in which case the tracker is reset and needs `init`
+    pub fn swap_model(&mut self, shared: Arc<VitTrackModel>) -> Option<TemplateQuality> {
+        self.shared = shared;
+        // The blended window follows the new score map size
+        self.set_window_influence(self.window_influence);
+        let config = &self.shared.config;
+        self.budget = config.build_budget(self.shared.fast_model.is_some());
+        // Scores of the new model are not comparable to the learned threshold
+        self.threshold = config.build_threshold();
+        self.fusion = config.score_fusion.map(ScoreFusion::new);
+        self.occlusion = config.occlusion.map(OcclusionEstimator::new);
+        self.template_history = config.template_update.map(TemplateHistory::new);
+        self.tensor_recorder = config.tensor_dump.clone().map(|dump| {
+            TensorRecorder::new(dump, config.template_size, config.search_size)
+        });
+        self.outputs = VitTrackOutputs::new();
+        self.stationary = config.stationary.map(StationaryCache::new);
+        self.inputs = self.shared.build_inputs();
+        self.inputs_template = false;
+
+        let (Some(_), Some(keyframe)) = (&self.template, &self.keyframe) else {
+            self.reset();
+            return None;
+        };
+        let (template, crop_size) = crop_resized(
+            &keyframe.crop.view(),
+            &keyframe.bbox,
+            config.template_factor,
+            config.template_size,
         );
+        let (template, quality) = self.template_tensor(&template, crop_size, &keyframe.bbox);
+        if let Some(history) = &mut self.template_history {
+            history.reset(TemplateSnapshot {
+                template: template.clone(),
+                score: 1.0,
+                quality: quality.score,
+            });
+        }
+        self.set_template(Some(template));
 
-        self.template = Some(template);
+        Some(quality)
     }
 
     /// Initialize tracker with raw bounding box values
-    pub fn init_with_rect(&mut self, image: &ArrayView3<u8>, x: i32, y: i32, w: i32, h: i32) {
-        self.init(image, BBox::new(x, y, w, h));
+    pub fn init_with_rect(
+        &mut self,
+        image: &ArrayView3<u8>,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+    ) -> TemplateQuality {
+        self.init(image, BBox::new(x, y, w, h))
     }
 
     /// Track object in new frame
@@ -94,46 +985,1709 @@ impl VitTrack {
     /// # Returns
     /// * Tracking result with bounding box and score
     pub fn update(&mut self, image: &ArrayView3<u8>) -> Result<TrackingResult, RknnError> {
-        let template = match &self.template {
-            Some(t) => t,
-            None => {
-                return Ok(TrackingResult::default());
+        if self.template.is_none() {
+            return Ok(TrackingResult::default());
+        }
+
+        match self.update_model(image) {
+            Err(error) if self.fallback.as_ref().is_some_and(|f| f.is_trained()) => {
+                Ok(self.track_fallback(image, Some(error)))
+            }
+            result => result,
+        }
+    }
+
+    /// `update` on the model, without the fallback on errors
+    fn update_model(&mut self, image: &ArrayView3<u8>) -> Result<TrackingResult, RknnError> {
+        let start = Instant::now();
+        self.head_candidate = None;
+        let level = self.budget.as_ref().map_or(Degradation::None, |b| b.level());
+        if let Some(budget) = &mut self.budget
+            && budget.should_skip()
+        {
+            if let Some(fallback) = &self.fallback
+                && fallback.config().on_budget_skip
+                && fallback.is_trained()
+            {
+                return Ok(self.track_fallback(image, None));
+            }
+            // Repeat the last box, not its per-frame events
+            let mut result = TrackingResult {
+                degradation: Degradation::SkippedInference,
+                reinit: None,
+                template_event: None,
+                cached: false,
+                timestamp: self.frame_timestamp.take(),
+                ..self.result_last
+            };
+            self.sync_pose(&mut result);
+            result.tag = self.label.as_ref().map(|label| label.tag);
+            return Ok(result);
+        }
+
+        if self.check_stationary(image)
+            && self.stationary.as_ref().is_some_and(|cache| cache.config().skip_inference)
+        {
+            let mut result = TrackingResult {
+                cached: true,
+                reinit: None,
+                template_event: None,
+                timestamp: self.frame_timestamp.take(),
+                ..self.result_last
+            };
+            self.sync_pose(&mut result);
+            result.tag = self.label.as_ref().map(|label| label.tag);
+            return Ok(result);
+        }
+
+        let scales = if level >= Degradation::SingleScale {
+            vec![1.0]
+        } else {
+            self.shared.config.search_scales.clone()
+        };
+        let max_search_crop = if level >= Degradation::DownscaledCrop {
+            let degraded = 2 * self.shared.config.search_size as i32;
+            Some(self.shared.config.max_search_crop.map_or(degraded, |max| max.min(degraded)))
+        } else {
+            self.shared.config.max_search_crop
+        };
+        let use_fast_model = level >= Degradation::FastModel;
+        let threshold = self.decision_threshold();
+        let widen = self.search_widening();
+
+        // Search at each configured scale, best response wins
+        let mut best: Option<(TrackingResult, [i32; 4], Option<FusedMap>)> = None;
+        for scale in scales {
+            let rect = scale_rect(&self.rect_last, scale * widen);
+            let mode = SearchMode::Track(threshold);
+            let (result, rect, fused) =
+                self.search_at(image, rect, use_fast_model, max_search_crop, mode)?;
+
+            if best.as_ref().is_none_or(|(b, _, _)| result.score > b.score) {
+                best = Some((result, rect, fused));
+            }
+        }
+
+        let Some((mut result, mut rect, fused)) = best else {
+            return Ok(TrackingResult::default());
+        };
+        if let (Some(fusion), Some(fused)) = (&mut self.fusion, fused) {
+            fusion.commit(fused);
+        }
+
+        // Watchdog: recover after sustained loss
+        self.updates = self.updates.wrapping_add(1);
+        let now = if self.shared.config.deterministic {
+            let nominal = Duration::from_secs(1) / 30 * self.updates;
+            self.clock_origin + self.frame_timestamp.unwrap_or(nominal)
+        } else {
+            Instant::now()
+        };
+        if let Some(watchdog) = &mut self.watchdog
+            && let Some(lost_for) = watchdog.observe(result.success, now)
+        {
+            let action = self.shared.config.reinit_action;
+            let recovered = match action {
+                ReinitAction::GlobalSweep => self.global_sweep(image, max_search_crop)?,
+                ReinitAction::Callback => self.request_reinit(image)?,
+            };
+            if let Some((recovered_result, recovered_rect)) = recovered {
+                result = recovered_result;
+                rect = recovered_rect;
             }
+            result.reinit = Some(ReinitEvent {
+                action,
+                lost_for,
+                recovered: result.success,
+            });
+        }
+
+        result.degradation = level;
+        let result = self.finish_frame(Some(image), result, rect, threshold);
+        if let Some(budget) = &mut self.budget {
+            budget.record(start.elapsed());
+        }
+
+        Ok(result)
+    }
+
+    /// Follow the target with the CPU correlation filter
+    ///
+    /// Moves the last box but leaves the model state (motion model, score
+    /// history, template) alone, so the model resumes where it stopped.
+    fn track_fallback(
+        &mut self,
+        image: &ArrayView3<u8>,
+        error: Option<RknnError>,
+    ) -> TrackingResult {
+        self.fallback_error = error;
+        let mut result = TrackingResult {
+            bbox: self.rect_last,
+            degradation: Degradation::Fallback,
+            timestamp: self.frame_timestamp.take(),
+            ..TrackingResult::default()
         };
+        if let Some(fallback) = &mut self.fallback
+            && let Some(found) = fallback.track(image, &self.rect_last)
+        {
+            result.score = found.score;
+            if found.success {
+                result.success = true;
+                result.bbox = found.bbox;
+                self.rect_last = found.bbox;
+                fallback.learn(image, &found.bbox);
+            }
+        }
+        self.missed_frames = if result.success { 0 } else { self.missed_frames + 1 };
+        result.score_smoothed = self.smooth_score(result.score);
+        self.mask = None;
+        self.keypoints.clear();
+
+        if let Some(camera) = &self.shared.config.camera {
+            result.angular = Some(camera.angular_target(&result.bbox));
+        }
+        self.sync_pose(&mut result);
+        result.tag = self.label.as_ref().map(|label| label.tag);
+        self.result_last = result;
+        result
+    }
+
+    /// Fold a frame's score into the moving average
+    fn smooth_score(&mut self, score: f32) -> f32 {
+        let alpha = self.score_smoothing.or(self.shared.config.score_smoothing);
+        let smoothed = smoothed_score(self.score_smoothed, score, alpha);
+        self.score_smoothed = Some(smoothed);
+        smoothed
+    }
+
+    /// Inference error behind the last `Degradation::Fallback` result
+    pub fn fallback_error(&self) -> Option<&RknnError> {
+        self.fallback_error.as_ref()
+    }
+
+    /// Best decoded box and score around the last box, without a decision
+    ///
+    /// Searches like `update` at the configured scales but applies no score
+    /// threshold and leaves the tracking state untouched (last box,
+    /// success/lost history, motion model, watchdog, template updates,
+    /// stationary cache, tensor dumps, mask and keypoints), for callers with
+    /// their own decision logic, e.g. fusion with radar. Only `score_map`
+    /// and `output` then show this search. Move the tracker with `init` or
+    /// `update`.
+    ///
+    /// # Returns
+    /// * None before `init`
+    pub fn update_raw(
+        &mut self,
+        image: &ArrayView3<u8>,
+    ) -> Result<Option<RawDetection>, RknnError> {
+        if self.template.is_none() {
+            return Ok(None);
+        }
+
+        let mut best: Option<RawDetection> = None;
+        for scale in self.shared.config.search_scales.clone() {
+            let rect = scale_rect(&self.rect_last, scale);
+            let max_search_crop = self.shared.config.max_search_crop;
+            let (result, _, _) =
+                self.search_at(image, rect, false, max_search_crop, SearchMode::Raw)?;
+            if best.is_none_or(|b| result.score > b.score) {
+                best = Some(RawDetection {
+                    bbox: result.bbox,
+                    score: result.score,
+                    peak_ratio: result.peak_ratio,
+                });
+            }
+        }
+
+        Ok(best)
+    }
 
-        let bbox = BBox::from_array(&self.rect_last);
+    /// Track using a search crop produced outside the crate (ISP/RGA)
+    ///
+    /// The crop must be `search_size` x `search_size` RGB and cover the frame
+    /// region described by `transform`; `next_crop` gives the region `update`
+    /// would use. Decoding, windowing and state updates are the same as
+    /// in `update`; steps that need the full frame (multi-scale search, watchdog
+    /// recovery, rotation, normalized output, template updates) are skipped.
+    ///
+    /// # Arguments
+    /// * `search_crop` - Resized search crop in HWC RGB format
+    /// * `transform` - Frame region the crop was taken from
+    pub fn update_with_crop(
+        &mut self,
+        search_crop: &Array3<u8>,
+        transform: CropTransform,
+    ) -> Result<TrackingResult, RknnError> {
+        let search = self.to_tensor(search_crop);
+        self.update_with_tensor(&search, transform)
+    }
 
-        let (search, crop_size) = crop_and_preprocess(
+    /// Track in a 10/16-bit frame (mono or RGB)
+    ///
+    /// Follows `update_with_crop`: steps that need an 8-bit frame are skipped.
+    ///
+    /// # Arguments
+    /// * `image` - Input image in HWC format, 1 or 3 channels
+    /// * `range` - Sample values mapped to black and white
+    pub fn update_u16(
+        &mut self,
+        image: &ArrayView3<u16>,
+        range: SampleRange,
+    ) -> Result<TrackingResult, RknnError> {
+        let (mut search, crop_size) = crop_resized_u16(
             image,
-            &bbox,
-            self.config.search_factor,
-            self.config.search_size,
+            &BBox::from_array(&self.rect_last),
+            self.search_factor(),
+            self.shared.config.search_size,
+            range,
         );
+        self.shared.config.normalization.apply(&mut search);
+        let search = InputTensor::from_unit_image(&search, self.shared.config.input_type);
+        let transform = CropTransform {
+            origin: crop_origin(&self.rect_last, crop_size),
+            crop_size,
+        };
+        self.update_with_tensor(&search, transform)
+    }
 
-        // Run RKNN inference
-        let outputs = self.model.inference(template, &search)?;
+    /// Track using a search tensor prepared by the caller
+    ///
+    /// Same as `update_with_crop` with the tensor conversion already done.
+    pub fn update_with_tensor(
+        &mut self,
+        search: &InputTensor,
+        transform: CropTransform,
+    ) -> Result<TrackingResult, RknnError> {
+        if self.template.is_none() {
+            return Ok(TrackingResult::default());
+        }
 
-        // Process outputs
-        let result = process_outputs(
-            &outputs.conf_map,
-            &outputs.size_map,
-            &outputs.offset_map,
-            &self.hanning,
-            &mut self.rect_last,
-            crop_size,
-            self.config.score_threshold,
-        );
+        let threshold = self.decision_threshold();
+        self.head_candidate = None;
+        let (x, y) = transform.origin;
+        let region = [x, y, transform.crop_size, transform.crop_size];
+        let crop_size = transform.crop_size;
+        let mode = SearchMode::Track(threshold);
+        let (result, rect, fused) =
+            self.search_tensor(Some(search), region, crop_size, false, (0, 0), mode)?;
+        if let (Some(fusion), Some(fused)) = (&mut self.fusion, fused) {
+            fusion.commit(fused);
+        }
 
-        Ok(result)
+        Ok(self.finish_frame(None, result, rect, threshold))
     }
 
-    /// Get current bounding box
-    pub fn get_bbox(&self) -> [i32; 4] {
-        self.rect_last
+    /// Frame region to crop for the next `update_with_crop`
+    pub fn next_crop(&self) -> CropTransform {
+        CropTransform::around(&BBox::from_array(&self.rect_last), self.search_factor())
     }
 
-    /// Check if tracker is initialized
-    pub fn is_initialized(&self) -> bool {
-        self.template.is_some()
+    /// Raw model outputs for a search tensor against the current template
+    ///
+    /// Runs only the inference stage; the score transform, fusion and state
+    /// updates of `update` are not applied. Decode the outputs with
+    /// `DecodedCandidates::decode` and `score_window`.
+    ///
+    /// # Returns
+    /// * None before `init`
+    pub fn infer(&self, search: &NormalizedTensor) -> Result<Option<VitTrackOutputs>, RknnError> {
+        let Some(template) = &self.template else {
+            return Ok(None);
+        };
+        let model = &self.shared.model;
+        let outputs = match &self.shared.template_encoder {
+            Some(encoder) => {
+                let features = match &self.template_features {
+                    Some(features) => features.clone(),
+                    None => encoder.encode(template)?,
+                };
+                let mut outputs = VitTrackOutputs::new();
+                model.inference_features_into(&features, &search.tensor, &mut outputs)?;
+                outputs
+            }
+            None => model.inference_tensors(template, &search.tensor)?,
+        };
+        Ok(Some(outputs))
+    }
+
+    /// Window the next frame's score map is multiplied with
+    pub fn score_window(&self) -> &[f32] {
+        self.window.as_deref().unwrap_or(&self.shared.hanning)
     }
-}
\ No newline at end of file
+
+    /// Frame region the next `update` will search
+    ///
+    /// Covers the crops of every search scale around the last box, widened
+    /// while the motion model coasts through an occlusion. Lets the ISP
+    /// (ROI auto-exposure) or the encoder (ROI coding) favour that area before
+    /// the frame arrives. The region may extend past the frame edges; it is
+    /// empty before `init`.
+    pub fn next_search_region(&self) -> BBox {
+        if self.template.is_none() {
+            return BBox::new(0, 0, 0, 0);
+        }
+        let widen = self.search_widening();
+        search_region(
+            &self.rect_last,
+            &self.shared.config.search_scales,
+            widen,
+            self.search_factor(),
+        )
+    }
+
+    /// Compare the next search region with the last inferred frame
+    ///
+    /// # Returns
+    /// * true when the stationary cache is enabled and the frame may reuse
+    ///   the previous work
+    fn check_stationary(&mut self, image: &ArrayView3<u8>) -> bool {
+        if self.stationary.is_none() {
+            return false;
+        }
+        let region = self.next_search_region().to_array();
+        let speed = match &self.kalman {
+            _ if !self.result_last.success || self.coast_frames > 0 => f32::INFINITY,
+            Some(kalman) => {
+                let [_, _, vx, vy] = kalman.state();
+                vx.hypot(vy)
+            }
+            None => 0.0,
+        };
+        self.stationary
+            .as_mut()
+            .is_some_and(|cache| cache.check(image, region, speed))
+    }
+
+    /// Motion model, state and derived outputs shared by all update paths
+    fn finish_frame(
+        &mut self,
+        image: Option<&ArrayView3<u8>>,
+        mut result: TrackingResult,
+        mut rect: [i32; 4],
+        threshold: f32,
+    ) -> TrackingResult {
+        if let Some(occlusion) = &mut self.occlusion {
+            let probability = occlusion.update(result.success, result.score, result.apce);
+            result.occlusion = Some(probability);
+            result.occluded = probability >= occlusion.config().threshold;
+        }
+
+        if let Some(kalman) = &mut self.kalman {
+            kalman.predict();
+            if result.success {
+                kalman.correct(&rect);
+                self.coast_frames = 0;
+            } else if let Some(coasting) = &self.shared.config.coasting {
+                // Occlusion: sharp score drop while the motion history is consistent
+                let occluded = self.coast_frames > 0
+                    || (self.result_last.success
+                        && result.score < self.result_last.score * coasting.score_drop
+                        && kalman.corrections() >= coasting.min_history);
+
+                if occluded && self.coast_frames < coasting.max_frames {
+                    self.coast_frames += 1;
+                    result.coasting = true;
+                    rect = kalman.bbox();
+                } else {
+                    self.coast_frames = 0;
+                }
+            }
+        }
+
+        self.missed_frames = if result.success { 0 } else { self.missed_frames + 1 };
+        result.timestamp = self.frame_timestamp.take();
+        if result.success || result.coasting {
+            self.rect_last = rect;
+            result.bbox = rect;
+        } else {
+            result.bbox = self.rect_last;
+        }
+        // The search follows the decoded box; the filtered one is reported
+        if let (MotionModel::Kalman { .. }, Some(kalman)) =
+            (self.shared.config.motion_model, &self.kalman)
+            && result.success
+        {
+            result.bbox_raw = Some(result.bbox);
+            result.bbox = kalman.bbox();
+        }
+
+        if let Some(camera) = &self.shared.config.camera {
+            result.angular = Some(camera.angular_target(&result.bbox));
+        }
+        self.sync_pose(&mut result);
+        result.tag = self.label.as_ref().map(|label| label.tag);
+
+        if self.shared.config.normalized_output
+            && let Some(image) = image
+        {
+            let (img_h, img_w, _) = image.dim();
+            result.bbox_normalized = Some(result.normalized_bbox(img_w, img_h));
+        }
+
+        if let Some(convention) = &self.shared.config.coordinates
+            && let Some(image) = image
+        {
+            let (img_h, img_w, _) = image.dim();
+            result.bbox_converted = Some(convention.convert(&result.bbox, (img_w, img_h)));
+        }
+
+        if self.shared.config.estimate_rotation
+            && result.success
+            && let Some(image) = image
+        {
+            result.rotated = Some(estimate_rotated_box(image, &result.bbox));
+        }
+
+        if let Some(plane) = &self.shared.config.ground_plane
+            && result.success
+        {
+            let position = plane.project_bbox(&result.bbox);
+            result.world = Some(plane.world_target(position, self.world_last));
+            self.world_last = Some(position);
+        }
+
+        if let Some(threshold) = &mut self.threshold
+            && result.success
+        {
+            threshold.push(result.score);
+        }
+
+        if let Some(history) = &mut self.template_history {
+            if let Some(template) = history.observe(result.success, result.score) {
+                self.template = Some(template.clone());
+                self.template_features = None;
+                self.inputs_template = false;
+                result.template_event = Some(TemplateEvent::RolledBack);
+            } else if !result.occluded
+                && history.should_update(result.success, result.score)
+                && let Some(image) = image
+            {
+                let strategy = history.strategy();
+                let (template, quality) =
+                    self.crop_template(image, &BBox::from_array(&result.bbox));
+                let template = strategy.apply(self.template.as_ref(), template);
+                if !quality.is_poor()
+                    && let Some(history) = &mut self.template_history
+                {
+                    history.push(TemplateSnapshot {
+                        template: template.clone(),
+                        score: result.score,
+                        quality: quality.score,
+                    });
+                    self.set_template(Some(template));
+                    result.template_event = Some(TemplateEvent::Updated);
+                }
+            }
+        }
+
+        if let Some(recorder) = &mut self.tensor_recorder {
+            recorder.end_frame_logged(result.success);
+        }
+
+        if let (Some(min_score), Some(image)) = (self.shared.config.keyframe_score, image)
+            && result.success
+            && result.score >= min_score
+        {
+            self.keyframe = Some(self.crop_keyframe(image, &BBox::from_array(&result.bbox)));
+        }
+
+        if let (Some(fallback), Some(image)) = (&mut self.fallback, image)
+            && result.success
+        {
+            fallback.learn(image, &result.bbox);
+        }
+        self.fallback_error = None;
+
+        result.score_smoothed = self.smooth_score(result.score);
+        // Keep head outputs only if they came from the search that produced the box
+        let decoded = result.bbox_raw.unwrap_or(result.bbox);
+        match self.head_candidate.take() {
+            Some(heads) if result.success && heads.bbox == decoded => {
+                self.mask = heads.mask;
+                self.keypoints = heads.keypoints;
+            }
+            _ => {
+                self.mask = None;
+                self.keypoints.clear();
+            }
+        }
+        self.result_last = result;
+        if self.score_history.len() >= self.shared.config.score_history_len {
+            self.score_history.pop_front();
+        }
+        if self.shared.config.score_history_len > 0 {
+            self.score_history.push_back(ScoreSample {
+                success: result.success,
+                score: result.score,
+                peak_ratio: result.peak_ratio,
+                threshold,
+            });
+        }
+
+        result
+    }
+
+    /// Set the callback used by `ReinitAction::Callback`
+    ///
+    /// The callback receives the current frame and returns a new init box, or
+    /// `None` to keep waiting.
+    pub fn set_reinit_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&ArrayView3<u8>) -> Option<BBox> + Send + 'static,
+    {
+        self.reinit_callback = Some(Box::new(callback));
+    }
+
+    /// Run one search around `rect`
+    ///
+    /// # Returns
+    /// * Tracking result and the updated rectangle (unchanged on failure)
+    /// * Fused confidence map when score fusion is enabled
+    fn search_at(
+        &mut self,
+        image: &ArrayView3<u8>,
+        rect: [i32; 4],
+        use_fast_model: bool,
+        max_search_crop: Option<i32>,
+        mode: SearchMode,
+    ) -> Result<(TrackingResult, [i32; 4], Option<FusedMap>), RknnError> {
+        if let Some(camera) = self.lens().copied() {
+            // Search in ideal pixels, then map the result back through the lens
+            let ideal = camera.undistort_rect(&rect);
+            let (search, crop_size) = camera.crop_undistorted(
+                image,
+                &BBox::from_array(&ideal),
+                self.search_factor(),
+                self.shared.config.search_size,
+            );
+            let search = self.to_tensor(&search);
+            let (mut result, _, fused) = self.search_tensor(
+                Some(&search),
+                ideal,
+                crop_size,
+                use_fast_model,
+                (0, 0),
+                mode,
+            )?;
+            result.bbox = camera.distort_rect(&result.bbox);
+            let rect = if result.success { result.bbox } else { rect };
+            return Ok((result, rect, fused));
+        }
+
+        // Keep the crop inside the frame; the target is then off-center
+        let (img_h, img_w, _) = image.dim();
+        let shift = if self.shared.config.border_keep_out {
+            let crop = crop_size(&BBox::from_array(&rect), self.search_factor());
+            border_shift(&rect, crop, (img_w as i32, img_h as i32))
+        } else {
+            (0, 0)
+        };
+        let search_rect = [rect[0] + shift.0, rect[1] + shift.1, rect[2], rect[3]];
+
+        // update_raw neither reads nor fills the stationary cache
+        let raw = mode == SearchMode::Raw;
+        let cached = self
+            .stationary
+            .as_ref()
+            .filter(|_| !raw)
+            .and_then(|cache| cache.tensor(search_rect, max_search_crop))
+            .map(|(search, crop_size)| (search.clone(), crop_size));
+        // Without a cache or dump needing the tensor, crop into the model input
+        let keep_tensor = self.tensor_recorder.is_some()
+            || self.stationary.as_ref().is_some_and(|cache| !raw && !cache.config().skip_inference);
+        let (search, crop_size) = match cached {
+            Some((search, crop_size)) => (Some(search), crop_size),
+            None if !keep_tensor && max_search_crop.is_none() && self.inputs.is_some() => {
+                (None, self.crop_search_into(image, &search_rect))
+            }
+            None => {
+                let (search, crop_size) = self.crop_search(image, &search_rect, max_search_crop);
+                if let Some(cache) = &mut self.stationary
+                    && !raw
+                    && !cache.config().skip_inference
+                {
+                    cache.store(search_rect, max_search_crop, &search, crop_size);
+                }
+                (Some(search), crop_size)
+            }
+        };
+
+        let (result, new_rect, fused) = self.search_tensor(
+            search.as_ref(),
+            search_rect,
+            crop_size,
+            use_fast_model,
+            shift,
+            mode,
+        )?;
+        let rect = if result.success { new_rect } else { rect };
+        Ok((result, rect, fused))
+    }
+
+    /// Search tensor and crop size (frame pixels) around `search_rect`
+    fn crop_search(
+        &self,
+        image: &ArrayView3<u8>,
+        search_rect: &[i32; 4],
+        max_search_crop: Option<i32>,
+    ) -> (InputTensor, i32) {
+        let step = self.shared.config.frame_downsample.max(1);
+        let image = downsampled(image, step);
+        let bbox = scale_down(&BBox::from_array(search_rect), step);
+        let (search, crop_size) = match max_search_crop {
+            Some(max_crop) => {
+                let (search, crop_size) = crop_resized_downscaled(
+                    &image,
+                    &bbox,
+                    self.search_factor(),
+                    self.shared.config.search_size,
+                    max_crop / step as i32,
+                );
+                (self.to_tensor(&search), crop_size)
+            }
+            None => {
+                let spec = CropSpec {
+                    factor: self.search_factor(),
+                    output_size: self.shared.config.search_size,
+                    input_type: self.shared.config.input_type,
+                    normalization: self.shared.config.normalization,
+                };
+                self.shared.config.preprocessor.crop_tensor(&image, &bbox, &spec)
+            }
+        };
+        (search, crop_size * step as i32)
+    }
+
+    /// Search crop around `search_rect` written into the float32 `inputs`
+    ///
+    /// # Returns
+    /// * Crop size in frame pixels
+    fn crop_search_into(&mut self, image: &ArrayView3<u8>, search_rect: &[i32; 4]) -> i32 {
+        let step = self.shared.config.frame_downsample.max(1);
+        let image = downsampled(image, step);
+        let bbox = scale_down(&BBox::from_array(search_rect), step);
+        let spec = CropSpec {
+            factor: self.search_factor(),
+            output_size: self.shared.config.search_size,
+            input_type: InputType::Float32,
+            normalization: self.shared.config.normalization,
+        };
+        let inputs = self.inputs.as_mut().expect("float32 input buffers");
+        let preprocessor = &self.shared.config.preprocessor;
+        preprocessor.crop_into(&image, &bbox, &spec, inputs.search_mut()) * step as i32
+    }
+
+    /// Run inference on a prepared search tensor and decode it
+    ///
+    /// # Arguments
+    /// * `search` - Search input cropped around `rect`; None when it was
+    ///   already written into `inputs`
+    /// * `rect` - Box the search crop is centered on
+    /// * `crop_size` - Crop size in original image pixels
+    /// * `shift` - Offset of `rect` from the target in pixels; the cosine
+    ///   window is moved back onto the target
+    /// * `mode` - Success threshold, or `Raw` for `update_raw`
+    fn search_tensor(
+        &mut self,
+        search: Option<&InputTensor>,
+        rect: [i32; 4],
+        crop_size: i32,
+        use_fast_model: bool,
+        shift: (i32, i32),
+        mode: SearchMode,
+    ) -> Result<(TrackingResult, [i32; 4], Option<FusedMap>), RknnError> {
+        let Some(template) = &self.template else {
+            return Ok((TrackingResult::default(), rect, None));
+        };
+        let model = match &self.shared.fast_model {
+            Some(fast_model) if use_fast_model => fast_model,
+            _ => &self.shared.model,
+        };
+
+        // Run RKNN inference; float32 inputs go through the reused buffers
+        match (&self.shared.template_encoder, &mut self.inputs, template, search) {
+            (Some(encoder), _, _, Some(search)) => {
+                let features = match &mut self.template_features {
+                    Some(features) => features,
+                    features => features.insert(encoder.encode(template)?),
+                };
+                model.inference_features_into(features, search, &mut self.outputs)?;
+            }
+            (None, Some(inputs), InputTensor::Float32(t), None | Some(InputTensor::Float32(_))) => {
+                if !self.inputs_template {
+                    inputs.template_mut().copy_from_slice(t);
+                    self.inputs_template = true;
+                }
+                if let Some(InputTensor::Float32(s)) = search {
+                    inputs.search_mut().copy_from_slice(s);
+                }
+                model.inference_buffers_into(inputs, &mut self.outputs)?;
+            }
+            (_, _, _, Some(search)) => {
+                model.inference_tensors_into(template, search, &mut self.outputs)?
+            }
+            (_, _, _, None) => unreachable!("search crop written without input buffers"),
+        }
+        // update_raw is not a tracked frame
+        let raw = mode == SearchMode::Raw;
+        let threshold = mode.threshold();
+        if let Some(recorder) = &mut self.tensor_recorder
+            && let Some(search) = search
+            && !raw
+        {
+            recorder.record(template, search);
+        }
+        self.shared.config.score_transform.apply(&mut self.outputs.conf_map);
+
+        // Fuse with the score history, aligned by the crop position
+        let fused = self.fusion.as_ref().map(|fusion| {
+            let origin = (
+                (rect[0] + (rect[2] - crop_size) / 2) as f32,
+                (rect[1] + (rect[3] - crop_size) / 2) as f32,
+            );
+            fusion.fuse(&self.outputs.conf_map, origin, crop_size as f32)
+        });
+        let conf_map = match &fused {
+            Some(fused) => &fused.conf,
+            None => &self.outputs.conf_map,
+        };
+
+        let adjusted;
+        let hanning = self.window.as_deref().unwrap_or(&self.shared.hanning);
+        let border = self.shared.config.score_border;
+        let window = if shift == (0, 0) && border == 0 {
+            hanning
+        } else {
+            let score_size = self.shared.config.score_size;
+            let mut window = if shift == (0, 0) {
+                hanning.to_vec()
+            } else {
+                let cells =
+                    |d: i32| (-d as f32 * score_size as f32 / crop_size as f32).round() as i32;
+                let (dx, dy) = (cells(shift.0), cells(shift.1));
+                shift_window(hanning, score_size, dx, dy)
+            };
+            suppress_border(&mut window, score_size, score_size, border);
+            adjusted = window;
+            &adjusted
+        };
+
+        // Process outputs
+        let mut result = process_outputs_with(
+            conf_map,
+            &self.outputs.size_map,
+            &self.outputs.offset_map,
+            window,
+            &rect,
+            crop_size,
+            threshold,
+            self.shared.config.score_size,
+            self.shared.config.localization,
+        );
+        // update_raw leaves all decisions to the caller
+        if let Some(guard) = &self.shared.config.teleport_guard
+            && result.success
+            && !raw
+        {
+            self.guard_teleport(guard, &mut result, conf_map, window, &rect, crop_size, threshold);
+        }
+        let config = &self.shared.config;
+        if (config.mask.is_some() || config.keypoints.is_some())
+            && !raw
+            && result.success
+            && self.head_candidate.as_ref().is_none_or(|heads| result.score > heads.score)
+        {
+            let origin = crop_origin(&rect, crop_size);
+            let mask = config.mask.as_ref().and_then(|mask| {
+                let transform = CropTransform { origin, crop_size };
+                TargetMask::decode(self.outputs.get(&mask.output)?, mask, transform)
+            });
+            let keypoints = config.keypoints.as_ref().map_or_else(Vec::new, |keypoints| {
+                self.outputs.get(&keypoints.output).map_or_else(Vec::new, |values| {
+                    decode_keypoints(values, keypoints, origin, crop_size)
+                })
+            });
+            self.head_candidate = Some(HeadCandidate {
+                score: result.score,
+                bbox: result.bbox,
+                mask,
+                keypoints,
+            });
+        }
+        let rect = if result.success { result.bbox } else { rect };
+
+        Ok((result, rect, fused))
+    }
+
+    /// Replace a peak too far from the predicted position with the best
+    /// candidate within reach, or reject it
+    #[allow(clippy::too_many_arguments)]
+    fn guard_teleport(
+        &self,
+        guard: &TeleportGuardConfig,
+        result: &mut TrackingResult,
+        conf_map: &[f32],
+        window: &[f32],
+        rect: &[i32; 4],
+        crop_size: i32,
+        threshold: f32,
+    ) {
+        const MAX_CANDIDATES: usize = 16;
+        let center = |bbox: &[i32; 4]| {
+            (bbox[0] as f32 + bbox[2] as f32 / 2.0, bbox[1] as f32 + bbox[3] as f32 / 2.0)
+        };
+        let predicted = match &self.kalman {
+            Some(kalman) => {
+                let [cx, cy, vx, vy] = kalman.state();
+                (cx + vx, cy + vy)
+            }
+            None => center(&self.rect_last),
+        };
+        let limit = guard.limit(&self.rect_last, self.missed_frames + 1)
+            * self.shared.config.frame_interval_ratio();
+        let reachable = |bbox: &[i32; 4]| {
+            let (x, y) = center(bbox);
+            (x - predicted.0).hypot(y - predicted.1) <= limit
+        };
+        if reachable(&result.bbox) {
+            return;
+        }
+
+        let score_size = self.shared.config.score_size;
+        let mut windowed = vec![0.0f32; conf_map.len()];
+        apply_window(conf_map, window, &mut windowed);
+        let mut peaks = [(0, 0.0f32); MAX_CANDIDATES];
+        let count = guard.candidates.clamp(1, MAX_CANDIDATES);
+        let found = top_peaks(&windowed, score_size, &mut peaks[..count]);
+        let origin = crop_origin(rect, crop_size);
+        let candidate = peaks[..found]
+            .iter()
+            .skip(1)
+            .take_while(|&&(_, score)| score >= threshold)
+            .map(|&(idx, score)| {
+                let crop_box =
+                    decode_box(&self.outputs.size_map, &self.outputs.offset_map, idx, score_size);
+                (crop_box.to_image(origin, crop_size), score)
+            })
+            .find(|(bbox, _)| reachable(bbox));
+        match candidate {
+            Some((bbox, score)) => {
+                result.bbox = bbox;
+                result.score = score;
+            }
+            None => {
+                result.success = false;
+                result.bbox = *rect;
+            }
+        }
+    }
+
+    /// Search the whole frame with the current template
+    ///
+    /// Runs at most `sweep_max_tiles` searches, plus one to refine the best
+    /// hit of a coarse sweep.
+    fn global_sweep(
+        &mut self,
+        image: &ArrayView3<u8>,
+        max_search_crop: Option<i32>,
+    ) -> Result<Option<(TrackingResult, [i32; 4])>, RknnError> {
+        let (img_h, img_w, _) = image.dim();
+        let [_, _, w, h] = self.rect_last;
+        let crop_size = crop_size(&BBox::from_array(&self.rect_last), self.search_factor());
+
+        let threshold = self.score_threshold();
+        let max_tiles = self.shared.config.sweep_max_tiles;
+        let rects = sweep_rects((img_w, img_h), (w, h), crop_size, max_tiles);
+        let coarse = rects.first().is_some_and(|rect| rect[2] != w || rect[3] != h);
+        let mut best: Option<(TrackingResult, [i32; 4], f32)> = None;
+        for rect in rects {
+            let (result, rect, _) =
+                self.search_at(image, rect, false, max_search_crop, SearchMode::Track(threshold))?;
+            if !result.success {
+                continue;
+            }
+
+            // Rank by appearance as well, rejecting grossly different candidates
+            let rank = match (&self.shared.config.redetect_histogram, &self.histogram) {
+                (Some(config), Some(histogram)) => {
+                    let similarity =
+                        histogram.similarity(&ColorHistogram::from_region(image, &rect));
+                    if similarity < config.min_similarity {
+                        continue;
+                    }
+                    (1.0 - config.weight) * result.score + config.weight * similarity
+                }
+                _ => result.score,
+            };
+            if best.as_ref().is_none_or(|(_, _, b)| rank > *b) {
+                best = Some((result, rect, rank));
+            }
+        }
+
+        // Refine a coarse hit with a search at the normal scale around it
+        if coarse && let Some((_, [x, y, bw, bh], _)) = best {
+            let around = [x + bw / 2 - w / 2, y + bh / 2 - h / 2, w, h];
+            let mode = SearchMode::Track(threshold);
+            let (result, rect, _) = self.search_at(image, around, false, max_search_crop, mode)?;
+            if result.success {
+                return Ok(Some((result, rect)));
+            }
+        }
+
+        Ok(best.map(|(result, rect, _)| (result, rect)))
+    }
+
+    /// Ask the application for a new init box and track from it
+    fn request_reinit(
+        &mut self,
+        image: &ArrayView3<u8>,
+    ) -> Result<Option<(TrackingResult, [i32; 4])>, RknnError> {
+        let Some(bbox) = self.reinit_callback.as_mut().and_then(|callback| callback(image)) else {
+            return Ok(None);
+        };
+
+        self.init(image, bbox);
+        let threshold = self.score_threshold();
+        let (mut result, rect, _) =
+            self.search_at(image, bbox.to_array(), false, None, SearchMode::Track(threshold))?;
+        if !result.success {
+            // Trust the application box even if the first search is weak
+            result.success = true;
+            result.bbox = bbox.to_array();
+            return Ok(Some((result, bbox.to_array())));
+        }
+
+        Ok(Some((result, rect)))
+    }
+
+    /// Compensate a known camera zoom before the next update
+    ///
+    /// Scales the last bounding box about the frame center so the search region
+    /// follows the target when the optical zoom changes faster than the size head
+    /// can follow.
+    ///
+    /// # Arguments
+    /// * `factor` - Zoom ratio between the next frame and the previous one
+    /// * `frame_size` - Frame (width, height) in pixels
+    pub fn apply_zoom(&mut self, factor: f32, frame_size: (usize, usize)) {
+        let center_x = frame_size.0 as f32 / 2.0;
+        let center_y = frame_size.1 as f32 / 2.0;
+        let [x, y, w, h] = self.rect_last.map(|v| v as f32);
+
+        self.rect_last = [
+            (center_x + (x - center_x) * factor).round() as i32,
+            (center_y + (y - center_y) * factor).round() as i32,
+            (w * factor).round().max(1.0) as i32,
+            (h * factor).round().max(1.0) as i32,
+        ];
+    }
+
+    /// Override the search factor until reset with `None`
+    ///
+    /// Widens (or narrows) the search region without rebuilding the tracker,
+    /// e.g. right after a known gimbal slew.
+    pub fn set_search_factor(&mut self, factor: Option<u32>) {
+        self.search_factor = factor.map(|factor| factor.max(1));
+    }
+
+    /// Search factor currently in effect, enlarged while re-detecting
+    pub fn search_factor(&self) -> u32 {
+        let factor = self.search_factor.unwrap_or(self.shared.config.search_factor);
+        match &self.shared.config.redetection {
+            Some(redetection) => redetection.search_factor(factor, self.missed_frames),
+            None => factor,
+        }
+    }
+
+    /// Search region growth while coasting through or detecting an occlusion
+    fn search_widening(&self) -> f32 {
+        let mut widen = match &self.shared.config.coasting {
+            Some(coasting) => 1.0 + coasting.search_growth * self.coast_frames as f32,
+            None => 1.0,
+        };
+        if let Some(occlusion) = &self.occlusion
+            && self.result_last.occluded
+        {
+            widen *= 1.0 + occlusion.config().search_growth;
+        }
+        widen
+    }
+
+    /// Whether the search is enlarged after a recent loss (`redetection`)
+    pub fn redetecting(&self) -> bool {
+        self.shared
+            .config
+            .redetection
+            .is_some_and(|redetection| redetection.active(self.missed_frames))
+    }
+
+    /// Success threshold of the next frame
+    ///
+    /// `score_threshold`, raised to the re-lock threshold while re-detecting.
+    fn decision_threshold(&self) -> f32 {
+        let threshold = self.score_threshold();
+        match &self.shared.config.redetection {
+            Some(redetection) if self.redetecting() => threshold.max(redetection.lock_threshold),
+            _ => threshold,
+        }
+    }
+
+    /// Approximate memory used by the model and this tracker
+    ///
+    /// The model and NPU parts are shared by all trackers on the same
+    /// `VitTrackModel`; only `buffer_bytes` adds up per tracker. The search
+    /// tensor allocated each frame is counted.
+    pub fn resource_usage(&self) -> ResourceUsage {
+        let config = &self.shared.config;
+        let model_bytes = self.shared.model.file_bytes()
+            + self.shared.fast_model.as_ref().map_or(0, RknnModel::file_bytes)
+            + self.shared.template_encoder.as_ref().map_or(0, RknnModel::file_bytes);
+        let npu_bytes = match self.shared.model.device() {
+            Device::Npu => crate::rknn::npu_mapped_bytes(),
+            _ => None,
+        };
+
+        let element = match config.input_type {
+            InputType::Float32 => 4,
+            InputType::Float16 => 2,
+            InputType::Uint8 | InputType::Int8 { .. } => 1,
+        };
+        let floats = self.outputs.conf_map.capacity()
+            + self.outputs.size_map.capacity()
+            + self.outputs.offset_map.capacity()
+            + self.outputs.extra.iter().map(|output| output.data.capacity()).sum::<usize>()
+            + self.shared.hanning.len()
+            + self.window.as_ref().map_or(0, |window| window.len());
+        // Reused input buffers hold the search tensor; otherwise one is built per frame
+        let search_bytes = config.search_size * config.search_size * 3 * element;
+        let mut buffers = floats * size_of::<f32>()
+            + self.inputs.as_ref().map_or(search_bytes, InputBuffers::byte_len)
+            + self.template.as_ref().map_or(0, InputTensor::byte_len)
+            + self.template_features.as_ref().map_or(0, |f| f.len() * size_of::<f32>())
+            + self.keyframe.as_ref().map_or(0, |keyframe| keyframe.crop.len())
+            + self.score_history.capacity() * size_of::<ScoreSample>();
+        if let Some(history) = &self.template_history {
+            buffers += history.snapshots().map(|s| s.template.byte_len()).sum::<usize>();
+        }
+        if let Some(recorder) = &self.tensor_recorder {
+            buffers += recorder.memory_bytes();
+        }
+        if let Some(cache) = &self.stationary {
+            buffers += cache.memory_bytes();
+        }
+
+        ResourceUsage {
+            model_bytes,
+            npu_bytes,
+            buffer_bytes: buffers as u64,
+        }
+    }
+
+    /// Metrics of the last `n` updated frames, oldest first
+    ///
+    /// At most `score_history_len` frames are kept; the history restarts at `init`.
+    pub fn score_history(&self, n: usize) -> impl Iterator<Item = &ScoreSample> {
+        self.score_history.iter().skip(self.score_history.len().saturating_sub(n))
+    }
+
+    /// Override the configured score threshold for this tracker
+    ///
+    /// None restores `score_threshold` from the config. With an adaptive
+    /// threshold the override replaces the fixed fallback.
+    pub fn set_score_threshold(&mut self, threshold: Option<f32>) {
+        self.score_threshold = threshold;
+    }
+
+    /// Blend the Hann window with a flat one
+    ///
+    /// 0 ignores the window (large jumps are as likely as small ones), 1 is
+    /// the full window; None restores it. Scaled down below the reference
+    /// frame rate like the default window.
+    pub fn set_window_influence(&mut self, influence: Option<f32>) {
+        self.window_influence = influence;
+        let config = &self.shared.config;
+        self.window = influence.filter(|&k| k < 1.0).map(|influence| {
+            cached_window(WindowSpec {
+                influence: config.window_influence(influence),
+                ..WindowSpec::hann(config.score_size)
+            })
+        });
+    }
+
+    /// Override the configured `score_smoothing` for this tracker
+    ///
+    /// None restores the configured weight; the running average is kept.
+    pub fn set_score_smoothing(&mut self, alpha: Option<f32>) {
+        self.score_smoothing = alpha;
+    }
+
+    /// Apply reloaded runtime settings; the track and template are kept
+    pub fn apply_settings(&mut self, settings: &RuntimeSettings) {
+        self.set_score_threshold(settings.score_threshold);
+        self.set_search_factor(settings.search_factor);
+        self.set_window_influence(settings.window_influence);
+        self.set_score_smoothing(settings.score_smoothing);
+    }
+
+    /// Runtime settings currently in effect (`None` = configured value)
+    pub fn settings(&self) -> RuntimeSettings {
+        RuntimeSettings {
+            score_threshold: self.score_threshold,
+            search_factor: self.search_factor,
+            window_influence: self.window_influence,
+            score_smoothing: self.score_smoothing,
+        }
+    }
+
+    /// Checkpoint the template, last box and runtime settings
+    ///
+    /// # Returns
+    /// * None before `init`
+    pub fn save_state(&self) -> Option<TrackerState> {
+        Some(TrackerState {
+            template: self.template.clone()?,
+            rect_last: self.rect_last,
+            settings: self.settings(),
+            config: StateConfig::from_config(&self.shared.config),
+        })
+    }
+
+    /// Resume a track saved with `save_state`, without a frame
+    ///
+    /// Like `init_with_tensor` with the saved template and box, then the
+    /// saved runtime settings. Motion, score and loss history start over;
+    /// a configured CPU fallback is retrained on the next successful frame.
+    /// The label and keyframe are not restored (set the label again; the
+    /// keyframe is taken on the next confident frame).
+    ///
+    /// # Returns
+    /// * `RknnError::InputError` naming the differing fields when the state
+    ///   was saved under another configuration than this tracker's
+    pub fn restore_state(&mut self, state: &TrackerState) -> Result<(), RknnError> {
+        let differences = state.config.differences(&StateConfig::from_config(&self.shared.config));
+        if !differences.is_empty() {
+            return Err(RknnError::InputError(format!(
+                "saved track was made under another configuration: {}",
+                differences.join(", ")
+            )));
+        }
+        self.init_with_tensor(state.template.clone(), BBox::from_array(&state.rect_last));
+        self.apply_settings(&state.settings);
+        Ok(())
+    }
+
+    /// Recorder of raw NPU inputs (only when `tensor_dump` is configured)
+    ///
+    /// Use it to request a dump of the current frame or to collect the
+    /// directory written by the last one.
+    pub fn tensor_recorder_mut(&mut self) -> Option<&mut TensorRecorder> {
+        self.tensor_recorder.as_mut()
+    }
+
+    /// Capture time of the frame passed to the next update
+    ///
+    /// Copied into `TrackingResult::timestamp` so tracks can be aligned with
+    /// other sensors on the capture clock rather than the processing time.
+    /// Applies to one update only.
+    pub fn set_frame_timestamp(&mut self, timestamp: Option<Duration>) {
+        self.frame_timestamp = timestamp;
+    }
+
+    /// Platform pose and target geolocation at the result's timestamp
+    fn sync_pose(&self, result: &mut TrackingResult) {
+        let Some(sync) = &self.shared.config.pose_sync else {
+            return;
+        };
+        result.platform = result.timestamp.and_then(|time| sync.pose_at(time));
+        result.geo = match (result.platform, result.angular) {
+            (Some(pose), Some(angular)) if result.success => {
+                pose.geolocate(&angular, sync.ground_altitude)
+            }
+            _ => None,
+        };
+    }
+
+    /// Success threshold currently in effect
+    pub fn score_threshold(&self) -> f32 {
+        let fixed = self.score_threshold.unwrap_or(self.shared.config.score_threshold);
+        match &self.threshold {
+            Some(threshold) => threshold.threshold(fixed),
+            None => fixed,
+        }
+    }
+
+    /// Attach an application identity, reported as `TrackingResult::tag`
+    ///
+    /// Kept across `init` and `reset`; None removes it.
+    pub fn set_label(&mut self, label: Option<TrackLabel>) {
+        self.label = label;
+    }
+
+    pub fn label(&self) -> Option<&TrackLabel> {
+        self.label.as_ref()
+    }
+
+    /// Replace the template; encoder features are recomputed on next use
+    fn set_template(&mut self, template: Option<InputTensor>) {
+        self.template = template;
+        self.template_features = None;
+        self.inputs_template = false;
+    }
+
+    /// Drop the template; `update` returns empty results until the next `init`
+    pub fn reset(&mut self) {
+        self.set_template(None);
+        self.reset_state(BBox::new(0, 0, 0, 0));
+    }
+
+    /// Confidence map of the last inference (score_size x score_size, row-major)
+    pub fn score_map(&self) -> &[f32] {
+        &self.outputs.conf_map
+    }
+
+    /// Model output of the last inference by tensor name
+    ///
+    /// Besides the tracking heads, returns the auxiliary outputs named in
+    /// `output_names` (quality branch, mask head, ...).
+    pub fn output(&self, name: &str) -> Option<&[f32]> {
+        self.outputs.get(name)
+    }
+
+    /// Target mask of the last frame
+    ///
+    /// None unless `mask` is configured and the last frame succeeded.
+    pub fn mask(&self) -> Option<&TargetMask> {
+        self.mask.as_ref()
+    }
+
+    /// Target keypoints of the last frame in frame pixels, in head order
+    ///
+    /// Empty unless `keypoints` is configured and the last frame succeeded;
+    /// `keypoint_orientation` turns them into an angle.
+    pub fn keypoints(&self) -> &[Keypoint] {
+        &self.keypoints
+    }
+
+    /// `update` returning the target mask along with the result
+    pub fn update_masked(&mut self, image: &ArrayView3<u8>) -> Result<MaskedResult, RknnError> {
+        let result = self.update(image)?;
+        Ok(MaskedResult {
+            result,
+            mask: self.mask.clone(),
+        })
+    }
+
+    /// Raw model outputs of the last inference, borrowed from the reused buffers
+    pub fn output_views(&self) -> Result<OutputViews<'_>, RknnError> {
+        self.outputs.views(self.shared.config.score_size)
+    }
+
+    /// Get current bounding box
+    pub fn get_bbox(&self) -> [i32; 4] {
+        self.rect_last
+    }
+
+    /// Box the motion model expects `frames` frames after the last update
+    ///
+    /// E.g. to steer a gimbal between updates at a lower rate than the
+    /// control loop. None without a motion model (see `kalman_noise`).
+    pub fn predicted_bbox(&self, frames: f32) -> Option<[i32; 4]> {
+        self.kalman.as_ref().map(|kalman| kalman.predicted(frames))
+    }
+
+    /// Move the last box, keeping template and motion model
+    ///
+    /// For positions agreed with other trackers or sensors; `update` searches
+    /// around it next.
+    pub fn set_bbox(&mut self, rect: [i32; 4]) {
+        self.rect_last = rect;
+    }
+
+    /// Check if tracker is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.template.is_some()
+    }
+}
+
+/// Several targets tracked on one shared model
+///
+/// Each target is a `VitTrack` with its own template and state, keyed by an
+/// application id that is also reported as `TrackingResult::tag`. Targets
+/// run on the NPU one after another, in id order.
+pub struct MultiVitTrack {
+    shared: Arc<VitTrackModel>,
+    targets: BTreeMap<u32, VitTrack>,
+}
+
+impl MultiVitTrack {
+    pub fn new(shared: Arc<VitTrackModel>) -> Self {
+        Self {
+            shared,
+            targets: BTreeMap::new(),
+        }
+    }
+
+    /// Start tracking a target, or re-initialize the target with this id
+    ///
+    /// # Arguments
+    /// * `id` - Application id of the target
+    /// * `image` - Frame showing the target, HWC RGB
+    /// * `bbox` - Target box in `image`
+    pub fn init_target(&mut self, id: u32, image: &ArrayView3<u8>, bbox: BBox) -> TemplateQuality {
+        let tracker = self.targets.entry(id).or_insert_with(|| {
+            let mut tracker = VitTrack::with_model(self.shared.clone());
+            tracker.set_label(Some(TrackLabel::new(id, id.to_string())));
+            tracker
+        });
+        tracker.init(image, bbox)
+    }
+
+    /// Stop tracking a target
+    ///
+    /// # Returns
+    /// * The target's tracker, None for an unknown id
+    pub fn remove_target(&mut self, id: u32) -> Option<VitTrack> {
+        self.targets.remove(&id)
+    }
+
+    /// Track every target in a new frame
+    ///
+    /// An inference error only fails its own target; the others are still
+    /// tracked.
+    ///
+    /// # Returns
+    /// * (id, result) per target, in id order
+    pub fn update_all(
+        &mut self,
+        image: &ArrayView3<u8>,
+    ) -> Vec<(u32, Result<TrackingResult, RknnError>)> {
+        self.targets
+            .iter_mut()
+            .map(|(&id, tracker)| (id, tracker.update(image)))
+            .collect()
+    }
+
+    /// Capture time of the next frame, for every target
+    pub fn set_frame_timestamp(&mut self, timestamp: Option<Duration>) {
+        for tracker in self.targets.values_mut() {
+            tracker.set_frame_timestamp(timestamp);
+        }
+    }
+
+    /// Tracker of one target, e.g. to change its label or threshold
+    pub fn target(&self, id: u32) -> Option<&VitTrack> {
+        self.targets.get(&id)
+    }
+
+    pub fn target_mut(&mut self, id: u32) -> Option<&mut VitTrack> {
+        self.targets.get_mut(&id)
+    }
+
+    /// Ids of the tracked targets, ascending
+    pub fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.targets.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+}
+
+/// Exponential moving average of the score; `alpha` is the weight of the
+/// new score (None = no smoothing), `average` None on the first frame
+fn smoothed_score(average: Option<f32>, score: f32, alpha: Option<f32>) -> f32 {
+    let alpha = alpha.map_or(1.0, |a| a.clamp(0.0, 1.0));
+    match average {
+        Some(average) => average + alpha * (score - average),
+        None => score,
+    }
+}
+
+/// Scale a rectangle [x, y, w, h] about its center
+fn scale_rect(rect: &[i32; 4], scale: f32) -> [i32; 4] {
+    if scale == 1.0 {
+        return *rect;
+    }
+
+    let [x, y, w, h] = rect.map(|v| v as f32);
+    let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+    let (sw, sh) = ((w * scale).max(1.0), (h * scale).max(1.0));
+
+    [
+        (cx - sw / 2.0).round() as i32,
+        (cy - sh / 2.0).round() as i32,
+        sw.round() as i32,
+        sh.round() as i32,
+    ]
+}
+
+/// Every `step`-th pixel of a frame (zero-copy strided view)
+fn downsampled<'a>(image: &ArrayView3<'a, u8>, step: usize) -> ArrayView3<'a, u8> {
+    let mut view = *image;
+    if step > 1 {
+        view.slice_collapse(s![..;step, ..;step, ..]);
+    }
+    view
+}
+
+/// Offset that moves the search crop around `rect` inside the frame
+///
+/// Crops larger than the frame stay centered on that axis.
+fn border_shift(rect: &[i32; 4], crop_size: i32, (width, height): (i32, i32)) -> (i32, i32) {
+    let (x1, y1) = crop_origin(rect, crop_size);
+    let keep_in = |start: i32, limit: i32| {
+        if crop_size >= limit {
+            0
+        } else {
+            start.clamp(0, limit - crop_size) - start
+        }
+    };
+    (keep_in(x1, width), keep_in(y1, height))
+}
+
+/// Union of the search crops around `rect` at each scale
+fn search_region(rect: &[i32; 4], scales: &[f32], widen: f32, search_factor: u32) -> BBox {
+    let (mut x1, mut y1, mut x2, mut y2) = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
+    for &scale in scales {
+        let crop = CropTransform::around(
+            &BBox::from_array(&scale_rect(rect, scale * widen)),
+            search_factor,
+        );
+        x1 = x1.min(crop.origin.0);
+        y1 = y1.min(crop.origin.1);
+        x2 = x2.max(crop.origin.0 + crop.crop_size);
+        y2 = y2.max(crop.origin.1 + crop.crop_size);
+    }
+    if x1 > x2 {
+        return BBox::from_array(rect);
+    }
+    BBox::new(x1, y1, x2 - x1, y2 - y1)
+}
+
+/// Box in the coordinates of a frame downsampled by `step`
+fn scale_down(bbox: &BBox, step: usize) -> BBox {
+    let step = step as i32;
+    BBox::new(
+        bbox.x.div_euclid(step),
+        bbox.y.div_euclid(step),
+        (bbox.width / step).max(1),
+        (bbox.height / step).max(1),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rknn::TEST_MODEL;
+
+    /// Model on the NPU for the hardware tests
+    fn npu_model(config: VitTrackConfig) -> Arc<VitTrackModel> {
+        let config = VitTrackConfig {
+            device: Device::Npu,
+            ..config
+        };
+        Arc::new(VitTrackModel::load(TEST_MODEL, config).unwrap())
+    }
+
+    #[test]
+    fn test_border_shift() {
+        // 160 px crop around a box at the left edge moves right by 70 px
+        assert_eq!(border_shift(&[0, 100, 20, 20], 160, (640, 480)), (70, 0));
+        assert_eq!(border_shift(&[300, 460, 20, 20], 160, (640, 480)), (0, -70));
+        assert_eq!(border_shift(&[300, 200, 20, 20], 160, (640, 480)), (0, 0));
+        assert_eq!(border_shift(&[0, 0, 20, 20], 800, (640, 480)), (0, 0));
+    }
+
+    #[test]
+    fn test_search_region_covers_all_scales() {
+        let rect = [100, 100, 40, 40];
+        let single = search_region(&rect, &[1.0], 1.0, 4);
+        let crop = CropTransform::around(&BBox::from_array(&rect), 4);
+        assert_eq!(
+            single.to_array(),
+            [crop.origin.0, crop.origin.1, crop.crop_size, crop.crop_size]
+        );
+
+        let multi = search_region(&rect, &[0.8, 1.0, 1.25], 1.0, 4);
+        assert!(multi.width > single.width && multi.x < single.x);
+        assert_eq!(search_region(&rect, &[1.0], 1.25, 4).to_array(), multi.to_array());
+    }
+
+    #[test]
+    #[ignore = "needs RKNPU"]
+    fn test_multi_target_bookkeeping() {
+        let model = npu_model(VitTrackConfig::default());
+        let frame = Array3::<u8>::zeros((240, 320, 3));
+        let mut multi = MultiVitTrack::new(model);
+
+        multi.init_target(7, &frame.view(), BBox::new(10, 10, 30, 30));
+        multi.init_target(3, &frame.view(), BBox::new(200, 100, 40, 20));
+        multi.init_target(7, &frame.view(), BBox::new(50, 60, 30, 30));
+        assert_eq!(multi.ids().collect::<Vec<_>>(), [3, 7]);
+        assert_eq!(multi.target(7).map(|t| t.get_bbox()), Some([50, 60, 30, 30]));
+        assert_eq!(multi.target(3).and_then(|t| t.label()).map(|l| l.tag.id), Some(3));
+
+        let results = multi.update_all(&frame.view());
+        assert_eq!(results.iter().map(|(id, _)| *id).collect::<Vec<_>>(), [3, 7]);
+
+        assert!(multi.remove_target(3).is_some());
+        assert!(multi.remove_target(3).is_none());
+        assert_eq!(multi.len(), 1);
+    }
+
+    #[test]
+    #[ignore = "needs RKNPU"]
+    fn test_swap_model_rebuilds_window() {
+        let small = npu_model(VitTrackConfig::default());
+        let large = npu_model(VitTrackConfig {
+            score_size: 20,
+            ..VitTrackConfig::default()
+        });
+        let mut tracker = VitTrack::with_model(small.clone());
+        tracker.set_window_influence(Some(0.5));
+
+        tracker.swap_model(large);
+        assert_eq!(tracker.window.as_ref().map(|w| w.len()), Some(20 * 20));
+        tracker.swap_model(small);
+        assert_eq!(tracker.window.as_ref().map(|w| w.len()), Some(16 * 16));
+    }
+
+    #[test]
+    #[ignore = "needs RKNPU"]
+    fn test_update_raw_leaves_state() {
+        let model = npu_model(VitTrackConfig {
+            stationary: Some(StationaryConfig::default()),
+            ..VitTrackConfig::default()
+        });
+        let frames: Vec<Array3<u8>> = (0..4)
+            .map(|t| {
+                Array3::from_shape_fn((240, 320, 3), |(y, x, c)| ((x + y * 2 + c + t) % 256) as u8)
+            })
+            .collect();
+        let mut plain = VitTrack::with_model(model.clone());
+        let mut probed = VitTrack::with_model(model);
+        plain.init(&frames[0].view(), BBox::new(100, 80, 40, 30));
+        probed.init(&frames[0].view(), BBox::new(100, 80, 40, 30));
+
+        for frame in &frames[1..] {
+            probed.update_raw(&frame.view()).unwrap();
+            let expected = plain.update(&frame.view()).unwrap();
+            let result = probed.update(&frame.view()).unwrap();
+            assert_eq!((result.success, result.bbox), (expected.success, expected.bbox));
+            assert_eq!((result.score, result.cached), (expected.score, expected.cached));
+        }
+    }
+
+    #[test]
+    #[ignore = "needs RKNPU"]
+    fn test_save_and_restore_state() {
+        let model = npu_model(VitTrackConfig::default());
+        let frame = Array3::from_shape_fn((240, 320, 3), |(y, x, c)| ((x * 3 + y + c) % 256) as u8);
+        let mut tracker = VitTrack::with_model(model.clone());
+        assert!(tracker.save_state().is_none());
+        tracker.init(&frame.view(), BBox::new(100, 80, 40, 30));
+        tracker.set_search_factor(Some(5));
+        let state = tracker.save_state().unwrap();
+
+        let mut resumed = VitTrack::with_model(model);
+        resumed.restore_state(&state).unwrap();
+        assert_eq!(resumed.get_bbox(), [100, 80, 40, 30]);
+        assert_eq!(resumed.search_factor(), 5);
+        assert_eq!(resumed.save_state(), Some(state.clone()));
+
+        let mut other = state.clone();
+        other.config.score_threshold = 0.5;
+        other.config.search_scales = vec![0.9, 1.0];
+        let Err(RknnError::InputError(message)) = resumed.restore_state(&other) else {
+            panic!("restored a state saved under another configuration");
+        };
+        assert!(message.ends_with("score_threshold, search_scales"));
+    }
+
+    #[test]
+    #[ignore = "needs RKNPU"]
+    fn test_template_encoder_loads_float_inputs() {
+        let model = npu_model(VitTrackConfig {
+            input_type: InputType::Uint8,
+            template_encoder_path: Some(PathBuf::from(TEST_MODEL)),
+            ..VitTrackConfig::default()
+        });
+        assert_eq!(model.config().input_type, InputType::Float32);
+
+        let tracker = VitTrack::with_model(model);
+        let file_bytes = std::fs::metadata(TEST_MODEL).unwrap().len();
+        assert_eq!(tracker.resource_usage().model_bytes, 2 * file_bytes);
+    }
+
+    #[test]
+    #[ignore = "needs RKNPU"]
+    fn test_resource_usage_counts_search_tensor_once() {
+        let config = VitTrackConfig::default();
+        let mut tracker = VitTrack::with_model(npu_model(config.clone()));
+        assert!(tracker.inputs.is_some());
+        let reused = tracker.resource_usage().buffer_bytes;
+
+        // Without reused inputs only the per-frame search tensor is counted
+        tracker.inputs = None;
+        let per_frame = tracker.resource_usage().buffer_bytes;
+        let template_bytes = config.template_size * config.template_size * 3 * size_of::<f32>();
+        assert_eq!(reused - per_frame, template_bytes as u64);
+    }
+
+    #[test]
+    fn test_score_smoothing() {
+        let mut average = None;
+        let smoothed = [0.8, 0.0, 0.4].map(|score| {
+            let smoothed = smoothed_score(average, score, Some(0.5));
+            average = Some(smoothed);
+            smoothed
+        });
+        assert_eq!(smoothed, [0.8, 0.4, 0.4]);
+        // Without smoothing the average follows the score
+        assert_eq!(smoothed_score(Some(0.5), 0.25, None), 0.25);
+    }
+
+    #[test]
+    fn test_frame_rate_scaling() {
+        let at = |fps| VitTrackConfig {
+            frame_rate: fps,
+            ..VitTrackConfig::default()
+        };
+        assert_eq!(at(None).frame_interval_ratio(), 1.0);
+        assert_eq!(at(Some(10.0)).frame_interval_ratio(), 3.0);
+        assert_eq!(at(Some(60.0)).frame_interval_ratio(), 0.5);
+        assert!((at(Some(10.0)).window_influence(0.9) - 0.3).abs() < 1e-6);
+        assert_eq!(at(Some(60.0)).window_influence(0.9), 0.9);
+    }
+
+    #[test]
+    fn test_deterministic_skips_wall_clock_state() {
+        let mut config = VitTrackConfig {
+            latency_budget: Some(Duration::from_millis(20)),
+            adaptive_threshold: Some(AdaptiveThresholdConfig::default()),
+            ..VitTrackConfig::default()
+        };
+        assert!(config.build_budget(true).is_some());
+        assert!(config.build_threshold().is_some());
+
+        config.deterministic = true;
+        assert!(config.build_budget(true).is_none());
+        assert!(config.build_threshold().is_none());
+    }
+
+    #[test]
+    fn test_downsampled_crop_coordinates() {
+        let image = Array3::from_shape_fn((8, 8, 3), |(y, x, _)| (y * 8 + x) as u8);
+        let view = downsampled(&image.view(), 2);
+        assert_eq!(view.dim(), (4, 4, 3));
+        assert_eq!(view[[1, 3, 0]], 2 * 8 + 6);
+
+        let bbox = scale_down(&BBox::new(-3, 5, 9, 1), 2);
+        assert_eq!(bbox.to_array(), [-2, 2, 4, 1]);
+    }
+}
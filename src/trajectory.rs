@@ -0,0 +1,120 @@
+use std::collections::VecDeque;
+
+use crate::postprocess::TrackingResult;
+
+/// Results of the inferred frames, keyed by frame index
+///
+/// Fills frames that were skipped (inference at a lower rate than capture)
+/// by interpolating between the surrounding results, so smoothing and export
+/// get one box per frame.
+#[derive(Debug, Clone)]
+pub struct Trajectory {
+    capacity: usize,
+    samples: VecDeque<(u64, TrackingResult)>,
+}
+
+impl Trajectory {
+    /// # Arguments
+    /// * `capacity` - Number of inferred frames kept
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            samples: VecDeque::with_capacity(capacity.max(1)),
+        }
+    }
+
+    /// Record the result of an inferred frame
+    ///
+    /// Frame indices must increase; a repeated index replaces the last result
+    /// and an older index is ignored.
+    pub fn push(&mut self, frame: u64, result: TrackingResult) {
+        match self.samples.back_mut() {
+            Some((last, _)) if frame < *last => return,
+            Some((last, stored)) if frame == *last => {
+                *stored = result;
+                return;
+            }
+            _ => {}
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((frame, result));
+    }
+
+    /// Result at a frame, interpolated when it was not inferred
+    ///
+    /// # Returns
+    /// * None outside the recorded range
+    pub fn at(&self, frame: u64) -> Option<TrackingResult> {
+        let next = self.samples.partition_point(|(f, _)| *f < frame);
+        let (next_frame, next_result) = self.samples.get(next)?;
+        if *next_frame == frame {
+            return Some(*next_result);
+        }
+        let (prev_frame, prev_result) = self.samples.get(next.checked_sub(1)?)?;
+        let t = (frame - prev_frame) as f32 / (next_frame - prev_frame) as f32;
+        Some(TrackingResult::interpolate(prev_result, next_result, t))
+    }
+
+    /// One result per frame from the first to the last recorded frame
+    pub fn dense(&self) -> impl Iterator<Item = (u64, TrackingResult)> + '_ {
+        let range = match (self.samples.front(), self.samples.back()) {
+            (Some((first, _)), Some((last, _))) => *first..*last + 1,
+            _ => 0..0,
+        };
+        range.filter_map(|frame| self.at(frame).map(|result| (frame, result)))
+    }
+
+    /// Recorded (inferred) frames, oldest first
+    pub fn samples(&self) -> impl Iterator<Item = &(u64, TrackingResult)> {
+        self.samples.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::Degradation;
+
+    fn result(bbox: [i32; 4], score: f32) -> TrackingResult {
+        TrackingResult {
+            success: true,
+            bbox,
+            score,
+            ..TrackingResult::default()
+        }
+    }
+
+    #[test]
+    fn test_fills_skipped_frames() {
+        let mut trajectory = Trajectory::new(8);
+        trajectory.push(10, result([0, 0, 20, 20], 0.8));
+        trajectory.push(14, result([40, 8, 20, 24], 0.4));
+
+        let dense: Vec<_> = trajectory.dense().collect();
+        assert_eq!(dense.len(), 5);
+        let (frame, mid) = dense[2];
+        assert_eq!(frame, 12);
+        assert_eq!(mid.bbox, [20, 4, 20, 22]);
+        assert!((mid.score - 0.6).abs() < 1e-6);
+        assert!(mid.success);
+        assert_eq!(mid.degradation, Degradation::SkippedInference);
+        assert_eq!(dense[0].1.degradation, Degradation::None);
+
+        assert!(trajectory.at(9).is_none());
+        assert!(trajectory.at(15).is_none());
+    }
+}
@@ -0,0 +1,122 @@
+//! wasm-bindgen wrapper of the pre/post-processing math
+//!
+//! Exposes cropping, tensor normalization and output decoding on flat buffers
+//! so a browser tool can load dumped tensors and step through the decoding.
+//! Build without the RKNN backend and generate the JS bindings:
+//!
+//! ```text
+//! cargo rustc --lib --release --no-default-features --features wasm \
+//!     --target wasm32-unknown-unknown --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/vit_tracker.wasm
+//! ```
+
+use ndarray::ArrayView3;
+use wasm_bindgen::prelude::*;
+
+use crate::postprocess::{self, Localization};
+use crate::preprocess::{self, BBox};
+
+/// 2D Hanning window (rows x cols, row-major)
+#[wasm_bindgen]
+pub fn hann2d(rows: usize, cols: usize) -> Vec<f32> {
+    postprocess::hann2d(rows, cols)
+}
+
+/// Crop and resize an RGB frame around a box
+///
+/// # Returns
+/// * RGB crop (output_size x output_size x 3); the crop size in frame pixels
+///   is `crop_size(bbox, factor)`
+#[wasm_bindgen]
+pub fn crop(
+    rgb: &[u8],
+    width: usize,
+    height: usize,
+    bbox: &[i32],
+    factor: u32,
+    output_size: usize,
+) -> Result<Vec<u8>, JsError> {
+    let image = frame_view(rgb, width, height)?;
+    let (crop, _) = preprocess::crop_resized(&image, &to_bbox(bbox)?, factor, output_size);
+    Ok(crop.into_raw_vec_and_offset().0)
+}
+
+/// Crop size in frame pixels for a box and context factor
+#[wasm_bindgen]
+pub fn crop_size(bbox: &[i32], factor: u32) -> Result<i32, JsError> {
+    Ok(preprocess::crop_size(&to_bbox(bbox)?, factor))
+}
+
+/// Normalize an RGB crop into the float32 NHWC model input
+#[wasm_bindgen]
+pub fn preprocess_nhwc(rgb: &[u8], width: usize, height: usize) -> Result<Vec<f32>, JsError> {
+    let image = frame_view(rgb, width, height)?;
+    Ok(preprocess::preprocess_nhwc(&image.to_owned()))
+}
+
+/// Decode one frame of model outputs
+///
+/// # Arguments
+/// * `temperature` - Soft-argmax temperature; 0 selects hard argmax
+///
+/// # Returns
+/// * [success, score, peak_ratio, x, y, w, h]
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn decode(
+    conf_map: &[f32],
+    size_map: &[f32],
+    offset_map: &[f32],
+    hanning: &[f32],
+    rect_last: &[i32],
+    crop_size: i32,
+    threshold: f32,
+    temperature: f32,
+) -> Result<Vec<f32>, JsError> {
+    let rect_last: [i32; 4] = rect_last
+        .try_into()
+        .map_err(|_| JsError::new("rect_last must have 4 elements"))?;
+    if conf_map.len() != 256 || size_map.len() != 512 || offset_map.len() != 512 {
+        return Err(JsError::new("expected 16x16 outputs (256, 512, 512 elements)"));
+    }
+
+    let localization = if temperature > 0.0 {
+        Localization::SoftArgmax { temperature }
+    } else {
+        Localization::Argmax
+    };
+    let result = postprocess::process_outputs_with(
+        conf_map,
+        size_map,
+        offset_map,
+        hanning,
+        &rect_last,
+        crop_size,
+        threshold,
+        16,
+        localization,
+    );
+
+    let [x, y, w, h] = result.bbox.map(|v| v as f32);
+    Ok(vec![
+        if result.success { 1.0 } else { 0.0 },
+        result.score,
+        result.peak_ratio,
+        x,
+        y,
+        w,
+        h,
+    ])
+}
+
+fn frame_view(rgb: &[u8], width: usize, height: usize) -> Result<ArrayView3<'_, u8>, JsError> {
+    ArrayView3::from_shape((height, width, 3), rgb)
+        .map_err(|_| JsError::new("buffer length must be width * height * 3"))
+}
+
+fn to_bbox(bbox: &[i32]) -> Result<BBox, JsError> {
+    match *bbox {
+        [x, y, w, h] => Ok(BBox::new(x, y, w, h)),
+        _ => Err(JsError::new("bbox must be [x, y, w, h]")),
+    }
+}
@@ -0,0 +1,213 @@
+use std::time::{Duration, Instant};
+
+/// What to do once the target has been lost for too long
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReinitAction {
+    /// Search the whole frame with the current template
+    #[default]
+    GlobalSweep,
+    /// Ask the application for a new init box via the reinit callback
+    Callback,
+}
+
+/// Watchdog event reported in the tracking result
+#[derive(Debug, Clone, Copy)]
+pub struct ReinitEvent {
+    pub action: ReinitAction,
+    /// How long the target had been lost when the watchdog fired
+    pub lost_for: Duration,
+    /// Whether the target was reacquired
+    pub recovered: bool,
+}
+
+/// Fires after the track has been lost continuously for `timeout`
+#[derive(Debug, Clone)]
+pub struct LossWatchdog {
+    timeout: Duration,
+    lost_since: Option<Instant>,
+}
+
+impl LossWatchdog {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            lost_since: None,
+        }
+    }
+
+    /// Observe a tracking outcome; returns the loss duration when the watchdog fires
+    ///
+    /// The timer restarts after firing, so a failed recovery is retried after
+    /// another `timeout`.
+    pub fn observe(&mut self, success: bool, now: Instant) -> Option<Duration> {
+        if success {
+            self.lost_since = None;
+            return None;
+        }
+
+        let since = *self.lost_since.get_or_insert(now);
+        let lost_for = now.duration_since(since);
+        if lost_for >= self.timeout {
+            self.lost_since = Some(now);
+            Some(lost_for)
+        } else {
+            None
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.lost_since = None;
+    }
+}
+
+/// Progressive re-detection right after the target is lost
+///
+/// For `frames` updates after a loss the search factor grows by `growth`
+/// per lost frame (up to `max_search_factor`), so the search crop catches a
+/// target that moved out of the normal region. While searching this way a
+/// peak only re-locks the track when it scores at least `lock_threshold`,
+/// which keeps the wider crop from latching onto a distractor.
+#[derive(Debug, Clone, Copy)]
+pub struct RedetectionConfig {
+    /// Lost frames the enlarged search lasts (K)
+    pub frames: u32,
+    /// Search factor growth per lost frame (0.5 = +50% per frame)
+    pub growth: f32,
+    /// Upper bound of the enlarged search factor
+    pub max_search_factor: u32,
+    /// Minimum score to re-lock while re-detecting
+    pub lock_threshold: f32,
+}
+
+impl Default for RedetectionConfig {
+    fn default() -> Self {
+        Self {
+            frames: 30,
+            growth: 0.5,
+            max_search_factor: 12,
+            lock_threshold: 0.5,
+        }
+    }
+}
+
+impl RedetectionConfig {
+    /// Whether the tracker is re-detecting after `missed_frames` lost updates
+    pub fn active(&self, missed_frames: u32) -> bool {
+        (1..=self.frames).contains(&missed_frames)
+    }
+
+    /// Search factor to use after `missed_frames` lost updates
+    ///
+    /// # Arguments
+    /// * `base` - Search factor used while tracking
+    /// * `missed_frames` - Updates since the last successful frame
+    pub fn search_factor(&self, base: u32, missed_frames: u32) -> u32 {
+        if !self.active(missed_frames) {
+            return base;
+        }
+        let grown = base as f32 * (1.0 + self.growth * missed_frames as f32);
+        (grown.round() as u32).clamp(base, self.max_search_factor.max(base))
+    }
+}
+
+/// Boxes tiling the frame for a global sweep, at most `max_tiles` of them
+///
+/// Tiles are spaced so that neighbouring search crops (of `crop_size` pixels)
+/// overlap by a quarter. When that takes more than `max_tiles` crops, box and
+/// crop grow by the smallest factor (in steps of 25%) that fits, so the frame
+/// is swept on a coarser grid at a lower resolution; the returned boxes are
+/// then larger than `box_size`.
+pub fn sweep_rects(
+    frame_size: (usize, usize),
+    box_size: (i32, i32),
+    crop_size: i32,
+    max_tiles: usize,
+) -> Vec<[i32; 4]> {
+    let (frame_w, frame_h) = (frame_size.0 as i32, frame_size.1 as i32);
+    let mut scale = 1.0f32;
+    loop {
+        let crop = (crop_size as f32 * scale).round() as i32;
+        let (xs, ys) = (sweep_centers(frame_w, crop), sweep_centers(frame_h, crop));
+        // A crop covering the whole frame always fits
+        if xs.len() * ys.len() <= max_tiles.max(1) || crop >= frame_w.max(frame_h) {
+            let w = (box_size.0 as f32 * scale).round() as i32;
+            let h = (box_size.1 as f32 * scale).round() as i32;
+            return ys
+                .iter()
+                .flat_map(|&cy| xs.iter().map(move |&cx| [cx - w / 2, cy - h / 2, w, h]))
+                .collect();
+        }
+        scale *= 1.25;
+    }
+}
+
+/// Crop centers along one frame axis, a quarter overlap apart
+fn sweep_centers(extent: i32, crop_size: i32) -> Vec<i32> {
+    let stride = (crop_size * 3 / 4).max(1);
+    let mut centers = Vec::new();
+    let mut c = (crop_size / 2).min(extent / 2);
+    loop {
+        centers.push(c);
+        if c + crop_size / 2 >= extent {
+            break;
+        }
+        c += stride;
+    }
+    centers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_fires_after_timeout() {
+        let mut watchdog = LossWatchdog::new(Duration::from_secs(2));
+        let t0 = Instant::now();
+        let at = |secs| t0 + Duration::from_secs(secs);
+
+        assert!(watchdog.observe(false, at(0)).is_none());
+        assert!(watchdog.observe(false, at(1)).is_none());
+        assert!(watchdog.observe(false, at(2)).is_some());
+        // Timer restarted after firing
+        assert!(watchdog.observe(false, at(3)).is_none());
+        // Success clears the timer
+        assert!(watchdog.observe(true, at(4)).is_none());
+        assert!(watchdog.observe(false, at(5)).is_none());
+    }
+
+    #[test]
+    fn test_redetection_grows_then_stops() {
+        let redetection = RedetectionConfig {
+            frames: 5,
+            ..RedetectionConfig::default()
+        };
+        let factors: Vec<u32> = (0..7).map(|missed| redetection.search_factor(4, missed)).collect();
+        assert_eq!(factors, [4, 6, 8, 10, 12, 12, 4]);
+        assert!(redetection.active(5));
+        assert!(!redetection.active(0));
+    }
+
+    #[test]
+    fn test_sweep_covers_frame() {
+        let rects = sweep_rects((1920, 1080), (50, 50), 400, usize::MAX);
+        let max_x = rects.iter().map(|r| r[0] + r[2] / 2 + 200).max().unwrap();
+        let max_y = rects.iter().map(|r| r[1] + r[3] / 2 + 200).max().unwrap();
+        assert!(max_x >= 1920);
+        assert!(max_y >= 1080);
+    }
+
+    #[test]
+    fn test_sweep_tile_cap() {
+        // A 20x20 box (80 px crops) would take 32 x 18 = 576 crops on 1080p
+        assert_eq!(sweep_rects((1920, 1080), (20, 20), 80, usize::MAX).len(), 576);
+
+        let rects = sweep_rects((1920, 1080), (20, 20), 80, 16);
+        assert!(!rects.is_empty() && rects.len() <= 16);
+        // Coarser grid of larger boxes
+        let [_, _, w, h] = rects[0];
+        assert!(w > 20 && w == h);
+
+        assert_eq!(sweep_rects((1920, 1080), (20, 20), 80, 1).len(), 1);
+    }
+}
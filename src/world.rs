@@ -0,0 +1,84 @@
+/// Ground-plane mapping from image pixels to world coordinates
+#[derive(Debug, Clone, Copy)]
+pub struct GroundPlane {
+    /// Row-major 3x3 homography mapping pixels to world units (meters)
+    pub homography: [[f32; 3]; 3],
+    /// Frame rate used to convert per-frame displacement to speed
+    pub fps: f32,
+}
+
+/// Target position and velocity on the ground plane
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WorldTarget {
+    /// Position in meters
+    pub x: f32,
+    pub y: f32,
+    /// Velocity in m/s (zero on the first frame after init)
+    pub vx: f32,
+    pub vy: f32,
+    /// Speed in m/s
+    pub speed: f32,
+}
+
+impl GroundPlane {
+    pub fn new(homography: [[f32; 3]; 3], fps: f32) -> Self {
+        Self { homography, fps }
+    }
+
+    /// Project a pixel onto the ground plane
+    pub fn project(&self, u: f32, v: f32) -> (f32, f32) {
+        let h = &self.homography;
+        let x = h[0][0] * u + h[0][1] * v + h[0][2];
+        let y = h[1][0] * u + h[1][1] * v + h[1][2];
+        let w = h[2][0] * u + h[2][1] * v + h[2][2];
+
+        (x / w, y / w)
+    }
+
+    /// Ground contact point of a bounding box [x, y, w, h] (bottom center)
+    pub fn project_bbox(&self, bbox: &[i32; 4]) -> (f32, f32) {
+        let [x, y, w, h] = bbox.map(|v| v as f32);
+        self.project(x + w / 2.0, y + h)
+    }
+
+    /// Build world target from the current position and the previous one
+    pub fn world_target(&self, position: (f32, f32), last: Option<(f32, f32)>) -> WorldTarget {
+        let (x, y) = position;
+        let (vx, vy) = match last {
+            Some((lx, ly)) => ((x - lx) * self.fps, (y - ly) * self.fps),
+            None => (0.0, 0.0),
+        };
+
+        WorldTarget {
+            x,
+            y,
+            vx,
+            vy,
+            speed: vx.hypot(vy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_homography() {
+        // 100 px per meter
+        let plane = GroundPlane::new([[0.01, 0.0, 0.0], [0.0, 0.01, 0.0], [0.0, 0.0, 1.0]], 25.0);
+        let (x, y) = plane.project_bbox(&[100, 100, 100, 100]);
+        assert!((x - 1.5).abs() < 1e-6);
+        assert!((y - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_speed() {
+        let plane = GroundPlane::new([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], 10.0);
+        let target = plane.world_target((3.0, 4.0), Some((0.0, 0.0)));
+        assert!((target.speed - 50.0).abs() < 1e-4);
+
+        let first = plane.world_target((3.0, 4.0), None);
+        assert_eq!(first.speed, 0.0);
+    }
+}
@@ -0,0 +1,130 @@
+//! Parity of this crate's pre/post-processing with the ONNX reference tracker
+//!
+//! Desktop only, needs ONNX Runtime and recorded data:
+//!
+//! ```text
+//! VIT_ONNX_MODEL=models/object_tracking_vittrack_2023sep.onnx \
+//! VIT_PARITY_DIR=data/parity \
+//! cargo test --no-default-features --features onnx --test onnx_parity -- --ignored
+//! ```
+//!
+//! `VIT_PARITY_DIR` holds the frames (png/jpg, processed in file-name order)
+//! and `reference.txt` with one `x,y,w,h,success` line per frame, recorded
+//! with OpenCV's `TrackerVit` on the same model; the first line is the init
+//! box. The crate side runs the same model on the CPU, so any disagreement
+//! comes from cropping, normalization or decoding.
+
+#![cfg(feature = "onnx")]
+
+use ndarray::Array3;
+use ort::session::Session;
+use ort::value::Tensor;
+use std::path::{Path, PathBuf};
+use vit_tracker::postprocess::{hann2d, process_outputs};
+use vit_tracker::preprocess::{crop_resized, BBox, InputTensor, InputType};
+
+const TEMPLATE_SIZE: usize = 128;
+const SEARCH_SIZE: usize = 256;
+const SCORE_SIZE: usize = 16;
+const TEMPLATE_FACTOR: u32 = 2;
+const SEARCH_FACTOR: u32 = 4;
+const SCORE_THRESHOLD: f32 = 0.2;
+/// Minimum overlap with the reference box on every frame it tracked
+const MIN_IOU: f32 = 0.9;
+
+fn load_rgb(path: &Path) -> Array3<u8> {
+    let image = image::open(path).unwrap().to_rgb8();
+    let (width, height) = image.dimensions();
+    Array3::from_shape_vec((height as usize, width as usize, 3), image.into_raw()).unwrap()
+}
+
+/// Reference lines: [x, y, w, h] and success
+fn load_reference(path: &Path) -> Vec<([i32; 4], bool)> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let values: Vec<i32> = line.split(',').map(|v| v.trim().parse().unwrap()).collect();
+            ([values[0], values[1], values[2], values[3]], values[4] != 0)
+        })
+        .collect()
+}
+
+/// Crate preprocessing, transposed from NHWC to the NCHW layout of the ONNX model
+fn to_nchw(crop: &Array3<u8>, size: usize) -> Tensor<f32> {
+    let InputTensor::Float32(nhwc) = InputTensor::from_image(crop, InputType::Float32) else {
+        unreachable!()
+    };
+    let mut nchw = vec![0.0f32; nhwc.len()];
+    for (i, value) in nhwc.into_iter().enumerate() {
+        let (pixel, channel) = (i / 3, i % 3);
+        nchw[channel * size * size + pixel] = value;
+    }
+    Tensor::from_array(([1usize, 3, size, size], nchw)).unwrap()
+}
+
+fn iou(a: &[i32; 4], b: &[i32; 4]) -> f32 {
+    let x1 = a[0].max(b[0]);
+    let y1 = a[1].max(b[1]);
+    let x2 = (a[0] + a[2]).min(b[0] + b[2]);
+    let y2 = (a[1] + a[3]).min(b[1] + b[3]);
+    let inter = ((x2 - x1).max(0) * (y2 - y1).max(0)) as f32;
+    let union = (a[2] * a[3] + b[2] * b[3]) as f32 - inter;
+    if union > 0.0 {
+        inter / union
+    } else {
+        0.0
+    }
+}
+
+#[test]
+#[ignore = "needs ONNX Runtime, VIT_ONNX_MODEL and VIT_PARITY_DIR"]
+fn test_onnx_parity() {
+    let model = std::env::var("VIT_ONNX_MODEL").expect("VIT_ONNX_MODEL not set");
+    let dir = PathBuf::from(std::env::var("VIT_PARITY_DIR").expect("VIT_PARITY_DIR not set"));
+    let mut session = Session::builder().unwrap().commit_from_file(&model).unwrap();
+
+    let mut frames: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("png" | "jpg")))
+        .collect();
+    frames.sort();
+    let reference = load_reference(&dir.join("reference.txt"));
+    assert_eq!(frames.len(), reference.len(), "one reference line per frame");
+
+    let hanning = hann2d(SCORE_SIZE, SCORE_SIZE);
+    let mut rect = reference[0].0;
+    let first = load_rgb(&frames[0]);
+    let (template, _) =
+        crop_resized(&first.view(), &BBox::from_array(&rect), TEMPLATE_FACTOR, TEMPLATE_SIZE);
+    let template = to_nchw(&template, TEMPLATE_SIZE);
+
+    let mut failures = Vec::new();
+    for (index, (path, &(expected, tracked))) in frames.iter().zip(&reference).enumerate().skip(1) {
+        let image = load_rgb(path);
+        let bbox = BBox::from_array(&rect);
+        let (search, crop) = crop_resized(&image.view(), &bbox, SEARCH_FACTOR, SEARCH_SIZE);
+        let outputs =
+            session.run(ort::inputs![template.clone(), to_nchw(&search, SEARCH_SIZE)]).unwrap();
+        let maps: Vec<Vec<f32>> =
+            (0..3).map(|i| outputs[i].try_extract_tensor::<f32>().unwrap().1.to_vec()).collect();
+
+        let result =
+            process_outputs(&maps[0], &maps[1], &maps[2], &hanning, &rect, crop, SCORE_THRESHOLD);
+        if result.success {
+            rect = result.bbox;
+        }
+
+        let overlap = iou(&rect, &expected);
+        if result.success != tracked || (tracked && overlap < MIN_IOU) {
+            failures.push(format!(
+                "frame {}: {:?} (success {}) vs reference {:?} (success {}), IoU {:.3}",
+                index, rect, result.success, expected, tracked, overlap
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{} frames differ:\n{}", failures.len(), failures.join("\n"));
+}